@@ -1,8 +1,27 @@
+use crate::config;
+use crate::csv_processor::{Broker, CsvProcessor};
 use crate::db;
-use crate::models::{Action, Campaign, OptionTrade};
-use ratatui::widgets::ListState;
-use rusqlite::Connection;
-use time::{Duration, OffsetDateTime};
+use crate::error::Result;
+use crate::integrity::{Issue, Repair};
+use crate::logic::{
+    AccountCash, CapitalAtRisk, CoveredCallStats, GoalProgress, MonthlyIncome, OpenLot,
+    ShareLedger, SymbolExposure, TradeQuality, account_cash, calculate_campaign_summary,
+    calculate_weekly_premium, capital_at_risk, covered_call_stats, cumulative_premium,
+    goal_progress, match_lots, monthly_income, symbol_exposure, this_friday, today, trade_quality,
+    trade_statuses,
+};
+use crate::metrics::Metrics;
+use crate::models::{
+    Action, Campaign, CashEvent, Dividend, Mark, OptionKind, OptionTrade, StockTrade, TradeStatus,
+};
+use crate::money::{format_cents, to_cents};
+use crate::quotes::{self, ChainQuote, QuoteProvider};
+use crate::storage::{self, Storage};
+use ratatui::widgets::{ListState, TableState};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
 pub enum AppScreen {
     Summary, // Added summary screen
@@ -14,15 +33,471 @@ pub enum AppScreen {
     AddTrade,
     ViewTrades,
     EditTrade,
+    Repair,
+    OptionChain,
+    ImportCsv,
+    MoveTrade,
+    MonthlyReport,
 }
 
-pub const ACTIONS: [&str; 6] = [
+/// Results of background work, delivered to the event loop through `App::events`.
+pub enum AppEvent {
+    /// An underlying's price, or why it couldn't be fetched, and its next ex-dividend date.
+    Quote {
+        symbol: String,
+        price: Result<f64, String>,
+        dividend: Option<Dividend>,
+    },
+    /// Option chain rows for the strike picker.
+    Chain(Result<Vec<ChainQuote>, String>),
+    /// How far along a long-running job is, for the status line.
+    Progress(String),
+    /// A job finished, with what to show on the status line. `reload` is set when it
+    /// changed the stored trades or campaigns.
+    Finished {
+        result: Result<String, String>,
+        reload: bool,
+    },
+}
+
+/// Lets a background job post progress updates while it runs.
+pub struct Progress(UnboundedSender<AppEvent>);
+
+impl Progress {
+    pub fn report(&self, message: impl Into<String>) {
+        let _ = self.0.send(AppEvent::Progress(message.into()));
+    }
+}
+
+/// A View Trades column the trades can be sorted by, in the order they are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeColumn {
+    Symbol,
+    Campaign,
+    Action,
+    Strike,
+    Delta,
+    Expiration,
+    Date,
+    Shares,
+    Credit,
+    TotalCredit,
+}
+
+impl TradeColumn {
+    /// Sortable columns; the digit keys 1-9 and 0 pick them in this order.
+    pub const ALL: [TradeColumn; 10] = [
+        TradeColumn::Symbol,
+        TradeColumn::Campaign,
+        TradeColumn::Action,
+        TradeColumn::Strike,
+        TradeColumn::Delta,
+        TradeColumn::Expiration,
+        TradeColumn::Date,
+        TradeColumn::Shares,
+        TradeColumn::Credit,
+        TradeColumn::TotalCredit,
+    ];
+
+    /// The column picked by digit key `c`, with 0 standing for the tenth.
+    pub fn from_digit(c: char) -> Option<Self> {
+        let n = c.to_digit(10)? as usize;
+        Self::ALL.get((n + 9) % 10).copied()
+    }
+
+    fn compare(self, a: &OptionTrade, b: &OptionTrade) -> std::cmp::Ordering {
+        let total = |t: &OptionTrade| t.credit * t.number_of_shares as f64;
+        match self {
+            TradeColumn::Symbol => a.symbol.cmp(&b.symbol),
+            TradeColumn::Campaign => a.campaign.cmp(&b.campaign),
+            TradeColumn::Action => a.action.as_str().cmp(b.action.as_str()),
+            TradeColumn::Strike => a.strike.total_cmp(&b.strike),
+            TradeColumn::Delta => a.delta.total_cmp(&b.delta),
+            TradeColumn::Expiration => a.expiration_date.cmp(&b.expiration_date),
+            TradeColumn::Date => a.date_of_action.cmp(&b.date_of_action),
+            TradeColumn::Shares => a.number_of_shares.cmp(&b.number_of_shares),
+            TradeColumn::Credit => a.credit.total_cmp(&b.credit),
+            TradeColumn::TotalCredit => total(a).total_cmp(&total(b)),
+        }
+    }
+}
+
+/// The order View Trades lists a campaign's trades in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeSort {
+    pub column: TradeColumn,
+    pub descending: bool,
+}
+
+impl Default for TradeSort {
+    fn default() -> Self {
+        Self {
+            column: TradeColumn::Expiration,
+            descending: false,
+        }
+    }
+}
+
+impl TradeSort {
+    /// Sort `trades` by the column, breaking ties by expiration and then id.
+    pub fn apply(self, trades: &mut [OptionTrade]) {
+        trades.sort_by(|a, b| {
+            let ordering = self.column.compare(a, b);
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+            .then_with(|| a.expiration_date.cmp(&b.expiration_date))
+            .then_with(|| a.id.cmp(&b.id))
+        });
+    }
+}
+
+/// Which of a campaign's trades View Trades shows.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradeFilter {
+    /// Text typed at the search prompt, matched without case against the symbol,
+    /// campaign, action, strike, dates and status.
+    pub search: String,
+    pub action: Option<Action>,
+    /// Only open trades when `Some(true)`, only the rest when `Some(false)`.
+    pub open: Option<bool>,
+    /// Only trades placed within this many days of today.
+    pub days: Option<i64>,
+}
+
+impl TradeFilter {
+    /// Date windows the date filter steps through.
+    const DAYS: [i64; 4] = [7, 30, 90, 365];
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether `trade`, with `status`, passes every filter as of `today`.
+    pub fn matches(&self, trade: &OptionTrade, status: TradeStatus, today: time::Date) -> bool {
+        let search = self.search.trim().to_lowercase();
+        let found = search.is_empty()
+            || [
+                trade.symbol.as_str(),
+                trade.campaign.as_str(),
+                trade.action.as_str(),
+                &trade.strike.to_string(),
+                &trade.expiration_date.to_string(),
+                &trade.date_of_action.to_string(),
+                status.as_str(),
+            ]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&search));
+        found
+            && self.action.as_ref().is_none_or(|a| *a == trade.action)
+            && self
+                .open
+                .is_none_or(|open| open == (status == TradeStatus::Open))
+            && self
+                .days
+                .is_none_or(|days| (today - trade.date_of_action).whole_days() <= days)
+    }
+
+    /// Step the action filter through every action and back to all of them.
+    pub fn cycle_action(&mut self) {
+        let next = match &self.action {
+            None => 0,
+            Some(action) => Action::ALL.iter().position(|a| a == action).unwrap_or(0) + 1,
+        };
+        self.action = Action::ALL.get(next).cloned();
+    }
+
+    /// Step from all trades to open ones, closed ones, and back.
+    pub fn cycle_open(&mut self) {
+        self.open = match self.open {
+            None => Some(true),
+            Some(true) => Some(false),
+            Some(false) => None,
+        };
+    }
+
+    /// Step the date window through the last week, month, quarter and year, and back to
+    /// all dates.
+    pub fn cycle_days(&mut self) {
+        let next = match self.days {
+            None => 0,
+            Some(days) => Self::DAYS.iter().position(|d| *d == days).unwrap_or(0) + 1,
+        };
+        self.days = Self::DAYS.get(next).copied();
+    }
+
+    /// The active filters, e.g. `"hood", SellPut, open, last 30 days`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.search.trim().is_empty() {
+            parts.push(format!("\"{}\"", self.search.trim()));
+        }
+        if let Some(action) = &self.action {
+            parts.push(action.as_str().to_string());
+        }
+        match self.open {
+            Some(true) => parts.push("open".to_string()),
+            Some(false) => parts.push("closed".to_string()),
+            None => {}
+        }
+        if let Some(days) = self.days {
+            parts.push(format!("last {days} days"));
+        }
+        parts.join(", ")
+    }
+}
+
+/// How many trade changes `undo` can step back through.
+const UNDO_LIMIT: usize = 100;
+
+/// A trade as stored, with its tags and mark.
+#[derive(Debug, Clone)]
+pub struct TradeRecord {
+    pub trade: OptionTrade,
+    pub tags: Vec<String>,
+    pub mark: Option<Mark>,
+}
+
+impl TradeRecord {
+    fn id(&self) -> i32 {
+        self.trade.id.unwrap_or_default()
+    }
+}
+
+/// A trade added, edited or deleted from the TUI, as the trade stored before and after
+/// it. An added trade has nothing before and a deleted one nothing after.
+#[derive(Debug, Clone)]
+pub struct TradeChange {
+    pub before: Option<TradeRecord>,
+    pub after: Option<TradeRecord>,
+}
+
+impl TradeChange {
+    /// The change that takes the trade back to how it was before this one.
+    fn reversed(&self) -> Self {
+        Self {
+            before: self.after.clone(),
+            after: self.before.clone(),
+        }
+    }
+
+    /// What the change did, for the status line, e.g. "edit of trade 12".
+    pub fn describe(&self) -> String {
+        match (&self.before, &self.after) {
+            (None, Some(after)) => format!("add of trade {}", after.id()),
+            (Some(before), None) => format!("delete of trade {}", before.id()),
+            (_, Some(after)) => format!("edit of trade {}", after.id()),
+            (None, None) => "nothing".to_string(),
+        }
+    }
+}
+
+/// One campaign's trades and the dashboard figures computed from them, cached so redraws
+/// don't refilter and re-sum the whole trade list.
+pub struct CampaignStats {
+    /// In the order View Trades lists them, set by `App::trade_sort`.
+    pub trades: Vec<OptionTrade>,
+    pub break_even: Option<f64>,
+    pub weeks_running: i32,
+    pub profit_per_week: Option<f64>,
+    /// In cents, as are the other totals.
+    pub total_credits: i64,
+    /// Commissions and fees paid, already taken out of `running_pl`.
+    pub costs: i64,
+    /// Includes `cash_income`.
+    pub running_pl: i64,
+    /// Dividends and interest paid to the campaign.
+    pub cash_income: i64,
+    pub weekly_premium: i64,
+    /// Net premium to date after each day with a trade, for the dashboard chart.
+    pub cumulative_premium: Vec<(time::Date, i64)>,
+    /// Win rate and the size of wins and losses of the closed positions.
+    pub quality: TradeQuality,
+    pub open: Vec<OpenLot>,
+    /// Collateral tied up by `open`.
+    pub capital: CapitalAtRisk,
+    /// Shares held from assignments and share trades.
+    pub shares: ShareLedger,
+    /// The shares held and the calls written on them, while shares are held.
+    pub covered_calls: Option<CoveredCallStats>,
+    /// Premium collected this week or month toward the campaign's goal, if it has one.
+    pub goal: Option<GoalProgress>,
+}
+
+impl CampaignStats {
+    fn new(
+        mut trades: Vec<OptionTrade>,
+        campaign: &Campaign,
+        cash_income: i64,
+        stocks: &[StockTrade],
+        today: time::Date,
+        sort: TradeSort,
+    ) -> Self {
+        sort.apply(&mut trades);
+        let refs: Vec<&OptionTrade> = trades.iter().collect();
+        let (break_even, weeks_running, profit_per_week, total_credits, running_pl) =
+            calculate_campaign_summary(&refs, stocks, campaign.target_exit_price);
+        let shares = ShareLedger::new(&trades, stocks, today);
+        let lots = match_lots(&trades, today);
+        // A trade marked closed by hand leaves the open positions
+        let open: Vec<OpenLot> = lots
+            .open
+            .iter()
+            .filter(|lot| lot.trade.status.is_none_or(|s| s == TradeStatus::Open))
+            .cloned()
+            .collect();
+        Self {
+            break_even,
+            weeks_running,
+            profit_per_week,
+            total_credits,
+            costs: trades.iter().map(OptionTrade::costs_cents).sum(),
+            running_pl: running_pl + cash_income,
+            cash_income,
+            weekly_premium: calculate_weekly_premium(&trades),
+            cumulative_premium: cumulative_premium(&trades),
+            quality: trade_quality(&lots),
+            capital: capital_at_risk(&open),
+            covered_calls: covered_call_stats(&shares, &open, running_pl),
+            goal: campaign
+                .goal
+                .map(|goal| goal_progress(&trades, goal, today, config::get().week_start)),
+            open,
+            shares,
+            trades,
+        }
+    }
+}
+
+/// Account-wide figures for the summary and campaign list, cached like `CampaignStats`.
+#[derive(Default)]
+pub struct SummaryStats {
+    /// Net premium sold plus dividends and interest, in cents.
+    pub total_pnl: i64,
+    pub weekly_premium: i64,
+    pub roic: Option<f64>,
+    /// Exposure per underlying across campaigns, most capital at risk first.
+    pub symbols: Vec<SymbolExposure>,
+    /// Income per calendar month across campaigns, oldest first.
+    pub months: Vec<MonthlyIncome>,
+    /// Collateral tied up by open positions across campaigns.
+    pub capital: CapitalAtRisk,
+    /// Cash in the account and the deposits less withdrawals that funded it.
+    pub account: AccountCash,
+}
+
+/// The CSV import screen: a file browser plus the broker and the campaign to import into.
+pub struct ImportForm {
+    /// Directory listed in the file browser.
+    pub dir: PathBuf,
+    /// `..`, then the subdirectories and CSV files of `dir`.
+    pub entries: Vec<PathBuf>,
+    pub list_state: ListState,
+    pub file: Option<PathBuf>,
+    /// Index into `Broker::supported_brokers()`.
+    pub broker_index: usize,
+    pub campaign: String,
+    pub symbol: String,
+    pub field: usize, // 0 = files, 1 = broker, 2 = campaign, 3 = symbol
+    pub error: Option<String>,
+}
+
+impl ImportForm {
+    fn new(dir: PathBuf) -> Self {
+        let broker_index = config::get()
+            .default_broker
+            .as_deref()
+            .and_then(Broker::from_name)
+            .and_then(|broker| {
+                Broker::supported_brokers()
+                    .iter()
+                    .position(|name| *name == broker.as_str())
+            })
+            .unwrap_or(0);
+        let mut form = Self {
+            dir: PathBuf::new(),
+            entries: Vec::new(),
+            list_state: ListState::default(),
+            file: None,
+            broker_index,
+            campaign: String::new(),
+            symbol: String::new(),
+            field: 0,
+            error: None,
+        };
+        form.open_dir(dir);
+        form
+    }
+
+    /// List `dir` in the file browser. Hidden entries and files other than CSVs are left
+    /// out.
+    pub fn open_dir(&mut self, dir: PathBuf) {
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        match std::fs::read_dir(&dir) {
+            Ok(read) => {
+                for path in read.filter_map(|e| e.ok()).map(|e| e.path()) {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    if name.starts_with('.') {
+                        continue;
+                    }
+                    if path.is_dir() {
+                        dirs.push(path);
+                    } else if path
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+                    {
+                        files.push(path);
+                    }
+                }
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("{}: {e}", dir.display())),
+        }
+        dirs.sort();
+        files.sort();
+        self.entries = std::iter::once(dir.join(".."))
+            .chain(dirs)
+            .chain(files)
+            .collect();
+        self.list_state.select(Some(0));
+        self.dir = dir;
+    }
+
+    pub fn broker_name(&self) -> &'static str {
+        Broker::supported_brokers()[self.broker_index]
+    }
+
+    /// Enter on the highlighted entry: step into a directory or pick a file.
+    pub fn choose_entry(&mut self) {
+        let Some(path) = self
+            .list_state
+            .selected()
+            .and_then(|i| self.entries.get(i))
+            .cloned()
+        else {
+            return;
+        };
+        if path.is_dir() {
+            // Canonicalized so `..` doesn't pile up in the path shown
+            self.open_dir(path.canonicalize().unwrap_or(path));
+        } else {
+            self.file = Some(path);
+            self.field = 1;
+        }
+    }
+}
+
+pub const ACTIONS: [&str; 7] = [
     "BuyPut",
     "SellPut",
     "BuyCall",
     "SellCall",
     "Exercised",
     "Assigned",
+    "Expired",
 ];
 
 pub struct App {
@@ -31,41 +506,118 @@ pub struct App {
     pub selected_campaign: Option<Campaign>,
     pub campaign_select_index: usize,
     pub campaign_list_state: ListState,
+    /// Whether archived campaigns are listed on the campaign select screen.
+    pub show_archived: bool,
+    /// Campaign waiting for the user to confirm its deletion.
+    pub delete_pending: Option<String>,
     pub new_campaign_name: String,
     pub new_campaign_symbol: String,
     pub new_campaign_target_price: String,
     pub new_campaign_field: usize, // 0 = name, 1 = symbol, 2 = target price
-    pub form_fields: [String; 6],  // strike, delta, expiration, date, shares, credit
+    pub form_fields: [String; 7],  // strike, delta, expiration, date, contracts, credit, tags
     pub form_index: usize,
     pub action_index: usize,
     pub form_error: Option<String>,
     pub trades: Vec<OptionTrade>,
-    pub table_scroll: usize,
-    pub db_conn: Connection,
-    pub edit_trade_fields: [String; 8], // symbol, action, strike, delta, expiration, date, shares, credit
+    /// Tags of the tagged trades by trade id.
+    pub trade_tags: HashMap<i32, Vec<String>>,
+    /// Latest option price entered for open trades, by trade id.
+    pub marks: HashMap<i32, Mark>,
+    /// Status of every trade by id, refreshed with the summary.
+    statuses: HashMap<i32, TradeStatus>,
+    /// Running totals over `trades`, updated trade by trade as the TUI saves them.
+    pub metrics: Metrics,
+    /// Figures derived from `trades`, refreshed whenever trades or campaigns change.
+    pub summary: SummaryStats,
+    campaign_stats: HashMap<String, CampaignStats>,
+    /// Highlighted row and scroll offset of the View Trades table.
+    pub trade_table_state: TableState,
+    /// Column and direction View Trades is sorted by.
+    pub trade_sort: TradeSort,
+    pub storage: Box<dyn Storage>,
+    /// Where `storage` was opened from, for jobs that need their own connection.
+    pub db_path: PathBuf,
+    /// Progress or outcome of the latest background job.
+    pub status: Option<String>,
+    /// A failure shown in a popup over the current screen until a key is pressed.
+    pub error: Option<String>,
+    pub edit_trade_fields: [String; 9], // symbol, action, strike, delta, expiration, date, contracts, credit, tags
     pub edit_action_index: usize,
     pub edit_form_index: usize,
     pub edit_trade_id: Option<i32>,
+    /// Campaign the trade on the Edit Trade screen is saved to.
+    pub edit_trade_campaign: String,
+    /// Campaign the trade on the Add Trade screen is saved to.
+    pub add_trade_campaign: String,
+    /// Trade being moved with the campaign picker, and the campaigns offered for it.
+    pub move_trade: Option<OptionTrade>,
+    pub move_targets: Vec<String>,
+    pub move_list_state: ListState,
+    /// Trade whose mark is being typed on View Trades, and the price typed so far.
+    pub mark_input: Option<(i32, String)>,
+    /// Which trades View Trades lists.
+    pub trade_filter: TradeFilter,
+    /// Whether the View Trades search prompt is taking keys.
+    pub searching: bool,
+    /// Trade changes made from the TUI, latest last, for `undo`, and the ones undone
+    /// since, for `redo`.
+    undo_stack: Vec<TradeChange>,
+    redo_stack: Vec<TradeChange>,
+    /// Problems found in the stored trades at startup, shown on the repair screen.
+    pub issues: Vec<Issue>,
+    pub issue_index: usize,
+    /// Configured market-data source, if any.
+    pub quote_provider: Option<Arc<dyn QuoteProvider>>,
+    /// Last fetched underlying price by symbol.
+    pub quotes: HashMap<String, f64>,
+    pub quote_error: Option<String>,
+    /// Known ex-dividend dates, for early-assignment warnings.
+    pub dividends: Vec<Dividend>,
+    /// Shares bought and sold, shown on the campaign dashboard.
+    pub stock_trades: Vec<StockTrade>,
+    /// Dividends and interest received, counted in the P/L figures.
+    pub cash_events: Vec<CashEvent>,
+    /// Option chain rows offered on the strike picker.
+    pub chain: Vec<ChainQuote>,
+    pub chain_list_state: ListState,
+    pub import: ImportForm,
+    /// Completed background work, waiting to be applied with `handle_event`.
+    pub events: UnboundedReceiver<AppEvent>,
+    event_sender: UnboundedSender<AppEvent>,
 }
 
 impl App {
-    pub fn new() -> Self {
-        let db_conn = Connection::open("options_trades.db").unwrap();
-        db::init_database(&db_conn).unwrap();
-        let mut campaigns = Campaign::get_all(&db_conn);
-        campaigns.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-        let trades = OptionTrade::get_all(&db_conn).unwrap_or_default();
-        let mut form_fields: [String; 6] = Default::default();
+    pub fn new(db_path: &Path) -> Result<Self> {
+        let quote_provider = quotes::from_config(config::get())?.map(Arc::from);
+        let mut storage = storage::open(db_path)?;
+        let issues = storage.integrity_issues()?;
+        let mut campaigns = storage.campaigns()?;
+        campaigns.sort_by_key(|a| a.name.to_lowercase());
+        let trades = storage.trades()?;
+        let trade_tags = storage.trade_tags()?;
+        let marks = storage.marks()?;
+        let dividends = storage.dividends()?;
+        let stock_trades = storage.stock_trades()?;
+        let cash_events = storage.cash_events()?;
+        let mut form_fields: [String; 7] = Default::default();
         // Set Date of Action (index 3) to today
-        form_fields[3] = OffsetDateTime::now_local().unwrap().date().to_string();
+        form_fields[3] = today().to_string();
         let mut campaign_list_state = ListState::default();
         campaign_list_state.select(Some(0));
-        Self {
-            screen: AppScreen::Summary, // Set summary as default
+        let (event_sender, events) = mpsc::unbounded_channel();
+        let mut app = Self {
+            // Summary is the default; problems that would hide trades come first
+            screen: if issues.is_empty() {
+                AppScreen::Summary
+            } else {
+                AppScreen::Repair
+            },
             campaigns,
             selected_campaign: None,
             campaign_select_index: 0,
             campaign_list_state,
+            show_archived: false,
+            delete_pending: None,
             new_campaign_name: String::new(),
             new_campaign_symbol: String::new(),
             new_campaign_target_price: String::new(),
@@ -74,14 +626,49 @@ impl App {
             form_index: 0,
             action_index: 0,
             form_error: None,
+            metrics: Metrics::new(&trades, config::get().week_start),
             trades,
-            table_scroll: 0,
-            db_conn,
+            trade_tags,
+            marks,
+            statuses: HashMap::new(),
+            summary: SummaryStats::default(),
+            campaign_stats: HashMap::new(),
+            trade_table_state: TableState::default().with_selected(Some(0)),
+            trade_sort: TradeSort::default(),
+            storage,
+            db_path: db_path.to_path_buf(),
+            status: None,
+            error: None,
             edit_trade_fields: Default::default(),
             edit_action_index: 0,
             edit_form_index: 0,
             edit_trade_id: None,
-        }
+            edit_trade_campaign: String::new(),
+            add_trade_campaign: String::new(),
+            move_trade: None,
+            move_targets: Vec::new(),
+            move_list_state: ListState::default(),
+            mark_input: None,
+            trade_filter: TradeFilter::default(),
+            searching: false,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            issues,
+            issue_index: 0,
+            quote_provider,
+            quotes: HashMap::new(),
+            quote_error: None,
+            dividends,
+            stock_trades,
+            cash_events,
+            chain: Vec::new(),
+            chain_list_state: ListState::default(),
+            import: ImportForm::new(std::env::current_dir().unwrap_or_default()),
+            events,
+            event_sender,
+        };
+        app.refresh_stats();
+        Ok(app)
     }
     pub fn reset_form(&mut self) {
         self.form_fields = Default::default();
@@ -89,23 +676,737 @@ impl App {
         self.action_index = 0;
         self.form_error = None;
         // Set Date of Action (index 3) to today
-        self.form_fields[3] = OffsetDateTime::now_local().unwrap().date().to_string();
+        self.form_fields[3] = today().to_string();
     }
+    /// Show `error` in a popup, prefixed with what was being done.
+    pub fn show_error(&mut self, doing: &str, error: impl std::fmt::Display) {
+        self.error = Some(format!("{doing}: {error}"));
+    }
+
+    /// Reread the trades and everything recorded alongside them. On failure the
+    /// previously loaded data stays and the error is shown.
     pub fn reload_trades(&mut self) {
-        let mut trades = OptionTrade::get_all(&self.db_conn).unwrap_or_default();
+        if let Err(e) = self.load_trades() {
+            self.show_error("Could not load trades", e);
+        }
+        self.metrics = Metrics::new(&self.trades, config::get().week_start);
+        self.refresh_stats();
+    }
+
+    fn load_trades(&mut self) -> Result<()> {
+        let mut trades = self.storage.trades()?;
         // Sort trades by expiration date (earliest first), then by date of action
-        trades.sort_by(|a, b| a.expiration_date.cmp(&b.expiration_date));
+        trades.sort_by_key(|a| a.expiration_date);
         self.trades = trades;
+        self.trade_tags = self.storage.trade_tags()?;
+        self.marks = self.storage.marks()?;
+        self.stock_trades = self.storage.stock_trades()?;
+        self.cash_events = self.storage.cash_events()?;
+        Ok(())
+    }
+
+    /// Save a new trade, updating only the figures it affects. Returns the trade's id.
+    pub fn insert_trade(&mut self, trade: &OptionTrade) -> Result<i32> {
+        let id = self.storage.insert_trade(trade)?;
+        self.metrics.add(trade);
+        self.trades_changed(&[&trade.campaign]);
+        Ok(id)
+    }
+
+    /// Replace the tags of trade `id`.
+    pub fn set_trade_tags(&mut self, id: i32, tags: &[String]) -> Result<()> {
+        self.storage.set_trade_tags(id, tags)?;
+        if tags.is_empty() {
+            self.trade_tags.remove(&id);
+        } else {
+            self.trade_tags.insert(id, tags.to_vec());
+        }
+        Ok(())
+    }
+
+    /// Save an edited trade, updating only the figures it affects.
+    pub fn update_trade(&mut self, trade: &OptionTrade) -> Result<()> {
+        self.storage.update_trade(trade)?;
+        let old = self.trades.iter().find(|t| t.id == trade.id).cloned();
+        match &old {
+            Some(old) => self.metrics.update(old, trade),
+            None => self.metrics.add(trade),
+        }
+        let old_campaign = old.map(|t| t.campaign);
+        let mut campaigns = vec![trade.campaign.as_str()];
+        campaigns.extend(old_campaign.as_deref());
+        self.trades_changed(&campaigns);
+        Ok(())
+    }
+
+    /// Save `trade`, as a new trade when it has no id, and replace its tags when `tags`
+    /// is given. The change can be undone. Returns the trade's id.
+    pub fn save_trade(&mut self, trade: &OptionTrade, tags: Option<&[String]>) -> Result<i32> {
+        let before = trade.id.and_then(|id| self.trade_record(id));
+        let id = match trade.id {
+            Some(id) => {
+                self.update_trade(trade)?;
+                id
+            }
+            None => self.insert_trade(trade)?,
+        };
+        if let Some(tags) = tags {
+            self.set_trade_tags(id, tags)?;
+        }
+        let after = self.trade_record(id);
+        self.remember(TradeChange { before, after });
+        Ok(id)
+    }
+
+    /// Delete the trade highlighted on View Trades. The deletion can be undone.
+    pub fn delete_selected_trade(&mut self) {
+        let Some(id) = self.selected_trade().and_then(|t| t.id) else {
+            return;
+        };
+        let change = TradeChange {
+            before: self.trade_record(id),
+            after: None,
+        };
+        match self.apply_change(&change) {
+            Ok(()) => {
+                self.status = Some(format!(
+                    "Deleted trade {id} ({} to undo)",
+                    config::get().keys.undo
+                ));
+                self.remember(change);
+            }
+            Err(e) => self.show_error(&format!("Could not delete trade {id}"), e),
+        }
+        self.select_trade(None);
+    }
+
+    /// Reverse the latest trade change made from the TUI.
+    pub fn undo(&mut self) {
+        let Some(change) = self.undo_stack.pop() else {
+            self.status = Some("Nothing to undo".to_string());
+            return;
+        };
+        match self.apply_change(&change.reversed()) {
+            Ok(()) => {
+                self.status = Some(format!("Undid {}", change.describe()));
+                self.redo_stack.push(change);
+            }
+            Err(e) => self.show_error(&format!("Could not undo {}", change.describe()), e),
+        }
+        self.select_trade(None);
+    }
+
+    /// Make the latest undone trade change again.
+    pub fn redo(&mut self) {
+        let Some(change) = self.redo_stack.pop() else {
+            self.status = Some("Nothing to redo".to_string());
+            return;
+        };
+        match self.apply_change(&change) {
+            Ok(()) => {
+                self.status = Some(format!("Redid {}", change.describe()));
+                self.undo_stack.push(change);
+            }
+            Err(e) => self.show_error(&format!("Could not redo {}", change.describe()), e),
+        }
+        self.select_trade(None);
+    }
+
+    /// Trade `id` as loaded, with its tags and mark.
+    fn trade_record(&self, id: i32) -> Option<TradeRecord> {
+        let trade = self.trades.iter().find(|t| t.id == Some(id))?.clone();
+        Some(TradeRecord {
+            trade,
+            tags: self.trade_tags.get(&id).cloned().unwrap_or_default(),
+            mark: self.marks.get(&id).cloned(),
+        })
+    }
+
+    /// Keep `change` for `undo`. A new change drops the ones waiting to be redone.
+    fn remember(&mut self, change: TradeChange) {
+        self.redo_stack.clear();
+        self.undo_stack.push(change);
+        if self.undo_stack.len() > UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    /// Store `change.after` in place of `change.before`. Refused when the stored trade
+    /// no longer matches `change.before`, as when it was edited or imported over since,
+    /// or when other trades link to a trade it would delete.
+    fn apply_change(&mut self, change: &TradeChange) -> Result<()> {
+        let id = match (&change.before, &change.after) {
+            (_, Some(record)) | (Some(record), None) => record.id(),
+            (None, None) => return Ok(()),
+        };
+        let stored = self.trades.iter().find(|t| t.id == Some(id));
+        if stored != change.before.as_ref().map(|r| &r.trade) {
+            return Err(format!("Trade {id} has changed since").into());
+        }
+        let mut campaigns: Vec<String> = Vec::new();
+        match (&change.before, &change.after) {
+            (Some(before), None) => {
+                let linked = self.trades.iter().find(|t| {
+                    [t.closes_trade_id, t.rolled_from_id, t.covered_by_id].contains(&Some(id))
+                });
+                if let Some(linked) = linked {
+                    return Err(format!(
+                        "Trade {} closes, rolls from or is covered by it",
+                        linked.id.unwrap_or_default()
+                    )
+                    .into());
+                }
+                self.storage.delete_trade(id)?;
+                self.metrics.remove(&before.trade);
+                self.trade_tags.remove(&id);
+                self.marks.remove(&id);
+                campaigns.push(before.trade.campaign.clone());
+            }
+            (before, Some(after)) => {
+                match before {
+                    Some(before) => {
+                        self.storage.update_trade(&after.trade)?;
+                        self.metrics.update(&before.trade, &after.trade);
+                        campaigns.push(before.trade.campaign.clone());
+                    }
+                    None => {
+                        self.storage.restore_trade(&after.trade)?;
+                        self.metrics.add(&after.trade);
+                    }
+                }
+                self.set_trade_tags(id, &after.tags)?;
+                if let Some(mark) = &after.mark {
+                    self.storage.save_mark(mark)?;
+                    self.marks.insert(id, mark.clone());
+                }
+                campaigns.push(after.trade.campaign.clone());
+            }
+            (None, None) => {}
+        }
+        let campaigns: Vec<&str> = campaigns.iter().map(String::as_str).collect();
+        self.trades_changed(&campaigns);
+        Ok(())
+    }
+
+    /// Refresh the trade list after a save, recomputing the summary from the running
+    /// totals and the stats of `campaigns` only.
+    fn trades_changed(&mut self, campaigns: &[&str]) {
+        // New trades get their id from the database, so the list itself is reread
+        match self.storage.trades() {
+            Ok(mut trades) => {
+                trades.sort_by_key(|a| a.expiration_date);
+                self.trades = trades;
+            }
+            Err(e) => self.show_error("Could not load trades", e),
+        }
+        self.refresh_summary();
+        let today = today();
+        for name in campaigns {
+            if let Some(campaign) = self.campaigns.iter().find(|c| c.name == *name) {
+                let trades = self
+                    .trades
+                    .iter()
+                    .filter(|t| t.campaign == campaign.name && t.symbol == campaign.symbol)
+                    .cloned()
+                    .collect();
+                let cash = self.cash_income(Some(&campaign.name));
+                let stocks = self.campaign_stock_trades(campaign);
+                let stats =
+                    CampaignStats::new(trades, campaign, cash, &stocks, today, self.trade_sort);
+                self.campaign_stats.insert(campaign.name.clone(), stats);
+            }
+        }
     }
     pub fn reload_campaigns(&mut self) {
-        self.campaigns = Campaign::get_all(&self.db_conn);
-        self.campaigns
-            .sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-        if self.campaign_select_index >= self.campaigns.len() {
-            self.campaign_select_index = self.campaigns.len().saturating_sub(1);
+        match self.storage.campaigns() {
+            Ok(campaigns) => self.campaigns = campaigns,
+            Err(e) => self.show_error("Could not load campaigns", e),
+        }
+        self.campaigns.sort_by_key(|a| a.name.to_lowercase());
+        let listed = self.listed_campaigns().len();
+        if self.campaign_select_index >= listed {
+            self.campaign_select_index = listed.saturating_sub(1);
         }
         self.campaign_list_state
             .select(Some(self.campaign_select_index));
+        self.refresh_stats();
+    }
+
+    /// Campaigns shown on the campaign select screen: archived ones only when
+    /// `show_archived` is set.
+    pub fn listed_campaigns(&self) -> Vec<&Campaign> {
+        self.campaigns
+            .iter()
+            .filter(|c| self.show_archived || !c.archived)
+            .collect()
+    }
+
+    /// The campaign highlighted on the campaign select screen.
+    pub fn highlighted_campaign(&self) -> Option<Campaign> {
+        self.listed_campaigns()
+            .get(self.campaign_select_index)
+            .map(|c| (*c).clone())
+    }
+
+    /// List or hide archived campaigns, keeping the highlighted campaign selected when it
+    /// stays in the list.
+    pub fn toggle_archived(&mut self) {
+        let highlighted = self.highlighted_campaign().map(|c| c.name);
+        self.show_archived = !self.show_archived;
+        self.campaign_select_index = highlighted
+            .and_then(|name| self.listed_campaigns().iter().position(|c| c.name == name))
+            .unwrap_or(0);
+        self.reload_campaigns();
+    }
+
+    /// Archive the highlighted campaign, or restore it if it is archived already.
+    pub fn toggle_campaign_archived(&mut self) {
+        let Some(campaign) = self.highlighted_campaign() else {
+            return;
+        };
+        match self
+            .storage
+            .set_campaign_archived(&campaign.name, !campaign.archived)
+        {
+            Ok(()) if campaign.archived => {
+                self.status = Some(format!("Restored {}", campaign.name))
+            }
+            Ok(()) => self.status = Some(format!("Archived {}", campaign.name)),
+            Err(e) => self.show_error(&format!("Could not archive {}", campaign.name), e),
+        }
+        self.reload_campaigns();
+    }
+
+    /// Delete the campaign awaiting confirmation along with its trades.
+    pub fn confirm_delete_campaign(&mut self) {
+        let Some(name) = self.delete_pending.take() else {
+            return;
+        };
+        match self.storage.delete_campaign(&name) {
+            Ok(trades) => self.status = Some(format!("Deleted {name} and {trades} trade(s)")),
+            Err(e) => self.show_error(&format!("Could not delete {name}"), e),
+        }
+        if self
+            .selected_campaign
+            .as_ref()
+            .is_some_and(|c| c.name == name)
+        {
+            self.selected_campaign = None;
+        }
+        self.reload_campaigns();
+        self.reload_trades();
+    }
+
+    fn refresh_summary(&mut self) {
+        let today = today();
+        self.statuses = trade_statuses(&self.trades, today);
+        let total = self.metrics.total();
+        let open: Vec<OpenLot> = match_lots(&self.trades, today)
+            .open
+            .into_iter()
+            .filter(|lot| lot.trade.status.is_none_or(|s| s == TradeStatus::Open))
+            .collect();
+        self.summary = SummaryStats {
+            total_pnl: total.net_premium() + self.cash_income(None),
+            weekly_premium: self.metrics.sold_expiring_on(this_friday(today)),
+            roic: total.roic(),
+            symbols: symbol_exposure(&self.trades, today),
+            months: monthly_income(&self.trades, today),
+            capital: capital_at_risk(&open),
+            account: account_cash(&self.trades, &self.stock_trades, &self.cash_events, today),
+        };
+    }
+
+    /// Recompute the cached summary and every campaign's figures from `trades`.
+    fn refresh_stats(&mut self) {
+        self.refresh_summary();
+        let today = today();
+        let mut by_campaign: HashMap<&str, Vec<OptionTrade>> = HashMap::new();
+        for trade in &self.trades {
+            by_campaign
+                .entry(trade.campaign.as_str())
+                .or_default()
+                .push(trade.clone());
+        }
+        let stats = self
+            .campaigns
+            .iter()
+            .map(|campaign| {
+                let trades = by_campaign
+                    .remove(campaign.name.as_str())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|t| t.symbol == campaign.symbol)
+                    .collect();
+                let cash = self.cash_income(Some(&campaign.name));
+                let stocks = self.campaign_stock_trades(campaign);
+                let stats =
+                    CampaignStats::new(trades, campaign, cash, &stocks, today, self.trade_sort);
+                (campaign.name.clone(), stats)
+            })
+            .collect();
+        self.campaign_stats = stats;
+    }
+
+    /// Dividends and interest paid to `campaign`, or to the whole account, in cents.
+    fn cash_income(&self, campaign: Option<&str>) -> i64 {
+        self.cash_events
+            .iter()
+            .filter(|e| e.kind.is_income())
+            .filter(|e| campaign.is_none() || e.campaign.as_deref() == campaign)
+            .map(|e| to_cents(e.amount))
+            .sum()
+    }
+
+    /// Share trades of `campaign` in its symbol.
+    fn campaign_stock_trades(&self, campaign: &Campaign) -> Vec<StockTrade> {
+        self.stock_trades
+            .iter()
+            .filter(|t| t.campaign == campaign.name && t.symbol == campaign.symbol)
+            .cloned()
+            .collect()
+    }
+
+    /// Cached figures for the selected campaign.
+    pub fn selected_stats(&self) -> Option<&CampaignStats> {
+        self.campaign_stats(&self.selected_campaign.as_ref()?.name)
+    }
+
+    /// Cached figures for the campaign named `name`.
+    pub fn campaign_stats(&self, name: &str) -> Option<&CampaignStats> {
+        self.campaign_stats.get(name)
+    }
+
+    /// Record today's account snapshot from the stored trades and cash events.
+    pub fn record_snapshot(&mut self) -> Result<()> {
+        let trades = self.storage.trades()?;
+        let stocks = self.storage.stock_trades()?;
+        let events = self.storage.cash_events()?;
+        let snapshot = crate::logic::calculate_snapshot(&trades, &stocks, &events, today());
+        self.storage.save_snapshot(&snapshot)
+    }
+
+    /// Resolve the selected repair-screen issue, moving on once none are left.
+    pub fn repair_selected(&mut self, repair: Repair) {
+        let Some(issue) = self.issues.get(self.issue_index).cloned() else {
+            return;
+        };
+        match self.storage.repair(&issue, repair) {
+            Ok(()) => {
+                self.form_error = None;
+                // Deleting a trade also resolves its other issues
+                self.issues.retain(|i| {
+                    i != &issue && !(repair == Repair::DeleteTrade && i.trade_id == issue.trade_id)
+                });
+                self.issue_index = self.issue_index.min(self.issues.len().saturating_sub(1));
+                if self.issues.is_empty() {
+                    self.finish_repairs();
+                }
+            }
+            Err(e) => self.form_error = Some(format!("Repair failed: {e}")),
+        }
+    }
+
+    /// Leave the repair screen, reloading whatever can be loaded now.
+    pub fn finish_repairs(&mut self) {
+        self.form_error = None;
+        self.reload_campaigns();
+        self.reload_trades();
+        self.screen = if self.selected_campaign.is_some() {
+            AppScreen::CampaignDashboard
+        } else {
+            AppScreen::Summary
+        };
+    }
+
+    /// Run `job` on the async runtime's blocking pool, delivering its result as an event.
+    /// Needs to be called from within the runtime.
+    fn spawn(&self, job: impl FnOnce(&Progress) -> AppEvent + Send + 'static) {
+        let progress = Progress(self.event_sender.clone());
+        tokio::task::spawn_blocking(move || {
+            let event = job(&progress);
+            // The receiver only goes away when the TUI exits
+            let _ = progress.0.send(event);
+        });
+    }
+
+    /// Apply the result of background work.
+    pub fn handle_event(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::Quote {
+                symbol,
+                price,
+                dividend,
+            } => {
+                match price {
+                    Ok(price) => {
+                        self.quotes.insert(symbol, price);
+                        self.quote_error = None;
+                    }
+                    Err(e) => {
+                        self.quote_error = Some(format!("Quote for {symbol} failed: {e}"));
+                        return;
+                    }
+                }
+                if let Some(dividend) = dividend {
+                    match self
+                        .storage
+                        .save_dividend(&dividend)
+                        .and_then(|()| self.storage.dividends())
+                    {
+                        Ok(dividends) => self.dividends = dividends,
+                        Err(e) => self.show_error("Could not save the ex-dividend date", e),
+                    }
+                }
+            }
+            // Dropped if the Add Trade form was left while the chain loaded
+            AppEvent::Chain(_) if !matches!(self.screen, AppScreen::AddTrade) => {}
+            AppEvent::Chain(Ok(chain)) => {
+                self.chain = chain;
+                self.chain_list_state.select(Some(0));
+                self.form_error = None;
+                self.screen = AppScreen::OptionChain;
+            }
+            AppEvent::Chain(Err(e)) => self.form_error = Some(e),
+            AppEvent::Progress(message) => self.status = Some(message),
+            AppEvent::Finished { result, reload } => {
+                match result {
+                    Ok(message) => self.status = Some(message),
+                    Err(e) => {
+                        self.status = None;
+                        self.show_error("Failed", e);
+                    }
+                }
+                if reload {
+                    self.reload_campaigns();
+                    self.reload_trades();
+                }
+            }
+        }
+    }
+
+    /// Start writing a JSON dump of the database (as `profit_tracker dump` does) next to
+    /// the database file, named after today's date.
+    pub fn start_backup(&mut self) {
+        let location = self.db_path.to_string_lossy();
+        if storage::is_postgres_url(&location) || location == ":memory:" {
+            self.status = Some("Backups need a SQLite database file".to_string());
+            return;
+        }
+        let today = today();
+        let db_path = self.db_path.clone();
+        let out = json_backup_path(&db_path, today);
+        self.status = Some(format!("Backing up to {}...", out.display()));
+        self.spawn(move |progress| {
+            let result = (|| -> Result<String> {
+                // SQLite connections can't be shared across threads, so the job opens its own
+                let dump =
+                    db::dump_json(&db::open_database(&db_path, &config::get().multipliers)?)?;
+                let rows: usize = db::DUMP_TABLES
+                    .iter()
+                    .filter_map(|table| dump["tables"][table].as_array())
+                    .map(Vec::len)
+                    .sum();
+                progress.report(format!("Writing {rows} rows to {}...", out.display()));
+                std::fs::write(&out, serde_json::to_string_pretty(&dump)? + "\n")?;
+                Ok(format!("Backed up {rows} rows to {}", out.display()))
+            })();
+            AppEvent::Finished {
+                result: result.map_err(|e| e.to_string()),
+                reload: false,
+            }
+        });
+    }
+
+    /// Show the import screen, offering the selected campaign as the one to import into.
+    pub fn open_import(&mut self) {
+        let dir = std::mem::take(&mut self.import.dir);
+        self.import = ImportForm::new(dir);
+        if let Some(campaign) = &self.selected_campaign {
+            self.import.campaign = campaign.name.clone();
+            self.import.symbol = campaign.symbol.clone();
+        }
+        self.screen = AppScreen::ImportCsv;
+    }
+
+    /// Start importing the file picked on the import screen, as `profit_tracker import`
+    /// does, and return to the summary where its progress is shown.
+    pub fn start_import(&mut self) {
+        let form = &mut self.import;
+        // An existing campaign brings its symbol along
+        if form.symbol.is_empty()
+            && let Some(campaign) = self.campaigns.iter().find(|c| c.name == form.campaign)
+        {
+            form.symbol = campaign.symbol.clone();
+        }
+        let Some(file) = form.file.clone() else {
+            form.error = Some("Pick a CSV file to import".to_string());
+            return;
+        };
+        if form.campaign.is_empty() || form.symbol.is_empty() {
+            form.error = Some("Enter the campaign and symbol to import into".to_string());
+            return;
+        }
+        if self.db_path.to_string_lossy() == ":memory:" {
+            form.error = Some("Imports from the TUI need a database file".to_string());
+            return;
+        }
+        let broker = Broker::from_name(form.broker_name()).expect("supported broker");
+        let (campaign, symbol) = (form.campaign.clone(), form.symbol.clone());
+        let db_path = self.db_path.clone();
+        self.status = Some(format!("Reading {}...", file.display()));
+        self.screen = AppScreen::Summary;
+        self.spawn(move |progress| {
+            let result = (|| -> Result<String> {
+                let mut parsed = CsvProcessor::new(broker).parse_csv(&file)?;
+                for trade in &mut parsed.trades {
+                    trade.campaign = campaign.clone();
+                    trade.symbol = symbol.clone();
+                }
+                for stock in &mut parsed.stocks {
+                    stock.campaign = campaign.clone();
+                    stock.symbol = symbol.clone();
+                }
+                for event in &mut parsed.cash {
+                    event.campaign = Some(campaign.clone());
+                    event.symbol = Some(symbol.clone());
+                }
+                progress.report(format!(
+                    "Importing {} trades from {}...",
+                    parsed.trades.len() + parsed.stocks.len() + parsed.cash.len(),
+                    file.display()
+                ));
+                // The job opens its own connection, as the backup does
+                let mut storage = storage::open(&db_path)?;
+                let (imported, duplicates) =
+                    storage::import_trades(storage.as_mut(), &campaign, &symbol, &parsed)?;
+                Ok(format!(
+                    "Imported {imported} trades from {} into '{campaign}' ({duplicates} already recorded)",
+                    file.display()
+                ))
+            })();
+            AppEvent::Finished {
+                result: result.map_err(|e| e.to_string()),
+                reload: true,
+            }
+        });
+    }
+
+    /// Start fetching the selected campaign's underlying price and next ex-dividend date,
+    /// when a quote provider is configured.
+    pub fn refresh_quote(&mut self) {
+        let (Some(provider), Some(campaign)) = (&self.quote_provider, &self.selected_campaign)
+        else {
+            return;
+        };
+        let provider = Arc::clone(provider);
+        let symbol = campaign.symbol.clone();
+        self.spawn(move |_| {
+            let price = provider.last_price(&symbol).map_err(|e| e.to_string());
+            // Not every provider plan includes dividends; the price is still useful without
+            let dividend = match price {
+                Ok(_) => provider.next_dividend(&symbol).ok().flatten(),
+                Err(_) => None,
+            };
+            AppEvent::Quote {
+                symbol,
+                price,
+                dividend,
+            }
+        });
+    }
+
+    /// Start fetching the selected campaign's option chain for the strike picker, keeping
+    /// the option type of the action chosen on the Add Trade form. The picker opens once
+    /// the chain arrives.
+    pub fn load_option_chain(&mut self) -> Result<()> {
+        let provider = self
+            .quote_provider
+            .as_ref()
+            .map(Arc::clone)
+            .ok_or("Set quote_provider in the config file to fetch option chains")?;
+        let symbol = self
+            .add_trade_target()
+            .ok_or("No campaign selected")?
+            .symbol
+            .clone();
+        let kind: Option<OptionKind> = Action::ALL[self.action_index].option_kind();
+        self.spawn(move |_| {
+            let chain = provider
+                .option_chain(&symbol)
+                .map_err(|e| e.to_string())
+                .and_then(|mut chain| {
+                    if let Some(kind) = kind {
+                        chain.retain(|quote| quote.kind == kind);
+                    }
+                    if chain.is_empty() {
+                        return Err(format!("No option chain available for {symbol}"));
+                    }
+                    Ok(chain)
+                });
+            AppEvent::Chain(chain)
+        });
+        Ok(())
+    }
+
+    /// Fill the Add Trade form from the highlighted option chain row, pricing it at the
+    /// mid: a credit when selling, a debit when buying.
+    pub fn pick_chain_quote(&mut self) {
+        let Some(quote) = self
+            .chain_list_state
+            .selected()
+            .and_then(|i| self.chain.get(i))
+        else {
+            return;
+        };
+        let credit = match Action::ALL[self.action_index] {
+            Action::BuyPut | Action::BuyCall => -quote.mid(),
+            _ => quote.mid(),
+        };
+        self.form_fields[0] = quote.strike.to_string();
+        self.form_fields[1] = quote.delta.map(|d| format!("{d:.2}")).unwrap_or_default();
+        self.form_fields[2] = quote.expiration_date.to_string();
+        self.form_fields[5] = credit.to_string();
+        self.form_error = None;
+    }
+
+    /// Select the named campaign and switch to its dashboard.
+    ///
+    /// Exact names win over case-insensitive matches. Returns false if no campaign matches.
+    pub fn open_campaign(&mut self, name: &str) -> bool {
+        let index = self
+            .campaigns
+            .iter()
+            .position(|c| c.name == name)
+            .or_else(|| {
+                self.campaigns
+                    .iter()
+                    .position(|c| c.name.eq_ignore_ascii_case(name))
+            });
+        match index {
+            Some(index) => {
+                let campaign = self.campaigns[index].clone();
+                // Opening an archived campaign by name lists the archived ones too
+                if campaign.archived {
+                    self.show_archived = true;
+                }
+                self.campaign_select_index = self
+                    .listed_campaigns()
+                    .iter()
+                    .position(|c| c.name == campaign.name)
+                    .unwrap_or(0);
+                self.campaign_list_state
+                    .select(Some(self.campaign_select_index));
+                self.selected_campaign = Some(campaign);
+                self.refresh_quote();
+                // With repairs pending, the dashboard opens once they are done
+                if !matches!(self.screen, AppScreen::Repair) {
+                    self.screen = AppScreen::CampaignDashboard;
+                }
+                true
+            }
+            None => false,
+        }
     }
     pub fn set_edit_trade(&mut self, trade: &OptionTrade) {
         self.edit_trade_id = trade.id;
@@ -116,8 +1417,13 @@ impl App {
             trade.delta.to_string(),
             trade.expiration_date.to_string(),
             trade.date_of_action.to_string(),
-            trade.number_of_shares.to_string(),
+            trade.contracts().to_string(),
             trade.credit.to_string(),
+            trade
+                .id
+                .and_then(|id| self.trade_tags.get(&id))
+                .map(|tags| tags.join(", "))
+                .unwrap_or_default(),
         ];
         self.edit_action_index = match trade.action {
             Action::BuyPut => 0,
@@ -126,79 +1432,604 @@ impl App {
             Action::SellCall => 3,
             Action::Exercised => 4,
             Action::Assigned => 5,
+            Action::Expired => 6,
         };
+        self.edit_trade_campaign = trade.campaign.clone();
         self.edit_form_index = 0;
     }
 
-    pub fn total_pnl(&self) -> f64 {
-        use crate::logic::calculate_total_premium_sold;
-        calculate_total_premium_sold(&self.trades)
+    /// Names of the campaigns a trade on `symbol` can be filed under: those for the same
+    /// underlying, or every campaign when none is.
+    pub fn campaigns_for_symbol(&self, symbol: &str) -> Vec<String> {
+        let matching: Vec<String> = self
+            .campaigns
+            .iter()
+            .filter(|c| c.symbol.eq_ignore_ascii_case(symbol.trim()))
+            .map(|c| c.name.clone())
+            .collect();
+        if matching.is_empty() {
+            self.campaigns.iter().map(|c| c.name.clone()).collect()
+        } else {
+            matching
+        }
+    }
+
+    /// Step the Edit Trade screen's campaign to the next (or previous) campaign for the
+    /// trade's symbol.
+    pub fn cycle_edit_campaign(&mut self, forward: bool) {
+        let names = self.campaigns_for_symbol(&self.edit_trade_fields[0]);
+        if names.is_empty() {
+            return;
+        }
+        let next = match names.iter().position(|n| *n == self.edit_trade_campaign) {
+            Some(i) if forward => (i + 1) % names.len(),
+            Some(i) => (i + names.len() - 1) % names.len(),
+            None => 0,
+        };
+        self.edit_trade_campaign = names[next].clone();
     }
 
-    pub fn trades_in_progress_this_week(&self) -> Vec<&crate::models::OptionTrade> {
-        let today = OffsetDateTime::now_local().unwrap().date();
-        let start_of_week = today - Duration::days(today.weekday().number_from_monday() as i64 - 1);
-        let end_of_week = start_of_week + Duration::days(6);
-        self.trades
+    /// Open the Add Trade form for the open campaign, or for the first active campaign
+    /// when none is open, as from the summary screen.
+    pub fn open_add_trade(&mut self) {
+        let campaign = self
+            .selected_campaign
+            .as_ref()
+            .or_else(|| self.campaigns.iter().find(|c| !c.archived));
+        match campaign {
+            Some(campaign) => {
+                self.add_trade_campaign = campaign.name.clone();
+                self.screen = AppScreen::AddTrade;
+            }
+            None => self.status = Some("Create a campaign before adding trades".to_string()),
+        }
+    }
+
+    /// The campaign picked on the Add Trade screen.
+    pub fn add_trade_target(&self) -> Option<&Campaign> {
+        self.campaigns
             .iter()
-            .filter(|t| t.expiration_date >= start_of_week && t.expiration_date <= end_of_week)
-            .collect()
+            .find(|c| c.name == self.add_trade_campaign)
     }
 
-    pub fn free_cash(&self) -> f64 {
-        // Net premium received (credits - debits)
-        let credits: f64 = self
-            .trades
+    /// Step the Add Trade screen's campaign to the next (or previous) active campaign.
+    pub fn cycle_add_campaign(&mut self, forward: bool) {
+        let names: Vec<&str> = self
+            .campaigns
             .iter()
-            .filter(|t| {
-                matches!(
-                    t.action,
-                    crate::models::Action::SellPut | crate::models::Action::SellCall
-                )
-            })
-            .map(|t| t.credit * t.number_of_shares as f64)
-            .sum();
-        let debits: f64 = self
-            .trades
+            .filter(|c| !c.archived || c.name == self.add_trade_campaign)
+            .map(|c| c.name.as_str())
+            .collect();
+        if names.is_empty() {
+            return;
+        }
+        let next = match names.iter().position(|n| *n == self.add_trade_campaign) {
+            Some(i) if forward => (i + 1) % names.len(),
+            Some(i) => (i + names.len() - 1) % names.len(),
+            None => 0,
+        };
+        self.add_trade_campaign = names[next].to_string();
+    }
+
+    /// Fill the Add Trade form from `template`: same campaign, action, strike, contracts
+    /// and tags, expiring the Friday after it (or this Friday, if it already expired),
+    /// dated today. The credit is left for the user, with the cursor on it.
+    pub fn fill_form_from(&mut self, template: &OptionTrade) {
+        let today = today();
+        let after = (template.expiration_date + time::Duration::days(1)).max(today);
+        self.reset_form();
+        self.add_trade_campaign = template.campaign.clone();
+        self.action_index = Action::ALL
             .iter()
-            .filter(|t| {
-                matches!(
-                    t.action,
-                    crate::models::Action::BuyPut
-                        | crate::models::Action::BuyCall
-                        | crate::models::Action::Assigned
-                )
-            })
-            .map(|t| t.credit * t.number_of_shares as f64)
-            .sum();
-        credits - debits
+            .position(|a| *a == template.action)
+            .unwrap_or(0);
+        self.form_fields[0] = template.strike.to_string();
+        self.form_fields[2] = this_friday(after).to_string();
+        self.form_fields[4] = template.contracts().to_string();
+        self.form_fields[6] = template
+            .id
+            .and_then(|id| self.trade_tags.get(&id))
+            .map(|tags| tags.join(", "))
+            .unwrap_or_default();
+        self.form_index = 6;
     }
 
-    pub fn roic(&self) -> Option<f64> {
-        // Return on Invested Capital = total P&L / total capital at risk
-        // capital at risk as sum of (strike * shares) for open short puts/calls
-        let capital_at_risk: f64 = self
+    /// Fill the Add Trade form from the latest opening trade of its campaign.
+    pub fn fill_form_from_last(&mut self) {
+        let trades: Vec<OptionTrade> = self
             .trades
             .iter()
-            .filter(|t| {
-                matches!(
-                    t.action,
-                    crate::models::Action::SellPut | crate::models::Action::SellCall
-                )
+            .filter(|t| t.campaign == self.add_trade_campaign)
+            .cloned()
+            .collect();
+        // Opening trades are the ones lots were opened by, whether or not still open
+        let matches = match_lots(&trades, today());
+        let openers: std::collections::HashSet<i32> = matches
+            .open
+            .iter()
+            .filter_map(|lot| lot.trade.id)
+            .chain(matches.closed.iter().filter_map(|lot| lot.open_trade_id))
+            .collect();
+        let last = trades
+            .into_iter()
+            .filter(|t| t.id.is_some_and(|id| openers.contains(&id)))
+            .max_by_key(|t| (t.date_of_action, t.id));
+        match last {
+            Some(trade) => self.fill_form_from(&trade),
+            None => {
+                self.form_error = Some(format!(
+                    "No earlier trade in {} to copy",
+                    self.add_trade_campaign
+                ))
+            }
+        }
+    }
+
+    /// Open the campaign picker for the trade highlighted on View Trades.
+    pub fn open_move_trade(&mut self) {
+        let Some(trade) = self.selected_trade().cloned() else {
+            return;
+        };
+        self.move_targets = self
+            .campaigns_for_symbol(&trade.symbol)
+            .into_iter()
+            .filter(|name| *name != trade.campaign)
+            .collect();
+        if self.move_targets.is_empty() {
+            self.status = Some(format!("No other campaign to move {} to", trade.symbol));
+            return;
+        }
+        self.move_list_state.select(Some(0));
+        self.move_trade = Some(trade);
+        self.screen = AppScreen::MoveTrade;
+    }
+
+    /// Move the trade being moved into the campaign highlighted on the picker and go back
+    /// to View Trades.
+    pub fn pick_move_target(&mut self) {
+        let target = self
+            .move_list_state
+            .selected()
+            .and_then(|i| self.move_targets.get(i))
+            .cloned();
+        if let (Some(mut trade), Some(target)) = (self.move_trade.take(), target) {
+            trade.campaign = target;
+            let id = trade.id.unwrap_or_default();
+            match self.save_trade(&trade, None) {
+                Ok(_) => self.status = Some(format!("Moved trade {id} to {}", trade.campaign)),
+                Err(e) => self.show_error(&format!("Could not move trade {id}"), e),
+            }
+            self.select_trade(None);
+        }
+        self.screen = AppScreen::ViewTrades;
+    }
+
+    /// Sort View Trades by `column`, reversing the direction when it is already sorted by
+    /// it. The highlighted trade stays highlighted.
+    pub fn sort_trades_by(&mut self, column: TradeColumn) {
+        self.trade_sort = TradeSort {
+            column,
+            descending: self.trade_sort.column == column && !self.trade_sort.descending,
+        };
+        let selected = self.selected_trade().and_then(|t| t.id);
+        for stats in self.campaign_stats.values_mut() {
+            self.trade_sort.apply(&mut stats.trades);
+        }
+        self.select_trade(selected);
+    }
+
+    /// The selected campaign's trades that pass `trade_filter`, as View Trades lists them.
+    pub fn visible_trades(&self) -> Vec<&OptionTrade> {
+        let today = today();
+        self.selected_stats()
+            .map(|stats| {
+                stats
+                    .trades
+                    .iter()
+                    .filter(|t| self.trade_filter.matches(t, self.trade_status(t), today))
+                    .collect()
             })
-            .map(|t| t.strike * t.number_of_shares as f64)
-            .sum();
-        if capital_at_risk > 0.0 {
-            Some(self.total_pnl() / capital_at_risk)
-        } else {
-            None
+            .unwrap_or_default()
+    }
+
+    /// The trade highlighted on View Trades.
+    pub fn selected_trade(&self) -> Option<&OptionTrade> {
+        let row = self.trade_table_state.selected()?;
+        self.visible_trades().get(row).copied()
+    }
+
+    /// Highlight the trade with `id` on View Trades if it is listed, and otherwise keep
+    /// the highlight within the list.
+    fn select_trade(&mut self, id: Option<i32>) {
+        let visible = self.visible_trades();
+        let row = match visible.iter().position(|t| t.id == id) {
+            Some(row) => row,
+            None => self.trade_table_state.selected().unwrap_or(0),
+        };
+        self.select_trade_row(row as isize);
+    }
+
+    /// Highlight row `row` of View Trades, clamped to the rows listed.
+    pub fn select_trade_row(&mut self, row: isize) {
+        let last = self.visible_trades().len() as isize - 1;
+        self.trade_table_state
+            .select((last >= 0).then(|| row.clamp(0, last) as usize));
+    }
+
+    /// Move the View Trades highlight down by `rows`, or up when negative.
+    pub fn scroll_trades(&mut self, rows: isize) {
+        let row = self.trade_table_state.selected().unwrap_or(0) as isize;
+        self.select_trade_row(row.saturating_add(rows));
+    }
+
+    /// Change which trades View Trades lists, keeping the highlighted trade if it passes.
+    pub fn filter_trades(&mut self, change: impl FnOnce(&mut TradeFilter)) {
+        let selected = self.selected_trade().and_then(|t| t.id);
+        change(&mut self.trade_filter);
+        self.select_trade(selected);
+    }
+
+    /// The open position of the trade highlighted on View Trades, if it is still open.
+    pub fn selected_open_lot(&self) -> Option<&OpenLot> {
+        let id = self.selected_trade()?.id?;
+        self.selected_stats()?
+            .open
+            .iter()
+            .find(|lot| lot.trade.id == Some(id))
+    }
+
+    /// Start typing a mark for the trade highlighted on View Trades.
+    pub fn open_mark_input(&mut self) {
+        match self.selected_open_lot().and_then(|lot| lot.trade.id) {
+            Some(id) => {
+                let typed = self
+                    .marks
+                    .get(&id)
+                    .map(|m| m.price.to_string())
+                    .unwrap_or_default();
+                self.mark_input = Some((id, typed));
+            }
+            None => self.status = Some("Only open positions can be marked".to_string()),
         }
     }
 
+    /// Save the mark typed on View Trades as today's price of its trade.
+    pub fn save_mark(&mut self) {
+        let Some((trade_id, typed)) = self.mark_input.take() else {
+            return;
+        };
+        let price = match typed.trim().parse::<f64>() {
+            Ok(price) if price >= 0.0 => price,
+            _ => {
+                self.status = Some(format!("Invalid mark '{typed}'"));
+                return;
+            }
+        };
+        let mark = Mark {
+            trade_id,
+            date: today(),
+            price,
+        };
+        if let Err(e) = self.storage.save_mark(&mark) {
+            self.show_error(&format!("Could not mark trade {trade_id}"), e);
+            return;
+        }
+        let unrealized = self
+            .selected_open_lot()
+            .map(|lot| lot.unrealized(price))
+            .unwrap_or_default();
+        self.status = Some(format!(
+            "Marked trade {trade_id} at ${price:.2}, unrealized P/L {}",
+            format_cents(unrealized)
+        ));
+        self.marks.insert(trade_id, mark);
+    }
+
+    /// Open trades expiring this week.
+    pub fn trades_in_progress_this_week(&self) -> Vec<&crate::models::OptionTrade> {
+        let today = today();
+        let (start_of_week, end_of_week) =
+            crate::logic::week_bounds(today, config::get().week_start);
+        self.trades
+            .iter()
+            .filter(|t| self.trade_status(t) == TradeStatus::Open)
+            .filter(|t| t.expiration_date >= start_of_week && t.expiration_date <= end_of_week)
+            .collect()
+    }
+
+    /// The trade's status, as set by hand or worked out from the other trades.
+    pub fn trade_status(&self, trade: &OptionTrade) -> TradeStatus {
+        trade
+            .id
+            .and_then(|id| self.statuses.get(&id).copied())
+            .or(trade.status)
+            .unwrap_or(TradeStatus::Open)
+    }
+
+    /// Step the status of the trade highlighted on View Trades to the next one, going
+    /// back to the worked-out status after the last.
+    pub fn cycle_trade_status(&mut self) {
+        let Some(mut trade) = self.selected_trade().cloned() else {
+            return;
+        };
+        trade.status = match trade.status {
+            None => Some(TradeStatus::ALL[0]),
+            Some(status) => {
+                let next = TradeStatus::ALL
+                    .iter()
+                    .position(|s| *s == status)
+                    .unwrap_or(0)
+                    + 1;
+                TradeStatus::ALL.get(next).copied()
+            }
+        };
+        let id = trade.id.unwrap_or_default();
+        if let Err(e) = self.save_trade(&trade, None) {
+            self.show_error(&format!("Could not update trade {id}"), e);
+            return;
+        }
+        // The new status may take it out of the filtered list
+        self.select_trade(trade.id);
+        self.status = Some(format!(
+            "Trade {id} is {}",
+            match trade.status {
+                Some(status) => status.as_str().to_string(),
+                None => format!("{} (automatic)", self.trade_status(&trade).as_str()),
+            }
+        ));
+    }
+
     #[allow(dead_code)]
     pub fn recent_trades(&self, n: usize) -> Vec<&crate::models::OptionTrade> {
         let mut trades: Vec<&crate::models::OptionTrade> = self.trades.iter().collect();
-        trades.sort_by(|a, b| b.date_of_action.cmp(&a.date_of_action));
+        trades.sort_by_key(|t| std::cmp::Reverse(t.date_of_action));
         trades.into_iter().take(n).collect()
     }
 }
+
+/// `trades-2025-07-03.json` beside `trades.db`: one backup per day, overwritten by later
+/// backups the same day.
+fn json_backup_path(db_path: &Path, date: time::Date) -> PathBuf {
+    let stem = db_path
+        .file_stem()
+        .map_or_else(|| "trades".into(), |s| s.to_string_lossy());
+    db_path.with_file_name(format!("{stem}-{date}.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn trade(id: i32, action: Action, strike: f64, credit: f64) -> OptionTrade {
+        OptionTrade {
+            id: Some(id),
+            symbol: "NVTS".to_string(),
+            campaign: "Wheel".to_string(),
+            action,
+            strike,
+            expiration_date: date!(2025 - 07 - 03),
+            date_of_action: date!(2025 - 06 - 26),
+            number_of_shares: 100,
+            credit,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_trade_filter_matches() {
+        let today = date!(2025 - 07 - 01);
+        let sold = trade(1, Action::SellPut, 6.5, 0.18);
+        let filter = |change: fn(&mut TradeFilter)| {
+            let mut filter = TradeFilter::default();
+            change(&mut filter);
+            filter
+        };
+
+        assert!(TradeFilter::default().matches(&sold, TradeStatus::Open, today));
+        // Search is case-blind and covers the status as well as the trade's fields
+        let search = filter(|f| f.search = " wheel ".to_string());
+        assert!(search.matches(&sold, TradeStatus::Open, today));
+        let search = filter(|f| f.search = "OPEN".to_string());
+        assert!(search.matches(&sold, TradeStatus::Open, today));
+        assert!(!search.matches(&sold, TradeStatus::Closed, today));
+        let search = filter(|f| f.search = "2025-07-03".to_string());
+        assert!(search.matches(&sold, TradeStatus::Closed, today));
+
+        let sells = filter(|f| f.action = Some(Action::SellPut));
+        assert!(sells.matches(&sold, TradeStatus::Open, today));
+        let bought = trade(2, Action::BuyPut, 6.5, 0.05);
+        assert!(!sells.matches(&bought, TradeStatus::Closed, today));
+
+        let closed = filter(|f| f.open = Some(false));
+        assert!(!closed.matches(&sold, TradeStatus::Open, today));
+        assert!(closed.matches(&sold, TradeStatus::Expired, today));
+
+        let week = filter(|f| f.days = Some(7));
+        assert!(week.matches(&sold, TradeStatus::Open, today));
+        assert!(!week.matches(&sold, TradeStatus::Open, date!(2025 - 07 - 04)));
+    }
+
+    #[test]
+    fn test_trade_sort_breaks_ties_by_expiration_then_id() {
+        let mut trades = vec![
+            trade(1, Action::SellPut, 6.5, 0.18),
+            OptionTrade {
+                expiration_date: date!(2025 - 06 - 27),
+                ..trade(2, Action::SellPut, 6.0, 0.18)
+            },
+            trade(3, Action::SellPut, 6.5, 0.30),
+            trade(4, Action::BuyPut, 6.5, 0.18),
+        ];
+        let ids = |trades: &[OptionTrade]| trades.iter().map(|t| t.id.unwrap()).collect::<Vec<_>>();
+
+        TradeSort {
+            column: TradeColumn::Credit,
+            descending: true,
+        }
+        .apply(&mut trades);
+        assert_eq!(ids(&trades), [3, 2, 1, 4]);
+
+        TradeSort {
+            column: TradeColumn::Strike,
+            descending: false,
+        }
+        .apply(&mut trades);
+        assert_eq!(ids(&trades), [2, 1, 3, 4]);
+
+        TradeSort::default().apply(&mut trades);
+        assert_eq!(ids(&trades), [2, 1, 3, 4]);
+    }
+
+    #[test]
+    fn test_trade_column_from_digit() {
+        assert_eq!(TradeColumn::from_digit('1'), Some(TradeColumn::Symbol));
+        assert_eq!(TradeColumn::from_digit('9'), Some(TradeColumn::Credit));
+        assert_eq!(TradeColumn::from_digit('0'), Some(TradeColumn::TotalCredit));
+        assert_eq!(TradeColumn::from_digit('a'), None);
+    }
+
+    #[test]
+    fn test_import_form_lists_directories_then_csv_files() {
+        let dir =
+            std::env::temp_dir().join(format!("profit_tracker_import_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("2025")).unwrap();
+        std::fs::create_dir_all(dir.join(".cache")).unwrap();
+        for file in ["b.csv", "A.CSV", "notes.txt", ".hidden.csv"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+
+        let mut form = ImportForm::new(dir.clone());
+        assert_eq!(form.error, None);
+        assert_eq!(
+            form.entries,
+            [
+                dir.join(".."),
+                dir.join("2025"),
+                dir.join("A.CSV"),
+                dir.join("b.csv")
+            ]
+        );
+        assert_eq!(form.list_state.selected(), Some(0));
+
+        // A directory that can't be read is reported, leaving only the way back up
+        form.open_dir(dir.join("missing"));
+        assert!(form.error.is_some());
+        assert_eq!(form.entries, [dir.join("missing").join("..")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// An app on an empty in-memory database with one sold put, in the campaign open on
+    /// View Trades.
+    fn app_with_trade() -> (App, OptionTrade) {
+        let mut app = App::new(Path::new(":memory:")).unwrap();
+        app.storage.insert_campaign("wheel", "NVTS", None).unwrap();
+        app.reload_campaigns();
+        app.selected_campaign = app.campaigns.first().cloned();
+        let trade = OptionTrade {
+            symbol: "NVTS".to_string(),
+            campaign: "wheel".to_string(),
+            strike: 6.5,
+            delta: 0.2,
+            expiration_date: date!(2025 - 07 - 03),
+            date_of_action: date!(2025 - 06 - 26),
+            number_of_shares: 100,
+            credit: 0.18,
+            ..Default::default()
+        };
+        let id = app
+            .save_trade(&trade, Some(&["earnings".to_string()]))
+            .unwrap();
+        let stored = app
+            .trades
+            .iter()
+            .find(|t| t.id == Some(id))
+            .unwrap()
+            .clone();
+        (app, stored)
+    }
+
+    #[test]
+    fn test_undo_delete_restores_the_same_trade() {
+        let (mut app, trade) = app_with_trade();
+        assert!(trade.uuid.is_some());
+        app.select_trade(trade.id);
+        app.delete_selected_trade();
+        assert!(app.trades.is_empty());
+
+        app.undo();
+        assert_eq!(app.error, None);
+        assert_eq!(app.trades, std::slice::from_ref(&trade));
+        assert_eq!(app.trade_tags[&trade.id.unwrap()], ["earnings"]);
+
+        app.redo();
+        assert!(app.trades.is_empty());
+        app.undo();
+        assert_eq!(app.storage.trades().unwrap(), [trade]);
+        // Undoing the deletion twice leaves the original addition to undo
+        app.undo();
+        assert!(app.storage.trades().unwrap().is_empty());
+        app.undo();
+        assert_eq!(app.status.as_deref(), Some("Nothing to undo"));
+    }
+
+    #[test]
+    fn test_undo_refuses_a_trade_changed_since() {
+        let (mut app, trade) = app_with_trade();
+        app.save_trade(
+            &OptionTrade {
+                delta: 0.25,
+                ..trade.clone()
+            },
+            None,
+        )
+        .unwrap();
+        // Edited somewhere else, as by an import or another TUI
+        let elsewhere = OptionTrade {
+            delta: 0.3,
+            ..trade.clone()
+        };
+        app.storage.update_trade(&elsewhere).unwrap();
+        app.reload_trades();
+
+        app.undo();
+        let error = app.error.take().unwrap();
+        assert!(error.contains("has changed since"), "{error}");
+        assert_eq!(app.trades, [elsewhere]);
+        assert!(app.redo_stack.is_empty());
+    }
+
+    #[test]
+    fn test_delete_refuses_a_linked_trade() {
+        let (mut app, trade) = app_with_trade();
+        let closing_id = app
+            .save_trade(
+                &OptionTrade {
+                    id: None,
+                    action: Action::BuyPut,
+                    date_of_action: date!(2025 - 07 - 01),
+                    credit: -0.05,
+                    closes_trade_id: trade.id,
+                    uuid: None,
+                    ..trade.clone()
+                },
+                None,
+            )
+            .unwrap();
+
+        app.select_trade(trade.id);
+        app.delete_selected_trade();
+        let error = app.error.take().unwrap();
+        assert!(
+            error.contains(&format!("Trade {closing_id} closes")),
+            "{error}"
+        );
+        assert_eq!(app.trades.len(), 2);
+
+        // Undoing the sold put's addition is refused the same way
+        app.undo_stack.pop();
+        app.undo();
+        assert!(app.error.take().unwrap().contains("closes, rolls from"));
+        assert_eq!(app.storage.trades().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::config;
+use crate::error::Result;
+use crate::models::{Action, OptionTrade};
+use crate::output::OutputFormat;
+use crate::storage::Storage;
+use time::{Date, OffsetDateTime};
+
+use super::query::trades_table;
+
+/// An option trade typed on the command line, sized in contracts.
+pub struct NewTrade {
+    pub campaign: String,
+    pub action: Action,
+    pub strike: f64,
+    pub expiration_date: Date,
+    pub contracts: i32,
+    pub credit: f64,
+    pub delta: f64,
+    pub date: Option<Date>,
+}
+
+/// Record `new` in its campaign, with the symbol and contract multiplier of that
+/// campaign, and return the stored trade.
+pub fn add_trade(storage: &mut dyn Storage, new: &NewTrade) -> Result<OptionTrade> {
+    let campaign = storage
+        .campaigns()?
+        .into_iter()
+        .find(|c| c.name == new.campaign)
+        .ok_or_else(|| format!("No campaign named '{}'", new.campaign))?;
+    if new.contracts <= 0 {
+        return Err(format!("Invalid contracts {}, expected 1 or more", new.contracts).into());
+    }
+    if new.strike < 0.0 || new.credit < 0.0 {
+        return Err("Strike and credit must be zero or more".into());
+    }
+    let multiplier = config::get().multiplier(&campaign.symbol);
+
+    let mut trade = OptionTrade {
+        id: None,
+        symbol: campaign.symbol,
+        campaign: campaign.name,
+        action: new.action.clone(),
+        strike: new.strike,
+        delta: new.delta,
+        expiration_date: new.expiration_date,
+        date_of_action: match new.date {
+            Some(d) => d,
+            None => OffsetDateTime::now_local()?.date(),
+        },
+        number_of_shares: new.contracts * multiplier,
+        credit: new.credit,
+        closes_trade_id: None,
+        rolled_from_id: None,
+        commission: 0.0,
+        fees: 0.0,
+        multiplier,
+        status: None,
+        collateral: None,
+        covered_by_id: None,
+        implied_volatility: None,
+        uuid: None,
+    };
+    trade.id = Some(storage.insert_trade(&trade)?);
+    Ok(trade)
+}
+
+pub fn run(storage: &mut dyn Storage, new: &NewTrade, format: OutputFormat) -> Result<()> {
+    let trade = add_trade(storage, new)?;
+
+    if format == OutputFormat::Table {
+        println!(
+            "Added trade {}: {:?} {} {} exp {} x{} @ ${:.2}",
+            trade.id.unwrap_or_default(),
+            trade.action,
+            trade.symbol,
+            trade.strike,
+            trade.expiration_date,
+            new.contracts,
+            trade.credit
+        );
+    } else {
+        trades_table([&trade]).print(format)?;
+    }
+    Ok(())
+}
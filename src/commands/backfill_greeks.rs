@@ -0,0 +1,347 @@
+use crate::config;
+use crate::error::Result;
+use crate::filter::Filter;
+use crate::logic::{black_scholes_delta, historical_volatility, match_lots};
+use crate::models::OptionTrade;
+use crate::output::{OutputFormat, Table};
+use crate::quotes::{self, ChainQuote, QuoteProvider};
+use rusqlite::Connection;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use time::{Date, Duration, OffsetDateTime};
+
+/// Trading days of closes before a trade used to estimate volatility.
+const VOLATILITY_WINDOW: usize = 30;
+
+/// Calendar days of history to fetch before the earliest trade, enough to cover the window.
+const HISTORY_DAYS: i64 = 60;
+
+/// A trade with its greeks filled in, and what they were worked out from.
+struct Backfilled {
+    trade: OptionTrade,
+    /// `chain` for quotes from the provider's option chain, `model` for Black-Scholes.
+    source: &'static str,
+    /// The underlying's close on the trade date, for model deltas.
+    spot: Option<f64>,
+    /// The chain's implied volatility, or the volatility a model delta was priced with.
+    volatility: Option<f64>,
+}
+
+/// Fill in the delta and implied volatility of option trades recorded without them
+/// (imports leave delta at 0).
+///
+/// Open positions are looked up in the provider's option chain. Trades the chain has
+/// nothing for, and closed ones, get a Black-Scholes delta from the underlying's close on
+/// the trade date and, as no historical implied volatility is freely available, either
+/// `volatility` or the underlying's historical volatility over the preceding month. That
+/// stand-in isn't the market's implied volatility, so it is shown but not stored.
+pub fn run(
+    db_conn: &Connection,
+    expression: &str,
+    volatility: Option<f64>,
+    dry_run: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let filter: Filter = expression.parse()?;
+    let provider = quotes::from_config(config::get())?
+        .ok_or("Set quote_provider in the config file to look up option chains and prices")?;
+    let today = OffsetDateTime::now_local()?.date();
+    let (updated, skipped) = backfill(db_conn, provider.as_ref(), &filter, volatility, today)?;
+
+    if updated.is_empty() && format == OutputFormat::Table {
+        println!(
+            "No trades without a delta or implied volatility could be backfilled \
+            ({skipped} without price history)"
+        );
+        return Ok(());
+    }
+    let mut table = Table::new(&[
+        "id",
+        "symbol",
+        "action",
+        "strike",
+        "expiration",
+        "date",
+        "source",
+        "spot",
+        "volatility",
+        "delta",
+    ]);
+    for Backfilled {
+        trade,
+        source,
+        spot,
+        volatility,
+    } in &updated
+    {
+        table.push_row(vec![
+            json!(trade.id),
+            json!(trade.symbol),
+            json!(format!("{:?}", trade.action)),
+            json!(trade.strike),
+            json!(trade.expiration_date.to_string()),
+            json!(trade.date_of_action.to_string()),
+            json!(source),
+            json!(spot),
+            json!(volatility.map(|v| (v * 100.0).round() / 100.0)),
+            json!(trade.delta),
+        ]);
+    }
+    table.print(format)?;
+    if !dry_run {
+        let tx = db_conn.unchecked_transaction()?;
+        for backfilled in &updated {
+            backfilled.trade.update(&tx)?;
+        }
+        tx.commit()?;
+    }
+    if format == OutputFormat::Table {
+        let verb = if dry_run { "Would update" } else { "Updated" };
+        println!(
+            "{verb} {} trade(s); {skipped} skipped without price history",
+            updated.len()
+        );
+    }
+    Ok(())
+}
+
+/// Work out the missing greeks of the option trades matching `filter`, returning the
+/// trades filled in and the number skipped for lack of price history. A delta already
+/// recorded is kept, and only the implied volatility is added from the chain.
+fn backfill(
+    db_conn: &Connection,
+    provider: &dyn QuoteProvider,
+    filter: &Filter,
+    volatility: Option<f64>,
+    today: Date,
+) -> Result<(Vec<Backfilled>, usize)> {
+    let trades = OptionTrade::get_all(db_conn)?;
+    let open: HashSet<i32> = match_lots(&trades, today)
+        .open
+        .iter()
+        .filter_map(|lot| lot.trade.id)
+        .collect();
+
+    let mut chains: HashMap<String, Vec<ChainQuote>> = HashMap::new();
+    let mut updated = Vec::new();
+    let mut by_symbol: BTreeMap<String, Vec<OptionTrade>> = BTreeMap::new();
+    for mut trade in trades {
+        let Some(kind) = trade.action.option_kind() else {
+            continue;
+        };
+        let missing = trade.delta == 0.0 || trade.implied_volatility.is_none();
+        if !missing || !filter.matches(&trade) {
+            continue;
+        }
+        if trade.id.is_some_and(|id| open.contains(&id)) {
+            if !chains.contains_key(&trade.symbol) {
+                chains.insert(trade.symbol.clone(), provider.option_chain(&trade.symbol)?);
+            }
+            let quote = chains[&trade.symbol].iter().find(|q| {
+                q.kind == kind
+                    && q.expiration_date == trade.expiration_date
+                    && (q.strike - trade.strike).abs() < 0.005
+            });
+            if let Some(&ChainQuote {
+                delta: Some(delta),
+                implied_volatility,
+                ..
+            }) = quote
+            {
+                if trade.delta == 0.0 {
+                    trade.delta = (delta * 100.0).round() / 100.0;
+                }
+                trade.implied_volatility = implied_volatility.or(trade.implied_volatility);
+                updated.push(Backfilled {
+                    trade,
+                    source: "chain",
+                    spot: None,
+                    volatility: implied_volatility,
+                });
+                continue;
+            }
+        }
+        // Without a chain quote only the delta can be worked out
+        if trade.delta == 0.0 {
+            by_symbol
+                .entry(trade.symbol.clone())
+                .or_default()
+                .push(trade);
+        }
+    }
+
+    let mut skipped = 0;
+    for (symbol, mut trades) in by_symbol {
+        trades.sort_by_key(|t| (t.date_of_action, t.id));
+        let first = trades[0].date_of_action - Duration::days(HISTORY_DAYS);
+        let last = trades[trades.len() - 1].date_of_action;
+        let closes = provider.daily_closes(&symbol, first, last)?;
+
+        for mut trade in trades {
+            let Some(kind) = trade.action.option_kind() else {
+                continue;
+            };
+            let before: Vec<f64> = closes
+                .iter()
+                .filter(|(date, _)| *date <= trade.date_of_action)
+                .map(|(_, close)| *close)
+                .collect();
+            let window = &before[before.len().saturating_sub(VOLATILITY_WINDOW + 1)..];
+            let (Some(&spot), Some(vol)) = (
+                before.last(),
+                volatility.or_else(|| historical_volatility(window)),
+            ) else {
+                skipped += 1;
+                continue;
+            };
+            let days = (trade.expiration_date - trade.date_of_action).whole_days();
+            let years = days.max(1) as f64 / 365.0;
+            let delta = black_scholes_delta(kind, spot, trade.strike, years, vol);
+            trade.delta = (delta * 100.0).round() / 100.0;
+            updated.push(Backfilled {
+                trade,
+                source: "model",
+                spot: Some(spot),
+                volatility: Some(vol),
+            });
+        }
+    }
+    Ok((updated, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_database;
+    use crate::models::{Action, Campaign, Dividend, OptionKind};
+    use profit_tracker::error::Result;
+    use time::macros::date;
+
+    /// Quotes from memory: a fixed option chain and daily closes.
+    struct StubProvider {
+        chain: Vec<ChainQuote>,
+        closes: Vec<(Date, f64)>,
+    }
+
+    impl QuoteProvider for StubProvider {
+        fn last_price(&self, _symbol: &str) -> Result<f64> {
+            Err("no quotes".into())
+        }
+
+        fn option_chain(&self, _symbol: &str) -> Result<Vec<ChainQuote>> {
+            Ok(self.chain.clone())
+        }
+
+        fn next_dividend(&self, _symbol: &str) -> Result<Option<Dividend>> {
+            Ok(None)
+        }
+
+        fn daily_closes(&self, _symbol: &str, from: Date, to: Date) -> Result<Vec<(Date, f64)>> {
+            Ok(self
+                .closes
+                .iter()
+                .filter(|(date, _)| (from..=to).contains(date))
+                .copied()
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_backfill_prefers_the_chain_for_open_positions() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_database(&conn, &Default::default()).unwrap();
+        Campaign::insert(&conn, "wheel", "NVTS", None).unwrap();
+        let sold = OptionTrade {
+            symbol: "NVTS".to_string(),
+            campaign: "wheel".to_string(),
+            strike: 6.5,
+            expiration_date: date!(2025 - 07 - 18),
+            date_of_action: date!(2025 - 06 - 26),
+            number_of_shares: 100,
+            credit: 0.30,
+            ..Default::default()
+        };
+        let trades = [
+            // Open, and quoted in the chain
+            sold.clone(),
+            // Open, but missing from the chain
+            OptionTrade {
+                strike: 6.0,
+                ..sold.clone()
+            },
+            // Closed before today
+            OptionTrade {
+                strike: 5.5,
+                ..sold.clone()
+            },
+            OptionTrade {
+                action: Action::BuyPut,
+                strike: 5.5,
+                date_of_action: date!(2025 - 07 - 01),
+                credit: -0.05,
+                ..sold.clone()
+            },
+        ];
+        for trade in &trades {
+            trade.insert(&conn).unwrap();
+        }
+        let provider = StubProvider {
+            chain: vec![ChainQuote {
+                kind: OptionKind::Put,
+                expiration_date: date!(2025 - 07 - 18),
+                strike: 6.5,
+                bid: 0.20,
+                ask: 0.24,
+                delta: Some(-0.314),
+                implied_volatility: Some(0.82),
+            }],
+            closes: (0..60)
+                .map(|day| {
+                    let price = if day % 2 == 0 { 6.6 } else { 6.8 };
+                    (date!(2025 - 05 - 01) + Duration::days(day), price)
+                })
+                .collect(),
+        };
+
+        let (updated, skipped) = backfill(
+            &conn,
+            &provider,
+            &Filter::default(),
+            None,
+            date!(2025 - 07 - 02),
+        )
+        .unwrap();
+        assert_eq!(skipped, 0);
+        let sources: Vec<(f64, &str)> =
+            updated.iter().map(|b| (b.trade.strike, b.source)).collect();
+        assert_eq!(
+            sources,
+            [
+                (6.5, "chain"),
+                (6.0, "model"),
+                (5.5, "model"),
+                (5.5, "model")
+            ]
+        );
+        for backfilled in &updated {
+            backfilled.trade.update(&conn).unwrap();
+        }
+
+        let mut stored = OptionTrade::get_all(&conn).unwrap();
+        stored.sort_by_key(|t| t.id);
+        assert_eq!(
+            (stored[0].delta, stored[0].implied_volatility),
+            (-0.31, Some(0.82))
+        );
+        // The historical volatility model deltas are priced with isn't stored as implied
+        assert!(
+            updated[1..]
+                .iter()
+                .all(|b| b.volatility.is_some_and(|v| v > 0.0))
+        );
+        for trade in &stored[1..] {
+            assert!(trade.delta < 0.0, "{trade:?}");
+            assert_eq!(trade.implied_volatility, None);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::error::Result;
+use crate::models::{Campaign, PremiumGoal};
+use crate::output::{OutputFormat, Table};
+use rusqlite::Connection;
+use serde_json::json;
+
+/// Move everything recorded in the `from` campaigns into `into` and delete the emptied
+/// campaigns. `into` is created when it doesn't exist yet; all the campaigns must be for
+/// the same symbol. Trades recorded in more than one of them are kept once.
+pub fn merge(
+    db_conn: &Connection,
+    from: &[String],
+    into: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let campaigns = Campaign::get_all(db_conn)?;
+    let find = |name: &str| campaigns.iter().find(|c| c.name == name);
+    let mut sources = Vec::new();
+    for name in from {
+        if name == into {
+            return Err(format!("Can't merge campaign '{name}' into itself").into());
+        }
+        let campaign = find(name).ok_or_else(|| format!("No campaign named '{name}'"))?;
+        if !sources.iter().any(|c: &&Campaign| c.name == *name) {
+            sources.push(campaign);
+        }
+    }
+    let target = find(into);
+    let symbol = target.unwrap_or(sources[0]).symbol.as_str();
+    if let Some(other) = sources.iter().find(|c| c.symbol != symbol) {
+        return Err(format!(
+            "Campaign '{}' is for {}, not {symbol}; only campaigns for the same symbol can be merged",
+            other.name, other.symbol
+        )
+        .into());
+    }
+
+    let tx = db_conn.unchecked_transaction()?;
+    if target.is_none() {
+        Campaign::insert(&tx, into, symbol, None)?;
+    }
+    let mut table = Table::new(&["campaign", "into", "trades", "duplicates"]);
+    let mut total = 0;
+    for campaign in &sources {
+        let (moved, duplicates) = Campaign::move_records(&tx, &campaign.name, into)?;
+        Campaign::delete(&tx, &campaign.name)?;
+        total += moved;
+        table.push_row(vec![
+            json!(campaign.name),
+            json!(into),
+            json!(moved),
+            json!(duplicates),
+        ]);
+    }
+    tx.commit()?;
+
+    table.print(format)?;
+    if format == OutputFormat::Table {
+        println!(
+            "Merged {} campaign(s) and {total} trade(s) into {into}",
+            sources.len()
+        );
+    }
+    Ok(())
+}
+
+/// Set the premium goal of campaign `name` to `goal`, e.g. `$500/month`, or clear it when
+/// `goal` is `None`.
+pub fn set_goal(db_conn: &Connection, name: &str, goal: Option<&str>) -> Result<()> {
+    let goal: Option<PremiumGoal> = goal.map(str::parse).transpose()?;
+    if Campaign::set_goal(db_conn, name, goal.as_ref())? == 0 {
+        return Err(format!("No campaign named '{name}'").into());
+    }
+    match goal {
+        Some(goal) => println!("Set the premium goal of {name} to {goal}"),
+        None => println!("Cleared the premium goal of {name}"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_database;
+    use crate::models::OptionTrade;
+
+    #[test]
+    fn test_merge_moves_trades_and_drops_duplicates() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_database(&conn, &Default::default()).unwrap();
+        conn.execute_batch(
+            "INSERT INTO campaigns (name, symbol, created_at) VALUES
+                ('NVTS_2025-07-03', 'NVTS', '2025-06-01'),
+                ('NVTS_2025-07-11', 'NVTS', '2025-06-01'),
+                ('HOOD', 'HOOD', '2025-06-01');
+            INSERT INTO option_trades (symbol, campaign_id, action, strike_cents, delta,
+                expiration_date, date_of_action, number_of_shares, premium_cents)
+            VALUES
+                ('NVTS', 1, 'SellPut', 650, 0.2, '2025-07-03', '2025-06-26', 1500, 27000),
+                ('NVTS', 2, 'SellPut', 650, 0.2, '2025-07-03', '2025-06-26', 1500, 27000),
+                ('NVTS', 2, 'SellPut', 600, 0.2, '2025-07-11', '2025-07-03', 1500, 30000);",
+        )
+        .unwrap();
+        let from = ["NVTS_2025-07-03".to_string(), "NVTS_2025-07-11".to_string()];
+
+        let err = merge(&conn, &from, "HOOD", OutputFormat::Json).unwrap_err();
+        assert!(err.to_string().contains("same symbol"));
+
+        merge(&conn, &from, "NVTS wheel", OutputFormat::Json).unwrap();
+        let names: Vec<String> = Campaign::get_all(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"NVTS wheel".to_string()));
+        let trades = OptionTrade::get_all(&conn).unwrap();
+        assert_eq!(trades.len(), 2);
+        assert!(trades.iter().all(|t| t.campaign == "NVTS wheel"));
+    }
+}
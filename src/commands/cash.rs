@@ -0,0 +1,91 @@
+use crate::error::Result;
+use crate::logic::account_cash;
+use crate::models::{CashEvent, CashKind, OptionTrade, StockTrade};
+use crate::money::{format_cents, to_cents};
+use crate::output::{OutputFormat, Table};
+use rusqlite::Connection;
+use serde_json::json;
+use time::{Date, OffsetDateTime};
+
+/// Record a deposit of `amount` dollars, or a withdrawal when `kind` is
+/// [`CashKind::Withdrawal`], on `date` (today by default).
+pub fn record(
+    db_conn: &Connection,
+    kind: CashKind,
+    amount: f64,
+    date: Option<Date>,
+) -> Result<CashEvent> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(format!("Invalid amount {amount}, expected more than zero").into());
+    }
+    let event = CashEvent {
+        id: None,
+        symbol: None,
+        campaign: None,
+        date: date.unwrap_or(OffsetDateTime::now_local()?.date()),
+        kind,
+        amount: if kind == CashKind::Withdrawal {
+            -amount
+        } else {
+            amount
+        },
+    };
+    event.insert(db_conn)?;
+    Ok(event)
+}
+
+pub fn run_record(
+    db_conn: &Connection,
+    kind: CashKind,
+    amount: f64,
+    date: Option<String>,
+    format: OutputFormat,
+) -> Result<()> {
+    let date = date.as_deref().map(super::parse_date).transpose()?;
+    let event = record(db_conn, kind, amount, date)?;
+    if format == OutputFormat::Table {
+        println!(
+            "Recorded a {} of {} on {}",
+            event.kind.as_str().to_lowercase(),
+            format_cents(to_cents(event.amount.abs())),
+            event.date
+        );
+        return Ok(());
+    }
+    events_table(&[event]).print(format)
+}
+
+/// List every cash event, followed in table output by the account's cash.
+pub fn run_list(db_conn: &Connection, format: OutputFormat) -> Result<()> {
+    let events = CashEvent::get_all(db_conn)?;
+    events_table(&events).print(format)?;
+    if format == OutputFormat::Table {
+        let today = OffsetDateTime::now_local()?.date();
+        let account = account_cash(
+            &OptionTrade::get_all(db_conn)?,
+            &StockTrade::get_all(db_conn)?,
+            &events,
+            today,
+        );
+        println!(
+            "Contributed {}, account cash {}",
+            format_cents(account.contributed),
+            format_cents(account.cash)
+        );
+    }
+    Ok(())
+}
+
+fn events_table(events: &[CashEvent]) -> Table {
+    let mut table = Table::new(&["date", "kind", "symbol", "campaign", "amount"]);
+    for event in events {
+        table.push_row(vec![
+            json!(event.date.to_string()),
+            json!(event.kind.as_str()),
+            json!(event.symbol),
+            json!(event.campaign),
+            json!(event.amount),
+        ]);
+    }
+    table
+}
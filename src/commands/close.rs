@@ -0,0 +1,143 @@
+use crate::error::Result;
+use crate::logic::match_lots;
+use crate::models::{Action, OptionKind, OptionTrade};
+use crate::output::OutputFormat;
+use crate::storage::Storage;
+use time::{Date, OffsetDateTime};
+
+use super::query::trades_table;
+
+/// The transaction that closes an open lot of the given type and side.
+pub fn closing_action(kind: OptionKind, short: bool) -> Action {
+    match (kind, short) {
+        (OptionKind::Put, true) => Action::BuyPut,
+        (OptionKind::Call, true) => Action::BuyCall,
+        (OptionKind::Put, false) => Action::SellPut,
+        (OptionKind::Call, false) => Action::SellCall,
+    }
+}
+
+/// Record a closing transaction for the open remainder of `trade_id` and return it. An
+/// `expired` lot is recorded as an expiration for nothing instead of a trade at `price`.
+pub fn close_trade(
+    storage: &mut dyn Storage,
+    trade_id: i32,
+    price: f64,
+    expired: bool,
+    date: Option<Date>,
+    contracts: Option<i32>,
+) -> Result<OptionTrade> {
+    let trades = storage.trades()?;
+    let opening = trades
+        .iter()
+        .find(|t| t.id == Some(trade_id))
+        .ok_or_else(|| format!("No trade with id {trade_id}"))?;
+
+    // Match as of expiration so the lot being closed is not already treated as expired
+    let lot = match_lots(&trades, opening.expiration_date)
+        .open
+        .into_iter()
+        .find(|lot| lot.trade.id == Some(trade_id))
+        .ok_or_else(|| format!("Trade {trade_id} has no open quantity to close"))?;
+
+    let multiplier = opening.multiplier;
+    let shares = match contracts {
+        Some(c) if c * multiplier > lot.shares || c <= 0 => {
+            return Err(format!(
+                "Cannot close {c} contracts; trade {trade_id} has {} open",
+                lot.shares / multiplier
+            )
+            .into());
+        }
+        Some(c) => c * multiplier,
+        None => lot.shares,
+    };
+    let date_of_action = match date {
+        Some(d) => d,
+        None => OffsetDateTime::now_local()?.date(),
+    };
+
+    let (action, price) = if expired {
+        (Action::Expired, 0.0)
+    } else {
+        (closing_action(lot.kind, lot.short), price)
+    };
+
+    let mut closing = OptionTrade {
+        id: None,
+        symbol: opening.symbol.clone(),
+        campaign: opening.campaign.clone(),
+        action,
+        strike: opening.strike,
+        delta: 0.0,
+        expiration_date: opening.expiration_date,
+        date_of_action,
+        number_of_shares: shares,
+        credit: price,
+        closes_trade_id: Some(trade_id),
+        rolled_from_id: None,
+        commission: 0.0,
+        fees: 0.0,
+        multiplier,
+        status: None,
+        collateral: None,
+        covered_by_id: None,
+        implied_volatility: None,
+        uuid: None,
+    };
+    closing.id = Some(storage.insert_trade(&closing)?);
+    Ok(closing)
+}
+
+pub fn run(
+    storage: &mut dyn Storage,
+    trade_id: i32,
+    price: Option<f64>,
+    date: Option<String>,
+    expired: bool,
+    contracts: Option<i32>,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut date = date.as_deref().map(super::parse_date).transpose()?;
+
+    if expired {
+        // Expired options close for nothing on their expiration date
+        let opening = storage
+            .trades()?
+            .into_iter()
+            .find(|t| t.id == Some(trade_id))
+            .ok_or_else(|| format!("No trade with id {trade_id}"))?;
+        date = date.or(Some(opening.expiration_date));
+    }
+    let closing = close_trade(
+        storage,
+        trade_id,
+        price.unwrap_or(0.0),
+        expired,
+        date,
+        contracts,
+    )?;
+
+    if format == OutputFormat::Table && expired {
+        println!(
+            "Recorded expiration of {} contracts on {} closing trade {} (new trade {})",
+            closing.contracts(),
+            closing.date_of_action,
+            trade_id,
+            closing.id.unwrap_or_default()
+        );
+    } else if format == OutputFormat::Table {
+        println!(
+            "Recorded {:?} of {} contracts @ ${:.2} on {} closing trade {} (new trade {})",
+            closing.action,
+            closing.contracts(),
+            closing.credit,
+            closing.date_of_action,
+            trade_id,
+            closing.id.unwrap_or_default()
+        );
+    } else {
+        trades_table([&closing]).print(format)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use crate::config::{self, DEFAULT_CONFIG};
+use crate::error::Result;
+
+/// Write the commented default config file, creating its directory, and return where
+/// it was written. An existing file is only replaced when `force` is set.
+pub fn init(force: bool) -> Result<std::path::PathBuf> {
+    let path = config::config_path().ok_or("No config directory found for this platform")?;
+    if path.exists() && !force {
+        return Err(format!(
+            "{} already exists; pass --force to replace it",
+            path.display()
+        )
+        .into());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, DEFAULT_CONFIG)?;
+    Ok(path)
+}
+
+pub fn run_init(force: bool) -> Result<()> {
+    let path = init(force)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use crate::error::Result;
+use crate::logic::ShareLedger;
+use crate::models::{Action, OptionTrade, StockTrade};
+use crate::output::OutputFormat;
+use crate::storage::Storage;
+use time::OffsetDateTime;
+
+use super::query::trades_table;
+
+/// What covers a short call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cover {
+    /// Shares held, at this per-share cost or, when `None`, the campaign's average cost.
+    Shares(Option<f64>),
+    /// The long call with this id, as in a poor man's covered call.
+    Leap(i32),
+    /// Nothing; the call is naked.
+    Naked,
+}
+
+/// Record what covers the short call `trade_id` and return the updated trade. Its
+/// capital at risk becomes the cost of the shares or long call instead of the strike.
+pub fn cover_trade(storage: &mut dyn Storage, trade_id: i32, cover: Cover) -> Result<OptionTrade> {
+    let trades = storage.trades()?;
+    let mut trade = trades
+        .iter()
+        .find(|t| t.id == Some(trade_id))
+        .cloned()
+        .ok_or_else(|| format!("No trade with id {trade_id}"))?;
+    if trade.action != Action::SellCall {
+        return Err(format!(
+            "Trade {trade_id} is a {}, not a SellCall",
+            trade.action.as_str()
+        )
+        .into());
+    }
+
+    (trade.collateral, trade.covered_by_id) = match cover {
+        Cover::Shares(Some(cost)) => (Some(cost), None),
+        Cover::Shares(None) => {
+            let held = |campaign: &str, symbol: &str| {
+                campaign == trade.campaign && symbol.eq_ignore_ascii_case(&trade.symbol)
+            };
+            let ours: Vec<OptionTrade> = trades
+                .iter()
+                .filter(|t| held(&t.campaign, &t.symbol))
+                .cloned()
+                .collect();
+            let stocks: Vec<StockTrade> = storage
+                .stock_trades()?
+                .into_iter()
+                .filter(|s| held(&s.campaign, &s.symbol))
+                .collect();
+            let ledger = ShareLedger::new(&ours, &stocks, OffsetDateTime::now_local()?.date());
+            let cost = ledger.average_cost().ok_or_else(|| {
+                format!(
+                    "Campaign '{}' holds no {} shares; give their cost with --cost",
+                    trade.campaign, trade.symbol
+                )
+            })?;
+            (Some(cost), None)
+        }
+        Cover::Leap(leap_id) => {
+            let leap = trades
+                .iter()
+                .find(|t| t.id == Some(leap_id))
+                .ok_or_else(|| format!("No trade with id {leap_id}"))?;
+            if leap.action != Action::BuyCall || leap.symbol != trade.symbol {
+                return Err(format!("Trade {leap_id} is not a long {} call", trade.symbol).into());
+            }
+            (Some(leap.credit.abs()), Some(leap_id))
+        }
+        Cover::Naked => (None, None),
+    };
+    storage.update_trade(&trade)?;
+    Ok(trade)
+}
+
+pub fn run(
+    storage: &mut dyn Storage,
+    trade_id: i32,
+    cover: Cover,
+    format: OutputFormat,
+) -> Result<()> {
+    let trade = cover_trade(storage, trade_id, cover)?;
+
+    if format == OutputFormat::Table {
+        match (trade.collateral, trade.covered_by_id) {
+            (Some(cost), Some(leap_id)) => {
+                println!("Trade {trade_id} is covered by long call {leap_id} (${cost:.2} a share)")
+            }
+            (Some(cost), None) => {
+                println!("Trade {trade_id} is covered by shares at ${cost:.2} a share")
+            }
+            _ => println!("Trade {trade_id} is naked"),
+        }
+    } else {
+        trades_table([&trade]).print(format)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use crate::csv_processor::{Broker, CsvProcessor};
+use crate::error::Result;
+use crate::filter::Filter;
+use crate::models::OptionTrade;
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Fields a statement line and a stored trade must agree on to be the same transaction.
+///
+/// Money is compared in whole cents and without sign, since imported buys are
+/// stored as negative credits while manually entered ones are positive.
+fn match_key(t: &OptionTrade) -> String {
+    format!(
+        "{}|{:?}|{}|{}|{}|{}|{}",
+        t.symbol.to_uppercase(),
+        t.action,
+        (t.strike * 100.0).round() as i64,
+        t.expiration_date,
+        t.date_of_action,
+        t.number_of_shares,
+        (t.credit.abs() * t.number_of_shares as f64 * 100.0).round() as i64
+    )
+}
+
+fn differences_table(statement_only: &[&OptionTrade], database_only: &[&OptionTrade]) -> Table {
+    let mut table = Table::new(&[
+        "missing_from",
+        "id",
+        "symbol",
+        "action",
+        "strike",
+        "expiration",
+        "date",
+        "shares",
+        "total",
+    ]);
+    let sides = [("database", statement_only), ("statement", database_only)];
+    for (missing_from, trades) in sides {
+        for t in trades {
+            table.push_row(vec![
+                json!(missing_from),
+                json!(t.id),
+                json!(t.symbol),
+                json!(format!("{:?}", t.action)),
+                json!(t.strike),
+                json!(t.expiration_date.to_string()),
+                json!(t.date_of_action.to_string()),
+                json!(t.number_of_shares),
+                json!((t.credit.abs() * t.number_of_shares as f64 * 100.0).round() / 100.0),
+            ]);
+        }
+    }
+    table
+}
+
+pub fn run(
+    storage: &mut dyn Storage,
+    broker: &str,
+    file: &Path,
+    symbol: Option<&str>,
+    expression: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let broker: Broker = broker.parse()?;
+    let filter: Filter = expression.parse()?;
+    let statement: Vec<OptionTrade> = CsvProcessor::new(broker)
+        .process_csv(file)?
+        .into_iter()
+        .map(|mut t| {
+            if let Some(symbol) = symbol {
+                t.symbol = symbol.to_string();
+            }
+            t
+        })
+        .filter(|t| filter.matches(t))
+        .collect();
+
+    // Only trades inside the statement's period can be expected to appear in it
+    let first = statement.iter().map(|t| t.date_of_action).min();
+    let last = statement.iter().map(|t| t.date_of_action).max();
+    let mut stored: Vec<OptionTrade> = storage
+        .trades()?
+        .into_iter()
+        .filter(|t| filter.matches(t))
+        .filter(|t| match (first, last) {
+            (Some(first), Some(last)) => (first..=last).contains(&t.date_of_action),
+            _ => false,
+        })
+        .collect();
+    stored.sort_by_key(|t| (t.date_of_action, t.id));
+
+    // Pair identical transactions one-to-one so repeated fills are counted correctly
+    let mut unmatched: HashMap<String, Vec<&OptionTrade>> = HashMap::new();
+    for t in &stored {
+        unmatched.entry(match_key(t)).or_default().push(t);
+    }
+    let mut statement_only = Vec::new();
+    for t in &statement {
+        if unmatched
+            .get_mut(&match_key(t))
+            .and_then(|v| v.pop())
+            .is_none()
+        {
+            statement_only.push(t);
+        }
+    }
+    let mut database_only: Vec<&OptionTrade> = unmatched.into_values().flatten().collect();
+    database_only.sort_by_key(|t| (t.date_of_action, t.id));
+    let matched = statement.len() - statement_only.len();
+
+    if format != OutputFormat::Table {
+        return differences_table(&statement_only, &database_only).print(format);
+    }
+    if statement_only.is_empty() && database_only.is_empty() {
+        println!(
+            "Database matches {}: {} transaction(s) compared",
+            file.display(),
+            matched
+        );
+        return Ok(());
+    }
+    differences_table(&statement_only, &database_only).print(format)?;
+    println!(
+        "{} matched, {} missing from the database, {} missing from the statement",
+        matched,
+        statement_only.len(),
+        database_only.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use crate::config;
+use crate::error::Result;
+use crate::logic::{dividend_exposed_symbols, dividend_risks, match_lots};
+use crate::models::Dividend;
+use crate::output::{OutputFormat, Table};
+use crate::quotes;
+use crate::storage::Storage;
+use serde_json::json;
+use std::collections::HashMap;
+use time::OffsetDateTime;
+
+/// A dividend to record by hand, for symbols the quote provider doesn't cover.
+pub struct NewDividend {
+    pub symbol: String,
+    pub ex_date: String,
+    pub amount: Option<f64>,
+}
+
+/// List upcoming ex-dividend dates for symbols with assigned shares or short calls and
+/// flag short calls at risk of early assignment before them.
+///
+/// `add` records a dividend first; `fetch` looks up the next ex-dates (and current prices,
+/// to tell which calls are in the money) from the configured quote provider.
+pub fn run(
+    storage: &mut dyn Storage,
+    add: Option<NewDividend>,
+    fetch: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    if let Some(add) = add {
+        storage.save_dividend(&Dividend {
+            symbol: add.symbol.to_uppercase(),
+            ex_date: super::parse_date(&add.ex_date)?,
+            amount: add.amount,
+        })?;
+    }
+
+    let trades = storage.trades()?;
+    let today = OffsetDateTime::now_local()?.date();
+    let matches = match_lots(&trades, today);
+    let symbols = dividend_exposed_symbols(&matches);
+
+    let mut prices = HashMap::new();
+    if fetch {
+        let provider = quotes::from_config(config::get())?
+            .ok_or("Set quote_provider in the config file to fetch dividends")?;
+        for symbol in &symbols {
+            if let Some(dividend) = provider.next_dividend(symbol)? {
+                storage.save_dividend(&dividend)?;
+            }
+            prices.insert(symbol.clone(), provider.last_price(symbol)?);
+        }
+    }
+
+    let dividends: Vec<Dividend> = storage
+        .dividends()?
+        .into_iter()
+        .filter(|d| d.ex_date >= today)
+        .filter(|d| symbols.iter().any(|s| s.eq_ignore_ascii_case(&d.symbol)))
+        .collect();
+    if dividends.is_empty() && format == OutputFormat::Table {
+        println!("No upcoming ex-dividend dates for symbols with shares or short calls");
+        return Ok(());
+    }
+
+    let risks = dividend_risks(&matches.open, &dividends, today, |s| prices.get(s).copied());
+    let mut table = Table::new(&["symbol", "ex_date", "amount", "days", "at_risk"]);
+    for dividend in &dividends {
+        let at_risk: Vec<String> = risks
+            .iter()
+            .filter(|r| std::ptr::eq(r.dividend, dividend))
+            .map(|r| {
+                format!(
+                    "trade {} ${:.2} call exp {}{}",
+                    r.lot.trade.id.unwrap_or_default(),
+                    r.lot.trade.strike,
+                    r.lot.trade.expiration_date,
+                    match r.in_the_money {
+                        Some(true) => " (ITM)",
+                        _ => "",
+                    }
+                )
+            })
+            .collect();
+        table.push_row(vec![
+            json!(dividend.symbol),
+            json!(dividend.ex_date.to_string()),
+            json!(dividend.amount),
+            json!((dividend.ex_date - today).whole_days()),
+            json!(at_risk.join("; ")),
+        ]);
+    }
+    table.print(format)?;
+    if format == OutputFormat::Table && !risks.is_empty() {
+        println!(
+            "{} short call(s) open over an ex-dividend date may be assigned early, \
+            most likely when in the money with little time value left",
+            risks.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use crate::db;
+use crate::error::Result;
+use crate::storage::Storage;
+use std::path::Path;
+
+pub fn run(storage: &mut dyn Storage, out: Option<&Path>) -> Result<()> {
+    let dump = storage.dump()?;
+    let text = serde_json::to_string_pretty(&dump)?;
+    match out {
+        Some(path) => {
+            std::fs::write(path, text + "\n")?;
+            let rows: usize = db::DUMP_TABLES
+                .iter()
+                .filter_map(|table| dump["tables"][table].as_array())
+                .map(Vec::len)
+                .sum();
+            println!(
+                "Wrote {} rows from {} tables to {}",
+                rows,
+                db::DUMP_TABLES.len(),
+                path.display()
+            );
+        }
+        None => println!("{text}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use super::query::trades_table;
+use crate::error::Result;
+use crate::filter::{Assignment, Filter};
+use crate::models::OptionTrade;
+use crate::output::OutputFormat;
+use crate::storage::Storage;
+
+fn apply(storage: &mut dyn Storage, trades: &[OptionTrade]) -> Result<()> {
+    storage.transaction(&mut |storage| {
+        for trade in trades {
+            // Moving trades into a new campaign creates it so the TUI can still show them
+            storage.insert_campaign(&trade.campaign, &trade.symbol, None)?;
+            storage.update_trade(trade)?;
+        }
+        Ok(())
+    })
+}
+
+pub fn run(
+    storage: &mut dyn Storage,
+    expression: &str,
+    assignments: &[String],
+    dry_run: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let filter: Filter = expression.parse()?;
+    let assignments = assignments
+        .iter()
+        .map(|s| s.parse())
+        .collect::<Result<Vec<Assignment>, _>>()?;
+
+    let mut trades: Vec<OptionTrade> = storage
+        .trades()?
+        .into_iter()
+        .filter(|t| filter.matches(t))
+        .collect();
+    trades.sort_by_key(|t| t.id);
+    for trade in trades.iter_mut() {
+        for assignment in &assignments {
+            assignment.apply(trade);
+        }
+    }
+
+    if trades.is_empty() {
+        if format == OutputFormat::Table {
+            println!("No trades match '{expression}'");
+        } else {
+            trades_table(&trades).print(format)?;
+        }
+        return Ok(());
+    }
+
+    trades_table(&trades).print(format)?;
+    if dry_run {
+        if format == OutputFormat::Table {
+            println!("Dry run: {} trade(s) would be updated", trades.len());
+        }
+        return Ok(());
+    }
+
+    apply(storage, &trades)?;
+    if format == OutputFormat::Table {
+        println!("Updated {} trade(s)", trades.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use super::workbook;
+use crate::csv_processor::{Broker, CsvProcessor};
+use crate::error::Result;
+use crate::filter::Filter;
+use crate::models::OptionTrade;
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use std::path::Path;
+
+pub fn run(
+    storage: &mut dyn Storage,
+    broker: &str,
+    out: &Path,
+    expression: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let filter: Filter = expression.parse()?;
+    let mut trades: Vec<OptionTrade> = storage
+        .trades()?
+        .into_iter()
+        .filter(|t| filter.matches(t))
+        .collect();
+    // Statements list transactions in the order they happened
+    trades.sort_by_key(|t| (t.date_of_action, t.id));
+
+    if broker.eq_ignore_ascii_case("xlsx") {
+        let sheets = workbook::write(&trades, out)?;
+        if format == OutputFormat::Table {
+            println!(
+                "Exported {} trades in {} campaign sheets to {}",
+                trades.len(),
+                sheets,
+                out.display()
+            );
+        } else {
+            let mut table = Table::new(&["file", "trades", "campaigns"]);
+            table.push_row(vec![
+                json!(out.display().to_string()),
+                json!(trades.len()),
+                json!(sheets),
+            ]);
+            table.print(format)?;
+        }
+        return Ok(());
+    }
+
+    let broker: Broker = broker.parse()?;
+    let written = CsvProcessor::new(broker.clone()).export_csv(&trades, out)?;
+    let skipped = trades.len() - written;
+
+    if format == OutputFormat::Table {
+        println!(
+            "Exported {} trades to {} in {} format",
+            written,
+            out.display(),
+            broker
+        );
+        if skipped > 0 {
+            println!("Skipped {skipped} assignment/exercise event(s) with no statement line");
+        }
+    } else {
+        let mut table = Table::new(&["file", "broker", "exported", "skipped"]);
+        table.push_row(vec![
+            json!(out.display().to_string()),
+            json!(broker.as_str()),
+            json!(written),
+            json!(skipped),
+        ]);
+        table.print(format)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use crate::config;
+use crate::db;
+use crate::error::Result;
+use crate::models::{Campaign, OptionTrade, Snapshot};
+use crate::output::{OutputFormat, Table};
+use rusqlite::Connection;
+use serde_json::json;
+use std::path::Path;
+
+pub fn run(db_conn: &Connection, db_path: &Path, format: OutputFormat) -> Result<()> {
+    let trades = OptionTrade::get_all(db_conn)?;
+    let first_trade = trades.iter().map(|t| t.date_of_action).min();
+    let last_trade = trades.iter().map(|t| t.date_of_action).max();
+    let file_size = std::fs::metadata(db_path).map(|m| m.len()).ok();
+
+    let mut table = Table::new(&["metric", "value"]);
+    table.push_row(vec![
+        json!("db_path"),
+        json!(
+            db_path
+                .canonicalize()
+                .unwrap_or_else(|_| db_path.to_path_buf())
+                .display()
+                .to_string()
+        ),
+    ]);
+    table.push_row(vec![
+        json!("schema_version"),
+        json!(db::schema_version(db_conn)?),
+    ]);
+    table.push_row(vec![
+        json!("campaigns"),
+        json!(Campaign::get_all(db_conn)?.len()),
+    ]);
+    table.push_row(vec![json!("trades"), json!(trades.len())]);
+    table.push_row(vec![
+        json!("snapshots"),
+        json!(Snapshot::get_all(db_conn)?.len()),
+    ]);
+    table.push_row(vec![
+        json!("first_trade"),
+        json!(first_trade.map(|d| d.to_string())),
+    ]);
+    table.push_row(vec![
+        json!("last_trade"),
+        json!(last_trade.map(|d| d.to_string())),
+    ]);
+    table.push_row(vec![json!("file_size_bytes"), json!(file_size)]);
+    table.push_row(vec![
+        json!("config_path"),
+        json!(config::config_path().map(|p| p.display().to_string())),
+    ]);
+    // Only the service names; the keys themselves stay out of the output
+    let mut services: Vec<&str> = config::get().api_keys.keys().map(String::as_str).collect();
+    services.sort_unstable();
+    table.push_row(vec![json!("api_keys"), json!(services.join(", "))]);
+    table.print(format)
+}
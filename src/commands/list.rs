@@ -0,0 +1,60 @@
+use crate::error::Result;
+use crate::filter::Filter;
+use crate::models::{Action, Campaign, OptionTrade};
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use time::Date;
+
+use super::query::trades_table;
+
+/// Every campaign with its symbol, target exit price and premium goal.
+pub fn campaigns_table(campaigns: &[Campaign]) -> Table {
+    let mut table = Table::new(&["name", "symbol", "target_exit_price", "goal"]);
+    for campaign in campaigns {
+        table.push_row(vec![
+            json!(campaign.name),
+            json!(campaign.symbol),
+            json!(campaign.target_exit_price),
+            json!(campaign.goal.map(|goal| goal.to_string())),
+        ]);
+    }
+    table
+}
+
+/// Print the trades of `campaign`, placed on or after `since`, and with `action`, by date;
+/// each filter is skipped when `None`.
+pub fn trades(
+    storage: &mut dyn Storage,
+    campaign: Option<&str>,
+    since: Option<Date>,
+    action: Option<&Action>,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut filter = Filter::default();
+    if let Some(name) = campaign {
+        if !storage.campaigns()?.iter().any(|c| c.name == name) {
+            return Err(format!("No campaign named '{name}'").into());
+        }
+        filter = filter.campaign(name);
+    }
+    if let Some(date) = since {
+        filter = filter.since(date);
+    }
+    if let Some(action) = action {
+        filter = filter.action(action);
+    }
+    let mut trades: Vec<OptionTrade> = storage
+        .trades()?
+        .into_iter()
+        .filter(|t| filter.matches(t))
+        .collect();
+    trades.sort_by_key(|t| (t.date_of_action, t.id));
+    trades_table(&trades).print(format)?;
+    Ok(())
+}
+
+pub fn campaigns(storage: &mut dyn Storage, format: OutputFormat) -> Result<()> {
+    campaigns_table(&storage.campaigns()?).print(format)?;
+    Ok(())
+}
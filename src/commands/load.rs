@@ -0,0 +1,11 @@
+use crate::db;
+use crate::error::Result;
+use rusqlite::Connection;
+use std::path::Path;
+
+pub fn run(db_conn: &Connection, file: &Path, replace: bool) -> Result<()> {
+    let dump: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(file)?)?;
+    let loaded = db::load_json(db_conn, &dump, replace)?;
+    println!("Loaded {} rows from {}", loaded, file.display());
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use crate::error::Result;
+use crate::logic::{OpenLot, match_lots};
+use crate::models::Mark;
+use crate::money::{format_cents, to_dollars};
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use time::{Date, OffsetDateTime};
+
+/// Record `price` as the current per-share price of open trade `trade_id`, replacing its
+/// earlier mark, and return the open lot it values.
+pub fn mark_trade(
+    storage: &mut dyn Storage,
+    trade_id: i32,
+    price: f64,
+    date: Option<Date>,
+) -> Result<(OpenLot, Mark)> {
+    if price < 0.0 {
+        return Err(format!("Invalid mark {price}, expected a price of zero or more").into());
+    }
+    let today = OffsetDateTime::now_local()?.date();
+    let lot = match_lots(&storage.trades()?, today)
+        .open
+        .into_iter()
+        .find(|lot| lot.trade.id == Some(trade_id))
+        .ok_or_else(|| format!("Trade {trade_id} is not an open position"))?;
+    let mark = Mark {
+        trade_id,
+        date: date.unwrap_or(today),
+        price,
+    };
+    storage.save_mark(&mark)?;
+    Ok((lot, mark))
+}
+
+pub fn run(
+    storage: &mut dyn Storage,
+    trade_id: i32,
+    price: f64,
+    date: Option<String>,
+    format: OutputFormat,
+) -> Result<()> {
+    let date = date.as_deref().map(super::parse_date).transpose()?;
+    let (lot, mark) = mark_trade(storage, trade_id, price, date)?;
+
+    if format == OutputFormat::Table {
+        let captured = lot
+            .premium_captured(mark.price)
+            .map(|c| format!(", {:.0}% of the premium captured", c * 100.0))
+            .unwrap_or_default();
+        println!(
+            "Marked trade {trade_id} at ${:.2} on {}: unrealized P/L {}{captured}",
+            mark.price,
+            mark.date,
+            format_cents(lot.unrealized(mark.price))
+        );
+    } else {
+        let mut table = Table::new(&["id", "date", "mark", "unrealized", "captured_pct"]);
+        table.push_row(vec![
+            json!(trade_id),
+            json!(mark.date.to_string()),
+            json!(mark.price),
+            json!(to_dollars(lot.unrealized(mark.price))),
+            json!(
+                lot.premium_captured(mark.price)
+                    .map(|c| (c * 1000.0).round() / 10.0)
+            ),
+        ]);
+        table.print(format)?;
+    }
+    Ok(())
+}
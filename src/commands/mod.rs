@@ -0,0 +1,40 @@
+pub mod add;
+pub mod backfill_greeks;
+pub mod campaign;
+pub mod cash;
+pub mod close;
+pub mod config;
+pub mod cover;
+pub mod diff;
+pub mod dividends;
+pub mod dump;
+pub mod edit;
+pub mod export;
+pub mod info;
+pub mod list;
+pub mod load;
+pub mod mark;
+pub mod notify;
+pub mod positions;
+pub mod prune;
+pub mod query;
+pub mod reconcile;
+pub mod remind;
+pub mod repl;
+pub mod report;
+pub mod roll;
+pub mod serve;
+pub mod snapshot;
+pub mod sync;
+pub mod tags;
+pub mod tax;
+pub mod watch;
+pub mod workbook;
+
+use time::Date;
+
+/// Parse a `YYYY-MM-DD` command-line date.
+pub fn parse_date(s: &str) -> Result<Date, String> {
+    let date_fmt = time::macros::format_description!("[year]-[month]-[day]");
+    Date::parse(s, &date_fmt).map_err(|_| format!("Invalid date '{s}', expected YYYY-MM-DD"))
+}
@@ -0,0 +1,174 @@
+use crate::config::{self, EmailConfig};
+use crate::error::Result;
+use crate::logic::{calculate_weekly_premium, match_lots, week_bounds};
+use crate::models::{OptionKind, OptionTrade};
+use crate::money::format_cents;
+use crate::storage::Storage;
+use lettre::message::{Mailbox, header::ContentType};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde_json::json;
+use time::{Date, OffsetDateTime};
+
+/// Plain-text weekly summary: premium, P/L, and open positions, those expiring this week
+/// first.
+pub fn weekly_summary(trades: &[OptionTrade], today: Date) -> String {
+    let (start_of_week, end_of_week) = week_bounds(today, config::get().week_start);
+    let matches = match_lots(trades, today);
+    let realized_week: i64 = matches
+        .closed
+        .iter()
+        .filter(|c| c.closed >= start_of_week && c.closed <= end_of_week)
+        .map(|c| c.gain())
+        .sum();
+    let realized_total: i64 = matches.closed.iter().map(|c| c.gain()).sum();
+
+    let mut lines = vec![
+        format!("Weekly summary for {start_of_week} to {end_of_week}"),
+        format!(
+            "Premium expiring this week: {}",
+            format_cents(calculate_weekly_premium(trades))
+        ),
+        format!("Realized P/L this week: {}", format_cents(realized_week)),
+        format!("Realized P/L to date: {}", format_cents(realized_total)),
+    ];
+
+    let mut open: Vec<_> = matches.open.iter().collect();
+    open.sort_by_key(|lot| lot.trade.expiration_date);
+    let (expiring, later): (Vec<_>, Vec<_>) = open
+        .into_iter()
+        .partition(|lot| lot.trade.expiration_date <= end_of_week);
+    if expiring.is_empty() {
+        lines.push("No open positions expiring this week".to_string());
+    }
+    for (heading, lots) in [("Expiring this week:", expiring), ("Open later:", later)] {
+        if lots.is_empty() {
+            continue;
+        }
+        lines.push(heading.to_string());
+        for lot in lots {
+            lines.push(format!(
+                "- {} {} {} {} x{} exp {} ({})",
+                if lot.short { "Short" } else { "Long" },
+                lot.trade.symbol,
+                lot.trade.strike,
+                match lot.kind {
+                    OptionKind::Put => "Put",
+                    OptionKind::Call => "Call",
+                },
+                lot.contracts(),
+                lot.trade.expiration_date,
+                lot.trade.campaign
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Discord rejects messages longer than this; Slack allows far more.
+const MAX_MESSAGE_LEN: usize = 2000;
+
+/// Split `text` at line breaks into messages no longer than `max` characters. A single
+/// line longer than `max` gets a message to itself.
+fn split_message(text: &str, max: usize) -> Vec<String> {
+    let mut messages: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match messages.last_mut() {
+            Some(message) if message.chars().count() + 1 + line.chars().count() <= max => {
+                message.push('\n');
+                message.push_str(line);
+            }
+            _ => messages.push(line.to_string()),
+        }
+    }
+    messages
+}
+
+/// Post `text` to a Slack or Discord incoming webhook, in several messages if it is long.
+pub fn post_webhook(url: &str, text: &str) -> Result<()> {
+    for message in split_message(text, MAX_MESSAGE_LEN) {
+        // Discord expects the message under "content", Slack under "text"
+        let body = if url.contains("discord") {
+            json!({ "content": message })
+        } else {
+            json!({ "text": message })
+        };
+        ureq::post(url).send_json(&body)?;
+    }
+    Ok(())
+}
+
+/// Parse an address from the `[email]` config.
+fn address(text: &str) -> Result<Mailbox> {
+    Ok(text
+        .parse()
+        .map_err(|e| format!("Invalid email address '{text}': {e}"))?)
+}
+
+/// Mail `text` with the first line as the subject, using the `[email]` config.
+pub fn send_email(email: &EmailConfig, text: &str) -> Result<()> {
+    let mut builder = Message::builder()
+        .from(address(&email.from)?)
+        .subject(text.lines().next().unwrap_or("Weekly summary"))
+        .header(ContentType::TEXT_PLAIN);
+    for to in &email.to {
+        builder = builder.to(address(to)?);
+    }
+    let message = builder.body(text.to_string())?;
+
+    let transport = if email.smtp_port == 465 {
+        SmtpTransport::relay(&email.smtp_host)?
+    } else {
+        SmtpTransport::starttls_relay(&email.smtp_host)?
+    };
+    let mut transport = transport.port(email.smtp_port);
+    if let (Some(username), Some(password)) = (&email.username, &email.password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+    transport.build().send(&message)?;
+    Ok(())
+}
+
+/// Send the weekly summary to the given webhook and/or by email. With neither given, it
+/// goes to every destination in the config file.
+pub fn run(
+    storage: &mut dyn Storage,
+    webhook: Option<&str>,
+    email: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let config = config::get();
+    let (webhook, email) = if webhook.is_none() && !email {
+        (config.webhook.as_deref(), config.email.as_ref())
+    } else {
+        let email = match email {
+            true => Some(
+                config
+                    .email
+                    .as_ref()
+                    .ok_or("Set up [email] in the config file")?,
+            ),
+            false => None,
+        };
+        (webhook, email)
+    };
+    let trades = storage.trades()?;
+    let summary = weekly_summary(&trades, OffsetDateTime::now_local()?.date());
+
+    if dry_run {
+        println!("{summary}");
+        return Ok(());
+    }
+    if webhook.is_none() && email.is_none() {
+        return Err("Pass --webhook, or set webhook or [email] in the config file".into());
+    }
+    if let Some(webhook) = webhook {
+        post_webhook(webhook, &summary)?;
+        println!("Posted weekly summary to webhook");
+    }
+    if let Some(email) = email {
+        send_email(email, &summary)?;
+        println!("Mailed weekly summary to {}", email.to.join(", "));
+    }
+    Ok(())
+}
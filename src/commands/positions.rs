@@ -0,0 +1,114 @@
+use crate::error::Result;
+use crate::logic::{OpenLot, match_lots};
+use crate::models::{Mark, OptionKind};
+use crate::money::{format_cents, to_dollars};
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use std::collections::HashMap;
+use time::{Date, OffsetDateTime};
+
+/// One row per open lot. `realized` is the gain in cents already taken on each opening
+/// trade by closing part of it; lots with a mark also show what closing at it would gain
+/// and the share of the premium captured.
+pub fn positions_table(
+    lots: &[OpenLot],
+    realized: &HashMap<i32, i64>,
+    marks: &HashMap<i32, Mark>,
+    today: Date,
+) -> Table {
+    let mut table = Table::new(&[
+        "id",
+        "campaign",
+        "symbol",
+        "type",
+        "side",
+        "contracts",
+        "strike",
+        "expiration",
+        "dte",
+        "days_held",
+        "collateral",
+        "premium",
+        "realized",
+        "mark",
+        "unrealized",
+        "captured_pct",
+    ]);
+    for lot in lots {
+        let mark = lot.trade.id.and_then(|id| marks.get(&id)).map(|m| m.price);
+        table.push_row(vec![
+            json!(lot.trade.id),
+            json!(lot.trade.campaign),
+            json!(lot.trade.symbol),
+            json!(match lot.kind {
+                OptionKind::Put => "Put",
+                OptionKind::Call => "Call",
+            }),
+            json!(if lot.short { "Short" } else { "Long" }),
+            json!(lot.shares as f64 / lot.trade.multiplier as f64),
+            json!(lot.trade.strike),
+            json!(lot.trade.expiration_date.to_string()),
+            json!((lot.trade.expiration_date - today).whole_days()),
+            json!(lot.days_held(today)),
+            json!(to_dollars(lot.collateral())),
+            json!(to_dollars(lot.premium_at_risk())),
+            json!(to_dollars(
+                lot.trade
+                    .id
+                    .and_then(|id| realized.get(&id).copied())
+                    .unwrap_or(0)
+            )),
+            json!(mark),
+            json!(mark.map(|m| to_dollars(lot.unrealized(m)))),
+            json!(
+                mark.and_then(|m| lot.premium_captured(m))
+                    .map(|c| (c * 1000.0).round() / 10.0)
+            ),
+        ]);
+    }
+    table
+}
+
+pub fn run(storage: &mut dyn Storage, format: OutputFormat) -> Result<()> {
+    let trades = storage.trades()?;
+    let today = OffsetDateTime::now_local()?.date();
+
+    let matches = match_lots(&trades, today);
+    let realized = matches.realized_by_trade();
+    let marks = storage.marks()?;
+    let mut lots = matches.open;
+    lots.sort_by_key(|lot| (lot.trade.expiration_date, lot.trade.campaign.clone()));
+
+    if lots.is_empty() && format == OutputFormat::Table {
+        println!("No open positions");
+        return Ok(());
+    }
+
+    positions_table(&lots, &realized, &marks, today).print(format)?;
+    if format == OutputFormat::Table {
+        let total_collateral: i64 = lots.iter().map(OpenLot::collateral).sum();
+        let total_premium: i64 = lots.iter().map(OpenLot::premium_at_risk).sum();
+        println!(
+            "{} open position(s), collateral {}, premium at risk {}",
+            lots.len(),
+            format_cents(total_collateral),
+            format_cents(total_premium)
+        );
+        let marked: Vec<i64> = lots
+            .iter()
+            .filter_map(|lot| {
+                let mark = marks.get(&lot.trade.id?)?;
+                Some(lot.unrealized(mark.price))
+            })
+            .collect();
+        if !marked.is_empty() {
+            println!(
+                "Unrealized P/L {} on {} marked position(s)",
+                format_cents(marked.iter().sum()),
+                marked.len()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use crate::error::Result;
+use crate::models::{Campaign, CashEvent, OptionTrade, StockTrade};
+use crate::output::{OutputFormat, Table};
+use rusqlite::Connection;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+
+enum Change {
+    TradeSymbol { trade: OptionTrade, from: String },
+    CampaignSymbol { campaign: Campaign, from: String },
+    DuplicateTrade { id: i32, keep: i32 },
+    EmptyCampaign { name: String },
+}
+
+/// Uppercase a ticker and drop any stray whitespace.
+pub fn normalize_symbol(symbol: &str) -> String {
+    symbol.split_whitespace().collect::<String>().to_uppercase()
+}
+
+fn duplicate_key(t: &OptionTrade) -> String {
+    format!(
+        "{}|{}|{:?}|{}|{}|{}|{}|{}|{}|{:?}|{:?}",
+        t.symbol,
+        t.campaign,
+        t.action,
+        t.strike,
+        t.delta,
+        t.expiration_date,
+        t.date_of_action,
+        t.number_of_shares,
+        t.credit,
+        t.closes_trade_id,
+        t.rolled_from_id
+    )
+}
+
+fn plan(db_conn: &Connection) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+    let mut trades = OptionTrade::get_all(db_conn)?;
+    trades.sort_by_key(|t| t.id);
+
+    for trade in trades.iter_mut() {
+        let normalized = normalize_symbol(&trade.symbol);
+        if normalized != trade.symbol {
+            let from = std::mem::replace(&mut trade.symbol, normalized);
+            changes.push(Change::TradeSymbol {
+                trade: trade.clone(),
+                from,
+            });
+        }
+    }
+
+    // Keep the oldest row of each group of identical trades
+    let mut kept: HashMap<String, i32> = HashMap::new();
+    let mut remaining_campaigns: HashSet<String> = HashSet::new();
+    for trade in &trades {
+        let (Some(id), key) = (trade.id, duplicate_key(trade)) else {
+            continue;
+        };
+        match kept.get(&key) {
+            Some(&keep) => changes.push(Change::DuplicateTrade { id, keep }),
+            None => {
+                kept.insert(key, id);
+                remaining_campaigns.insert(trade.campaign.clone());
+            }
+        }
+    }
+
+    // Campaigns holding only shares or dividends aren't empty
+    for stock in StockTrade::get_all(db_conn)? {
+        remaining_campaigns.insert(stock.campaign);
+    }
+    remaining_campaigns.extend(
+        CashEvent::get_all(db_conn)?
+            .into_iter()
+            .filter_map(|e| e.campaign),
+    );
+
+    for mut campaign in Campaign::get_all(db_conn)? {
+        if !remaining_campaigns.contains(&campaign.name) {
+            changes.push(Change::EmptyCampaign {
+                name: campaign.name,
+            });
+            continue;
+        }
+        let normalized = normalize_symbol(&campaign.symbol);
+        if normalized != campaign.symbol {
+            let from = std::mem::replace(&mut campaign.symbol, normalized);
+            changes.push(Change::CampaignSymbol { campaign, from });
+        }
+    }
+
+    Ok(changes)
+}
+
+fn apply(db_conn: &Connection, changes: &[Change]) -> Result<(), rusqlite::Error> {
+    let tx = db_conn.unchecked_transaction()?;
+    for change in changes {
+        match change {
+            Change::TradeSymbol { trade, .. } => {
+                trade.update(&tx)?;
+            }
+            Change::CampaignSymbol { campaign, .. } => {
+                campaign.update(&tx)?;
+            }
+            Change::DuplicateTrade { id, keep } => {
+                OptionTrade::replace_links(&tx, *id, *keep)?;
+                OptionTrade::delete(&tx, *id)?;
+            }
+            Change::EmptyCampaign { name } => {
+                Campaign::delete(&tx, name)?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+fn changes_table(changes: &[Change]) -> Table {
+    let mut table = Table::new(&["change", "target", "detail"]);
+    for change in changes {
+        let (kind, target, detail) = match change {
+            Change::TradeSymbol { trade, from } => (
+                "normalize symbol",
+                format!("trade {}", trade.id.unwrap_or_default()),
+                format!("'{from}' -> '{}'", trade.symbol),
+            ),
+            Change::CampaignSymbol { campaign, from } => (
+                "normalize symbol",
+                format!("campaign {}", campaign.name),
+                format!("'{from}' -> '{}'", campaign.symbol),
+            ),
+            Change::DuplicateTrade { id, keep } => (
+                "remove duplicate",
+                format!("trade {id}"),
+                format!("duplicate of trade {keep}"),
+            ),
+            Change::EmptyCampaign { name } => (
+                "remove campaign",
+                format!("campaign {name}"),
+                "no trades".to_string(),
+            ),
+        };
+        table.push_row(vec![json!(kind), json!(target), json!(detail)]);
+    }
+    table
+}
+
+pub fn run(db_conn: &Connection, dry_run: bool, format: OutputFormat) -> Result<()> {
+    let changes = plan(db_conn)?;
+
+    if changes.is_empty() {
+        if format == OutputFormat::Table {
+            println!("Nothing to prune");
+        } else {
+            changes_table(&changes).print(format)?;
+        }
+        return Ok(());
+    }
+
+    changes_table(&changes).print(format)?;
+    if dry_run {
+        if format == OutputFormat::Table {
+            println!("Dry run: {} change(s) not applied", changes.len());
+        }
+        return Ok(());
+    }
+
+    apply(db_conn, &changes)?;
+    if format == OutputFormat::Table {
+        println!("Applied {} change(s)", changes.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use crate::error::Result;
+use crate::models::{OptionTrade, TradeQuery};
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+
+pub const TRADE_COLUMNS: [&str; 11] = [
+    "id",
+    "symbol",
+    "campaign",
+    "action",
+    "strike",
+    "delta",
+    "expiration",
+    "date",
+    "shares",
+    "credit",
+    "total",
+];
+
+/// Build an output table with one row per trade.
+pub fn trades_table<'a>(trades: impl IntoIterator<Item = &'a OptionTrade>) -> Table {
+    let mut table = Table::new(&TRADE_COLUMNS);
+    for t in trades {
+        table.push_row(vec![
+            json!(t.id),
+            json!(t.symbol),
+            json!(t.campaign),
+            json!(format!("{:?}", t.action)),
+            json!(t.strike),
+            json!(t.delta),
+            json!(t.expiration_date.to_string()),
+            json!(t.date_of_action.to_string()),
+            json!(t.number_of_shares),
+            json!(t.credit),
+            json!((t.credit * t.number_of_shares as f64 * 100.0).round() / 100.0),
+        ]);
+    }
+    table
+}
+
+/// Print the trades matching `expression`, ordered by `sort` keys (then date and id) and
+/// paged with `limit` and `offset`.
+pub fn run(
+    storage: &mut dyn Storage,
+    expression: &str,
+    sort: &[String],
+    limit: Option<usize>,
+    offset: usize,
+    format: OutputFormat,
+) -> Result<()> {
+    let query = TradeQuery {
+        filter: expression.parse()?,
+        sort: sort.iter().map(|s| s.parse()).collect::<Result<_, _>>()?,
+        limit,
+        offset,
+    };
+    let trades = storage.query_trades(&query)?;
+    trades_table(&trades).print(format)?;
+
+    if format == OutputFormat::Table && (limit.is_some() || offset > 0) {
+        let total = storage.count_trades(&query.filter)?;
+        if trades.is_empty() {
+            println!("No trades past {offset} of {total}");
+        } else {
+            println!("Trades {}-{} of {total}", offset + 1, offset + trades.len());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use crate::csv_processor::read_positions;
+use crate::error::Result;
+use crate::logic::{Instrument, holdings, match_lots};
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use time::OffsetDateTime;
+
+/// Compare a broker's positions export against the open positions and share inventory
+/// computed from the stored trades, listing every instrument where they disagree.
+///
+/// Shares are only compared for symbols that have trades in the database, so stock
+/// bought outside the options strategy doesn't show up as drift.
+pub fn run(storage: &mut dyn Storage, file: &Path, format: OutputFormat) -> Result<()> {
+    let trades = storage.trades()?;
+    let today = OffsetDateTime::now_local()?.date();
+    let tracker = holdings(&match_lots(&trades, today));
+    let traded: HashSet<String> = trades.iter().map(|t| t.symbol.to_uppercase()).collect();
+
+    let mut broker: BTreeMap<Instrument, i32> = BTreeMap::new();
+    for (instrument, quantity) in read_positions(file)? {
+        if instrument.option.is_none() && !traded.contains(&instrument.symbol) {
+            continue;
+        }
+        *broker.entry(instrument).or_default() += quantity;
+    }
+
+    let mut instruments: Vec<&Instrument> = tracker.keys().chain(broker.keys()).collect();
+    instruments.sort();
+    instruments.dedup();
+    let mut table = Table::new(&["instrument", "tracker", "broker", "difference"]);
+    let mut mismatches = 0;
+    for instrument in &instruments {
+        let ours = tracker.get(instrument).copied().unwrap_or(0);
+        let theirs = broker.get(instrument).copied().unwrap_or(0);
+        if ours != theirs {
+            mismatches += 1;
+            table.push_row(vec![
+                json!(instrument.to_string()),
+                json!(ours),
+                json!(theirs),
+                json!(theirs - ours),
+            ]);
+        }
+    }
+
+    if format == OutputFormat::Table && mismatches == 0 {
+        println!(
+            "Positions match {}: {} holding(s) compared",
+            file.display(),
+            instruments.len()
+        );
+        return Ok(());
+    }
+    table.print(format)?;
+    if format == OutputFormat::Table {
+        println!(
+            "{} of {} holding(s) differ; quantities are contracts for options (negative when \
+            short) and shares for stock",
+            mismatches,
+            instruments.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use crate::config;
+use crate::error::Result;
+use crate::logic::{OpenLot, match_lots};
+use crate::models::{OptionKind, OptionTrade};
+use crate::quotes;
+use crate::storage::Storage;
+use std::collections::HashMap;
+use std::process::Command;
+use time::{Date, OffsetDateTime};
+
+/// One line per open position expiring within `days` of `today` or sitting in the money.
+///
+/// `prices` holds current underlying prices; symbols without one are only checked for
+/// expiration.
+pub fn reminders(
+    open: &[OpenLot],
+    today: Date,
+    days: i64,
+    prices: &HashMap<String, f64>,
+) -> Vec<String> {
+    let mut lots: Vec<&OpenLot> = open.iter().collect();
+    lots.sort_by_key(|lot| lot.trade.expiration_date);
+    lots.into_iter()
+        .filter_map(|lot| {
+            let dte = (lot.trade.expiration_date - today).whole_days();
+            let price = prices.get(&lot.trade.symbol).copied();
+            let itm = price.filter(|&p| lot.in_the_money(p));
+            let mut notes = Vec::new();
+            if dte <= days {
+                notes.push(match dte {
+                    0 => "expires today".to_string(),
+                    1 => "expires tomorrow".to_string(),
+                    _ => format!("expires in {dte} days"),
+                });
+            }
+            if let Some(price) = itm {
+                notes.push(format!("ITM at ${price:.2}"));
+            }
+            (!notes.is_empty()).then(|| {
+                format!(
+                    "{} {} ${:.2} {} exp {}: {}",
+                    if lot.short { "Short" } else { "Long" },
+                    lot.trade.symbol,
+                    lot.trade.strike,
+                    match lot.kind {
+                        OptionKind::Put => "put",
+                        OptionKind::Call => "call",
+                    },
+                    lot.trade.expiration_date,
+                    notes.join(", ")
+                )
+            })
+        })
+        .collect()
+}
+
+/// Current prices for the symbols of `open` from the configured quote provider, if any.
+/// Symbols that fail to quote are skipped.
+fn fetch_prices(open: &[OpenLot]) -> Result<HashMap<String, f64>> {
+    let mut prices = HashMap::new();
+    let Some(provider) = quotes::from_config(config::get())? else {
+        return Ok(prices);
+    };
+    for lot in open {
+        let symbol = &lot.trade.symbol;
+        if !prices.contains_key(symbol)
+            && let Ok(price) = provider.last_price(symbol)
+        {
+            prices.insert(symbol.clone(), price);
+        }
+    }
+    Ok(prices)
+}
+
+/// Show a desktop notification using the platform's notification tool.
+fn desktop_notify(title: &str, body: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            quote(body),
+            quote(title)
+        ));
+        command
+    } else if cfg!(windows) {
+        return Err("Desktop notifications are not supported on Windows yet; use --dry-run".into());
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg(title).arg(body);
+        command
+    };
+    let program = command.get_program().to_string_lossy().into_owned();
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{program} failed ({status})").into()),
+        Err(e) => Err(format!("Could not run {program}: {e}").into()),
+    }
+}
+
+/// Check open positions and raise one desktop notification listing those that need
+/// attention. Returns the reminders that were found.
+pub fn check(trades: &[OptionTrade], days: i64, dry_run: bool) -> Result<Vec<String>> {
+    let today = OffsetDateTime::now_local()?.date();
+    let open = match_lots(trades, today).open;
+    let prices = fetch_prices(&open)?;
+    let lines = reminders(&open, today, days, &prices);
+    if !lines.is_empty() && !dry_run {
+        let title = format!("{} option position(s) need attention", lines.len());
+        desktop_notify(&title, &lines.join("\n"))?;
+    }
+    Ok(lines)
+}
+
+pub fn run(storage: &mut dyn Storage, days: Option<i64>, dry_run: bool) -> Result<()> {
+    let days = days.unwrap_or(config::get().remind_days);
+    let lines = check(&storage.trades()?, days, dry_run)?;
+    if lines.is_empty() {
+        println!("No positions expiring within {days} days or in the money");
+    } else {
+        for line in lines {
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
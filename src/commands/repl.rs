@@ -0,0 +1,151 @@
+use crate::error::Result;
+use crate::output::OutputFormat;
+use crate::storage::Storage;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+
+use super::add::NewTrade;
+
+const HISTORY_FILE: &str = ".profit_tracker_history";
+
+const HELP: &str = "\
+Commands:
+  add <campaign> <action> <strike> <expiration> <contracts> <credit> [delta]
+                                 Record a trade (action: SellPut, BuyCall, ...)
+  list [filter]                  List trades, e.g. list symbol=NVTS and action=SellPut
+  positions                      List open positions
+  report [campaign]              Show summary metrics overall or for one campaign
+  close <id> <price|expired> [date]
+                                 Close an open trade
+  help                           Show this help
+  quit                           Exit (also Ctrl-D)
+Quote campaign names containing spaces: add \"My Wheel\" SellPut 6.5 2025-07-03 15 0.18";
+
+enum Outcome {
+    Continue,
+    Quit,
+}
+
+/// Split a command line on whitespace, keeping double-quoted sections together.
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for ch in line.chars() {
+        match ch {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn add_trade(storage: &mut dyn Storage, args: &[String], format: OutputFormat) -> Result<()> {
+    if args.len() < 6 {
+        return Err(
+            "Usage: add <campaign> <action> <strike> <expiration> <contracts> <credit> [delta]"
+                .into(),
+        );
+    }
+    let new = NewTrade {
+        campaign: args[0].clone(),
+        action: args[1].parse()?,
+        strike: args[2]
+            .parse()
+            .map_err(|_| format!("Invalid strike '{}'", args[2]))?,
+        expiration_date: super::parse_date(&args[3])?,
+        contracts: args[4]
+            .parse()
+            .map_err(|_| format!("Invalid contracts '{}'", args[4]))?,
+        credit: args[5]
+            .parse()
+            .map_err(|_| format!("Invalid credit '{}'", args[5]))?,
+        delta: match args.get(6) {
+            Some(d) => d.parse().map_err(|_| format!("Invalid delta '{d}'"))?,
+            None => 0.0,
+        },
+        date: None,
+    };
+    super::add::run(storage, &new, format)
+}
+
+fn execute(storage: &mut dyn Storage, line: &str, format: OutputFormat) -> Result<Outcome> {
+    let tokens = tokenize(line);
+    let Some((command, args)) = tokens.split_first() else {
+        return Ok(Outcome::Continue);
+    };
+
+    match command.to_lowercase().as_str() {
+        "help" | "?" => println!("{HELP}"),
+        "quit" | "exit" => return Ok(Outcome::Quit),
+        "add" => add_trade(storage, args, format)?,
+        "list" => super::query::run(storage, &args.join(" "), &[], None, 0, format)?,
+        "positions" => super::positions::run(storage, format)?,
+        "report" => super::report::run(storage, args.first().map(String::as_str), format)?,
+        "close" => {
+            let (Some(id), Some(price)) = (args.first(), args.get(1)) else {
+                return Err("Usage: close <id> <price|expired> [date]".into());
+            };
+            let trade_id: i32 = id.parse().map_err(|_| format!("Invalid trade id '{id}'"))?;
+            let expired = price.eq_ignore_ascii_case("expired");
+            let price = if expired {
+                None
+            } else {
+                Some(
+                    price
+                        .parse()
+                        .map_err(|_| format!("Invalid price '{price}'"))?,
+                )
+            };
+            super::close::run(
+                storage,
+                trade_id,
+                price,
+                args.get(2).cloned(),
+                expired,
+                None,
+                format,
+            )?;
+        }
+        other => return Err(format!("Unknown command '{other}', type 'help' for a list").into()),
+    }
+    Ok(Outcome::Continue)
+}
+
+pub fn run(storage: &mut dyn Storage, format: OutputFormat) -> Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    // A missing history file just means this is the first session
+    let _ = editor.load_history(HISTORY_FILE);
+
+    println!("profit_tracker REPL. Type 'help' for commands, 'quit' to exit.");
+    loop {
+        match editor.readline("profit_tracker> ") {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                editor.add_history_entry(line)?;
+                match execute(storage, line, format) {
+                    Ok(Outcome::Continue) => {}
+                    Ok(Outcome::Quit) => break,
+                    Err(err) => eprintln!("Error: {err}"),
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    editor.save_history(HISTORY_FILE)?;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use crate::error::Result;
+use crate::logic::{
+    calculate_campaign_summary, calculate_total_premium_sold, calculate_weekly_premium, match_lots,
+};
+use crate::models::{Campaign, CashEvent, OptionTrade, StockTrade};
+use crate::money::to_cents;
+use crate::money::to_dollars;
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use time::OffsetDateTime;
+
+/// Summary metrics for one campaign, or for the whole account when `campaign_name` is
+/// `None`, as `metric`/`value` rows.
+pub fn report_table(
+    campaigns: &[Campaign],
+    trades: &[OptionTrade],
+    stocks: &[StockTrade],
+    cash: &[CashEvent],
+    campaign_name: Option<&str>,
+) -> Result<Table> {
+    let mut table = Table::new(&["metric", "value"]);
+
+    match campaign_name {
+        Some(name) => {
+            let campaign = campaigns
+                .iter()
+                .find(|c| c.name == name)
+                .ok_or_else(|| format!("No campaign named '{name}'"))?;
+            let campaign_trades: Vec<OptionTrade> = trades
+                .iter()
+                .filter(|t| t.campaign == campaign.name && t.symbol == campaign.symbol)
+                .cloned()
+                .collect();
+            let stocks: Vec<StockTrade> = stocks
+                .iter()
+                .filter(|s| s.campaign == campaign.name && s.symbol == campaign.symbol)
+                .cloned()
+                .collect();
+            let trade_refs: Vec<&OptionTrade> = campaign_trades.iter().collect();
+            let (break_even, weeks_running, profit_per_week, total_credits, running_profit_loss) =
+                calculate_campaign_summary(&trade_refs, &stocks, campaign.target_exit_price);
+            let cash_income: i64 = cash
+                .iter()
+                .filter(|e| e.kind.is_income())
+                .filter(|e| e.campaign.as_deref() == Some(campaign.name.as_str()))
+                .map(|e| to_cents(e.amount))
+                .sum();
+            let money = |v: Option<f64>| v.map(|v| format!("{v:.2}")).unwrap_or("N/A".into());
+
+            table.push_row(vec![json!("campaign"), json!(campaign.name)]);
+            table.push_row(vec![json!("symbol"), json!(campaign.symbol)]);
+            table.push_row(vec![
+                json!("target_exit_price"),
+                json!(money(campaign.target_exit_price)),
+            ]);
+            table.push_row(vec![
+                json!("total_credits"),
+                json!(money(Some(to_dollars(total_credits)))),
+            ]);
+            let costs: i64 = campaign_trades.iter().map(OptionTrade::costs_cents).sum();
+            table.push_row(vec![
+                json!("commissions_and_fees"),
+                json!(money(Some(to_dollars(costs)))),
+            ]);
+            table.push_row(vec![
+                json!("cash_income"),
+                json!(money(Some(to_dollars(cash_income)))),
+            ]);
+            table.push_row(vec![
+                json!("running_pl"),
+                json!(money(Some(to_dollars(running_profit_loss + cash_income)))),
+            ]);
+            table.push_row(vec![json!("break_even"), json!(money(break_even))]);
+            table.push_row(vec![json!("weeks_running"), json!(weeks_running)]);
+            table.push_row(vec![
+                json!("profit_per_week"),
+                json!(money(profit_per_week)),
+            ]);
+            table.push_row(vec![
+                json!("weekly_premium"),
+                json!(money(Some(to_dollars(calculate_weekly_premium(
+                    &campaign_trades
+                ))))),
+            ]);
+        }
+        None => {
+            let today = OffsetDateTime::now_local()?.date();
+            let cash_income: i64 = cash
+                .iter()
+                .filter(|e| e.kind.is_income())
+                .map(|e| to_cents(e.amount))
+                .sum();
+            let costs: i64 = trades.iter().map(OptionTrade::costs_cents).sum();
+            table.push_row(vec![
+                json!("commissions_and_fees"),
+                json!(format!("{:.2}", to_dollars(costs))),
+            ]);
+            table.push_row(vec![
+                json!("cash_income"),
+                json!(format!("{:.2}", to_dollars(cash_income))),
+            ]);
+            table.push_row(vec![
+                json!("total_pl"),
+                json!(format!(
+                    "{:.2}",
+                    to_dollars(calculate_total_premium_sold(trades) + cash_income)
+                )),
+            ]);
+            table.push_row(vec![
+                json!("weekly_premium"),
+                json!(format!(
+                    "{:.2}",
+                    to_dollars(calculate_weekly_premium(trades))
+                )),
+            ]);
+            table.push_row(vec![
+                json!("open_positions"),
+                json!(match_lots(trades, today).open.len()),
+            ]);
+            table.push_row(vec![json!("campaigns"), json!(campaigns.len())]);
+            table.push_row(vec![json!("trades"), json!(trades.len())]);
+        }
+    }
+    Ok(table)
+}
+
+pub fn run(
+    storage: &mut dyn Storage,
+    campaign_name: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    report_table(
+        &storage.campaigns()?,
+        &storage.trades()?,
+        &storage.stock_trades()?,
+        &storage.cash_events()?,
+        campaign_name,
+    )?
+    .print(format)?;
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use crate::error::Result;
+use crate::models::OptionTrade;
+use crate::money::format_cents;
+use crate::output::OutputFormat;
+use crate::storage::Storage;
+use time::Date;
+
+use super::close::close_trade;
+use super::query::trades_table;
+
+pub struct RollTarget {
+    pub strike: Option<f64>,
+    pub expiration_date: Date,
+    pub debit: f64,
+    pub credit: f64,
+    pub date: Option<Date>,
+}
+
+/// Close `trade_id` and open the replacement position in one transaction.
+///
+/// Returns the closing trade and the new opening trade, which records the original trade
+/// in `rolled_from_id`.
+pub fn roll_trade(
+    storage: &mut dyn Storage,
+    trade_id: i32,
+    target: &RollTarget,
+) -> Result<(OptionTrade, OptionTrade)> {
+    let mut trades = None;
+    storage.transaction(&mut |storage| {
+        let opening = storage
+            .trades()?
+            .into_iter()
+            .find(|t| t.id == Some(trade_id))
+            .ok_or_else(|| format!("No trade with id {trade_id}"))?;
+
+        let closing = close_trade(storage, trade_id, target.debit, false, target.date, None)?;
+
+        let mut rolled = OptionTrade {
+            id: None,
+            symbol: opening.symbol.clone(),
+            campaign: opening.campaign.clone(),
+            action: opening.action.clone(),
+            strike: target.strike.unwrap_or(opening.strike),
+            delta: 0.0,
+            expiration_date: target.expiration_date,
+            date_of_action: closing.date_of_action,
+            number_of_shares: closing.number_of_shares,
+            credit: target.credit,
+            closes_trade_id: None,
+            rolled_from_id: Some(trade_id),
+            commission: 0.0,
+            fees: 0.0,
+            multiplier: closing.multiplier,
+            status: None,
+            // The new call is covered by the same shares or long call
+            collateral: opening.collateral,
+            covered_by_id: opening.covered_by_id,
+            implied_volatility: None,
+            uuid: None,
+        };
+        rolled.id = Some(storage.insert_trade(&rolled)?);
+        trades = Some((closing, rolled));
+        Ok(())
+    })?;
+    Ok(trades.expect("set by the committed transaction"))
+}
+
+pub fn run(
+    storage: &mut dyn Storage,
+    trade_id: i32,
+    target: RollTarget,
+    format: OutputFormat,
+) -> Result<()> {
+    let (closing, rolled) = roll_trade(storage, trade_id, &target)?;
+
+    if format == OutputFormat::Table {
+        let net = rolled.premium_cents() - closing.premium_cents();
+        println!(
+            "Rolled trade {} to {} {} exp {} (closing trade {}, new trade {}), net {} {}",
+            trade_id,
+            rolled.symbol,
+            rolled.strike,
+            rolled.expiration_date,
+            closing.id.unwrap_or_default(),
+            rolled.id.unwrap_or_default(),
+            if net >= 0 { "credit" } else { "debit" },
+            format_cents(net.abs())
+        );
+    } else {
+        trades_table([&closing, &rolled]).print(format)?;
+    }
+    Ok(())
+}
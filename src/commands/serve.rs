@@ -0,0 +1,169 @@
+use crate::error::Result;
+use crate::logic::match_lots;
+use crate::models::{OptionTrade, TradeQuery};
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use time::OffsetDateTime;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::list::campaigns_table;
+
+const ENDPOINTS: &str = "/campaigns, /campaigns/<name>[/trades], \
+    /trades[?q=<filter>&sort=<fields>&limit=<n>&offset=<n>], \
+    /positions, /snapshots, /summary";
+
+/// Single-page dashboard served at `/`, drawn from the JSON endpoints.
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+/// HTTP status, content type and body of a response.
+type Reply = (u16, &'static str, String);
+
+/// Serve the dashboard and read-only JSON views of the database until the process is
+/// stopped. JSON responses carry the same rows the CLI prints with `--format json`.
+pub fn run(storage: &mut dyn Storage, host: &str, port: u16) -> Result<()> {
+    let server = Server::http((host, port)).map_err(|e| format!("{host}:{port}: {e}"))?;
+    println!(
+        "Serving the dashboard on http://{host}:{port}/ and JSON at {ENDPOINTS} (Ctrl-C to stop)"
+    );
+    for request in server.incoming_requests() {
+        let (status, content_type, body) = if *request.method() == Method::Get {
+            route(storage, request.url()).unwrap_or_else(|e| error(500, &e.to_string()))
+        } else {
+            error(405, "Only GET is supported")
+        };
+        respond(request, status, content_type, body)?;
+    }
+    Ok(())
+}
+
+fn route(storage: &mut dyn Storage, url: &str) -> Result<Reply> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    if let ["campaigns", name, ..] = segments[..]
+        && !storage.campaigns()?.iter().any(|c| c.name == name)
+    {
+        return Ok(error(404, &format!("No campaign named '{name}'")));
+    }
+    let table = match segments[..] {
+        [] => return Ok((200, "text/html; charset=utf-8", DASHBOARD.to_string())),
+        ["campaigns"] => campaigns_table(&storage.campaigns()?),
+        ["campaigns", name] => report_table(storage, Some(name))?,
+        ["campaigns", name, "trades"] => {
+            let mut trades: Vec<OptionTrade> = storage
+                .trades()?
+                .into_iter()
+                .filter(|t| t.campaign == name)
+                .collect();
+            trades.sort_by_key(|t| (t.date_of_action, t.id));
+            super::query::trades_table(&trades)
+        }
+        ["trades"] => match trade_query(query) {
+            Ok(query) => super::query::trades_table(&storage.query_trades(&query)?),
+            Err(e) => return Ok(error(400, &e)),
+        },
+        ["positions"] => {
+            let today = OffsetDateTime::now_local()?.date();
+            let matches = match_lots(&storage.trades()?, today);
+            let realized = matches.realized_by_trade();
+            let mut lots = matches.open;
+            lots.sort_by_key(|lot| (lot.trade.expiration_date, lot.trade.campaign.clone()));
+            super::positions::positions_table(&lots, &realized, &storage.marks()?, today)
+        }
+        ["snapshots"] => super::snapshot::snapshots_table(&storage.snapshots()?),
+        ["summary"] => report_table(storage, None)?,
+        _ => return Ok(error(404, &format!("Unknown path, try {ENDPOINTS}"))),
+    };
+    Ok((200, "application/json", table.render(OutputFormat::Json)?))
+}
+
+/// The `report` metrics for campaign `name`, or the whole account.
+fn report_table(storage: &mut dyn Storage, name: Option<&str>) -> Result<Table> {
+    super::report::report_table(
+        &storage.campaigns()?,
+        &storage.trades()?,
+        &storage.stock_trades()?,
+        &storage.cash_events()?,
+        name,
+    )
+}
+
+/// Build a trade query from the `q`, `sort`, `limit` and `offset` parameters.
+fn trade_query(query: &str) -> Result<TradeQuery, String> {
+    let number = |name: &str| {
+        query_param(query, name)
+            .map(|v| {
+                v.parse::<usize>()
+                    .map_err(|_| format!("Invalid {name} '{v}'"))
+            })
+            .transpose()
+    };
+    Ok(TradeQuery {
+        filter: query_param(query, "q").unwrap_or_default().parse()?,
+        sort: query_param(query, "sort")
+            .iter()
+            .flat_map(|s| s.split(','))
+            .map(str::parse)
+            .collect::<Result<_, _>>()?,
+        limit: number("limit")?,
+        offset: number("offset")?.unwrap_or(0),
+    })
+}
+
+fn error(status: u16, message: &str) -> Reply {
+    (
+        status,
+        "application/json",
+        json!({ "error": message }).to_string(),
+    )
+}
+
+fn respond(request: Request, status: u16, content_type: &str, body: String) -> std::io::Result<()> {
+    let header = |name: &str, value: &str| {
+        Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+    };
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", content_type))
+        // Lets a web page served from elsewhere (e.g. a phone dashboard) fetch the data
+        .with_header(header("Access-Control-Allow-Origin", "*"));
+    request.respond(response)
+}
+
+/// The decoded value of `name` in a `a=1&b=2` query string.
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(&value.replace('+', " ")))
+}
+
+/// Undo URL percent-encoding, leaving malformed escapes as they are.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,59 @@
+use crate::error::Result;
+use crate::logic::calculate_snapshot;
+use crate::models::Snapshot;
+use crate::money::to_dollars;
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use time::{Date, OffsetDateTime};
+
+pub fn snapshots_table(snapshots: &[Snapshot]) -> Table {
+    let mut table = Table::new(&[
+        "date",
+        "realized_pl",
+        "open_collateral",
+        "cash",
+        "share_value",
+    ]);
+    for s in snapshots {
+        table.push_row(vec![
+            json!(s.date.to_string()),
+            json!(to_dollars(s.realized_pl)),
+            json!(to_dollars(s.open_collateral)),
+            json!(to_dollars(s.cash)),
+            json!(to_dollars(s.share_value)),
+        ]);
+    }
+    table
+}
+
+/// Compute and store a snapshot for `date` (today by default).
+pub fn record_snapshot(storage: &mut dyn Storage, date: Option<Date>) -> Result<Snapshot> {
+    let date = match date {
+        Some(d) => d,
+        None => OffsetDateTime::now_local()?.date(),
+    };
+    let snapshot = calculate_snapshot(
+        &storage.trades()?,
+        &storage.stock_trades()?,
+        &storage.cash_events()?,
+        date,
+    );
+    storage.save_snapshot(&snapshot)?;
+    Ok(snapshot)
+}
+
+pub fn run(
+    storage: &mut dyn Storage,
+    date: Option<String>,
+    list: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    if list {
+        return snapshots_table(&storage.snapshots()?).print(format);
+    }
+
+    let date = date.as_deref().map(super::parse_date).transpose()?;
+    let snapshot = record_snapshot(storage, date)?;
+    snapshots_table(&[snapshot]).print(format)
+}
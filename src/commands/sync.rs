@@ -0,0 +1,475 @@
+use crate::config::DEFAULT_MULTIPLIER;
+use crate::error::Result;
+use crate::models::{Action, Campaign, OptionTrade, TradeStatus};
+use crate::output::{OutputFormat, Table};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// A trade as exchanged between devices, with links expressed as uuids.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SyncedTrade {
+    uuid: String,
+    symbol: String,
+    campaign: String,
+    action: Action,
+    strike: f64,
+    delta: f64,
+    expiration_date: String,
+    date_of_action: String,
+    number_of_shares: i32,
+    credit: f64,
+    closes_trade_uuid: Option<String>,
+    rolled_from_uuid: Option<String>,
+    // Left out when zero so trades synced before these existed keep their fingerprint
+    #[serde(default, skip_serializing_if = "is_zero")]
+    commission: f64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    fees: f64,
+    #[serde(
+        default = "default_multiplier",
+        skip_serializing_if = "is_default_multiplier"
+    )]
+    multiplier: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<TradeStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    collateral: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    covered_by_uuid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    implied_volatility: Option<f64>,
+}
+
+fn is_zero(amount: &f64) -> bool {
+    *amount == 0.0
+}
+
+fn default_multiplier() -> i32 {
+    DEFAULT_MULTIPLIER
+}
+
+fn is_default_multiplier(multiplier: &i32) -> bool {
+    *multiplier == DEFAULT_MULTIPLIER
+}
+
+/// Everything one device knows, written to `<device>.json` in the sync folder.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChangeSet {
+    device: String,
+    trades: Vec<SyncedTrade>,
+}
+
+#[derive(Debug, Default)]
+struct MergeReport {
+    added: usize,
+    updated: usize,
+    deleted: usize,
+    conflicts: Vec<(String, String, String)>, // device, uuid, reason
+}
+
+impl SyncedTrade {
+    fn fingerprint(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// The fields the database's dedup key is built from.
+    fn dedup_key(&self) -> (&str, &str, &Action, f64, &str, &str, i32, f64) {
+        (
+            &self.symbol,
+            &self.campaign,
+            &self.action,
+            self.strike,
+            &self.expiration_date,
+            &self.date_of_action,
+            self.number_of_shares,
+            self.credit,
+        )
+    }
+
+    /// Whether both describe the same transaction, which the database records only once
+    /// whatever its delta, identity or links.
+    fn same_transaction(&self, other: &SyncedTrade) -> bool {
+        self.dedup_key() == other.dedup_key()
+    }
+
+    fn to_trade(&self, id: Option<i32>) -> Result<OptionTrade, String> {
+        Ok(OptionTrade {
+            id,
+            symbol: self.symbol.clone(),
+            campaign: self.campaign.clone(),
+            action: self.action.clone(),
+            strike: self.strike,
+            delta: self.delta,
+            expiration_date: super::parse_date(&self.expiration_date)?,
+            date_of_action: super::parse_date(&self.date_of_action)?,
+            number_of_shares: self.number_of_shares,
+            credit: self.credit,
+            closes_trade_id: None,
+            rolled_from_id: None,
+            commission: self.commission,
+            fees: self.fees,
+            multiplier: self.multiplier,
+            status: self.status,
+            collateral: self.collateral,
+            covered_by_id: None,
+            implied_volatility: self.implied_volatility,
+            uuid: Some(self.uuid.clone()),
+        })
+    }
+}
+
+/// This database's device id, generated on first use.
+fn device_id(db_conn: &Connection) -> Result<String, rusqlite::Error> {
+    let existing: Option<String> = db_conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'device_id'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+    db_conn.execute(
+        "INSERT INTO meta (key, value) VALUES ('device_id', lower(hex(randomblob(8))))",
+        [],
+    )?;
+    device_id(db_conn)
+}
+
+/// Local trades keyed by uuid, along with their row ids.
+fn local_trades(db_conn: &Connection) -> Result<HashMap<String, (i32, SyncedTrade)>> {
+    let mut stmt = db_conn.prepare("SELECT id, uuid FROM option_trades")?;
+    let uuids: HashMap<i32, String> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    let link = |id: Option<i32>| id.and_then(|id| uuids.get(&id).cloned());
+
+    let mut trades = HashMap::new();
+    for t in OptionTrade::get_all(db_conn)? {
+        let Some(id) = t.id else { continue };
+        let uuid = uuids[&id].clone();
+        let synced = SyncedTrade {
+            uuid: uuid.clone(),
+            symbol: t.symbol,
+            campaign: t.campaign,
+            action: t.action,
+            strike: t.strike,
+            delta: t.delta,
+            expiration_date: t.expiration_date.to_string(),
+            date_of_action: t.date_of_action.to_string(),
+            number_of_shares: t.number_of_shares,
+            credit: t.credit,
+            closes_trade_uuid: link(t.closes_trade_id),
+            rolled_from_uuid: link(t.rolled_from_id),
+            commission: t.commission,
+            fees: t.fees,
+            multiplier: t.multiplier,
+            status: t.status,
+            collateral: t.collateral,
+            covered_by_uuid: link(t.covered_by_id),
+            implied_volatility: t.implied_volatility,
+        };
+        trades.insert(uuid, (id, synced));
+    }
+    Ok(trades)
+}
+
+fn set_base(
+    db_conn: &Connection,
+    device: &str,
+    uuid: &str,
+    fingerprint: Option<&str>,
+) -> Result<(), rusqlite::Error> {
+    match fingerprint {
+        Some(fp) => db_conn.execute(
+            "INSERT INTO sync_state (device, uuid, fingerprint) VALUES (?1, ?2, ?3)
+            ON CONFLICT(device, uuid) DO UPDATE SET fingerprint = ?3",
+            params![device, uuid, fp],
+        )?,
+        None => db_conn.execute(
+            "DELETE FROM sync_state WHERE device = ?1 AND uuid = ?2",
+            params![device, uuid],
+        )?,
+    };
+    Ok(())
+}
+
+/// Three-way merge of a remote device's trades into the local database.
+///
+/// The base is the last state this database and the remote agreed on. A side that
+/// still matches the base takes the other side's change; when both changed, the
+/// local trade is kept and the difference reported as a conflict.
+fn merge(db_conn: &Connection, remote: &ChangeSet, report: &mut MergeReport) -> Result<()> {
+    let device = remote.device.as_str();
+    let local = local_trades(db_conn)?;
+    let remote_trades: HashMap<&str, &SyncedTrade> =
+        remote.trades.iter().map(|t| (t.uuid.as_str(), t)).collect();
+    let mut stmt = db_conn.prepare("SELECT uuid, fingerprint FROM sync_state WHERE device = ?1")?;
+    let base: HashMap<String, String> = stmt
+        .query_map([device], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let uuids: BTreeSet<&str> = local
+        .keys()
+        .map(String::as_str)
+        .chain(remote_trades.keys().copied())
+        .chain(base.keys().map(String::as_str))
+        .collect();
+
+    let mut conflict = |uuid: &str, reason: &str| {
+        report
+            .conflicts
+            .push((device.to_string(), uuid.to_string(), reason.to_string()));
+    };
+    let mut incoming: Vec<(Option<i32>, &SyncedTrade)> = Vec::new();
+    let mut removed: Vec<(i32, &str)> = Vec::new();
+    for uuid in uuids {
+        let local = local.get(uuid);
+        let remote = remote_trades.get(uuid).copied();
+        let base = base.get(uuid).map(String::as_str);
+        match (local, remote) {
+            (Some((id, l)), Some(r)) => {
+                let (lf, rf) = (l.fingerprint(), r.fingerprint());
+                if lf == rf {
+                    set_base(db_conn, device, uuid, Some(&lf))?;
+                } else if base == Some(lf.as_str()) {
+                    incoming.push((Some(*id), r));
+                } else if base != Some(rf.as_str()) {
+                    conflict(uuid, "changed on both devices");
+                }
+            }
+            (None, Some(r)) => match base {
+                None => incoming.push((None, r)),
+                Some(b) if b == r.fingerprint() => {} // deleted here, not yet there
+                Some(_) => conflict(uuid, "deleted here but changed on the other device"),
+            },
+            (Some((id, l)), None) => match base {
+                None => {} // new here, not yet there
+                Some(b) if b == l.fingerprint() => removed.push((*id, uuid)),
+                Some(_) => conflict(uuid, "deleted on the other device but changed here"),
+            },
+            (None, None) => set_base(db_conn, device, uuid, None)?,
+        }
+    }
+
+    // Write rows first and links second, since links may point at incoming trades
+    let mut written: Vec<(i32, &SyncedTrade)> = Vec::new();
+    for (id, r) in incoming {
+        let trade = r.to_trade(id)?;
+        if !Campaign::get_all(db_conn)?
+            .iter()
+            .any(|c| c.name == trade.campaign)
+        {
+            Campaign::insert(db_conn, &trade.campaign, &trade.symbol, None)?;
+        }
+        match id {
+            Some(id) => {
+                trade.update(db_conn)?;
+                report.updated += 1;
+                written.push((id, r));
+            }
+            None if trade.insert_or_ignore(db_conn)? => {
+                let id = db_conn.last_insert_rowid() as i32;
+                db_conn.execute(
+                    "UPDATE option_trades SET uuid = ?1 WHERE id = ?2",
+                    params![r.uuid, id],
+                )?;
+                report.added += 1;
+                written.push((id, r));
+            }
+            None => {
+                // Both devices recorded the same transaction independently (e.g. by
+                // importing the same statement). Settle on the smaller uuid so every
+                // device converges on one identity without duplicating the trade.
+                let twin = local
+                    .values()
+                    .find(|(_, l)| l.same_transaction(r))
+                    .filter(|(_, l)| r.uuid < l.uuid);
+                if let Some((twin_id, _)) = twin {
+                    db_conn.execute(
+                        "UPDATE option_trades SET uuid = ?1 WHERE id = ?2",
+                        params![r.uuid, twin_id],
+                    )?;
+                }
+            }
+        }
+    }
+    for (id, r) in written {
+        let resolve = |uuid: &Option<String>| -> Result<Option<i32>, rusqlite::Error> {
+            match uuid {
+                Some(uuid) => db_conn
+                    .query_row(
+                        "SELECT id FROM option_trades WHERE uuid = ?1",
+                        [uuid],
+                        |row| row.get(0),
+                    )
+                    .optional(),
+                None => Ok(None),
+            }
+        };
+        db_conn.execute(
+            "UPDATE option_trades SET closes_trade_id = ?1, rolled_from_id = ?2, covered_by_id = ?3
+            WHERE id = ?4",
+            params![
+                resolve(&r.closes_trade_uuid)?,
+                resolve(&r.rolled_from_uuid)?,
+                resolve(&r.covered_by_uuid)?,
+                id
+            ],
+        )?;
+        set_base(db_conn, device, &r.uuid, Some(&r.fingerprint()))?;
+    }
+    for (id, uuid) in removed {
+        db_conn.execute(
+            "UPDATE option_trades SET closes_trade_id = NULL WHERE closes_trade_id = ?1",
+            [id],
+        )?;
+        db_conn.execute(
+            "UPDATE option_trades SET rolled_from_id = NULL WHERE rolled_from_id = ?1",
+            [id],
+        )?;
+        db_conn.execute(
+            "UPDATE option_trades SET covered_by_id = NULL WHERE covered_by_id = ?1",
+            [id],
+        )?;
+        OptionTrade::delete(db_conn, id)?;
+        set_base(db_conn, device, uuid, None)?;
+        report.deleted += 1;
+    }
+    Ok(())
+}
+
+/// Merge every other device's change set in `dir`, then publish this device's own.
+fn sync_dir(db_conn: &Connection, dir: &Path) -> Result<MergeReport> {
+    std::fs::create_dir_all(dir)?;
+    let device = device_id(db_conn)?;
+    let mut report = MergeReport::default();
+
+    let tx = db_conn.unchecked_transaction()?;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json")
+            || path.file_stem().is_some_and(|stem| *stem == *device)
+        {
+            continue;
+        }
+        let remote: ChangeSet = serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| format!("Invalid change set {}: {e}", path.display()))?;
+        merge(&tx, &remote, &mut report)?;
+    }
+    tx.commit()?;
+
+    let mut trades: Vec<SyncedTrade> = local_trades(db_conn)?
+        .into_values()
+        .map(|(_, t)| t)
+        .collect();
+    trades.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+    let own = ChangeSet {
+        device: device.clone(),
+        trades,
+    };
+    // Write then rename so other devices never read a half-written file
+    let tmp = dir.join(format!(".{device}.json.tmp"));
+    std::fs::write(&tmp, serde_json::to_string_pretty(&own)?)?;
+    std::fs::rename(&tmp, dir.join(format!("{device}.json")))?;
+    Ok(report)
+}
+
+pub fn run(db_conn: &Connection, dir: &Path, format: OutputFormat) -> Result<()> {
+    let report = sync_dir(db_conn, dir)?;
+
+    if format != OutputFormat::Table || !report.conflicts.is_empty() {
+        let mut table = Table::new(&["device", "uuid", "conflict"]);
+        for (device, uuid, reason) in &report.conflicts {
+            table.push_row(vec![json!(device), json!(uuid), json!(reason)]);
+        }
+        table.print(format)?;
+    }
+    if format == OutputFormat::Table {
+        println!(
+            "Synced with {}: {} added, {} updated, {} deleted, {} conflict(s)",
+            dir.display(),
+            report.added,
+            report.updated,
+            report.deleted,
+            report.conflicts.len()
+        );
+        if !report.conflicts.is_empty() {
+            println!(
+                "Conflicting trades were left as they are here; edit them to the intended values and sync again."
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_database;
+
+    fn open() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_database(&conn, &Default::default()).unwrap();
+        conn.execute_batch(
+            "INSERT INTO campaigns (name, symbol, created_at) VALUES ('wheel', 'NVTS', '2025-06-01');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn credits(conn: &Connection) -> Vec<f64> {
+        let mut credits: Vec<f64> = OptionTrade::get_all(conn)
+            .unwrap()
+            .iter()
+            .map(|t| t.credit)
+            .collect();
+        credits.sort_by(f64::total_cmp);
+        credits
+    }
+
+    #[test]
+    fn test_sync_merges_and_detects_conflicts() {
+        let dir = std::env::temp_dir().join(format!("profit_tracker_sync_{}", std::process::id()));
+        let (a, b) = (open(), open());
+        let insert = "INSERT INTO option_trades (symbol, campaign_id, action, strike_cents, delta,
+            expiration_date, date_of_action, number_of_shares, premium_cents)
+            VALUES ('NVTS', 1, 'SellPut', 650, 0.2, '2025-07-03', '2025-06-26', 1500, ?1)";
+        a.execute(insert, [27000]).unwrap();
+        b.execute(insert, [37500]).unwrap();
+
+        // The same transaction imported on both devices must not be duplicated
+        a.execute(insert, [45000]).unwrap();
+        b.execute(insert, [45000]).unwrap();
+
+        sync_dir(&a, &dir).unwrap();
+        sync_dir(&b, &dir).unwrap();
+        sync_dir(&a, &dir).unwrap();
+        assert_eq!(credits(&a), [0.18, 0.25, 0.30]);
+        assert_eq!(credits(&b), [0.18, 0.25, 0.30]);
+
+        // One-sided edits propagate; edits to the same trade on both sides conflict
+        let edit = "UPDATE option_trades SET premium_cents = ?1 WHERE premium_cents = ?2";
+        a.execute(edit, [28500, 27000]).unwrap();
+        b.execute(edit, [39000, 37500]).unwrap();
+        a.execute(edit, [40500, 37500]).unwrap();
+        sync_dir(&a, &dir).unwrap();
+        let report = sync_dir(&b, &dir).unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(credits(&b), [0.19, 0.26, 0.30]);
+
+        // Deletions propagate too
+        a.execute("DELETE FROM option_trades WHERE premium_cents = 45000", [])
+            .unwrap();
+        sync_dir(&a, &dir).unwrap();
+        assert_eq!(sync_dir(&b, &dir).unwrap().deleted, 1);
+        assert_eq!(credits(&b), [0.19, 0.26]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+use crate::config;
+use crate::error::Result;
+use crate::metrics::Metrics;
+use crate::models::OptionTrade;
+use crate::money::{format_cents, to_dollars};
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+
+/// Premium and P/L per tag. A trade with several tags counts toward each of them, and
+/// untagged trades are left out.
+pub fn tags_table(trades: &[OptionTrade], tags: &HashMap<i32, Vec<String>>) -> Table {
+    let mut by_tag: BTreeMap<&str, Vec<OptionTrade>> = BTreeMap::new();
+    for trade in trades {
+        for tag in trade.id.and_then(|id| tags.get(&id)).into_iter().flatten() {
+            by_tag.entry(tag).or_default().push(trade.clone());
+        }
+    }
+    let mut table = Table::new(&[
+        "tag",
+        "trades",
+        "premium_sold",
+        "premium_bought",
+        "costs",
+        "net_premium",
+        "running_pl",
+    ]);
+    for (tag, tagged) in &by_tag {
+        let totals = Metrics::new(tagged, config::get().week_start).total();
+        table.push_row(vec![
+            json!(tag),
+            json!(totals.trades),
+            json!(to_dollars(totals.sold)),
+            json!(to_dollars(totals.bought)),
+            json!(to_dollars(totals.costs)),
+            json!(to_dollars(totals.net_premium())),
+            json!(to_dollars(totals.running_pl())),
+        ]);
+    }
+    table
+}
+
+pub fn run(storage: &mut dyn Storage, format: OutputFormat) -> Result<()> {
+    let trades = storage.trades()?;
+    let tags = storage.trade_tags()?;
+    if tags.is_empty() && format == OutputFormat::Table {
+        println!("No tagged trades");
+        return Ok(());
+    }
+
+    tags_table(&trades, &tags).print(format)?;
+    if format == OutputFormat::Table {
+        let untagged: Vec<OptionTrade> = trades
+            .into_iter()
+            .filter(|t| t.id.is_none_or(|id| !tags.contains_key(&id)))
+            .collect();
+        let totals = Metrics::new(&untagged, config::get().week_start).total();
+        println!(
+            "{} untagged trade(s), running P/L {}",
+            totals.trades,
+            format_cents(totals.running_pl())
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Action;
+    use time::macros::date;
+
+    #[test]
+    fn test_tags_table_counts_trade_under_each_tag() {
+        let trade = |id, action, credit| OptionTrade {
+            id: Some(id),
+            symbol: "NVTS".to_string(),
+            campaign: "wheel".to_string(),
+            action,
+            strike: 6.5,
+            delta: 0.2,
+            expiration_date: date!(2025 - 07 - 03),
+            date_of_action: date!(2025 - 06 - 26),
+            number_of_shares: 100,
+            credit,
+            ..Default::default()
+        };
+        let trades = [
+            trade(1, Action::SellPut, 0.5),
+            trade(2, Action::BuyPut, 0.2),
+            trade(3, Action::SellPut, 0.3),
+        ];
+        let tags = HashMap::from([
+            (1, vec!["earnings".to_string(), "high-iv".to_string()]),
+            (2, vec!["earnings".to_string()]),
+        ]);
+
+        let json = tags_table(&trades, &tags)
+            .render(OutputFormat::Json)
+            .unwrap();
+        let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(rows.as_array().unwrap().len(), 2);
+        assert_eq!(rows[0]["tag"], "earnings");
+        assert_eq!(rows[0]["trades"], 2);
+        assert_eq!(rows[0]["running_pl"], 30.0);
+        assert_eq!(rows[1]["tag"], "high-iv");
+        assert_eq!(rows[1]["running_pl"], 50.0);
+    }
+}
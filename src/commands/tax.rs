@@ -0,0 +1,133 @@
+use crate::error::Result;
+use crate::logic::{CloseReason, ClosedLot, WashSale, match_lots, wash_sales};
+use crate::models::OptionKind;
+use crate::money::{format_cents, to_dollars};
+use crate::output::{OutputFormat, Table};
+use crate::storage::Storage;
+use serde_json::json;
+use std::path::PathBuf;
+use time::OffsetDateTime;
+
+fn describe(lot: &ClosedLot) -> String {
+    let kind = match lot.kind {
+        OptionKind::Put => "Put",
+        OptionKind::Call => "Call",
+    };
+    format!(
+        "{} {} {} {} {}",
+        lot.contracts(),
+        lot.symbol,
+        lot.expiration_date,
+        lot.strike,
+        kind
+    )
+}
+
+/// Build a Form 8949 style table of realized gains, one row per matched lot. Wash sales
+/// get adjustment code W and the disallowed loss as the adjustment.
+pub fn gains_table(lots: &[(&ClosedLot, Option<&WashSale>)]) -> Table {
+    let mut table = Table::new(&[
+        "description",
+        "campaign",
+        "date_acquired",
+        "date_sold",
+        "proceeds",
+        "cost_basis",
+        "code",
+        "adjustment",
+        "gain",
+        "close_reason",
+        "replaced_by",
+    ]);
+    for (lot, wash) in lots {
+        // Short options are "acquired" when the position is closed
+        let (acquired, sold) = if lot.short {
+            (lot.closed, lot.opened)
+        } else {
+            (lot.opened, lot.closed)
+        };
+        table.push_row(vec![
+            json!(describe(lot)),
+            json!(lot.campaign),
+            json!(acquired.to_string()),
+            json!(sold.to_string()),
+            json!(to_dollars(lot.proceeds())),
+            json!(to_dollars(lot.cost_basis())),
+            json!(wash.map(|_| "W")),
+            json!(wash.map(|w| to_dollars(w.disallowed))),
+            json!(to_dollars(lot.gain() + wash.map_or(0, |w| w.disallowed))),
+            json!(lot.reason.as_str()),
+            json!(wash.and_then(|w| w.replacement_id)),
+        ]);
+    }
+    table
+}
+
+pub fn run(
+    storage: &mut dyn Storage,
+    year: i32,
+    out: Option<PathBuf>,
+    format: OutputFormat,
+) -> Result<()> {
+    let trades = storage.trades()?;
+    let today = OffsetDateTime::now_local()?.date();
+
+    let matches = match_lots(&trades, today);
+    let washes = wash_sales(&matches, &trades);
+    let lots: Vec<(&ClosedLot, Option<&WashSale>)> = matches
+        .closed
+        .iter()
+        .enumerate()
+        .filter(|(_, lot)| lot.closed.year() == year)
+        .map(|(i, lot)| (lot, washes.get(&i)))
+        .collect();
+    let disallowed: i64 = lots
+        .iter()
+        .filter_map(|(_, w)| w.map(|w| w.disallowed))
+        .sum();
+    let total: i64 = lots.iter().map(|(lot, _)| lot.gain()).sum::<i64>() + disallowed;
+    let assigned = lots
+        .iter()
+        .filter(|(lot, _)| lot.reason == CloseReason::Assigned)
+        .count();
+
+    let table = gains_table(&lots);
+    match out {
+        Some(path) => {
+            // Tables are meant for terminals; files default to CSV
+            let file_format = match format {
+                OutputFormat::Table => OutputFormat::Csv,
+                other => other,
+            };
+            std::fs::write(&path, table.render(file_format)?)?;
+            println!(
+                "Wrote {} realized gains/losses for {} to {} (net {})",
+                lots.len(),
+                year,
+                path.display(),
+                format_cents(total)
+            );
+        }
+        None => {
+            table.print(format)?;
+            if format != OutputFormat::Table {
+                return Ok(());
+            }
+            println!("Net realized gain/loss for {year}: {}", format_cents(total));
+        }
+    }
+    if disallowed > 0 {
+        println!(
+            "Note: {} wash sale(s) disallow {} of losses (code W); the replacement positions' basis goes up by that much.",
+            lots.iter().filter(|(_, w)| w.is_some()).count(),
+            format_cents(disallowed)
+        );
+    }
+    if assigned > 0 {
+        println!(
+            "Note: {assigned} lot(s) closed by assignment; their premium may adjust the basis of the assigned shares instead."
+        );
+    }
+
+    Ok(())
+}
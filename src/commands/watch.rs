@@ -0,0 +1,155 @@
+use crate::csv_processor::{Broker, CsvProcessor, ParsedCsv};
+use crate::error::Result;
+use crate::storage::{self, Storage};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use time::OffsetDateTime;
+
+/// How often the folder is scanned for new files.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Size and modification time, used to notice new and rewritten files.
+type Stamp = (u64, Option<SystemTime>);
+
+/// Import every broker statement that appears in `dir` until the process is stopped.
+///
+/// CSVs already in the folder when watching starts are left alone. A new file is
+/// imported once it has stopped changing between two scans, so downloads still being
+/// written aren't read half finished. Files that aren't a recognized broker statement
+/// are reported once and skipped. Trades keep the campaign and symbol the parser gives
+/// them unless `campaign` or `symbol` override them, and trades already recorded are
+/// skipped as on any import.
+pub fn run(
+    storage: &mut dyn Storage,
+    dir: &Path,
+    campaign: Option<&str>,
+    symbol: Option<&str>,
+) -> Result<()> {
+    let mut seen = scan(dir)?;
+    let mut pending: HashMap<PathBuf, Stamp> = HashMap::new();
+    println!(
+        "Watching {} for broker CSVs ({} already there are skipped; Ctrl-C to stop)",
+        dir.display(),
+        seen.len()
+    );
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        for (path, stamp) in scan(dir)? {
+            if seen.get(&path) == Some(&stamp) {
+                continue;
+            }
+            // Wait for a second scan with the same size and time before reading it
+            if pending.insert(path.clone(), stamp) != Some(stamp) {
+                continue;
+            }
+            pending.remove(&path);
+            seen.insert(path.clone(), stamp);
+            let time = OffsetDateTime::now_local()?.format(time::macros::format_description!(
+                "[hour]:[minute]:[second]"
+            ))?;
+            match import_file(storage, &path, campaign, symbol) {
+                Ok(message) => println!("{time} {}: {message}", path.display()),
+                Err(e) => eprintln!("{time} {}: {e}", path.display()),
+            }
+        }
+    }
+}
+
+/// The CSV files directly inside `dir`.
+fn scan(dir: &Path) -> Result<HashMap<PathBuf, Stamp>> {
+    let mut files = HashMap::new();
+    for entry in std::fs::read_dir(dir).map_err(|e| format!("{}: {e}", dir.display()))? {
+        let entry = entry?;
+        let path = entry.path();
+        if !path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+        {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue; // removed while scanning
+        };
+        if metadata.is_file() {
+            files.insert(path, (metadata.len(), metadata.modified().ok()));
+        }
+    }
+    Ok(files)
+}
+
+/// Import one statement, returning what was done.
+fn import_file(
+    storage: &mut dyn Storage,
+    path: &Path,
+    campaign: Option<&str>,
+    symbol: Option<&str>,
+) -> Result<String> {
+    let Some(broker) = Broker::detect(path)? else {
+        return Ok("not a recognized broker statement, skipped".to_string());
+    };
+    let parsed = CsvProcessor::new(broker.clone()).parse_csv(path)?;
+    let mut by_campaign: BTreeMap<(String, String), ParsedCsv> = BTreeMap::new();
+    let key = |trade_campaign: &str, trade_symbol: &str| {
+        (
+            campaign.unwrap_or(trade_campaign).to_string(),
+            symbol.unwrap_or(trade_symbol).to_string(),
+        )
+    };
+    for mut trade in parsed.trades {
+        (trade.campaign, trade.symbol) = key(&trade.campaign, &trade.symbol);
+        by_campaign
+            .entry((trade.campaign.clone(), trade.symbol.clone()))
+            .or_default()
+            .trades
+            .push(trade);
+    }
+    for mut stock in parsed.stocks {
+        (stock.campaign, stock.symbol) = key(&stock.campaign, &stock.symbol);
+        by_campaign
+            .entry((stock.campaign.clone(), stock.symbol.clone()))
+            .or_default()
+            .stocks
+            .push(stock);
+    }
+    // Interest on the account's cash stays out of the campaigns unless one is given
+    let mut account_cash = Vec::new();
+    for mut event in parsed.cash {
+        let (Some(event_campaign), Some(event_symbol)) = (
+            campaign.or(event.campaign.as_deref()),
+            symbol.or(event.symbol.as_deref()),
+        ) else {
+            account_cash.push(event);
+            continue;
+        };
+        let (event_campaign, event_symbol) = (event_campaign.to_string(), event_symbol.to_string());
+        event.campaign = Some(event_campaign.clone());
+        event.symbol = Some(event_symbol.clone());
+        by_campaign
+            .entry((event_campaign, event_symbol))
+            .or_default()
+            .cash
+            .push(event);
+    }
+
+    let (mut imported, mut duplicates) = (0, 0);
+    storage.transaction(&mut |storage| {
+        (imported, duplicates) = (0, 0);
+        for ((campaign, symbol), parsed) in &by_campaign {
+            let (new, skipped) = storage::import_trades(storage, campaign, symbol, parsed)?;
+            imported += new;
+            duplicates += skipped;
+        }
+        for event in &account_cash {
+            if storage.insert_cash_event_if_new(event)? {
+                imported += 1;
+            } else {
+                duplicates += 1;
+            }
+        }
+        Ok(())
+    })?;
+    Ok(format!(
+        "imported {imported} trades from a {broker} statement, {duplicates} already recorded"
+    ))
+}
@@ -0,0 +1,207 @@
+//! Excel export: a summary sheet with campaign and account totals and weekly premium,
+//! then one sheet of trades per campaign.
+
+use crate::config;
+use crate::error::Result;
+use crate::metrics::{Metrics, Totals};
+use crate::models::OptionTrade;
+use crate::money::to_dollars;
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, Worksheet, XlsxError};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use time::Date;
+
+const SUMMARY_HEADERS: [&str; 10] = [
+    "Campaign",
+    "Trades",
+    "Premium sold",
+    "Premium bought",
+    "Assigned",
+    "Costs",
+    "Net premium",
+    "Running P/L",
+    "Capital at risk",
+    "ROIC",
+];
+
+const TRADE_HEADERS: [&str; 12] = [
+    "ID",
+    "Symbol",
+    "Date",
+    "Action",
+    "Strike",
+    "Expiration",
+    "Shares",
+    "Delta",
+    "Credit",
+    "Premium",
+    "Commission",
+    "Fees",
+];
+
+/// Excel's longest sheet name.
+const MAX_SHEET_NAME: usize = 31;
+
+/// Cell formats shared by every sheet.
+struct Formats {
+    header: Format,
+    money: Format,
+    percent: Format,
+    date: Format,
+}
+
+/// Write `trades` to an .xlsx workbook at `out`: a `Summary` sheet with each campaign's
+/// totals, the account totals and net premium by week, followed by a sheet per campaign
+/// listing its trades. Returns the number of campaign sheets written.
+pub fn write(trades: &[OptionTrade], out: &Path) -> Result<usize> {
+    let metrics = Metrics::new(trades, config::get().week_start);
+    let mut campaigns: BTreeMap<&str, Vec<&OptionTrade>> = BTreeMap::new();
+    for trade in trades {
+        campaigns.entry(&trade.campaign).or_default().push(trade);
+    }
+    let formats = Formats {
+        header: Format::new().set_bold(),
+        money: Format::new().set_num_format("#,##0.00"),
+        percent: Format::new().set_num_format("0.00%"),
+        date: Format::new().set_num_format("yyyy-mm-dd"),
+    };
+
+    let mut workbook = Workbook::new();
+    let summary = workbook.add_worksheet().set_name("Summary")?;
+    summary.write_row_with_format(0, 0, SUMMARY_HEADERS, &formats.header)?;
+    let mut row = 0;
+    for (name, totals) in campaigns
+        .keys()
+        .map(|name| (*name, metrics.campaign(name)))
+        .chain([("Total", metrics.total())])
+    {
+        row += 1;
+        write_totals(summary, row, name, &totals, &formats)?;
+    }
+    row += 2;
+    summary.write_row_with_format(row, 0, ["Week", "Net premium"], &formats.header)?;
+    for (week, premium) in metrics.weekly_premium() {
+        row += 1;
+        summary.write_datetime_with_format(row, 0, excel_date(*week)?, &formats.date)?;
+        summary.write_number_with_format(row, 1, to_dollars(*premium), &formats.money)?;
+    }
+    summary.set_freeze_panes(1, 0)?;
+    summary.autofit();
+
+    // Names are compared without case, as Excel does
+    let mut taken = HashSet::from(["summary".to_string(), "history".to_string()]);
+    for (name, campaign_trades) in &campaigns {
+        let sheet = workbook
+            .add_worksheet()
+            .set_name(sheet_name(name, &mut taken))?;
+        sheet.write_row_with_format(0, 0, TRADE_HEADERS, &formats.header)?;
+        for (row, trade) in (1..).zip(campaign_trades) {
+            write_trade(sheet, row, trade, &formats)?;
+        }
+        sheet.set_freeze_panes(1, 0)?;
+        sheet.autofit();
+    }
+
+    workbook.save(out)?;
+    Ok(campaigns.len())
+}
+
+fn write_totals(
+    sheet: &mut Worksheet,
+    row: u32,
+    name: &str,
+    totals: &Totals,
+    formats: &Formats,
+) -> Result<(), XlsxError> {
+    sheet.write_string(row, 0, name)?;
+    sheet.write_number(row, 1, totals.trades as f64)?;
+    for (col, cents) in (2..).zip([
+        totals.sold,
+        totals.bought,
+        totals.assigned,
+        totals.costs,
+        totals.net_premium(),
+        totals.running_pl(),
+        totals.capital_at_risk,
+    ]) {
+        sheet.write_number_with_format(row, col, to_dollars(cents), &formats.money)?;
+    }
+    if let Some(roic) = totals.roic() {
+        sheet.write_number_with_format(row, 9, roic, &formats.percent)?;
+    }
+    Ok(())
+}
+
+fn write_trade(
+    sheet: &mut Worksheet,
+    row: u32,
+    trade: &OptionTrade,
+    formats: &Formats,
+) -> Result<()> {
+    if let Some(id) = trade.id {
+        sheet.write_number(row, 0, id)?;
+    }
+    sheet.write_string(row, 1, &trade.symbol)?;
+    sheet.write_datetime_with_format(row, 2, excel_date(trade.date_of_action)?, &formats.date)?;
+    sheet.write_string(row, 3, trade.action.as_str())?;
+    sheet.write_number_with_format(row, 4, trade.strike, &formats.money)?;
+    sheet.write_datetime_with_format(row, 5, excel_date(trade.expiration_date)?, &formats.date)?;
+    sheet.write_number(row, 6, trade.number_of_shares)?;
+    sheet.write_number(row, 7, trade.delta)?;
+    sheet.write_number(row, 8, trade.credit)?;
+    sheet.write_number_with_format(row, 9, to_dollars(trade.premium_cents()), &formats.money)?;
+    sheet.write_number_with_format(row, 10, trade.commission, &formats.money)?;
+    sheet.write_number_with_format(row, 11, trade.fees, &formats.money)?;
+    Ok(())
+}
+
+fn excel_date(date: Date) -> Result<ExcelDateTime, XlsxError> {
+    ExcelDateTime::from_ymd(date.year() as u16, date.month() as u8, date.day())
+}
+
+/// A sheet name for `campaign` that Excel accepts and no other sheet has: without the
+/// characters Excel forbids, at most 31 characters, and numbered when taken.
+fn sheet_name(campaign: &str, taken: &mut HashSet<String>) -> String {
+    let cleaned: String = campaign
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '-',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches('\'');
+    let base: String = if cleaned.is_empty() {
+        "Campaign".to_string()
+    } else {
+        cleaned.chars().take(MAX_SHEET_NAME).collect()
+    };
+    let mut name = base.clone();
+    let mut n = 1;
+    while !taken.insert(name.to_lowercase()) {
+        n += 1;
+        let suffix = format!(" ({n})");
+        let keep = MAX_SHEET_NAME - suffix.chars().count();
+        name = format!("{}{suffix}", base.chars().take(keep).collect::<String>());
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheet_name() {
+        let mut taken = HashSet::from(["summary".to_string()]);
+        assert_eq!(sheet_name("NVTS wheel", &mut taken), "NVTS wheel");
+        assert_eq!(sheet_name("nvts WHEEL", &mut taken), "nvts WHEEL (2)");
+        assert_eq!(sheet_name("Summary", &mut taken), "Summary (2)");
+        assert_eq!(sheet_name("2025/07: puts?", &mut taken), "2025-07- puts-");
+        let long = "A campaign name well over the limit";
+        assert_eq!(sheet_name(long, &mut taken), &long[..31]);
+        assert_eq!(
+            sheet_name(long, &mut taken),
+            "A campaign name well over t (2)"
+        );
+    }
+}
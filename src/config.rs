@@ -0,0 +1,400 @@
+//! User settings from `config.toml`, loaded once at startup and read through [`get`].
+
+use directories::ProjectDirs;
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use time::Weekday;
+
+/// Environment variable that points at a config file other than the default one.
+pub const CONFIG_PATH_ENV: &str = "PROFIT_TRACKER_CONFIG";
+
+/// Shares per contract when no override is configured for the symbol.
+pub const DEFAULT_MULTIPLIER: i32 = 100;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// The file `config init` writes: every setting at its default, with the optional
+/// ones commented out.
+pub const DEFAULT_CONFIG: &str = r##"# profit_tracker settings. Every key is optional; delete or comment out a line
+# to keep its built-in default.
+
+# Database file or connection string, used when neither --db nor
+# PROFIT_TRACKER_DB is given
+# db = "/home/me/Documents/trades.db"
+
+# Broker assumed by import, export and diff when none is named
+# default_broker = "robinhood"
+
+# First day of the week for weekly premium and summaries
+week_start = "monday"
+
+# Where the dashboard gets underlying prices: "yahoo" or "finnhub" (needs
+# api_keys.finnhub). Quotes are off when unset.
+# quote_provider = "yahoo"
+
+# Slack or Discord incoming webhook that `notify` posts the weekly summary to
+# webhook = "https://discord.com/api/webhooks/..."
+
+# How many days ahead `remind` warns about expirations, and whether the TUI
+# runs that check at startup
+remind_days = 3
+remind_on_start = false
+
+# TUI colors: names ("light-blue"), hex ("#ff8800") or 256-color indexes
+[theme]
+accent = "cyan"
+positive = "green"
+negative = "red"
+highlight = "yellow"
+
+# Single-key TUI shortcuts
+[keys]
+quit = "q"
+campaigns = "c"
+new_campaign = "n"
+add_trade = "a"
+view_trades = "v"
+edit_trade = "e"
+refresh_quotes = "r"
+option_chain = "o"
+backup = "b"
+import = "i"
+archive_campaign = "x"
+delete_campaign = "d"
+show_archived = "h"
+move_trade = "m"
+trade_status = "s"
+mark = "k"
+search = "/"
+filter_action = "f"
+filter_open = "p"
+filter_dates = "w"
+clone_trade = "y"
+delete_trade = "d"
+undo = "u"
+redo = "U"
+monthly_report = "m"
+
+# Shares per contract by symbol, for adjusted or mini options (default 100)
+[multipliers]
+# XYZ1 = 10
+
+# Credentials for external services
+[api_keys]
+# finnhub = "..."
+
+# SMTP server that `notify --email` mails the weekly summary through
+# [email]
+# smtp_host = "smtp.example.com"
+# smtp_port = 587
+# username = "me@example.com"
+# password = "..."
+# from = "tracker@example.com"
+# to = ["me@example.com"]
+"##;
+
+/// User settings from `config.toml`. Every key is optional; missing ones keep the
+/// built-in defaults.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Database file or connection string, used when neither `--db` nor
+    /// `PROFIT_TRACKER_DB` is given.
+    pub db: Option<String>,
+    /// Broker assumed by import, export and diff when none is named.
+    pub default_broker: Option<String>,
+    /// First day of the week for weekly premium and summaries.
+    #[serde(deserialize_with = "deserialize_weekday")]
+    pub week_start: Weekday,
+    pub theme: Theme,
+    pub keys: KeyBindings,
+    /// Shares per contract by symbol, for adjusted or mini options.
+    pub multipliers: HashMap<String, i32>,
+    /// Slack or Discord incoming webhook that `notify` posts to when none is given.
+    pub webhook: Option<String>,
+    /// SMTP server that `notify` mails the weekly summary through.
+    pub email: Option<EmailConfig>,
+    /// How many days ahead `remind` warns about expirations.
+    pub remind_days: i64,
+    /// Run the `remind` check whenever the TUI starts.
+    pub remind_on_start: bool,
+    /// Where the dashboard gets underlying prices; quotes are off when unset.
+    pub quote_provider: Option<QuoteSource>,
+    /// Credentials for external services, keyed by service name.
+    pub api_keys: HashMap<String, String>,
+}
+
+/// Market-data services that can supply underlying prices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteSource {
+    Yahoo,
+    /// Needs `api_keys.finnhub`.
+    Finnhub,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            db: None,
+            default_broker: None,
+            week_start: Weekday::Monday,
+            theme: Theme::default(),
+            keys: KeyBindings::default(),
+            multipliers: HashMap::new(),
+            webhook: None,
+            email: None,
+            remind_days: 3,
+            remind_on_start: false,
+            quote_provider: None,
+            api_keys: HashMap::new(),
+        }
+    }
+}
+
+/// Where and how to mail the weekly summary.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    /// 587 (STARTTLS) unless set; 465 connects over TLS from the start.
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    /// Login for servers that require one; leave both unset to send without.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+/// TUI colors, as names (`"light-blue"`), hex (`"#ff8800"`) or 256-color indexes.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Borders and titles.
+    pub accent: Color,
+    pub positive: Color,
+    pub negative: Color,
+    /// Selected rows and headers.
+    pub highlight: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            accent: Color::Cyan,
+            positive: Color::Green,
+            negative: Color::Red,
+            highlight: Color::Yellow,
+        }
+    }
+}
+
+/// Single-key TUI shortcuts.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyBindings {
+    pub quit: char,
+    pub campaigns: char,
+    pub new_campaign: char,
+    pub add_trade: char,
+    pub view_trades: char,
+    pub edit_trade: char,
+    pub refresh_quotes: char,
+    /// Pick a strike from the option chain, on the Add Trade screen's Action field.
+    pub option_chain: char,
+    /// Write a JSON backup of the database from the summary screen.
+    pub backup: char,
+    /// Open the CSV import screen from the summary screen.
+    pub import: char,
+    /// Archive or restore the selected campaign on the campaign select screen.
+    pub archive_campaign: char,
+    /// Delete the selected campaign and its trades, after confirmation.
+    pub delete_campaign: char,
+    /// Show or hide archived campaigns on the campaign select screen.
+    pub show_archived: char,
+    /// Move the selected trade to another campaign, from the View Trades screen.
+    pub move_trade: char,
+    /// Step the selected trade's status on the View Trades screen.
+    pub trade_status: char,
+    /// Enter the current option price of the selected open trade on View Trades.
+    pub mark: char,
+    /// Search the trades listed on View Trades.
+    pub search: char,
+    /// Step View Trades through showing one action at a time.
+    pub filter_action: char,
+    /// Step View Trades between all, open and closed trades.
+    pub filter_open: char,
+    /// Step View Trades through trades of the last week, month, quarter and year.
+    pub filter_dates: char,
+    /// Fill the Add Trade form from the selected trade on View Trades, or from the
+    /// campaign's latest trade on Add Trade.
+    pub clone_trade: char,
+    /// Delete the selected trade on View Trades.
+    pub delete_trade: char,
+    /// Reverse the latest trade added, edited or deleted in the TUI.
+    pub undo: char,
+    /// Make the latest undone trade change again.
+    pub redo: char,
+    /// Open the monthly income report from the summary screen.
+    pub monthly_report: char,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            quit: 'q',
+            campaigns: 'c',
+            new_campaign: 'n',
+            add_trade: 'a',
+            view_trades: 'v',
+            edit_trade: 'e',
+            refresh_quotes: 'r',
+            option_chain: 'o',
+            backup: 'b',
+            import: 'i',
+            archive_campaign: 'x',
+            delete_campaign: 'd',
+            show_archived: 'h',
+            move_trade: 'm',
+            trade_status: 's',
+            mark: 'k',
+            search: '/',
+            filter_action: 'f',
+            filter_open: 'p',
+            filter_dates: 'w',
+            clone_trade: 'y',
+            delete_trade: 'd',
+            undo: 'u',
+            redo: 'U',
+            monthly_report: 'm',
+        }
+    }
+}
+
+impl Config {
+    /// Shares per contract for `symbol`.
+    pub fn multiplier(&self, symbol: &str) -> i32 {
+        self.multipliers
+            .iter()
+            .find(|(s, _)| s.eq_ignore_ascii_case(symbol))
+            .map_or(DEFAULT_MULTIPLIER, |(_, m)| *m)
+    }
+}
+
+/// Where the config file is read from: `PROFIT_TRACKER_CONFIG` if set, otherwise
+/// `config.toml` in the platform config directory (`~/.config/profit_tracker` on Linux).
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    ProjectDirs::from("", "", "profit_tracker").map(|dirs| dirs.config_dir().join("config.toml"))
+}
+
+/// Read the config file. A missing file gives the defaults.
+pub fn load() -> crate::error::Result<Config> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+    let message = match std::fs::read_to_string(&path) {
+        Ok(text) => match toml::from_str(&text) {
+            Ok(config) => return Ok(config),
+            Err(e) => e.to_string(),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => e.to_string(),
+    };
+    Err(crate::error::Error::Config { path, message })
+}
+
+/// Make `config` the settings returned by `get`. Only the first call has any effect.
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// The settings loaded at startup, or the defaults if none were loaded (as in tests).
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+fn deserialize_weekday<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Weekday, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    let mut day = Weekday::Monday;
+    for _ in 0..7 {
+        if day.to_string().eq_ignore_ascii_case(&name) {
+            return Ok(day);
+        }
+        day = day.next();
+    }
+    Err(serde::de::Error::custom(format!(
+        "unknown weekday '{name}', expected e.g. \"sunday\" or \"monday\""
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_parses_to_defaults() {
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        let defaults = Config::default();
+        assert_eq!(config.db, None);
+        assert_eq!(config.week_start, defaults.week_start);
+        assert_eq!(config.remind_days, defaults.remind_days);
+        assert_eq!(config.theme.accent, defaults.theme.accent);
+        assert_eq!(config.keys.quit, defaults.keys.quit);
+        assert_eq!(config.keys.clone_trade, defaults.keys.clone_trade);
+        assert!(config.multipliers.is_empty() && config.api_keys.is_empty());
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = toml::from_str(
+            r##"
+            db = "/tmp/trades.db"
+            week_start = "Sunday"
+            quote_provider = "finnhub"
+
+            [theme]
+            accent = "light-blue"
+            negative = "#ff8800"
+
+            [keys]
+            quit = "x"
+
+            [multipliers]
+            xyz1 = 10
+
+            [email]
+            smtp_host = "smtp.example.com"
+            from = "tracker@example.com"
+            to = ["me@example.com"]
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.db.as_deref(), Some("/tmp/trades.db"));
+        assert_eq!(config.week_start, Weekday::Sunday);
+        assert_eq!(config.quote_provider, Some(QuoteSource::Finnhub));
+        assert_eq!(config.theme.accent, Color::LightBlue);
+        assert_eq!(config.theme.negative, Color::Rgb(0xff, 0x88, 0x00));
+        assert_eq!(config.theme.positive, Color::Green);
+        assert_eq!(config.keys.quit, 'x');
+        assert_eq!(config.keys.campaigns, 'c');
+        assert_eq!(config.multiplier("XYZ1"), 10);
+        assert_eq!(config.multiplier("NVTS"), DEFAULT_MULTIPLIER);
+        let email = config.email.unwrap();
+        assert_eq!(email.smtp_port, 587);
+        assert_eq!(email.to, ["me@example.com"]);
+
+        assert!(toml::from_str::<Config>("week_start = \"someday\"").is_err());
+        assert!(toml::from_str::<Config>("dbpath = \"typo\"").is_err());
+    }
+}
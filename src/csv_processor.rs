@@ -1,20 +1,30 @@
-use crate::models::{Action, OptionTrade};
-use csv::Reader;
+//! Reading and writing broker CSV files: transaction statements (E*TRADE, Robinhood and
+//! Schwab) and positions lists.
+
+use crate::config;
+use crate::logic::Instrument;
+use crate::models::{Action, CashEvent, CashKind, OptionKind, OptionTrade, StockTrade};
+use crate::money::{to_cents, to_dollars};
+use csv::{Reader, ReaderBuilder, Writer};
 use std::fs::File;
 use std::path::Path;
-use time::{Date, OffsetDateTime};
+use std::sync::LazyLock;
+use time::Date;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Broker {
     ETrade,
     Robinhood,
+    /// Also reads the TD Ameritrade/thinkorswim exports Schwab accounts inherited.
+    Schwab,
 }
 
 impl Broker {
-    pub fn from_str(s: &str) -> Option<Self> {
+    pub fn from_name(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "etrade" => Some(Broker::ETrade),
             "robinhood" => Some(Broker::Robinhood),
+            "schwab" | "thinkorswim" | "tdameritrade" => Some(Broker::Schwab),
             _ => None,
         }
     }
@@ -23,11 +33,35 @@ impl Broker {
         match self {
             Broker::ETrade => "etrade",
             Broker::Robinhood => "robinhood",
+            Broker::Schwab => "schwab",
         }
     }
 
     pub fn supported_brokers() -> Vec<&'static str> {
-        vec!["etrade", "robinhood"]
+        vec!["etrade", "robinhood", "schwab"]
+    }
+
+    /// The broker whose statement layout `path` is in, recognized from the header row
+    /// within the first few lines; `None` for other CSVs.
+    pub fn detect<P: AsRef<Path>>(path: P) -> crate::error::Result<Option<Self>> {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)?;
+        for record in reader.records().take(5) {
+            let Ok(record) = record else { continue };
+            let has = |name: &str| record.iter().any(|h| h.trim() == name);
+            if has("Date / Time") && has("Type") {
+                return Ok(Some(Broker::ETrade));
+            }
+            if has("Activity Date") && has("Trans Code") {
+                return Ok(Some(Broker::Robinhood));
+            }
+            if SchwabLayout::detect(&record).is_some() {
+                return Ok(Some(Broker::Schwab));
+            }
+        }
+        Ok(None)
     }
 }
 
@@ -35,7 +69,7 @@ impl std::str::FromStr for Broker {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Broker::from_str(s).ok_or_else(|| {
+        Broker::from_name(s).ok_or_else(|| {
             let supported = Broker::supported_brokers().join(", ");
             format!("Invalid broker: '{s}'. Supported brokers: {supported}")
         })
@@ -48,6 +82,68 @@ impl std::fmt::Display for Broker {
     }
 }
 
+const ETRADE_HEADERS: [&str; 8] = [
+    "Date / Time",
+    "Type",
+    "Account #",
+    "Account Name",
+    "Description",
+    "Fee",
+    "Comm",
+    "Amount",
+];
+
+const ROBINHOOD_HEADERS: [&str; 9] = [
+    "Activity Date",
+    "Process Date",
+    "Settle Date",
+    "Instrument",
+    "Description",
+    "Trans Code",
+    "Quantity",
+    "Price",
+    "Amount",
+];
+
+const SCHWAB_HEADERS: [&str; 8] = [
+    "Date",
+    "Action",
+    "Symbol",
+    "Description",
+    "Quantity",
+    "Price",
+    "Fees & Comm",
+    "Amount",
+];
+
+/// A statement row that was not imported, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedRow {
+    /// 1-based line in the file.
+    pub line: u64,
+    pub reason: String,
+}
+
+impl SkippedRow {
+    fn new(record: &csv::StringRecord, reason: impl Into<String>) -> Self {
+        Self {
+            line: record.position().map_or(0, |p| p.line()),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Trades read from a statement along with the rows that were passed over.
+#[derive(Debug, Default)]
+pub struct ParsedCsv {
+    pub trades: Vec<OptionTrade>,
+    /// Shares bought and sold, including those delivered by an assignment.
+    pub stocks: Vec<StockTrade>,
+    /// Dividends and interest paid in cash.
+    pub cash: Vec<CashEvent>,
+    pub skipped: Vec<SkippedRow>,
+}
+
 pub struct CsvProcessor {
     broker: Broker,
 }
@@ -60,29 +156,87 @@ impl CsvProcessor {
     pub fn process_csv<P: AsRef<Path>>(
         &self,
         file_path: P,
-    ) -> Result<Vec<OptionTrade>, Box<dyn std::error::Error>> {
+    ) -> crate::error::Result<Vec<OptionTrade>> {
+        Ok(self.parse_csv(file_path)?.trades)
+    }
+
+    /// Read a statement, keeping a note of every row that isn't imported.
+    pub fn parse_csv<P: AsRef<Path>>(&self, file_path: P) -> crate::error::Result<ParsedCsv> {
         let file = File::open(file_path)?;
-        let reader = Reader::from_reader(file);
 
         match self.broker {
-            Broker::ETrade => self.process_etrade_csv(reader),
-            Broker::Robinhood => self.process_robinhood_csv(reader),
+            Broker::ETrade => self.process_etrade_csv(Reader::from_reader(file)),
+            Broker::Robinhood => self.process_robinhood_csv(Reader::from_reader(file)),
+            // Older Schwab exports put a title line above the header and a totals row last
+            Broker::Schwab => self.process_schwab_csv(
+                ReaderBuilder::new()
+                    .has_headers(false)
+                    .flexible(true)
+                    .from_reader(file),
+            ),
         }
     }
 
-    fn process_etrade_csv(
+    /// Write trades in the broker's own statement layout so other tools can read them.
+    ///
+    /// Assignment, exercise and expiration events have no option line in a statement
+    /// and are skipped. Returns the number of trades written.
+    pub fn export_csv<P: AsRef<Path>>(
         &self,
-        mut reader: Reader<File>,
-    ) -> Result<Vec<OptionTrade>, Box<dyn std::error::Error>> {
+        trades: &[OptionTrade],
+        file_path: P,
+    ) -> crate::error::Result<usize> {
+        let mut writer = Writer::from_path(file_path)?;
+        match self.broker {
+            Broker::ETrade => writer.write_record(ETRADE_HEADERS)?,
+            Broker::Robinhood => writer.write_record(ROBINHOOD_HEADERS)?,
+            Broker::Schwab => writer.write_record(SCHWAB_HEADERS)?,
+        }
+
+        let mut written = 0;
+        for trade in trades {
+            let Some(kind) = trade.action.option_kind() else {
+                continue;
+            };
+            let record = match self.broker {
+                Broker::ETrade => etrade_record(trade, kind),
+                Broker::Robinhood => robinhood_record(trade, kind),
+                Broker::Schwab => schwab_record(trade, kind),
+            };
+            writer.write_record(&record)?;
+            written += 1;
+        }
+        writer.flush()?;
+        Ok(written)
+    }
+
+    fn process_etrade_csv(&self, mut reader: Reader<File>) -> crate::error::Result<ParsedCsv> {
+        let mut skipped = Vec::new();
         let mut trades = Vec::new();
+        let mut stocks = Vec::new();
+        let mut cash = Vec::new();
         let date_fmt = time::macros::format_description!(
             "[month]/[day]/[year] [hour]:[minute]:[second] [period]"
         );
+        // Share trades: "100 SOFI @ $14.00"
+        let stock_re =
+            regex::Regex::new(r"^(?P<qty>[\d,]+) (?P<symbol>[A-Za-z.]+) @ \$(?P<price>[\d,.]+)")
+                .unwrap();
 
         for result in reader.records() {
             let record = match result {
                 Ok(r) if r.len() >= 8 => r,
-                _ => continue,
+                Ok(r) => {
+                    skipped.push(SkippedRow::new(&r, "too few columns"));
+                    continue;
+                }
+                Err(e) => {
+                    skipped.push(SkippedRow {
+                        line: e.position().map_or(0, |p| p.line()),
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
             };
 
             let date_str = record[0].trim_matches('"').trim();
@@ -100,12 +254,13 @@ impl CsvProcessor {
             };
 
             // Split description on spaces to extract option trade details
-            // Format: "15 Put NVTS 07/03/25 6.500 @ $0.18"
+            // Format: "15 Put NVTS 07/03/25 6.500 @ $0.18", without the price for
+            // assignments, exercises and expirations
             let parts: Vec<&str> = description.split_whitespace().collect();
 
             // Only process if we have enough parts and it looks like an option trade
-            if parts.len() >= 6 && (parts[1] == "Put" || parts[1] == "Call") {
-                let qty: i32 = parts[0].parse().unwrap_or(0);
+            if parts.len() >= 5 && (parts[1] == "Put" || parts[1] == "Call") {
+                let qty: i32 = parts[0].trim_start_matches('-').parse().unwrap_or(0);
                 let option_type = parts[1];
                 let symbol = parts[2].to_string();
                 let exp_str = parts[3];
@@ -118,29 +273,22 @@ impl CsvProcessor {
                 };
 
                 // Parse expiration date (MM/DD/YY)
-                let exp_parts: Vec<&str> = exp_str.split('/').collect();
-                let expiration_date = if exp_parts.len() == 3 {
-                    let month: u8 = exp_parts[0].parse().unwrap_or(1);
-                    let day: u8 = exp_parts[1].parse().unwrap_or(1);
-                    let year: u16 = exp_parts[2].parse().unwrap_or(0);
-                    let year = if year < 100 {
-                        2000 + year as i32
-                    } else {
-                        year as i32
-                    };
-                    Date::from_calendar_date(
-                        year,
-                        time::Month::try_from(month).unwrap_or(time::Month::January),
-                        day,
-                    )
-                    .unwrap_or_else(|_| OffsetDateTime::now_local().unwrap().date())
-                } else {
-                    OffsetDateTime::now_local().unwrap().date()
+                let Some(expiration_date) = parse_month_day_year(exp_str) else {
+                    skipped.push(SkippedRow::new(
+                        &record,
+                        format!("unreadable expiration date '{exp_str}'"),
+                    ));
+                    continue;
                 };
 
                 // Parse date of action
-                let date_of_action = Date::parse(date_str, &date_fmt)
-                    .unwrap_or_else(|_| OffsetDateTime::now_local().unwrap().date());
+                let Ok(date_of_action) = Date::parse(date_str, &date_fmt) else {
+                    skipped.push(SkippedRow::new(
+                        &record,
+                        format!("unreadable date '{date_str}'"),
+                    ));
+                    continue;
+                };
 
                 // Map type_str and option_type to Action
                 let action = match (type_str, option_type) {
@@ -152,7 +300,16 @@ impl CsvProcessor {
                     ("Sold Short", "Call") => Action::SellCall,
                     ("Bought To Cover", "Put") => Action::BuyPut,
                     ("Bought To Cover", "Call") => Action::BuyCall,
-                    _ => continue, // skip unknown
+                    ("Option Assignment", _) => Action::Assigned,
+                    ("Option Exercise", _) => Action::Exercised,
+                    ("Option Expired", _) => Action::Expired,
+                    _ => {
+                        skipped.push(SkippedRow::new(
+                            &record,
+                            format!("unsupported transaction type '{type_str}'"),
+                        ));
+                        continue;
+                    }
                 };
 
                 // Delta is not available
@@ -160,8 +317,18 @@ impl CsvProcessor {
                 // Campaign: use symbol + year + month as a default
                 let campaign = symbol.clone();
 
-                let number_of_shares = qty * 100;
-                let credit = amount / (qty as f64 * 100.0); // per share
+                let multiplier = config::get().multiplier(&symbol);
+                let number_of_shares = qty * multiplier;
+                // "--" when nothing was charged
+                let fees = parse_amount(&record[5]).unwrap_or(0.0).abs();
+                let commission = parse_amount(&record[6]).unwrap_or(0.0).abs();
+                // Per share, before the costs the amount is net of. The shares an
+                // assignment or exercise moves are paid for at the strike, not here.
+                let credit = if action.option_kind().is_some() && number_of_shares != 0 {
+                    (amount + commission + fees) / number_of_shares as f64
+                } else {
+                    0.0
+                };
 
                 let trade = OptionTrade {
                     id: None,
@@ -174,33 +341,93 @@ impl CsvProcessor {
                     date_of_action,
                     number_of_shares,
                     credit,
+                    closes_trade_id: None,
+                    rolled_from_id: None,
+                    commission,
+                    fees,
+                    multiplier,
+                    status: None,
+                    collateral: None,
+                    covered_by_id: None,
+                    implied_volatility: None,
+                    uuid: None,
                 };
                 trades.push(trade);
+            } else if let Some(caps) = stock_re.captures(description).filter(|_| {
+                matches!(
+                    type_str,
+                    "Bought" | "Sold" | "Sold Short" | "Bought To Cover"
+                )
+            }) {
+                let Ok(date) = Date::parse(date_str, &date_fmt) else {
+                    skipped.push(SkippedRow::new(&record, "no transaction date"));
+                    continue;
+                };
+                stocks.push(stock_trade(
+                    &caps["symbol"],
+                    date,
+                    type_str.starts_with("Sold"),
+                    parse_amount(&caps["qty"]).unwrap_or(0.0),
+                    parse_amount(&caps["price"]).unwrap_or(0.0),
+                    amount,
+                ));
+            } else if let Some(kind) = match type_str {
+                "Dividend" | "Qualified Dividend" => Some(CashKind::Dividend),
+                "Interest" | "Interest Income" => Some(CashKind::Interest),
+                _ => None,
+            } {
+                let Ok(date) = Date::parse(date_str, &date_fmt) else {
+                    skipped.push(SkippedRow::new(&record, "no transaction date"));
+                    continue;
+                };
+                // E*TRADE names the security only in free text, so the symbol is left blank
+                cash.push(cash_event(None, date, kind, amount));
+            } else {
+                skipped.push(SkippedRow::new(&record, "not an option trade"));
             }
         }
-        Ok(trades)
+        Ok(ParsedCsv {
+            trades,
+            stocks,
+            cash,
+            skipped,
+        })
     }
 
-    fn process_robinhood_csv(
-        &self,
-        mut reader: Reader<File>,
-    ) -> Result<Vec<OptionTrade>, Box<dyn std::error::Error>> {
+    fn process_robinhood_csv(&self, mut reader: Reader<File>) -> crate::error::Result<ParsedCsv> {
+        let mut skipped = Vec::new();
         let mut trades = Vec::new();
+        let mut stocks = Vec::new();
+        let mut cash = Vec::new();
         use regex::Regex;
         let option_re = Regex::new(r"(?P<symbol>\w+) (?P<exp>\d{1,2}/\d{1,2}/\d{4}) (?P<type>Call|Put) \$(?P<strike>[\d.]+)").unwrap();
-        let date_fmt = time::macros::format_description!("%m/%d/%Y");
         // let ymd_fmt = time::macros::format_description!("[year]-[month]-[day]"); // removed unused
         for result in reader.records() {
             let record = match result {
                 Ok(r) if r.len() >= 9 => r,
-                _ => continue,
+                Ok(r) => {
+                    skipped.push(SkippedRow::new(&r, "too few columns"));
+                    continue;
+                }
+                Err(e) => {
+                    skipped.push(SkippedRow {
+                        line: e.position().map_or(0, |p| p.line()),
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
             };
             let activity_date = &record[0];
-            // let instrument = &record[3]; // removed unused
+            let instrument = record[3].trim();
             let description = &record[4];
             let trans_code = &record[5];
-            let quantity: i32 = record[6].replace(",", "").parse().unwrap_or(0);
-            let amount_str = record[7]
+            // Expirations are written like "15S" for a short position
+            let quantity: i32 = record[6]
+                .replace(",", "")
+                .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+                .parse()
+                .unwrap_or(0);
+            let amount_str = record[8]
                 .replace("$", "")
                 .replace(",", "")
                 .replace("(", "")
@@ -211,32 +438,56 @@ impl CsvProcessor {
                 amount_str.parse().unwrap_or(0.0)
             };
 
+            // Options are described as "APLD 6/27/2025 Call $10.00", or named only by
+            // their OCC symbol in the Instrument column
+            let option = match option_re.captures(description) {
+                Some(caps) => Some((
+                    caps["symbol"].to_string(),
+                    parse_month_day_year(&caps["exp"]),
+                    if &caps["type"] == "Put" {
+                        OptionKind::Put
+                    } else {
+                        OptionKind::Call
+                    },
+                    caps["strike"].parse::<f64>().unwrap_or(0.0),
+                )),
+                None => parse_occ_symbol(instrument)
+                    .map(|occ| (occ.symbol, Some(occ.expiration), occ.kind, occ.strike)),
+            };
+
             // Only process option trades
-            if let Some(caps) = option_re.captures(description) {
-                let symbol = caps.name("symbol").unwrap().as_str().to_string();
-                let exp_str = caps.name("exp").unwrap().as_str();
-                let option_type = caps.name("type").unwrap().as_str();
-                let strike: f64 = caps.name("strike").unwrap().as_str().parse().unwrap_or(0.0);
-
-                // Parse expiration date
-                let expiration_date = Date::parse(exp_str, &date_fmt)
-                    .unwrap_or_else(|_| OffsetDateTime::now_local().unwrap().date());
-                // Parse activity date
-                let date_of_action = Date::parse(activity_date, &date_fmt)
-                    .unwrap_or_else(|_| OffsetDateTime::now_local().unwrap().date());
+            if let Some((symbol, expiration_date, option_type, strike)) = option {
+                let Some(expiration_date) = expiration_date else {
+                    skipped.push(SkippedRow::new(&record, "unreadable expiration date"));
+                    continue;
+                };
+                let Some(date_of_action) = parse_month_day_year(activity_date) else {
+                    skipped.push(SkippedRow::new(
+                        &record,
+                        format!("unreadable date '{activity_date}'"),
+                    ));
+                    continue;
+                };
 
                 // Map trans_code + option_type to Action
                 let action = match (trans_code, option_type) {
-                    ("BTO", "Call") => Action::BuyCall,
-                    ("BTO", "Put") => Action::BuyPut,
-                    ("STO", "Call") => Action::SellCall,
-                    ("STO", "Put") => Action::SellPut,
-                    ("BTC", "Call") => Action::BuyCall, // closing a short call
-                    ("BTC", "Put") => Action::BuyPut,   // closing a short put
-                    ("STC", "Call") => Action::SellCall, // closing a long call
-                    ("STC", "Put") => Action::SellPut,  // closing a long put
+                    ("BTO", OptionKind::Call) => Action::BuyCall,
+                    ("BTO", OptionKind::Put) => Action::BuyPut,
+                    ("STO", OptionKind::Call) => Action::SellCall,
+                    ("STO", OptionKind::Put) => Action::SellPut,
+                    ("BTC", OptionKind::Call) => Action::BuyCall, // closing a short call
+                    ("BTC", OptionKind::Put) => Action::BuyPut,   // closing a short put
+                    ("STC", OptionKind::Call) => Action::SellCall, // closing a long call
+                    ("STC", OptionKind::Put) => Action::SellPut,  // closing a long put
                     ("OASGN", _) => Action::Assigned,
-                    _ => continue, // skip unknown
+                    ("OEXP", _) => Action::Expired,
+                    _ => {
+                        skipped.push(SkippedRow::new(
+                            &record,
+                            format!("unsupported transaction code '{trans_code}'"),
+                        ));
+                        continue;
+                    }
                 };
 
                 // Delta is not available in Robinhood CSV
@@ -244,6 +495,22 @@ impl CsvProcessor {
                 // Campaign: use symbol + year + month as a default
                 let campaign = format!("{symbol}_{expiration_date}");
 
+                let multiplier = config::get().multiplier(&symbol);
+                let number_of_shares = quantity * multiplier;
+                // Robinhood charges no commission but nets regulatory fees out of the
+                // amount, so they show as the gap between price times shares and it
+                let fees = match parse_amount(&record[7]) {
+                    Some(price) if price > 0.0 && amount != 0.0 => {
+                        let gross = price * number_of_shares as f64;
+                        let gap = if amount < 0.0 {
+                            -amount - gross
+                        } else {
+                            gross - amount
+                        };
+                        to_dollars(to_cents(gap).max(0))
+                    }
+                    _ => 0.0,
+                };
                 let trade = OptionTrade {
                     id: None,
                     symbol,
@@ -253,16 +520,730 @@ impl CsvProcessor {
                     delta,
                     expiration_date,
                     date_of_action,
-                    number_of_shares: quantity * 100, // contracts to shares
-                    credit: amount / (quantity as f64 * 100.0), // per share
+                    number_of_shares,
+                    credit: (amount + fees) / number_of_shares as f64, // per share
+                    closes_trade_id: None,
+                    rolled_from_id: None,
+                    commission: 0.0,
+                    fees,
+                    multiplier,
+                    status: None,
+                    collateral: None,
+                    covered_by_id: None,
+                    implied_volatility: None,
+                    uuid: None,
                 };
                 trades.push(trade);
+            } else if matches!(trans_code, "Buy" | "Sell") && !instrument.is_empty() {
+                let Some(date) = parse_month_day_year(activity_date) else {
+                    skipped.push(SkippedRow::new(&record, "no transaction date"));
+                    continue;
+                };
+                stocks.push(stock_trade(
+                    instrument,
+                    date,
+                    trans_code == "Sell",
+                    quantity as f64,
+                    parse_amount(&record[7]).unwrap_or(0.0),
+                    amount,
+                ));
+            } else if let Some(kind) = match trans_code {
+                "CDIV" => Some(CashKind::Dividend),
+                "INT" => Some(CashKind::Interest),
+                _ => None,
+            } {
+                let Some(date) = parse_month_day_year(activity_date) else {
+                    skipped.push(SkippedRow::new(&record, "no transaction date"));
+                    continue;
+                };
+                let symbol = Some(instrument).filter(|s| !s.is_empty());
+                cash.push(cash_event(symbol, date, kind, amount));
+            } else {
+                skipped.push(SkippedRow::new(&record, "not an option trade"));
+            }
+        }
+        Ok(ParsedCsv {
+            trades,
+            stocks,
+            cash,
+            skipped,
+        })
+    }
+
+    /// Schwab's transaction history (`Date, Action, Symbol, ..., Amount`, with options as
+    /// `NVTS 07/03/2025 6.50 P`) or TD Ameritrade's `transactions.csv` (`DATE, TRANSACTION
+    /// ID, DESCRIPTION, ..., AMOUNT`, with descriptions like `Sold 15 NVTS Jul 3 2025 6.5
+    /// Put @ 0.18`). The layout is recognized from the header row. Share trades are read
+    /// from `Buy`/`Sell` rows with a plain ticker, or TD Ameritrade's `Bought 100 SOFI @ 14`,
+    /// and dividends and interest from rows whose action or description names them.
+    fn process_schwab_csv(&self, mut reader: Reader<File>) -> crate::error::Result<ParsedCsv> {
+        let mut skipped = Vec::new();
+        use regex::Regex;
+        let schwab_symbol = Regex::new(
+            r"^(?P<symbol>[A-Za-z.]+) (?P<exp>\d{1,2}/\d{1,2}/\d{4}) (?P<strike>[\d.]+) (?P<type>[PC])$",
+        )
+        .unwrap();
+        let tda_description = Regex::new(
+            r"^(?P<side>Bought|Sold) (?P<qty>[\d,]+) (?P<symbol>[A-Za-z.]+) (?P<month>[A-Za-z]{3}) (?P<day>\d{1,2}) (?P<year>\d{4}) (?P<strike>[\d.]+) (?P<type>Put|Call) @",
+        )
+        .unwrap();
+        let tda_stock = Regex::new(
+            r"^(?P<side>Bought|Sold) (?P<qty>[\d,]+) (?P<symbol>[A-Za-z.]+) @ (?P<price>[\d,.]+)$",
+        )
+        .unwrap();
+        let ticker = Regex::new(r"^[A-Za-z.]+$").unwrap();
+        let tda_paren_symbol = Regex::new(r"\(([A-Za-z.]+)\)$").unwrap();
+
+        let mut layout = None;
+        let mut trades = Vec::new();
+        let mut stocks = Vec::new();
+        let mut cash = Vec::new();
+        for result in reader.records() {
+            let record = match result {
+                Ok(record) => record,
+                Err(e) => {
+                    skipped.push(SkippedRow {
+                        line: e.position().map_or(0, |p| p.line()),
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            // Title lines above the header aren't transactions
+            let Some(columns) = &layout else {
+                layout = SchwabLayout::detect(&record);
+                continue;
+            };
+            let field = |col: usize| record.get(col).unwrap_or("").trim();
+
+            let (date_col, amount_col) = match *columns {
+                SchwabLayout::Schwab { date, amount, .. }
+                | SchwabLayout::TdAmeritrade { date, amount, .. } => (date, amount),
+            };
+            // "07/01/2025 as of 06/30/2025" is reported on the first date
+            let Some(date_of_action) = field(date_col)
+                .split_whitespace()
+                .next()
+                .and_then(parse_month_day_year)
+            else {
+                skipped.push(SkippedRow::new(&record, "no transaction date"));
+                continue;
+            };
+
+            let (symbol, strike, expiration_date, contracts, action) = match *columns {
+                SchwabLayout::Schwab {
+                    action,
+                    symbol,
+                    quantity,
+                    price,
+                    ..
+                } => {
+                    // "NVTS 07/03/2025 6.50 P", or an OCC symbol
+                    let option = match schwab_symbol.captures(field(symbol)) {
+                        Some(caps) => Some((
+                            caps["symbol"].to_uppercase(),
+                            caps["strike"].parse().unwrap_or(0.0),
+                            parse_month_day_year(&caps["exp"]),
+                            if &caps["type"] == "P" {
+                                OptionKind::Put
+                            } else {
+                                OptionKind::Call
+                            },
+                        )),
+                        None => parse_occ_symbol(field(symbol))
+                            .map(|occ| (occ.symbol, occ.strike, Some(occ.expiration), occ.kind)),
+                    };
+                    let Some((option_symbol, strike, expiration_date, kind)) = option else {
+                        let side = field(action).to_lowercase();
+                        let ticker_symbol = Some(field(symbol)).filter(|s| ticker.is_match(s));
+                        if let Some(kind) = cash_kind(&side) {
+                            let amount = parse_amount(field(amount_col)).unwrap_or(0.0);
+                            cash.push(cash_event(ticker_symbol, date_of_action, kind, amount));
+                        } else if matches!(side.as_str(), "buy" | "sell") && ticker_symbol.is_some()
+                        {
+                            stocks.push(stock_trade(
+                                field(symbol),
+                                date_of_action,
+                                side == "sell",
+                                parse_amount(field(quantity)).unwrap_or(0.0),
+                                price
+                                    .and_then(|col| parse_amount(field(col)))
+                                    .unwrap_or(0.0),
+                                parse_amount(field(amount_col)).unwrap_or(0.0),
+                            ));
+                        } else {
+                            skipped.push(SkippedRow::new(&record, "not an option trade"));
+                        }
+                        continue;
+                    };
+                    let action = match (field(action).to_lowercase().as_str(), kind) {
+                        ("sell to open" | "sell to close", OptionKind::Put) => Action::SellPut,
+                        ("sell to open" | "sell to close", OptionKind::Call) => Action::SellCall,
+                        ("buy to open" | "buy to close", OptionKind::Put) => Action::BuyPut,
+                        ("buy to open" | "buy to close", OptionKind::Call) => Action::BuyCall,
+                        ("assigned", _) => Action::Assigned,
+                        ("exchange or exercise", _) => Action::Exercised,
+                        ("expired", _) => Action::Expired,
+                        // Stock and cash rows
+                        _ => {
+                            skipped.push(SkippedRow::new(
+                                &record,
+                                format!("unsupported action '{}'", field(action)),
+                            ));
+                            continue;
+                        }
+                    };
+                    let contracts: f64 = field(quantity).replace(',', "").parse().unwrap_or(0.0);
+                    (
+                        option_symbol,
+                        strike,
+                        expiration_date,
+                        contracts.abs() as i32,
+                        action,
+                    )
+                }
+                SchwabLayout::TdAmeritrade {
+                    description,
+                    symbol,
+                    ..
+                } => {
+                    let Some(caps) = tda_description.captures(field(description)) else {
+                        if let Some(kind) = cash_kind(&field(description).to_lowercase()) {
+                            // "ORDINARY DIVIDEND (AAPL)", or the symbol column when present
+                            let symbol = tda_paren_symbol
+                                .captures(field(description))
+                                .map(|caps| caps.get(1).unwrap().as_str())
+                                .or(symbol.map(field).filter(|s| ticker.is_match(s)));
+                            let amount = parse_amount(field(amount_col)).unwrap_or(0.0);
+                            cash.push(cash_event(symbol, date_of_action, kind, amount));
+                        } else if let Some(caps) = tda_stock.captures(field(description)) {
+                            stocks.push(stock_trade(
+                                &caps["symbol"],
+                                date_of_action,
+                                &caps["side"] == "Sold",
+                                parse_amount(&caps["qty"]).unwrap_or(0.0),
+                                parse_amount(&caps["price"]).unwrap_or(0.0),
+                                parse_amount(field(amount_col)).unwrap_or(0.0),
+                            ));
+                        } else {
+                            skipped.push(SkippedRow::new(&record, "not an option trade"));
+                        }
+                        continue;
+                    };
+                    let action = match (&caps["side"], &caps["type"]) {
+                        ("Sold", "Put") => Action::SellPut,
+                        ("Sold", _) => Action::SellCall,
+                        (_, "Put") => Action::BuyPut,
+                        _ => Action::BuyCall,
+                    };
+                    let expiration_date = month_from_name(&caps["month"]).and_then(|month| {
+                        Date::from_calendar_date(
+                            caps["year"].parse().ok()?,
+                            month,
+                            caps["day"].parse().ok()?,
+                        )
+                        .ok()
+                    });
+                    (
+                        caps["symbol"].to_uppercase(),
+                        caps["strike"].parse().unwrap_or(0.0),
+                        expiration_date,
+                        caps["qty"].replace(',', "").parse().unwrap_or(0),
+                        action,
+                    )
+                }
+            };
+            let Some(expiration_date) = expiration_date else {
+                skipped.push(SkippedRow::new(&record, "invalid expiration date"));
+                continue;
+            };
+
+            let multiplier = config::get().multiplier(&symbol);
+            let number_of_shares = contracts * multiplier;
+            if number_of_shares == 0 {
+                skipped.push(SkippedRow::new(&record, "no quantity"));
+                continue;
+            }
+            let amount = parse_amount(field(amount_col)).unwrap_or(0.0);
+            let cost = |col: Option<usize>| {
+                col.and_then(|col| parse_amount(field(col)))
+                    .unwrap_or(0.0)
+                    .abs()
+            };
+            // Schwab reports one combined "Fees & Comm" figure, kept as the commission
+            let (commission, fees) = match *columns {
+                SchwabLayout::Schwab { fees, .. } => (cost(fees), 0.0),
+                SchwabLayout::TdAmeritrade {
+                    commission, fees, ..
+                } => (cost(commission), cost(fees)),
+            };
+            trades.push(OptionTrade {
+                id: None,
+                campaign: symbol.clone(),
+                symbol,
+                action,
+                strike,
+                // Delta is not available in Schwab exports
+                delta: 0.0,
+                expiration_date,
+                date_of_action,
+                number_of_shares,
+                // Per share, before the costs the amount is net of
+                credit: (amount + commission + fees) / number_of_shares as f64,
+                closes_trade_id: None,
+                rolled_from_id: None,
+                commission,
+                fees,
+                multiplier,
+                status: None,
+                collateral: None,
+                covered_by_id: None,
+                implied_volatility: None,
+                uuid: None,
+            });
+        }
+        Ok(ParsedCsv {
+            trades,
+            stocks,
+            cash,
+            skipped,
+        })
+    }
+}
+
+/// Column positions in a Schwab or TD Ameritrade transactions file.
+enum SchwabLayout {
+    Schwab {
+        date: usize,
+        action: usize,
+        symbol: usize,
+        quantity: usize,
+        price: Option<usize>,
+        fees: Option<usize>,
+        amount: usize,
+    },
+    TdAmeritrade {
+        date: usize,
+        description: usize,
+        symbol: Option<usize>,
+        commission: Option<usize>,
+        fees: Option<usize>,
+        amount: usize,
+    },
+}
+
+impl SchwabLayout {
+    /// The layout `header` is the header row of, if it is one.
+    fn detect(header: &csv::StringRecord) -> Option<Self> {
+        let find = |name: &str| {
+            header
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name))
+        };
+        let (date, amount) = (find("Date")?, find("Amount")?);
+        // Schwab names the option in Symbol and the trade in Action; TD Ameritrade puts
+        // both in the description
+        match (find("Action"), find("Symbol"), find("Quantity")) {
+            (Some(action), Some(symbol), Some(quantity)) => Some(Self::Schwab {
+                date,
+                action,
+                symbol,
+                quantity,
+                price: find("Price"),
+                fees: find("Fees & Comm"),
+                amount,
+            }),
+            _ => Some(Self::TdAmeritrade {
+                date,
+                description: find("Description")?,
+                symbol: find("Symbol"),
+                commission: find("Commission"),
+                fees: find("Reg Fee"),
+                amount,
+            }),
+        }
+    }
+}
+
+/// A share trade, kept in a campaign named after the symbol like the option trades.
+fn stock_trade(
+    symbol: &str,
+    date: Date,
+    sold: bool,
+    quantity: f64,
+    price: f64,
+    amount: f64,
+) -> StockTrade {
+    let shares = quantity.abs() as i32;
+    let symbol = symbol.to_uppercase();
+    StockTrade {
+        id: None,
+        campaign: symbol.clone(),
+        symbol,
+        date,
+        shares: if sold { -shares } else { shares },
+        price,
+        amount,
+    }
+}
+
+/// Whether a lowercased Schwab action or TD Ameritrade description is a dividend
+/// ("Qualified Dividend", "Non-Qualified Div") or interest ("Credit Interest") payment.
+fn cash_kind(text: &str) -> Option<CashKind> {
+    if text.contains("reinvest shares") {
+        None // the share purchase that follows a reinvested dividend
+    } else if text.contains("div") {
+        Some(CashKind::Dividend)
+    } else if text.contains("interest") {
+        Some(CashKind::Interest)
+    } else {
+        None
+    }
+}
+
+/// A dividend or interest payment, kept in a campaign named after its symbol.
+fn cash_event(symbol: Option<&str>, date: Date, kind: CashKind, amount: f64) -> CashEvent {
+    let symbol = symbol.map(str::to_uppercase);
+    CashEvent {
+        id: None,
+        campaign: symbol.clone(),
+        symbol,
+        date,
+        kind,
+        amount,
+    }
+}
+
+/// Read a broker's positions export (not its transactions): any CSV with `Symbol` and
+/// `Quantity` columns, such as E*TRADE's portfolio download. Lines before the header and
+/// rows without a numeric quantity (totals, cash) are skipped.
+///
+/// Options may be written as E*TRADE does (`NVTS Jul 03 '25 $6.50 Put`), as Robinhood does
+/// (`NVTS 7/3/2025 Put $6.50`), or as OCC symbols (`NVTS250703P00006500`). Short positions
+/// have negative quantities.
+pub fn read_positions<P: AsRef<Path>>(
+    file_path: P,
+) -> crate::error::Result<Vec<(Instrument, i32)>> {
+    let path = file_path.as_ref();
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)?;
+    let mut columns = None;
+    let mut positions = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let Some((symbol_col, quantity_col)) = columns else {
+            let find = |names: &[&str]| {
+                record
+                    .iter()
+                    .position(|h| names.iter().any(|n| h.trim().eq_ignore_ascii_case(n)))
+            };
+            if let (Some(s), Some(q)) =
+                (find(&["Symbol", "Instrument"]), find(&["Quantity", "Qty"]))
+            {
+                columns = Some((s, q));
             }
+            continue;
+        };
+        let (Some(symbol), Some(quantity)) = (record.get(symbol_col), record.get(quantity_col))
+        else {
+            continue;
+        };
+        let quantity = quantity.trim().replace(',', "");
+        let quantity: f64 = match quantity.strip_prefix('(').and_then(|q| q.strip_suffix(')')) {
+            Some(negative) => match negative.parse::<f64>() {
+                Ok(q) => -q,
+                Err(_) => continue,
+            },
+            None => match quantity.parse() {
+                Ok(q) => q,
+                Err(_) => continue,
+            },
+        };
+        let instrument = parse_option_symbol(symbol).unwrap_or_else(|| Instrument {
+            symbol: symbol.trim().to_uppercase(),
+            option: None,
+        });
+        if instrument.symbol.is_empty() {
+            continue;
         }
-        Ok(trades)
+        positions.push((instrument, quantity.round() as i32));
+    }
+    if columns.is_none() {
+        return Err(format!(
+            "{}: no header with Symbol and Quantity columns",
+            path.display()
+        )
+        .into());
+    }
+    Ok(positions)
+}
+
+/// Parse an option as written in a broker's positions list; `None` for anything else.
+fn parse_option_symbol(s: &str) -> Option<Instrument> {
+    use regex::Regex;
+    let s = s.trim();
+    let etrade = Regex::new(
+        r"^(?P<symbol>[A-Za-z.]+) (?P<month>[A-Za-z]{3}) (?P<day>\d{1,2}) '(?P<year>\d{2}) \$(?P<strike>[\d.,]+) (?P<type>Put|Call)$",
+    )
+    .unwrap();
+    let robinhood = Regex::new(
+        r"^(?P<symbol>[A-Za-z.]+) (?P<exp>\d{1,2}/\d{1,2}/\d{2,4}) (?P<type>Put|Call) \$(?P<strike>[\d.,]+)$",
+    )
+    .unwrap();
+
+    let (caps, expiration, strike) = if let Some(caps) = etrade.captures(s) {
+        let expiration = Date::from_calendar_date(
+            2000 + caps["year"].parse::<i32>().ok()?,
+            month_from_name(&caps["month"])?,
+            caps["day"].parse().ok()?,
+        )
+        .ok()?;
+        let strike: f64 = caps["strike"].replace(',', "").parse().ok()?;
+        (caps, expiration, strike)
+    } else if let Some(caps) = robinhood.captures(s) {
+        let expiration = parse_month_day_year(&caps["exp"])?;
+        let strike: f64 = caps["strike"].replace(',', "").parse().ok()?;
+        (caps, expiration, strike)
+    } else if let Some(occ) = parse_occ_symbol(s) {
+        return Some(Instrument {
+            symbol: occ.symbol,
+            option: Some((occ.kind, to_cents(occ.strike), occ.expiration)),
+        });
+    } else {
+        return None;
+    };
+    let kind = match &caps["type"] {
+        "Put" | "P" => OptionKind::Put,
+        _ => OptionKind::Call,
+    };
+    Some(Instrument {
+        symbol: caps["symbol"].to_uppercase(),
+        option: Some((kind, to_cents(strike), expiration)),
+    })
+}
+
+/// An option contract named by its OCC symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccSymbol {
+    pub symbol: String,
+    pub expiration: Date,
+    pub kind: OptionKind,
+    pub strike: f64,
+}
+
+static OCC_SYMBOL: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r"^(?P<symbol>[A-Za-z.]{1,6}) *(?P<year>\d{2})(?P<month>\d{2})(?P<day>\d{2})(?P<type>[PC])(?P<strike>\d{8})$",
+    )
+    .unwrap()
+});
+
+/// Parse an OCC option symbol such as `NVTS250703P00006500`: the underlying (padded to
+/// six characters with spaces or not), the expiration as `YYMMDD`, `P` or `C`, and the
+/// strike in thousandths of a dollar. `None` for anything else.
+pub fn parse_occ_symbol(s: &str) -> Option<OccSymbol> {
+    let caps = OCC_SYMBOL.captures(s.trim())?;
+    let expiration = Date::from_calendar_date(
+        2000 + caps["year"].parse::<i32>().ok()?,
+        time::Month::try_from(caps["month"].parse::<u8>().ok()?).ok()?,
+        caps["day"].parse().ok()?,
+    )
+    .ok()?;
+    Some(OccSymbol {
+        symbol: caps["symbol"].to_uppercase(),
+        expiration,
+        kind: if &caps["type"] == "P" {
+            OptionKind::Put
+        } else {
+            OptionKind::Call
+        },
+        strike: caps["strike"].parse::<f64>().ok()? / 1000.0,
+    })
+}
+
+/// Parse `M/D/YYYY` or `MM/DD/YY` dates as written in broker statements.
+pub(crate) fn parse_month_day_year(s: &str) -> Option<Date> {
+    let parts: Vec<&str> = s.trim().split('/').collect();
+    let [month, day, year] = parts.as_slice() else {
+        return None;
+    };
+    let month: u8 = month.parse().ok()?;
+    let day: u8 = day.parse().ok()?;
+    let year: i32 = year.parse().ok()?;
+    let year = if year < 100 { 2000 + year } else { year };
+    Date::from_calendar_date(year, time::Month::try_from(month).ok()?, day).ok()
+}
+
+/// The month of a three-letter name such as `Jul`, in any case.
+fn month_from_name(name: &str) -> Option<time::Month> {
+    let index = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ]
+    .iter()
+    .position(|m| name.eq_ignore_ascii_case(m))?;
+    Some(time::Month::January.nth_next(index as u8))
+}
+
+/// Parse a dollar amount written as `$1,234.56`, `-$12.00` or `($12.00)`.
+fn parse_amount(s: &str) -> Option<f64> {
+    let negative = s.starts_with('-') || s.starts_with('(');
+    let amount: f64 = s
+        .trim_matches(|c| matches!(c, '-' | '(' | ')' | '$'))
+        .replace(['$', ','], "")
+        .parse()
+        .ok()?;
+    Some(if negative { -amount } else { amount })
+}
+
+/// Per-share price, total amount after commission and fees, and whether money was
+/// received for a trade.
+fn trade_amounts(trade: &OptionTrade) -> (f64, f64, bool) {
+    let sold = matches!(trade.action, Action::SellPut | Action::SellCall);
+    let price = trade.credit.abs();
+    let gross = price * trade.number_of_shares as f64;
+    let costs = trade.commission + trade.fees;
+    (
+        price,
+        if sold { gross - costs } else { gross + costs },
+        sold,
+    )
+}
+
+/// A cost column, `--` when nothing was charged as E*TRADE writes it.
+fn cost_field(amount: f64) -> String {
+    if amount == 0.0 {
+        "--".to_string()
+    } else {
+        format!("${amount:.2}")
+    }
+}
+
+fn kind_name(kind: OptionKind) -> &'static str {
+    match kind {
+        OptionKind::Put => "Put",
+        OptionKind::Call => "Call",
     }
 }
 
+fn etrade_record(trade: &OptionTrade, kind: OptionKind) -> Vec<String> {
+    let (price, amount, sold) = trade_amounts(trade);
+    let closing = trade.closes_trade_id.is_some();
+    let type_str = match (sold, closing) {
+        (true, false) => "Sold Short",
+        (true, true) => "Sold",
+        (false, false) => "Bought",
+        (false, true) => "Bought To Cover",
+    };
+    let date = trade.date_of_action;
+    let exp = trade.expiration_date;
+    vec![
+        format!(
+            " {:02}/{:02}/{} 12:00:00 PM",
+            u8::from(date.month()),
+            date.day(),
+            date.year()
+        ),
+        type_str.to_string(),
+        String::new(),
+        String::new(),
+        format!(
+            "{} {} {} {:02}/{:02}/{:02} {:.3} @ ${:.2}",
+            trade.contracts(),
+            kind_name(kind),
+            trade.symbol,
+            u8::from(exp.month()),
+            exp.day(),
+            exp.year() % 100,
+            trade.strike,
+            price
+        ),
+        cost_field(trade.fees),
+        cost_field(trade.commission),
+        if sold {
+            format!("${amount:.2}")
+        } else {
+            format!("-${amount:.2}")
+        },
+    ]
+}
+
+fn robinhood_record(trade: &OptionTrade, kind: OptionKind) -> Vec<String> {
+    let (price, amount, sold) = trade_amounts(trade);
+    let closing = trade.closes_trade_id.is_some();
+    let trans_code = match (sold, closing) {
+        (true, false) => "STO",
+        (true, true) => "STC",
+        (false, false) => "BTO",
+        (false, true) => "BTC",
+    };
+    let mdy = |d: Date| format!("{}/{}/{}", u8::from(d.month()), d.day(), d.year());
+    let activity_date = mdy(trade.date_of_action);
+    vec![
+        activity_date.clone(),
+        activity_date.clone(),
+        activity_date,
+        trade.symbol.clone(),
+        format!(
+            "{} {} {} ${:.2}",
+            trade.symbol,
+            mdy(trade.expiration_date),
+            kind_name(kind),
+            trade.strike
+        ),
+        trans_code.to_string(),
+        trade.contracts().to_string(),
+        format!("${price:.2}"),
+        if sold {
+            format!("${amount:.2}")
+        } else {
+            format!("(${amount:.2})")
+        },
+    ]
+}
+
+fn schwab_record(trade: &OptionTrade, kind: OptionKind) -> Vec<String> {
+    let (price, amount, sold) = trade_amounts(trade);
+    let closing = trade.closes_trade_id.is_some();
+    let action = match (sold, closing) {
+        (true, false) => "Sell to Open",
+        (true, true) => "Sell to Close",
+        (false, false) => "Buy to Open",
+        (false, true) => "Buy to Close",
+    };
+    let mdy = |d: Date| format!("{:02}/{:02}/{}", u8::from(d.month()), d.day(), d.year());
+    let exp = trade.expiration_date;
+    vec![
+        mdy(trade.date_of_action),
+        action.to_string(),
+        format!(
+            "{} {} {:.2} {}",
+            trade.symbol,
+            mdy(exp),
+            trade.strike,
+            &kind_name(kind)[..1]
+        ),
+        format!(
+            "{} {} ${:.2} EXP {:02}/{:02}/{:02}",
+            kind_name(kind).to_uppercase(),
+            trade.symbol,
+            trade.strike,
+            u8::from(exp.month()),
+            exp.day(),
+            exp.year() % 100
+        ),
+        trade.contracts().to_string(),
+        format!("${price:.2}"),
+        format!("${:.2}", trade.commission + trade.fees),
+        if sold {
+            format!("${amount:.2}")
+        } else {
+            format!("-${amount:.2}")
+        },
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,6 +1290,16 @@ mod tests {
 
         assert!(!hood_trades.is_empty(), "No HOOD trades found");
 
+        // The assignment moves 200 shares at the strike and carries no premium
+        let assigned = hood_trades
+            .iter()
+            .find(|t| t.action == Action::Assigned)
+            .expect("Expected the HOOD assignment");
+        assert_eq!(assigned.strike, 80.0);
+        assert_eq!(assigned.number_of_shares, 200);
+        assert_eq!(assigned.credit, 0.0);
+        assert_eq!(assigned.expiration_date, date!(2025 - 07 - 03));
+
         // Verify that non-option entries are filtered out
         let non_option_trades: Vec<_> = trades
             .iter()
@@ -338,6 +1329,7 @@ mod tests {
                     Action::SellCall => "SellCall",
                     Action::Exercised => "Exercised",
                     Action::Assigned => "Assigned",
+                    Action::Expired => "Expired",
                 },
                 trade.strike,
                 trade.expiration_date,
@@ -346,4 +1338,212 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_process_schwab_csv() {
+        let processor = CsvProcessor::new(Broker::Schwab);
+        let ParsedCsv {
+            trades,
+            stocks,
+            cash,
+            skipped,
+        } = processor.parse_csv("tests/schwab.csv").unwrap();
+        let actions: Vec<_> = trades
+            .iter()
+            .map(|t| (t.symbol.as_str(), t.action.clone()))
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                ("NVTS", Action::BuyPut),
+                ("RKLB", Action::Expired),
+                ("NVTS", Action::SellPut),
+                ("HOOD", Action::SellCall),
+                ("SOFI", Action::Assigned),
+            ]
+        );
+        let closed = &trades[0];
+        assert_eq!(closed.date_of_action, date!(2025 - 07 - 01));
+        assert_eq!(closed.expiration_date, date!(2025 - 07 - 03));
+        assert_eq!(closed.strike, 6.5);
+        assert_eq!(closed.number_of_shares, 1500);
+        // -$84.98 paid, $9.98 of it in fees
+        assert!((closed.credit + 0.05).abs() < 1e-9);
+        assert_eq!(closed.commission, 9.98);
+        assert_eq!(trades[1].number_of_shares, 500);
+        assert_eq!(trades[4].number_of_shares, 300);
+        // The totals row
+        let lines: Vec<u64> = skipped.iter().map(|row| row.line).collect();
+        assert_eq!(lines, [11]);
+        let cash: Vec<_> = cash
+            .iter()
+            .map(|e| (e.symbol.as_deref(), e.kind, e.amount))
+            .collect();
+        assert_eq!(
+            cash,
+            [
+                (Some("AAPL"), CashKind::Dividend, 12.5),
+                (None, CashKind::Interest, 0.87)
+            ]
+        );
+        // The shares the assignment delivered
+        assert_eq!(stocks.len(), 1);
+        assert_eq!((stocks[0].symbol.as_str(), stocks[0].shares), ("SOFI", 300));
+        assert_eq!((stocks[0].price, stocks[0].amount), (14.0, -4200.0));
+
+        let ParsedCsv { trades, stocks, .. } =
+            processor.parse_csv("tests/thinkorswim.csv").unwrap();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].action, Action::SellPut);
+        assert_eq!(trades[0].expiration_date, date!(2025 - 07 - 03));
+        assert!((trades[0].credit - 0.18).abs() < 1e-9);
+        assert_eq!((trades[0].commission, trades[0].fees), (9.75, 0.23));
+        assert_eq!(trades[1].action, Action::BuyPut);
+        assert_eq!(stocks[0].shares, -100);
+        assert_eq!(stocks[0].amount, 1524.98);
+    }
+
+    #[test]
+    fn test_detect_broker() {
+        let detect = |path| Broker::detect(path).unwrap();
+        assert_eq!(detect("tests/etrade.csv"), Some(Broker::ETrade));
+        assert_eq!(detect("tests/schwab.csv"), Some(Broker::Schwab));
+        assert_eq!(detect("tests/thinkorswim.csv"), Some(Broker::Schwab));
+        assert_eq!(detect("tests/positions.csv"), None);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let opened = OptionTrade {
+            id: Some(1),
+            symbol: "NVTS".to_string(),
+            campaign: "NVTS".to_string(),
+            strike: 6.5,
+            expiration_date: date!(2025 - 07 - 03),
+            date_of_action: date!(2025 - 06 - 26),
+            number_of_shares: 1500,
+            credit: 0.18,
+            commission: 9.75,
+            fees: 0.23,
+            ..Default::default()
+        };
+        let closed = OptionTrade {
+            id: Some(2),
+            action: Action::BuyPut,
+            date_of_action: date!(2025 - 07 - 01),
+            credit: 0.05,
+            closes_trade_id: Some(1),
+            ..opened.clone()
+        };
+        let assigned = OptionTrade {
+            id: Some(3),
+            action: Action::Assigned,
+            ..opened.clone()
+        };
+
+        for broker in [Broker::ETrade, Broker::Robinhood, Broker::Schwab] {
+            let path = std::env::temp_dir().join(format!(
+                "profit_tracker_export_{}_{}.csv",
+                broker,
+                std::process::id()
+            ));
+            let processor = CsvProcessor::new(broker.clone());
+            let written = processor
+                .export_csv(&[opened.clone(), closed.clone(), assigned.clone()], &path)
+                .unwrap();
+            assert_eq!(written, 2);
+
+            let trades = processor.process_csv(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(trades.len(), 2, "{broker}");
+            for (exported, imported) in [&opened, &closed].iter().zip(&trades) {
+                assert_eq!(imported.symbol, exported.symbol);
+                assert_eq!(imported.action, exported.action);
+                assert_eq!(imported.strike, exported.strike);
+                assert_eq!(imported.expiration_date, exported.expiration_date);
+                assert_eq!(imported.date_of_action, exported.date_of_action);
+                assert_eq!(imported.number_of_shares, exported.number_of_shares);
+                assert!((imported.credit.abs() - exported.credit).abs() < 1e-9);
+                assert_eq!(imported.costs_cents(), exported.costs_cents(), "{broker}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_unreadable_dates_are_skipped() {
+        let path = std::env::temp_dir().join(format!(
+            "profit_tracker_bad_dates_{}.csv",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            r#""Date / Time","Type","Account #","Account Name","Description","Fee","Comm","Amount"
+" 06/26/2025 02:38:22 PM","Sold","x","Individual","15 Put NVTS 07/03/25 6.500 @ $0.18","--","$7.50","$262.22"
+"yesterday","Sold","x","Individual","15 Put NVTS 07/03/25 6.500 @ $0.18","--","$7.50","$262.22"
+" 06/26/2025 02:38:22 PM","Sold","x","Individual","15 Put NVTS 13/45/25 6.500 @ $0.18","--","$7.50","$262.22"
+"#,
+        )
+        .unwrap();
+        let parsed = CsvProcessor::new(Broker::ETrade).parse_csv(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(parsed.trades.len(), 1);
+        let reasons: Vec<&str> = parsed.skipped.iter().map(|s| s.reason.as_str()).collect();
+        assert_eq!(
+            reasons,
+            [
+                "unreadable date 'yesterday'",
+                "unreadable expiration date '13/45/25'"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_positions() {
+        let positions = read_positions("tests/positions.csv").unwrap();
+        let option = |symbol: &str, kind, strike, expiration| Instrument {
+            symbol: symbol.to_string(),
+            option: Some((kind, strike, expiration)),
+        };
+        let shares = |symbol: &str| Instrument {
+            symbol: symbol.to_string(),
+            option: None,
+        };
+        assert_eq!(
+            positions,
+            vec![
+                (shares("NVTS"), 1500),
+                (
+                    option("NVTS", OptionKind::Call, 700, date!(2025 - 07 - 18)),
+                    -15
+                ),
+                (
+                    option("RKLB", OptionKind::Put, 2500, date!(2025 - 08 - 15)),
+                    -2
+                ),
+                (
+                    option("SPY", OptionKind::Call, 60000, date!(2025 - 09 - 19)),
+                    1
+                ),
+                (shares("VTI"), 10),
+                (shares("CASH"), 5000),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_occ_symbol() {
+        let nvts = parse_occ_symbol("NVTS  250703P00006500").unwrap();
+        assert_eq!(nvts.symbol, "NVTS");
+        assert_eq!(nvts.expiration, date!(2025 - 07 - 03));
+        assert_eq!(nvts.kind, OptionKind::Put);
+        assert_eq!(nvts.strike, 6.5);
+        assert_eq!(parse_occ_symbol("NVTS250703P00006500"), Some(nvts));
+        assert_eq!(
+            parse_occ_symbol("SPY250919C00600000").map(|o| (o.kind, o.strike)),
+            Some((OptionKind::Call, 600.0))
+        );
+        assert_eq!(parse_occ_symbol("NVTS"), None);
+        assert_eq!(parse_occ_symbol("NVTS251303P00006500"), None);
+    }
 }
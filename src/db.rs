@@ -1,34 +1,1093 @@
-use rusqlite::Connection;
+//! The SQLite trade database: opening and migrating it, and JSON dumps of every table.
 
-pub fn init_database(conn: &Connection) -> Result<(), rusqlite::Error> {
-    // Create campaigns table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS campaigns (
+use directories::ProjectDirs;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{Connection, Transaction};
+use serde_json::{Map, json};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// File name of the database inside the data directory.
+pub const DB_FILE_NAME: &str = "options_trades.db";
+
+/// Environment variable that overrides the database location.
+pub const DB_PATH_ENV: &str = "PROFIT_TRACKER_DB";
+
+/// Resolve where the database lives.
+///
+/// `PROFIT_TRACKER_DB` wins if set, then `db` from the config file. Otherwise the
+/// database sits in the platform data directory (`$XDG_DATA_HOME/profit_tracker` on
+/// Linux, `~/Library/Application Support` on macOS, `%APPDATA%` on Windows), which is
+/// created on first run. A database left in the working directory by older versions keeps
+/// being used until it is moved.
+pub fn default_db_path() -> std::io::Result<PathBuf> {
+    if let Some(path) = std::env::var_os(DB_PATH_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+    if let Some(path) = &crate::config::get().db {
+        return Ok(PathBuf::from(path));
+    }
+    let legacy = Path::new(DB_FILE_NAME);
+    if legacy.exists() {
+        return Ok(legacy.to_path_buf());
+    }
+    let dirs = ProjectDirs::from("", "", "profit_tracker").ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Could not determine a home directory; set {DB_PATH_ENV}"),
+        )
+    })?;
+    std::fs::create_dir_all(dirs.data_dir())?;
+    Ok(dirs.data_dir().join(DB_FILE_NAME))
+}
+
+/// Open the database at `path` and make sure all tables exist.
+///
+/// Databases from older versions are backed up next to the file (see `backup_path`) and
+/// then migrated, giving trades recorded before the multiplier column the contract size
+/// `multipliers` has for their symbol. Databases written by a newer version are refused
+/// rather than misread.
+pub fn open_database<P: AsRef<Path>>(
+    path: P,
+    multipliers: &HashMap<String, i32>,
+) -> crate::error::Result<Connection> {
+    let path = path.as_ref();
+    let conn = Connection::open(path)?;
+    let version = schema_version(&conn)?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "{} has schema version {version}, but this build only understands up to \
+            version {SCHEMA_VERSION}; upgrade profit_tracker to open it",
+            path.display()
+        )
+        .into());
+    }
+    if version < SCHEMA_VERSION && table_exists(&conn, "option_trades")? && conn.path() != Some("")
+    {
+        let backup = backup_path(path, version);
+        if !backup.exists() {
+            conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])?;
+        }
+        eprintln!(
+            "Upgrading database schema from version {version} to {SCHEMA_VERSION} (backup: {})",
+            backup.display()
+        );
+    }
+    // WAL lets CLI commands read while a TUI session holds the database open;
+    // the timeout makes concurrent writers wait for each other instead of failing
+    conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    conn.pragma_update(None, "foreign_keys", true)?;
+    init_database(&conn, multipliers)?;
+    Ok(conn)
+}
+
+/// Where the copy of a version `version` database is kept before it is migrated,
+/// e.g. `options_trades.db.v4.bak`.
+pub fn backup_path(path: &Path, version: i64) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".v{version}.bak"));
+    PathBuf::from(name)
+}
+
+/// Schema version written by this build: the number of steps in `MIGRATIONS`.
+pub const SCHEMA_VERSION: i64 = 21;
+
+/// Column definitions of `campaigns`, shared by table creation and migrations.
+const CAMPAIGNS_COLUMNS: &str = "
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             name TEXT NOT NULL UNIQUE,
             symbol TEXT NOT NULL,
             created_at TEXT NOT NULL,
-            target_exit_price REAL
-        )",
-        [],
-    )?;
+            target_exit_price_cents INTEGER,
+            archived INTEGER NOT NULL DEFAULT 0,
+            goal_cents INTEGER,
+            goal_period TEXT CHECK (goal_period IN ('Week', 'Month'))
+        ";
 
-    // Create option_trades table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS option_trades (
+/// Column definitions of `option_trades`. `premium_cents` is the trade's total premium
+/// (per-share credit times shares) so amounts imported from statements stay exact, and
+/// `number_of_shares` is the contracts times the `multiplier` in effect for the trade.
+const OPTION_TRADES_COLUMNS: &str = "
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             symbol TEXT NOT NULL,
-            campaign TEXT NOT NULL,
-            action TEXT NOT NULL,
-            strike REAL NOT NULL,
+            campaign_id INTEGER NOT NULL
+                REFERENCES campaigns(id) ON UPDATE CASCADE ON DELETE RESTRICT,
+            action TEXT NOT NULL CHECK (action IN
+                ('BuyPut', 'SellPut', 'BuyCall', 'SellCall', 'Exercised', 'Assigned', 'Expired')),
+            strike_cents INTEGER NOT NULL,
             delta REAL NOT NULL,
             expiration_date TEXT NOT NULL,
             date_of_action TEXT NOT NULL,
             number_of_shares INTEGER NOT NULL,
-            credit REAL NOT NULL
-        )",
+            premium_cents INTEGER NOT NULL,
+            closes_trade_id INTEGER REFERENCES option_trades(id),
+            rolled_from_id INTEGER REFERENCES option_trades(id),
+            commission_cents INTEGER NOT NULL DEFAULT 0,
+            fees_cents INTEGER NOT NULL DEFAULT 0,
+            multiplier INTEGER NOT NULL DEFAULT 100 CHECK (multiplier > 0),
+            status TEXT CHECK (status IN ('Open', 'Closed', 'Expired', 'Assigned', 'Rolled')),
+            collateral_cents INTEGER,
+            covered_by_id INTEGER REFERENCES option_trades(id),
+            implied_volatility REAL,
+            uuid TEXT NOT NULL UNIQUE DEFAULT (
+                lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4' ||
+                substr(lower(hex(randomblob(2))), 2) || '-' ||
+                substr('89ab', 1 + abs(random()) % 4, 1) ||
+                substr(lower(hex(randomblob(2))), 2) || '-' || lower(hex(randomblob(6)))
+            ),
+            dedup_key TEXT GENERATED ALWAYS AS (
+                symbol || '|' || campaign_id || '|' || action || '|' || strike_cents || '|' ||
+                expiration_date || '|' || date_of_action || '|' || number_of_shares || '|' ||
+                premium_cents
+            ) VIRTUAL,
+            contracts INTEGER GENERATED ALWAYS AS (number_of_shares / multiplier) VIRTUAL
+        ";
+
+/// Column definitions of `snapshots`; all amounts are in cents.
+const SNAPSHOTS_COLUMNS: &str = "
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL UNIQUE,
+            realized_pl_cents INTEGER NOT NULL,
+            open_collateral_cents INTEGER NOT NULL,
+            cash_cents INTEGER NOT NULL,
+            share_value_cents INTEGER NOT NULL
+        ";
+
+/// Column definitions of `cash_events`.
+const CASH_EVENTS_COLUMNS: &str = "
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT,
+            campaign_id INTEGER
+                REFERENCES campaigns(id) ON UPDATE CASCADE ON DELETE RESTRICT,
+            date TEXT NOT NULL,
+            kind TEXT NOT NULL CHECK (kind IN ('Dividend', 'Interest', 'Deposit', 'Withdrawal')),
+            amount_cents INTEGER NOT NULL
+        ";
+
+/// Column definitions of `dividends`.
+const DIVIDENDS_COLUMNS: &str = "
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            ex_date TEXT NOT NULL,
+            amount_cents INTEGER,
+            UNIQUE (symbol, ex_date)
+        ";
+
+/// Column definitions of `stock_trades`.
+const STOCK_TRADES_COLUMNS: &str = "
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            campaign_id INTEGER NOT NULL
+                REFERENCES campaigns(id) ON UPDATE CASCADE ON DELETE RESTRICT,
+            date TEXT NOT NULL,
+            shares INTEGER NOT NULL,
+            price_cents INTEGER NOT NULL,
+            amount_cents INTEGER NOT NULL,
+            UNIQUE (symbol, campaign_id, date, shares, price_cents, amount_cents)
+        ";
+
+/// Column definitions of `trade_tags`.
+const TRADE_TAGS_COLUMNS: &str = "
+            trade_id INTEGER NOT NULL REFERENCES option_trades(id) ON DELETE CASCADE,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (trade_id, tag)
+        ";
+
+/// Column definitions of `marks`.
+const MARKS_COLUMNS: &str = "
+            trade_id INTEGER PRIMARY KEY REFERENCES option_trades(id) ON DELETE CASCADE,
+            date TEXT NOT NULL,
+            price_cents INTEGER NOT NULL
+        ";
+
+/// SQL converting a dollar amount column to whole cents.
+fn cents_sql(expr: &str) -> String {
+    format!("CAST(ROUND(({expr}) * 100) AS INTEGER)")
+}
+
+/// The schema version recorded in the database file (`PRAGMA user_version`).
+pub fn schema_version(conn: &Connection) -> Result<i64, rusqlite::Error> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// One schema change, upgrading the database by a single version.
+type Migration = fn(&Transaction) -> Result<(), rusqlite::Error>;
+
+/// Every schema change in order: `MIGRATIONS[n]` upgrades a version `n` database to
+/// version `n + 1`. Add new steps at the end and bump `SCHEMA_VERSION` to match.
+///
+/// Steps tolerate finding their change already made, since databases from before
+/// version 5 are rebuilt straight into the current `option_trades` layout.
+const MIGRATIONS: &[Migration] = &[
+    // 1: trades reference their campaign by id instead of by name; 2: actions are limited
+    // to known values; 3: dedup key. All three are made by the version 5 rebuild
+    |_| Ok(()),
+    |_| Ok(()),
+    |_| Ok(()),
+    // 4: stable uuids for sync (the uuid column itself comes with the version 5 rebuild)
+    |tx| {
+        create_table(tx, "meta", META_COLUMNS)?;
+        create_table(tx, "sync_state", SYNC_STATE_COLUMNS)
+    },
+    // 5: money in integer cents
+    migrate_to_cents,
+    // 6: dividends table
+    |tx| create_table(tx, "dividends", DIVIDENDS_COLUMNS),
+    // 7: the dedup key leaves out delta, which backfill-greeks fills in after import
+    |tx| {
+        if !table_sql(tx, "option_trades")?.contains("delta ||") {
+            return Ok(());
+        }
+        rebuild_option_trades(tx)
+    },
+    // 8: stock_trades table
+    |tx| create_table(tx, "stock_trades", STOCK_TRADES_COLUMNS),
+    // 9: cash_events table
+    |tx| create_table(tx, "cash_events", CASH_EVENTS_COLUMNS),
+    // 10: commission and fee columns
+    |tx| {
+        add_column_if_missing(
+            tx,
+            "option_trades",
+            "commission_cents",
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+        add_column_if_missing(
+            tx,
+            "option_trades",
+            "fees_cents",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+    },
+    // 11: the Expired action. SQLite can't change a CHECK constraint in place, so the
+    // table is copied
+    |tx| {
+        if table_sql(tx, "option_trades")?.contains("'Expired'") {
+            return Ok(());
+        }
+        rebuild_option_trades(tx)
+    },
+    // 12: archived campaigns
+    |tx| add_column_if_missing(tx, "campaigns", "archived", "INTEGER NOT NULL DEFAULT 0"),
+    // 13: trade_tags table
+    |tx| create_table(tx, "trade_tags", TRADE_TAGS_COLUMNS),
+    // 14: contracts and multiplier columns (`apply_migrations` sets the multiplier of the
+    // trades already recorded)
+    |tx| {
+        add_column_if_missing(
+            tx,
+            "option_trades",
+            "multiplier",
+            "INTEGER NOT NULL DEFAULT 100 CHECK (multiplier > 0)",
+        )?;
+        add_column_if_missing(
+            tx,
+            "option_trades",
+            "contracts",
+            "INTEGER GENERATED ALWAYS AS (number_of_shares / multiplier) VIRTUAL",
+        )
+    },
+    // 15: status column
+    |tx| {
+        add_column_if_missing(
+            tx,
+            "option_trades",
+            "status",
+            "TEXT CHECK (status IN ('Open', 'Closed', 'Expired', 'Assigned', 'Rolled'))",
+        )
+    },
+    // 16: collateral columns
+    |tx| {
+        add_column_if_missing(tx, "option_trades", "collateral_cents", "INTEGER")?;
+        add_column_if_missing(
+            tx,
+            "option_trades",
+            "covered_by_id",
+            "INTEGER REFERENCES option_trades(id)",
+        )
+    },
+    // 17: marks table
+    |tx| create_table(tx, "marks", MARKS_COLUMNS),
+    // 18: implied volatility of trades
+    |tx| add_column_if_missing(tx, "option_trades", "implied_volatility", "REAL"),
+    // 19: deposits and withdrawals in cash_events
+    |tx| {
+        if table_sql(tx, "cash_events")?.contains("'Deposit'") {
+            return Ok(());
+        }
+        let columns = table_columns(tx, "cash_events")?.join(", ");
+        rebuild_table(
+            tx,
+            "cash_events",
+            CASH_EVENTS_COLUMNS,
+            "",
+            &columns,
+            &format!("SELECT {columns} FROM cash_events"),
+        )
+    },
+    // 20: campaign premium goals
+    |tx| {
+        add_column_if_missing(tx, "campaigns", "goal_cents", "INTEGER")?;
+        add_column_if_missing(
+            tx,
+            "campaigns",
+            "goal_period",
+            "TEXT CHECK (goal_period IN ('Week', 'Month'))",
+        )
+    },
+    // 21: the dedup index only keeps imports from recording a trade twice, so identical
+    // trades entered by hand are allowed
+    |tx| {
+        tx.execute("DROP INDEX IF EXISTS idx_option_trades_dedup", [])?;
+        create_option_trades_indexes(tx)
+    },
+];
+
+const _: () = assert!(MIGRATIONS.len() as i64 == SCHEMA_VERSION);
+
+/// The version whose migration adds the `multiplier` column.
+const MULTIPLIER_VERSION: i64 = 14;
+
+/// Column definitions of the machine-local `meta` settings table.
+const META_COLUMNS: &str = "
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        ";
+
+/// Column definitions of `sync_state`, the sync bookkeeping table.
+const SYNC_STATE_COLUMNS: &str = "
+            device TEXT NOT NULL,
+            uuid TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            PRIMARY KEY (device, uuid)
+        ";
+
+/// Create the current schema in an empty database, or bring an older one up to date by
+/// applying the `MIGRATIONS` it is missing. `multipliers` maps symbols to the contract
+/// size their trades used before it was recorded per trade.
+pub fn init_database(
+    conn: &Connection,
+    multipliers: &HashMap<String, i32>,
+) -> Result<(), rusqlite::Error> {
+    let version = schema_version(conn)?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+    if !table_exists(conn, "option_trades")? {
+        return create_schema(conn);
+    }
+
+    // Copying a table drops the one it replaces, which foreign keys would refuse or
+    // cascade, and they cannot be switched off inside a transaction
+    let foreign_keys: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let result = apply_migrations(conn, version, multipliers);
+    conn.pragma_update(None, "foreign_keys", foreign_keys)?;
+    result
+}
+
+/// Apply `MIGRATIONS[version..]`, each in its own transaction that also records the
+/// version it reaches, so an interrupted upgrade resumes where it stopped.
+fn apply_migrations(
+    conn: &Connection,
+    version: i64,
+    multipliers: &HashMap<String, i32>,
+) -> Result<(), rusqlite::Error> {
+    for (step, migration) in (version..).zip(&MIGRATIONS[version as usize..]) {
+        let tx = conn.unchecked_transaction()?;
+        migration(&tx)?;
+        // Trades recorded before the multiplier column used the configured one
+        if step + 1 == MULTIPLIER_VERSION {
+            for (symbol, multiplier) in multipliers {
+                tx.execute(
+                    "UPDATE option_trades SET multiplier = ?2 WHERE upper(symbol) = upper(?1)",
+                    rusqlite::params![symbol, multiplier],
+                )?;
+            }
+        }
+        tx.pragma_update(None, "user_version", step + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Create every table of the current schema in an empty database.
+fn create_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    create_table(&tx, "campaigns", CAMPAIGNS_COLUMNS)?;
+    create_table(&tx, "option_trades", OPTION_TRADES_COLUMNS)?;
+    create_option_trades_indexes(&tx)?;
+    create_table(&tx, "snapshots", SNAPSHOTS_COLUMNS)?;
+    // Announced ex-dividend dates, for early-assignment warnings on short calls
+    create_table(&tx, "dividends", DIVIDENDS_COLUMNS)?;
+    // Shares bought and sold outright, imported alongside option trades
+    create_table(&tx, "stock_trades", STOCK_TRADES_COLUMNS)?;
+    // Dividends and interest paid in cash, and deposits and withdrawals. Events not tied
+    // to a symbol have no symbol or campaign and count toward the account only
+    create_table(&tx, "cash_events", CASH_EVENTS_COLUMNS)?;
+    // Free-form labels on trades, many per trade, for reporting by strategy or setup
+    create_table(&tx, "trade_tags", TRADE_TAGS_COLUMNS)?;
+    // The latest option price entered for an open trade, for unrealized P/L
+    create_table(&tx, "marks", MARKS_COLUMNS)?;
+    // Machine-local settings and sync bookkeeping
+    create_table(&tx, "meta", META_COLUMNS)?;
+    create_table(&tx, "sync_state", SYNC_STATE_COLUMNS)?;
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    tx.commit()
+}
+
+fn create_table(tx: &Transaction, table: &str, columns: &str) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        &format!("CREATE TABLE IF NOT EXISTS {table} ({columns})"),
         [],
     )?;
+    Ok(())
+}
+
+/// Indexes on `option_trades`, which have to be recreated whenever the table is copied.
+fn create_option_trades_indexes(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    // Campaign dashboards and expiry lookups filter on these columns
+    tx.execute(
+        "CREATE INDEX IF NOT EXISTS idx_option_trades_campaign
+            ON option_trades (campaign_id, symbol, expiration_date)",
+        [],
+    )?;
+    // Imports look trades up by their dedup key to skip ones already recorded
+    tx.execute(
+        "CREATE INDEX IF NOT EXISTS idx_option_trades_dedup ON option_trades (dedup_key)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Version 5: rebuild `option_trades` in the current layout from whatever older one it
+/// has, merge trades recorded twice by repeated imports, and convert the dollar
+/// amounts in `campaigns` and `snapshots` to cents.
+fn migrate_to_cents(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    // Links between trades predate schema versions, so early databases may lack them
+    add_column_if_missing(
+        tx,
+        "option_trades",
+        "closes_trade_id",
+        "INTEGER REFERENCES option_trades(id)",
+    )?;
+    add_column_if_missing(
+        tx,
+        "option_trades",
+        "rolled_from_id",
+        "INTEGER REFERENCES option_trades(id)",
+    )?;
+    upgrade_option_trades(tx)?;
+    remove_duplicate_trades(tx)?;
+    create_option_trades_indexes(tx)?;
 
+    if column_exists(tx, "campaigns", "target_exit_price")? {
+        rebuild_table(
+            tx,
+            "campaigns",
+            CAMPAIGNS_COLUMNS,
+            "",
+            "id, name, symbol, created_at, target_exit_price_cents",
+            &format!(
+                "SELECT id, name, symbol, created_at, {} FROM campaigns",
+                cents_sql("target_exit_price")
+            ),
+        )?;
+    }
+    create_table(tx, "snapshots", SNAPSHOTS_COLUMNS)?;
+    if column_exists(tx, "snapshots", "realized_pl")? {
+        rebuild_table(
+            tx,
+            "snapshots",
+            SNAPSHOTS_COLUMNS,
+            "",
+            "id, date, realized_pl_cents, open_collateral_cents, cash_cents, share_value_cents",
+            &format!(
+                "SELECT id, date, {}, {}, {}, {} FROM snapshots",
+                cents_sql("realized_pl"),
+                cents_sql("open_collateral"),
+                cents_sql("cash"),
+                cents_sql("share_value")
+            ),
+        )?;
+    }
     Ok(())
 }
+
+/// Copy `option_trades` into the current layout, keeping every column it already has,
+/// then merge trades the dedup key now treats as the same and restore the indexes.
+fn rebuild_option_trades(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    let columns = table_columns(tx, "option_trades")?.join(", ");
+    rebuild_table(
+        tx,
+        "option_trades",
+        OPTION_TRADES_COLUMNS,
+        "",
+        &columns,
+        &format!("SELECT {columns} FROM option_trades"),
+    )?;
+    remove_duplicate_trades(tx)?;
+    create_option_trades_indexes(tx)
+}
+
+/// Rebuild `option_trades` from any older layout into the current one.
+///
+/// A free-text `campaign` column becomes a `campaign_id` reference (creating campaigns
+/// for names that only appeared on trades), dollar `strike`/`credit` columns become
+/// cents, and existing uuids are kept.
+fn upgrade_option_trades(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    let existing = table_columns(tx, "option_trades")?;
+    let has = |column: &str| existing.iter().any(|c| c == column);
+
+    let mut prelude = "";
+    let mut from = "option_trades t";
+    let mut campaign_id = "t.campaign_id";
+    if has("campaign") {
+        prelude = "INSERT INTO campaigns (name, symbol, created_at)
+            SELECT campaign, MIN(symbol), MIN(date_of_action) FROM option_trades
+            WHERE campaign NOT IN (SELECT name FROM campaigns)
+            GROUP BY campaign;";
+        from = "option_trades t JOIN campaigns c ON c.name = t.campaign";
+        campaign_id = "c.id";
+    }
+    let (strike, premium) = if has("credit") {
+        (
+            cents_sql("t.strike"),
+            cents_sql("t.credit * t.number_of_shares"),
+        )
+    } else {
+        ("t.strike_cents".to_string(), "t.premium_cents".to_string())
+    };
+
+    let mut columns = vec![
+        "id",
+        "symbol",
+        "campaign_id",
+        "action",
+        "strike_cents",
+        "delta",
+        "expiration_date",
+        "date_of_action",
+        "number_of_shares",
+        "premium_cents",
+        "closes_trade_id",
+        "rolled_from_id",
+    ];
+    let mut values = vec![
+        "t.id",
+        "t.symbol",
+        campaign_id,
+        "t.action",
+        &strike,
+        "t.delta",
+        "t.expiration_date",
+        "t.date_of_action",
+        "t.number_of_shares",
+        &premium,
+        "t.closes_trade_id",
+        "t.rolled_from_id",
+    ];
+    // Rows from before version 4 are assigned a uuid by the column default
+    if has("uuid") {
+        columns.push("uuid");
+        values.push("t.uuid");
+    }
+
+    rebuild_table(
+        tx,
+        "option_trades",
+        OPTION_TRADES_COLUMNS,
+        prelude,
+        &columns.join(", "),
+        &format!("SELECT {} FROM {from}", values.join(", ")),
+    )
+}
+
+/// Recreate `table` with `definition`, filling it with `select` (yielding `columns` in
+/// order). `prelude` runs first. Foreign keys must be off, or dropping the old table
+/// would fail or cascade.
+fn rebuild_table(
+    tx: &Transaction,
+    table: &str,
+    definition: &str,
+    prelude: &str,
+    columns: &str,
+    select: &str,
+) -> Result<(), rusqlite::Error> {
+    tx.execute_batch(&format!(
+        "{prelude}
+        CREATE TABLE {table}_new ({definition});
+        INSERT INTO {table}_new ({columns}) {select};
+        DROP TABLE {table};
+        ALTER TABLE {table}_new RENAME TO {table};"
+    ))
+}
+
+/// Delete all but the oldest of each set of identical trades, pointing close, roll and
+/// collateral links at the survivor and giving it their tags and mark.
+fn remove_duplicate_trades(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    // Foreign keys are off while migrating, so deleting a trade won't cascade to these
+    let mut attached = String::new();
+    if table_exists(tx, "trade_tags")? {
+        attached.push_str(
+            "INSERT OR IGNORE INTO trade_tags (trade_id, tag)
+                SELECT d.keep_id, g.tag FROM trade_tags g JOIN duplicate_trades d ON d.id = g.trade_id;
+            DELETE FROM trade_tags WHERE trade_id IN (SELECT id FROM duplicate_trades);",
+        );
+    }
+    if table_exists(tx, "marks")? {
+        // The survivor keeps its own mark, or else takes the latest of its duplicates'
+        attached.push_str(
+            "INSERT OR IGNORE INTO marks (trade_id, date, price_cents)
+                SELECT d.keep_id, m.date, m.price_cents FROM marks m
+                JOIN duplicate_trades d ON d.id = m.trade_id ORDER BY m.date DESC;
+            DELETE FROM marks WHERE trade_id IN (SELECT id FROM duplicate_trades);",
+        );
+    }
+    tx.execute_batch(&format!(
+        "CREATE TEMP TABLE duplicate_trades AS
+            SELECT t.id AS id, k.keep_id AS keep_id FROM option_trades t
+            JOIN (SELECT dedup_key, MIN(id) AS keep_id FROM option_trades GROUP BY dedup_key) k
+                ON k.dedup_key = t.dedup_key
+            WHERE t.id != k.keep_id;
+        UPDATE option_trades SET closes_trade_id =
+                (SELECT keep_id FROM duplicate_trades WHERE id = closes_trade_id)
+            WHERE closes_trade_id IN (SELECT id FROM duplicate_trades);
+        UPDATE option_trades SET rolled_from_id =
+                (SELECT keep_id FROM duplicate_trades WHERE id = rolled_from_id)
+            WHERE rolled_from_id IN (SELECT id FROM duplicate_trades);
+        UPDATE option_trades SET covered_by_id =
+                (SELECT keep_id FROM duplicate_trades WHERE id = covered_by_id)
+            WHERE covered_by_id IN (SELECT id FROM duplicate_trades);
+        {attached}
+        DELETE FROM option_trades WHERE id IN (SELECT id FROM duplicate_trades);
+        DROP TABLE duplicate_trades;"
+    ))
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool, rusqlite::Error> {
+    conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")?
+        .exists([table])
+}
+
+/// The `CREATE TABLE` statement `table` was created with.
+fn table_sql(conn: &Connection, table: &str) -> Result<String, rusqlite::Error> {
+    conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get(0),
+    )
+}
+
+/// Whether `table` has `column`, generated columns included.
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_xinfo({table})"))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), rusqlite::Error> {
+    if !column_exists(conn, table, column)? {
+        conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Tables included in JSON dumps, parents first so loads satisfy foreign keys. The
+/// machine-local `meta` and `sync_state` tables are left out.
+pub const DUMP_TABLES: [&str; 8] = [
+    "campaigns",
+    "option_trades",
+    "trade_tags",
+    "marks",
+    "stock_trades",
+    "cash_events",
+    "snapshots",
+    "dividends",
+];
+
+/// Serialize every table to `{"schema_version": n, "tables": {"name": [{column: value}]}}`.
+pub fn dump_json(conn: &Connection) -> crate::error::Result<serde_json::Value> {
+    let mut tables = Map::new();
+    for table in DUMP_TABLES {
+        let columns = table_columns(conn, table)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM {table} ORDER BY rowid",
+            columns.join(", ")
+        ))?;
+        let mut rows = stmt.query([])?;
+        let mut dumped = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut object = Map::new();
+            for (i, column) in columns.iter().enumerate() {
+                let value = match row.get_ref(i)? {
+                    ValueRef::Null => serde_json::Value::Null,
+                    ValueRef::Integer(v) => json!(v),
+                    ValueRef::Real(v) => json!(v),
+                    ValueRef::Text(v) => json!(String::from_utf8_lossy(v)),
+                    ValueRef::Blob(_) => {
+                        return Err(format!("Cannot dump blob in {table}.{column}").into());
+                    }
+                };
+                object.insert(column.clone(), value);
+            }
+            dumped.push(serde_json::Value::Object(object));
+        }
+        tables.insert(table.to_string(), serde_json::Value::Array(dumped));
+    }
+    Ok(json!({
+        "schema_version": schema_version(conn)?,
+        "tables": tables,
+    }))
+}
+
+/// Load a document produced by `dump_json`, keeping row ids. The database must be
+/// empty unless `replace` is set, in which case all existing rows are deleted first.
+///
+/// Columns missing from the dump take their defaults, so dumps from older versions load.
+/// Returns the number of rows inserted.
+pub fn load_json(
+    conn: &Connection,
+    dump: &serde_json::Value,
+    replace: bool,
+) -> crate::error::Result<usize> {
+    let version = dump["schema_version"]
+        .as_i64()
+        .ok_or("Dump is missing its schema_version")?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Dump has schema version {version}, newer than this build supports ({SCHEMA_VERSION})"
+        )
+        .into());
+    }
+    let tables = dump["tables"]
+        .as_object()
+        .ok_or("Dump is missing its tables")?;
+    if let Some(name) = tables
+        .keys()
+        .find(|name| !DUMP_TABLES.contains(&name.as_str()))
+    {
+        return Err(format!("Dump contains unknown table '{name}'").into());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    // Trades can link to trades with higher ids, so check references at commit
+    tx.pragma_update(None, "defer_foreign_keys", true)?;
+    if replace {
+        for table in DUMP_TABLES.iter().rev() {
+            tx.execute(&format!("DELETE FROM {table}"), [])?;
+        }
+    } else {
+        for table in DUMP_TABLES {
+            if tx.prepare(&format!("SELECT 1 FROM {table}"))?.exists([])? {
+                return Err(
+                    "Database is not empty; load into a new database or pass --replace".into(),
+                );
+            }
+        }
+    }
+
+    let mut loaded = 0;
+    for table in DUMP_TABLES {
+        let Some(rows) = tables.get(table) else {
+            continue;
+        };
+        let rows = rows
+            .as_array()
+            .ok_or_else(|| format!("Table '{table}' must be a list of rows"))?;
+        let known = table_columns(&tx, table)?;
+        for row in rows {
+            let mut row = row
+                .as_object()
+                .ok_or_else(|| format!("Rows of '{table}' must be objects"))?
+                .clone();
+            if version < 5 {
+                dollars_to_cents(table, &mut row);
+            }
+            let mut columns = Vec::new();
+            let mut values = Vec::new();
+            for (column, value) in &row {
+                if !known.contains(column) {
+                    return Err(format!("Unknown column '{column}' in table '{table}'").into());
+                }
+                columns.push(column.as_str());
+                values.push(match value {
+                    serde_json::Value::Null => Value::Null,
+                    serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+                    serde_json::Value::Number(n) => match n.as_i64() {
+                        Some(i) => Value::Integer(i),
+                        None => Value::Real(n.as_f64().unwrap_or_default()),
+                    },
+                    serde_json::Value::String(s) => Value::Text(s.clone()),
+                    _ => return Err(format!("Unsupported value in {table}.{column}").into()),
+                });
+            }
+            let placeholders = vec!["?"; columns.len()].join(", ");
+            tx.execute(
+                &format!(
+                    "INSERT INTO {table} ({}) VALUES ({placeholders})",
+                    columns.join(", ")
+                ),
+                rusqlite::params_from_iter(values),
+            )?;
+            loaded += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(loaded)
+}
+
+/// Rewrite the dollar amounts of a row dumped before version 5 as the cents columns
+/// that replaced them.
+fn dollars_to_cents(table: &str, row: &mut Map<String, serde_json::Value>) {
+    let shares = row
+        .get("number_of_shares")
+        .and_then(|v| v.as_f64())
+        .unwrap_or_default();
+    let mut convert = |from: &str, to: &str, scale: f64| {
+        if let Some(value) = row.remove(from) {
+            let cents = value.as_f64().map(|v| crate::money::to_cents(v * scale));
+            row.insert(to.to_string(), json!(cents));
+        }
+    };
+    match table {
+        "campaigns" => convert("target_exit_price", "target_exit_price_cents", 1.0),
+        "snapshots" => {
+            convert("realized_pl", "realized_pl_cents", 1.0);
+            convert("open_collateral", "open_collateral_cents", 1.0);
+            convert("cash", "cash_cents", 1.0);
+            convert("share_value", "share_value_cents", 1.0);
+        }
+        "option_trades" => {
+            convert("strike", "strike_cents", 1.0);
+            convert("credit", "premium_cents", shares);
+        }
+        _ => {}
+    }
+}
+
+/// Stored columns of `table`, excluding generated ones.
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    stmt.query_map([], |row| row.get::<_, String>(1))?.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OptionTrade;
+
+    #[test]
+    fn test_migrate_legacy_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE campaigns (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                symbol TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                target_exit_price REAL
+            );
+            CREATE TABLE option_trades (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                symbol TEXT NOT NULL,
+                campaign TEXT NOT NULL,
+                action TEXT NOT NULL,
+                strike REAL NOT NULL,
+                delta REAL NOT NULL,
+                expiration_date TEXT NOT NULL,
+                date_of_action TEXT NOT NULL,
+                number_of_shares INTEGER NOT NULL,
+                credit REAL NOT NULL
+            );
+            INSERT INTO campaigns (name, symbol, created_at) VALUES ('wheel', 'NVTS', '2025-06-01');
+            INSERT INTO option_trades VALUES
+                (1, 'NVTS', 'wheel', 'SellPut', 6.5, 0.2, '2025-07-03', '2025-06-26', 1500, 0.18),
+                (2, 'HOOD', 'HOOD', 'SellPut', 80.0, 0.3, '2025-07-03', '2025-06-25', 200, 1.41),
+                (3, 'NVTS', 'wheel', 'SellPut', 6.5, 0.2, '2025-07-03', '2025-06-26', 1500, 0.18),
+                (4, 'NVTS', 'wheel', 'SellPut', 6.5, 0.3, '2025-07-03', '2025-06-26', 1500, 0.18);",
+        )
+        .unwrap();
+
+        init_database(&conn, &Default::default()).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert!(!column_exists(&conn, "option_trades", "campaign").unwrap());
+        let mut trades = OptionTrade::get_all(&conn).unwrap();
+        trades.sort_by_key(|t| t.id);
+        let campaigns: Vec<&str> = trades.iter().map(|t| t.campaign.as_str()).collect();
+        assert_eq!(campaigns, ["wheel", "HOOD"]);
+        assert_eq!((trades[0].credit, trades[0].strike), (0.18, 6.5));
+        assert_eq!(trades[1].premium_cents(), 28200);
+        assert!(!trades[0].insert_or_ignore(&conn).unwrap());
+        // A delta filled in after import doesn't make the same transaction look new
+        trades[0].delta = 0.35;
+        assert!(!trades[0].insert_or_ignore(&conn).unwrap());
+
+        let bogus = conn.execute(
+            "INSERT INTO option_trades (symbol, campaign_id, action, strike_cents, delta,
+                expiration_date, date_of_action, number_of_shares, premium_cents)
+                VALUES ('NVTS', 1, 'Hold', 650, 0.0, '2025-07-03', '2025-06-26', 100, 1000)",
+            [],
+        );
+        assert!(bogus.is_err(), "unknown actions must be rejected");
+
+        // Campaigns that still have trades cannot be deleted out from under them
+        conn.pragma_update(None, "foreign_keys", true).unwrap();
+        assert!(crate::models::Campaign::delete(&conn, "HOOD").is_err());
+    }
+
+    #[test]
+    fn test_removing_duplicates_keeps_their_tags_and_mark() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_database(&conn, &Default::default()).unwrap();
+        conn.execute_batch(
+            "INSERT INTO campaigns (name, symbol, created_at) VALUES ('wheel', 'NVTS', '2025-06-01');
+            INSERT INTO option_trades (id, symbol, campaign_id, action, strike_cents, delta,
+                expiration_date, date_of_action, number_of_shares, premium_cents)
+            VALUES
+                (1, 'NVTS', 1, 'SellPut', 650, 0.2, '2025-07-03', '2025-06-26', 1500, 27000),
+                (2, 'NVTS', 1, 'SellPut', 650, 0.3, '2025-07-03', '2025-06-26', 1500, 27000);
+            INSERT INTO trade_tags (trade_id, tag) VALUES (1, 'wheel'), (2, 'earnings');
+            INSERT INTO marks (trade_id, date, price_cents) VALUES (2, '2025-06-30', 9);",
+        )
+        .unwrap();
+
+        let tx = conn.unchecked_transaction().unwrap();
+        remove_duplicate_trades(&tx).unwrap();
+        tx.commit().unwrap();
+
+        let tags = OptionTrade::tags_by_trade(&conn).unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[&1], ["earnings", "wheel"]);
+        let marked: Vec<i32> = conn
+            .prepare("SELECT trade_id FROM marks")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(marked, [1]);
+    }
+
+    #[test]
+    fn test_open_version_zero_fixture() {
+        let path =
+            std::env::temp_dir().join(format!("profit_tracker_v0_{}.db", std::process::id()));
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(&std::fs::read_to_string("tests/v0.sql").unwrap())
+            .unwrap();
+        drop(conn);
+
+        let multipliers = HashMap::from([("hood".to_string(), 10)]);
+        let conn = open_database(&path, &multipliers).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        for table in DUMP_TABLES {
+            assert!(
+                table_exists(&conn, table).unwrap(),
+                "{table} was not created"
+            );
+        }
+        let mut trades = OptionTrade::get_all(&conn).unwrap();
+        trades.sort_by_key(|t| t.id);
+        assert_eq!(trades.len(), 3, "the repeated sell is merged");
+        assert_eq!(trades[0].premium_cents(), 6200);
+        assert_eq!(trades[2].campaign, "HOOD");
+        let multipliers: Vec<i32> = trades.iter().map(|t| t.multiplier).collect();
+        assert_eq!(multipliers, [100, 100, 10]);
+        let target: i64 = conn
+            .query_row("SELECT target_exit_price_cents FROM campaigns", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(target, 1650);
+        drop(conn);
+
+        for file in [path.clone(), backup_path(&path, 0)] {
+            std::fs::remove_file(file).unwrap();
+        }
+        for suffix in ["-wal", "-shm"] {
+            let mut name = path.as_os_str().to_owned();
+            name.push(suffix);
+            let _ = std::fs::remove_file(name);
+        }
+    }
+
+    #[test]
+    fn test_open_backs_up_old_and_refuses_newer_databases() {
+        let path =
+            std::env::temp_dir().join(format!("profit_tracker_open_{}.db", std::process::id()));
+        let conn = Connection::open(&path).unwrap();
+        init_database(&conn, &Default::default()).unwrap();
+        conn.pragma_update(None, "user_version", 4).unwrap();
+        drop(conn);
+
+        open_database(&path, &Default::default()).unwrap();
+        let backup = backup_path(&path, 4);
+        assert!(backup.exists());
+
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        drop(conn);
+        let err = open_database(&path, &Default::default())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("upgrade profit_tracker"), "{err}");
+
+        for file in [path.clone(), backup] {
+            std::fs::remove_file(file).unwrap();
+        }
+        for suffix in ["-wal", "-shm"] {
+            let mut name = path.as_os_str().to_owned();
+            name.push(suffix);
+            let _ = std::fs::remove_file(name);
+        }
+    }
+
+    #[test]
+    fn test_dump_load_round_trip() {
+        let source = Connection::open_in_memory().unwrap();
+        init_database(&source, &Default::default()).unwrap();
+        source
+            .execute_batch(
+                "INSERT INTO campaigns (name, symbol, created_at) VALUES ('wheel', 'NVTS', '2025-06-01');
+                INSERT INTO option_trades (id, symbol, campaign_id, action, strike_cents, delta,
+                    expiration_date, date_of_action, number_of_shares, premium_cents, closes_trade_id)
+                VALUES
+                    (1, 'NVTS', 1, 'BuyPut', 650, 0.0, '2025-07-03', '2025-07-01', 1500, -8220, 2),
+                    (2, 'NVTS', 1, 'SellPut', 650, 0.2, '2025-07-03', '2025-06-26', 1500, 26220, NULL);
+                INSERT INTO snapshots (date, realized_pl_cents, open_collateral_cents, cash_cents,
+                    share_value_cents) VALUES ('2025-06-27', 0, 975000, 26220, 0);
+                INSERT INTO stock_trades (symbol, campaign_id, date, shares, price_cents,
+                    amount_cents) VALUES ('NVTS', 1, '2025-06-20', 100, 640, -64000);
+                INSERT INTO cash_events (symbol, campaign_id, date, kind, amount_cents)
+                VALUES ('NVTS', 1, '2025-07-15', 'Dividend', 1200), (NULL, NULL, '2025-07-31', 'Interest', 87);
+                INSERT INTO dividends (symbol, ex_date, amount_cents) VALUES ('NVTS', '2025-08-08', 24);",
+            )
+            .unwrap();
+        let dump = dump_json(&source).unwrap();
+
+        let target = Connection::open_in_memory().unwrap();
+        init_database(&target, &Default::default()).unwrap();
+        target.pragma_update(None, "foreign_keys", true).unwrap();
+        assert_eq!(load_json(&target, &dump, false).unwrap(), 8);
+        assert_eq!(dump_json(&target).unwrap(), dump);
+        assert!(load_json(&target, &dump, false).is_err());
+        assert_eq!(load_json(&target, &dump, true).unwrap(), 8);
+    }
+}
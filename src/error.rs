@@ -0,0 +1,92 @@
+//! The error type returned by the library.
+
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{}", database_message(.0))]
+    Database(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// A date out of range, as in a bad timestamp from a quote provider.
+    #[error(transparent)]
+    Date(#[from] time::error::ComponentRange),
+    /// The local time zone couldn't be determined, so neither could today's date.
+    #[error(transparent)]
+    LocalOffset(#[from] time::error::IndeterminateOffset),
+    #[error(transparent)]
+    TimeFormat(#[from] time::error::Format),
+    /// A request to a quote provider failed.
+    #[error(transparent)]
+    Http(#[from] ureq::Error),
+    /// A reminder email couldn't be built.
+    #[error(transparent)]
+    Email(#[from] lettre::error::Error),
+    /// A reminder email couldn't be sent.
+    #[error(transparent)]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    /// A spreadsheet couldn't be written.
+    #[error(transparent)]
+    Xlsx(#[from] rust_xlsxwriter::XlsxError),
+    /// The REPL couldn't read a line.
+    #[error(transparent)]
+    Readline(#[from] rustyline::error::ReadlineError),
+    #[cfg(feature = "postgres")]
+    #[error(transparent)]
+    Postgres(#[from] postgres::Error),
+    /// The config file couldn't be read or parsed.
+    #[error("{}: {message}", path.display())]
+    Config { path: PathBuf, message: String },
+    /// Anything else, described for the user.
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Describe a database error, rewording a write SQLite refused for breaking a
+/// constraint in terms of the rows involved.
+fn database_message(error: &rusqlite::Error) -> String {
+    let rusqlite::Error::SqliteFailure(failure, Some(message)) = error else {
+        return error.to_string();
+    };
+    if failure.code != rusqlite::ErrorCode::ConstraintViolation {
+        return error.to_string();
+    }
+    match message.split_once(" constraint failed: ") {
+        Some(("NOT NULL", column)) if column.ends_with(".campaign_id") => {
+            "There is no campaign with that name".to_string()
+        }
+        Some(("UNIQUE", columns)) => {
+            let (table, _) = columns.split_once('.').unwrap_or(("the table", ""));
+            let names: Vec<&str> = columns
+                .split(", ")
+                .map(|c| c.split_once('.').map_or(c, |(_, name)| name))
+                .collect();
+            format!(
+                "{table} already has a row with the same {}",
+                names.join(", ")
+            )
+        }
+        _ if message.starts_with("FOREIGN KEY") => {
+            "The change would leave a record pointing at a missing campaign or trade".to_string()
+        }
+        _ => format!("The database refused the change: {message}"),
+    }
+}
@@ -0,0 +1,531 @@
+//! Filter expressions (`symbol=NVDA and credit>0.3`) and field assignments used to
+//! select and batch-edit trades.
+
+use crate::models::{Action, OptionTrade};
+use rusqlite::types::Value as SqlValue;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use time::Date;
+
+/// A parsed filter expression such as `symbol=NVDA and action=SellPut and credit>0.3`.
+///
+/// Conditions are joined with `and`; every condition must match for a trade to pass.
+/// An empty expression matches every trade.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+/// A `field=value` assignment used to batch-edit trades, e.g. `campaign=NVTS-wheel`.
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    field: Field,
+    value: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Id,
+    Symbol,
+    Campaign,
+    Action,
+    Strike,
+    Delta,
+    Expiration,
+    Date,
+    Shares,
+    Contracts,
+    Credit,
+    Total,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Number(f64),
+    Date(Date),
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: Value,
+}
+
+// Longer operators first so `>=` is not read as `>` followed by `=`.
+const OPERATORS: [(&str, Op); 6] = [
+    (">=", Op::Ge),
+    ("<=", Op::Le),
+    ("!=", Op::Ne),
+    ("=", Op::Eq),
+    (">", Op::Gt),
+    ("<", Op::Lt),
+];
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "id" => Some(Field::Id),
+            "symbol" => Some(Field::Symbol),
+            "campaign" => Some(Field::Campaign),
+            "action" => Some(Field::Action),
+            "strike" => Some(Field::Strike),
+            "delta" => Some(Field::Delta),
+            "expiration" | "exp" => Some(Field::Expiration),
+            "date" | "date_of_action" => Some(Field::Date),
+            "shares" | "number_of_shares" => Some(Field::Shares),
+            "contracts" => Some(Field::Contracts),
+            "credit" => Some(Field::Credit),
+            "total" => Some(Field::Total),
+            _ => None,
+        }
+    }
+
+    fn parse_value(self, raw: &str) -> Result<Value, String> {
+        match self {
+            Field::Symbol | Field::Campaign | Field::Action => Ok(Value::Text(raw.to_string())),
+            Field::Expiration | Field::Date => {
+                let date_fmt = time::macros::format_description!("[year]-[month]-[day]");
+                Date::parse(raw, &date_fmt)
+                    .map(Value::Date)
+                    .map_err(|_| format!("Invalid date '{raw}', expected YYYY-MM-DD"))
+            }
+            _ => raw
+                .parse::<f64>()
+                .map(Value::Number)
+                .map_err(|_| format!("Invalid number '{raw}'")),
+        }
+    }
+
+    /// The field as an SQL expression over `option_trades t JOIN campaigns c`, worked out
+    /// from the stored cents the same way `OptionTrade` does so both sides agree exactly.
+    fn sql(self) -> &'static str {
+        // Per-share credit, rounded like `money::per_share`
+        macro_rules! credit {
+            () => {
+                "(CASE WHEN t.number_of_shares = 0 THEN 0.0 \
+                ELSE round(t.premium_cents * 10000.0 / t.number_of_shares) / 1000000.0 END)"
+            };
+        }
+        match self {
+            Field::Id => "t.id",
+            Field::Symbol => "t.symbol",
+            Field::Campaign => "c.name",
+            Field::Action => "t.action",
+            Field::Strike => "(t.strike_cents / 100.0)",
+            Field::Delta => "t.delta",
+            Field::Expiration => "t.expiration_date",
+            Field::Date => "t.date_of_action",
+            Field::Shares => "t.number_of_shares",
+            Field::Contracts => "t.contracts",
+            Field::Credit => credit!(),
+            Field::Total => concat!(credit!(), " * t.number_of_shares"),
+        }
+    }
+
+    fn value_of(self, trade: &OptionTrade) -> Value {
+        match self {
+            Field::Id => Value::Number(trade.id.unwrap_or_default() as f64),
+            Field::Symbol => Value::Text(trade.symbol.clone()),
+            Field::Campaign => Value::Text(trade.campaign.clone()),
+            Field::Action => Value::Text(trade.action.as_str().to_string()),
+            Field::Strike => Value::Number(trade.strike),
+            Field::Delta => Value::Number(trade.delta),
+            Field::Expiration => Value::Date(trade.expiration_date),
+            Field::Date => Value::Date(trade.date_of_action),
+            Field::Shares => Value::Number(trade.number_of_shares as f64),
+            Field::Contracts => Value::Number(trade.contracts() as f64),
+            Field::Credit => Value::Number(trade.credit),
+            Field::Total => Value::Number(trade.credit * trade.number_of_shares as f64),
+        }
+    }
+}
+
+impl Condition {
+    fn parse(clause: &str) -> Result<Self, String> {
+        let (pos, token, op) = OPERATORS
+            .iter()
+            .filter_map(|(token, op)| clause.find(token).map(|pos| (pos, *token, *op)))
+            .min_by_key(|(pos, token, _)| (*pos, std::cmp::Reverse(token.len())))
+            .ok_or_else(|| format!("Missing operator in condition '{clause}'"))?;
+
+        let name = clause[..pos].trim();
+        let raw = clause[pos + token.len()..].trim().trim_matches(['\'', '"']);
+        let field = Field::from_name(name).ok_or_else(|| format!("Unknown field '{name}'"))?;
+        if raw.is_empty() {
+            return Err(format!("Missing value in condition '{clause}'"));
+        }
+        let value = field.parse_value(raw)?;
+        Ok(Condition { field, op, value })
+    }
+
+    /// The condition as SQL, pushing its value onto `params`. Text compares without case,
+    /// as in `matches`.
+    fn to_sql(&self, params: &mut Vec<SqlValue>) -> String {
+        let op = match self.op {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        };
+        let column = self.field.sql();
+        match &self.value {
+            Value::Text(v) => {
+                params.push(SqlValue::Text(v.to_lowercase()));
+                format!("lower({column}) {op} ?")
+            }
+            Value::Number(n) => {
+                params.push(SqlValue::Real(*n));
+                format!("{column} {op} ?")
+            }
+            Value::Date(d) => {
+                params.push(SqlValue::Text(d.to_string()));
+                format!("{column} {op} ?")
+            }
+        }
+    }
+
+    fn matches(&self, trade: &OptionTrade) -> bool {
+        let ordering = match (self.field.value_of(trade), &self.value) {
+            (Value::Text(actual), Value::Text(expected)) => {
+                actual.to_lowercase().cmp(&expected.to_lowercase())
+            }
+            (Value::Number(actual), Value::Number(expected)) => {
+                match actual.partial_cmp(expected) {
+                    Some(ordering) => ordering,
+                    None => return false,
+                }
+            }
+            (Value::Date(actual), Value::Date(expected)) => actual.cmp(expected),
+            _ => return false,
+        };
+        match self.op {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl Filter {
+    /// Narrow the filter to trades in the campaign `name`.
+    pub fn campaign(mut self, name: &str) -> Self {
+        self.conditions.push(Condition {
+            field: Field::Campaign,
+            op: Op::Eq,
+            value: Value::Text(name.to_string()),
+        });
+        self
+    }
+
+    /// Narrow the filter to trades placed on or after `date`.
+    pub fn since(mut self, date: Date) -> Self {
+        self.conditions.push(Condition {
+            field: Field::Date,
+            op: Op::Ge,
+            value: Value::Date(date),
+        });
+        self
+    }
+
+    /// Narrow the filter to `action` trades.
+    pub fn action(mut self, action: &Action) -> Self {
+        self.conditions.push(Condition {
+            field: Field::Action,
+            op: Op::Eq,
+            value: Value::Text(action.as_str().to_string()),
+        });
+        self
+    }
+
+    pub fn matches(&self, trade: &OptionTrade) -> bool {
+        self.conditions.iter().all(|c| c.matches(trade))
+    }
+
+    /// The filter as an SQL condition over `option_trades t JOIN campaigns c`, with `?`
+    /// placeholders for the returned parameters. Selects the same trades as `matches`.
+    pub fn to_sql(&self) -> (String, Vec<SqlValue>) {
+        let mut params = Vec::new();
+        let clauses: Vec<String> = self
+            .conditions
+            .iter()
+            .map(|c| c.to_sql(&mut params))
+            .collect();
+        if clauses.is_empty() {
+            return ("1".to_string(), params);
+        }
+        (clauses.join(" AND "), params)
+    }
+}
+
+/// A sort key for trades: a field name, descending when prefixed with `-` (`-credit`).
+#[derive(Debug, Clone, Copy)]
+pub struct Sort {
+    field: Field,
+    descending: bool,
+}
+
+impl Sort {
+    /// The key as an SQL `ORDER BY` term.
+    pub fn to_sql(&self) -> String {
+        let direction = if self.descending { "DESC" } else { "ASC" };
+        format!("{} {direction}", self.field.sql())
+    }
+
+    /// Order `a` and `b` by this key, the way the `to_sql` term orders their rows.
+    pub fn compare(&self, a: &OptionTrade, b: &OptionTrade) -> Ordering {
+        let ordering = match (self.field.value_of(a), self.field.value_of(b)) {
+            (Value::Text(a), Value::Text(b)) => a.cmp(&b),
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(&b),
+            (Value::Date(a), Value::Date(b)) => a.cmp(&b),
+            _ => Ordering::Equal,
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+impl FromStr for Sort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, descending) = match s.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (s, false),
+        };
+        let field = Field::from_name(name).ok_or_else(|| format!("Unknown field '{name}'"))?;
+        Ok(Sort { field, descending })
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut conditions = Vec::new();
+        let mut clause = Vec::new();
+        for word in s.split_whitespace() {
+            if word.eq_ignore_ascii_case("and") {
+                conditions.push(Condition::parse(&clause.join(" "))?);
+                clause.clear();
+            } else {
+                clause.push(word);
+            }
+        }
+        if !clause.is_empty() {
+            conditions.push(Condition::parse(&clause.join(" "))?);
+        } else if !conditions.is_empty() {
+            return Err("Filter expression ends with a dangling 'and'".to_string());
+        }
+        Ok(Filter { conditions })
+    }
+}
+
+impl Assignment {
+    pub fn apply(&self, trade: &mut OptionTrade) {
+        match (self.field, &self.value) {
+            (Field::Symbol, Value::Text(v)) => trade.symbol = v.clone(),
+            (Field::Campaign, Value::Text(v)) => trade.campaign = v.clone(),
+            (Field::Action, Value::Text(v)) => {
+                if let Ok(action) = v.parse() {
+                    trade.action = action;
+                }
+            }
+            (Field::Strike, Value::Number(v)) => trade.strike = *v,
+            (Field::Delta, Value::Number(v)) => trade.delta = *v,
+            (Field::Expiration, Value::Date(v)) => trade.expiration_date = *v,
+            (Field::Date, Value::Date(v)) => trade.date_of_action = *v,
+            (Field::Shares, Value::Number(v)) => trade.number_of_shares = *v as i32,
+            (Field::Contracts, Value::Number(v)) => {
+                trade.number_of_shares = *v as i32 * trade.multiplier;
+            }
+            (Field::Credit, Value::Number(v)) => trade.credit = *v,
+            _ => {}
+        }
+    }
+}
+
+impl FromStr for Assignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, raw) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected field=value, got '{s}'"))?;
+        let name = name.trim();
+        let raw = raw.trim().trim_matches(['\'', '"']);
+        let field = Field::from_name(name).ok_or_else(|| format!("Unknown field '{name}'"))?;
+        if matches!(field, Field::Id | Field::Total) {
+            return Err(format!("Field '{name}' cannot be set"));
+        }
+        if raw.is_empty() {
+            return Err(format!("Missing value in assignment '{s}'"));
+        }
+        let value = field.parse_value(raw)?;
+        match (field, &value) {
+            (Field::Action, Value::Text(v)) => {
+                v.parse::<Action>()?;
+            }
+            (Field::Shares, Value::Number(v)) if v.fract() != 0.0 => {
+                return Err(format!("Invalid share count '{raw}'"));
+            }
+            (Field::Contracts, Value::Number(v)) if v.fract() != 0.0 => {
+                return Err(format!("Invalid contract count '{raw}'"));
+            }
+            _ => {}
+        }
+        Ok(Assignment { field, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn trade(symbol: &str, action: Action, credit: f64) -> OptionTrade {
+        OptionTrade {
+            id: Some(1),
+            symbol: symbol.to_string(),
+            campaign: "default".to_string(),
+            action,
+            strike: 6.5,
+            expiration_date: date!(2025 - 07 - 03),
+            date_of_action: date!(2025 - 06 - 26),
+            number_of_shares: 1500,
+            credit,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_matches_conditions() {
+        let filter: Filter = "symbol=nvts and action=SellPut and credit>0.15"
+            .parse()
+            .unwrap();
+        assert!(filter.matches(&trade("NVTS", Action::SellPut, 0.18)));
+        assert!(!filter.matches(&trade("NVTS", Action::SellPut, 0.10)));
+        assert!(!filter.matches(&trade("NVTS", Action::BuyPut, 0.18)));
+        assert!(!filter.matches(&trade("HOOD", Action::SellPut, 0.18)));
+
+        let filter: Filter = "exp >= 2025-07-01 and shares != 100".parse().unwrap();
+        assert!(filter.matches(&trade("NVTS", Action::SellPut, 0.18)));
+
+        let filter: Filter = "".parse().unwrap();
+        assert!(filter.matches(&trade("NVTS", Action::SellPut, 0.18)));
+
+        let filter = Filter::default()
+            .campaign("Default")
+            .since(date!(2025 - 06 - 26))
+            .action(&Action::SellPut);
+        assert!(filter.matches(&trade("NVTS", Action::SellPut, 0.18)));
+        assert!(!filter.matches(&trade("NVTS", Action::SellCall, 0.18)));
+        assert!(!filter.clone().since(date!(2025 - 06 - 27)).matches(&trade(
+            "NVTS",
+            Action::SellPut,
+            0.18
+        )));
+    }
+
+    #[test]
+    fn test_filter_sql_selects_same_trades() {
+        use crate::models::{Campaign, TradeQuery};
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::init_database(&conn, &Default::default()).unwrap();
+        Campaign::insert(&conn, "default", "NVTS", None).unwrap();
+        for (symbol, action, credit) in [
+            ("NVTS", Action::SellPut, 0.1748),
+            ("nvts", Action::BuyPut, -0.0548),
+            ("HOOD", Action::SellCall, 0.18),
+        ] {
+            trade(symbol, action, credit).insert(&conn).unwrap();
+        }
+        let all = OptionTrade::get_all(&conn).unwrap();
+        for expression in [
+            "",
+            "symbol=NVTS",
+            "action=sellput and credit>=0.1748",
+            "credit=0.1748",
+            "total<0 and exp=2025-07-03",
+            "strike=6.5 and date<2025-06-27 and id>1",
+        ] {
+            let filter: Filter = expression.parse().unwrap();
+            let expected: Vec<_> = all
+                .iter()
+                .filter(|t| filter.matches(t))
+                .map(|t| t.id)
+                .collect();
+            let query = TradeQuery {
+                filter,
+                ..Default::default()
+            };
+            let found: Vec<_> = OptionTrade::query(&conn, &query)
+                .unwrap()
+                .iter()
+                .map(|t| t.id)
+                .collect();
+            assert_eq!(found, expected, "{expression}");
+        }
+
+        let query = TradeQuery {
+            sort: vec!["-credit".parse().unwrap()],
+            limit: Some(1),
+            offset: 1,
+            ..Default::default()
+        };
+        let page = OptionTrade::query(&conn, &query).unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].symbol, "NVTS");
+        assert_eq!(
+            OptionTrade::count(&conn, &"symbol=nvts".parse().unwrap()).unwrap(),
+            2
+        );
+        assert!("-bogus".parse::<Sort>().is_err());
+    }
+
+    #[test]
+    fn test_filter_rejects_invalid_expressions() {
+        assert!("bogus=1".parse::<Filter>().is_err());
+        assert!("strike>abc".parse::<Filter>().is_err());
+        assert!("symbol".parse::<Filter>().is_err());
+        assert!("symbol=NVTS and".parse::<Filter>().is_err());
+        assert!("exp=07/03/25".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn test_assignment_apply() {
+        let mut t = trade("NVTS", Action::SellPut, 0.18);
+        for set in ["campaign=NVTS-wheel", "action=buy_put", "exp=2025-07-11"] {
+            set.parse::<Assignment>().unwrap().apply(&mut t);
+        }
+        assert_eq!(t.campaign, "NVTS-wheel");
+        assert_eq!(t.action, Action::BuyPut);
+        assert_eq!(t.expiration_date, date!(2025 - 07 - 11));
+
+        assert!("id=4".parse::<Assignment>().is_err());
+        assert!("action=Hold".parse::<Assignment>().is_err());
+        assert!("shares=1.5".parse::<Assignment>().is_err());
+        assert!("contracts=1.5".parse::<Assignment>().is_err());
+        assert!("symbol".parse::<Assignment>().is_err());
+    }
+}
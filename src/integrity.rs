@@ -0,0 +1,224 @@
+//! Checks for inconsistent rows in the trade database and the repairs offered for them.
+
+use crate::csv_processor::parse_month_day_year;
+use rusqlite::{Connection, Result, params};
+use time::Date;
+use time::macros::format_description;
+
+/// Something wrong with a stored trade that would make it fail to load or be miscounted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// A date column that isn't `YYYY-MM-DD`, with the date it most likely means.
+    BadDate {
+        column: &'static str,
+        value: String,
+        fixed: Option<Date>,
+    },
+    NegativeShares(i32),
+    /// The trade points at a campaign row that no longer exists.
+    MissingCampaign(i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub trade_id: i32,
+    pub problem: Problem,
+}
+
+/// How to resolve an issue: apply the suggested fix or drop the trade altogether.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repair {
+    Fix,
+    DeleteTrade,
+}
+
+impl Issue {
+    pub fn describe(&self) -> String {
+        match &self.problem {
+            Problem::BadDate { column, value, .. } => format!(
+                "Trade {}: {column} '{value}' is not a YYYY-MM-DD date",
+                self.trade_id
+            ),
+            Problem::NegativeShares(shares) => {
+                format!("Trade {}: negative share count {shares}", self.trade_id)
+            }
+            Problem::MissingCampaign(id) => {
+                format!("Trade {}: campaign {id} does not exist", self.trade_id)
+            }
+        }
+    }
+
+    /// What `Repair::Fix` would do, or `None` when the trade can only be deleted.
+    pub fn fix_description(&self) -> Option<String> {
+        match &self.problem {
+            Problem::BadDate { column, fixed, .. } => {
+                fixed.map(|date| format!("set {column} to {date}"))
+            }
+            Problem::NegativeShares(shares) => Some(format!("use {} shares", shares.abs())),
+            Problem::MissingCampaign(id) => Some(format!("recreate campaign {id}")),
+        }
+    }
+}
+
+/// Best guess at the date meant by text that failed to parse as `YYYY-MM-DD`.
+fn guess_date(value: &str) -> Option<Date> {
+    let value = value.trim();
+    let iso = format_description!("[year]-[month]-[day]");
+    // Timestamps such as "2025-06-13 00:00:00" or "2025-06-13T16:00:00Z"
+    value
+        .get(..10)
+        .and_then(|prefix| Date::parse(prefix, iso).ok())
+        .or_else(|| Date::parse(&value.replace('/', "-"), iso).ok())
+        .or_else(|| parse_month_day_year(value))
+}
+
+/// Scan the trades table for rows the rest of the app can't handle.
+pub fn check(conn: &Connection) -> Result<Vec<Issue>> {
+    let iso = format_description!("[year]-[month]-[day]");
+    let mut issues = Vec::new();
+
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.expiration_date, t.date_of_action, t.number_of_shares, t.campaign_id,
+            c.id IS NULL
+        FROM option_trades t LEFT JOIN campaigns c ON c.id = t.campaign_id
+        ORDER BY t.id",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let trade_id: i32 = row.get(0)?;
+        for (idx, column) in [(1, "expiration_date"), (2, "date_of_action")] {
+            let value: String = row.get(idx)?;
+            if Date::parse(&value, iso).is_err() {
+                issues.push(Issue {
+                    trade_id,
+                    problem: Problem::BadDate {
+                        column,
+                        fixed: guess_date(&value),
+                        value,
+                    },
+                });
+            }
+        }
+        let shares: i32 = row.get(3)?;
+        if shares < 0 {
+            issues.push(Issue {
+                trade_id,
+                problem: Problem::NegativeShares(shares),
+            });
+        }
+        if row.get(5)? {
+            issues.push(Issue {
+                trade_id,
+                problem: Problem::MissingCampaign(row.get(4)?),
+            });
+        }
+    }
+    Ok(issues)
+}
+
+/// Resolve `issue` as chosen. Deleting a trade first clears close, roll and collateral
+/// links to it.
+pub fn repair(conn: &Connection, issue: &Issue, repair: Repair) -> Result<()> {
+    let id = issue.trade_id;
+    if repair == Repair::DeleteTrade {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE option_trades SET closes_trade_id = NULL WHERE closes_trade_id = ?1",
+            params![id],
+        )?;
+        tx.execute(
+            "UPDATE option_trades SET rolled_from_id = NULL WHERE rolled_from_id = ?1",
+            params![id],
+        )?;
+        tx.execute(
+            "UPDATE option_trades SET covered_by_id = NULL WHERE covered_by_id = ?1",
+            params![id],
+        )?;
+        tx.execute("DELETE FROM option_trades WHERE id = ?1", params![id])?;
+        return tx.commit();
+    }
+
+    match &issue.problem {
+        Problem::BadDate {
+            column,
+            fixed: Some(date),
+            ..
+        } => {
+            conn.execute(
+                &format!("UPDATE option_trades SET {column} = ?1 WHERE id = ?2"),
+                params![date.to_string(), id],
+            )?;
+        }
+        Problem::BadDate { fixed: None, .. } => {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "no replacement date; delete the trade instead".to_string(),
+            ));
+        }
+        Problem::NegativeShares(_) => {
+            conn.execute(
+                "UPDATE option_trades SET number_of_shares = abs(number_of_shares) WHERE id = ?1",
+                params![id],
+            )?;
+        }
+        Problem::MissingCampaign(campaign_id) => {
+            conn.execute(
+                "INSERT INTO campaigns (id, name, symbol, created_at)
+                SELECT ?1, 'Recovered ' || ?1, symbol, date('now') FROM option_trades WHERE id = ?2",
+                params![campaign_id, id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_database;
+    use crate::models::OptionTrade;
+
+    #[test]
+    fn test_check_and_repair() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_database(&conn, &Default::default()).unwrap();
+        conn.execute_batch(
+            "INSERT INTO campaigns (name, symbol, created_at) VALUES ('wheel', 'NVTS', '2025-06-01');
+            INSERT INTO option_trades (symbol, campaign_id, action, strike_cents, delta,
+                expiration_date, date_of_action, number_of_shares, premium_cents)
+            VALUES
+                ('NVTS', 1, 'SellPut', 650, 0.2, '07/03/2025', '2025-06-26', -1500, 26220),
+                ('NVTS', 7, 'SellPut', 700, 0.2, '2025-07-03', 'last friday', 100, 2000);",
+        )
+        .unwrap();
+        assert!(OptionTrade::get_all(&conn).is_err());
+
+        let issues = check(&conn).unwrap();
+        let problems: Vec<&Problem> = issues.iter().map(|i| &i.problem).collect();
+        assert_eq!(
+            problems,
+            [
+                &Problem::BadDate {
+                    column: "expiration_date",
+                    value: "07/03/2025".to_string(),
+                    fixed: Some(time::macros::date!(2025 - 07 - 03)),
+                },
+                &Problem::NegativeShares(-1500),
+                &Problem::BadDate {
+                    column: "date_of_action",
+                    value: "last friday".to_string(),
+                    fixed: None,
+                },
+                &Problem::MissingCampaign(7),
+            ]
+        );
+
+        repair(&conn, &issues[0], Repair::Fix).unwrap();
+        repair(&conn, &issues[1], Repair::Fix).unwrap();
+        assert!(repair(&conn, &issues[2], Repair::Fix).is_err());
+        repair(&conn, &issues[2], Repair::DeleteTrade).unwrap();
+        assert!(check(&conn).unwrap().is_empty());
+        let trades = OptionTrade::get_all(&conn).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].number_of_shares, 1500);
+    }
+}
@@ -0,0 +1,31 @@
+//! Options trade tracking for wheel-style campaigns.
+//!
+//! The `profit_tracker` binary is a TUI and CLI on top of this library; scripts and
+//! other tools can use it directly to read broker statements and the trade database
+//! and to run the same analytics:
+//!
+//! ```no_run
+//! use profit_tracker::{config, db, logic, models::OptionTrade};
+//!
+//! let conn = db::open_database(db::default_db_path()?, &config::get().multipliers)?;
+//! let trades = OptionTrade::get_all(&conn)?;
+//! let today = time::OffsetDateTime::now_utc().date();
+//! for lot in logic::match_lots(&trades, today).open {
+//!     println!("{} {} exp {}", lot.trade.symbol, lot.trade.strike, lot.trade.expiration_date);
+//! }
+//! # Ok::<(), profit_tracker::error::Error>(())
+//! ```
+//!
+//! Money is kept in whole cents wherever it is summed; see [`money`].
+
+pub mod config;
+pub mod csv_processor;
+pub mod db;
+pub mod error;
+pub mod filter;
+pub mod integrity;
+pub mod logic;
+pub mod metrics;
+pub mod models;
+pub mod money;
+pub mod quotes;
@@ -1,12 +1,27 @@
-use crate::models::{Action, OptionTrade};
-use time::OffsetDateTime;
+//! Analytics over trades: campaign summaries, FIFO lot matching, holdings, snapshots,
+//! dividend risk and option pricing.
 
+use crate::models::{
+    Action, CashEvent, CashKind, Dividend, GoalPeriod, OptionKind, OptionTrade, PremiumGoal,
+    Snapshot, StockTrade, TradeStatus,
+};
+use crate::money::{to_cents, to_dollars};
+use time::{Date, OffsetDateTime, Weekday};
+
+/// Returns break-even per share, weeks running, projected profit per week, and the
+/// total credits and running profit/loss in cents. Commissions and fees come out of the
+/// running profit/loss, and so out of break-even, but not out of the credits.
+///
+/// While shares are held, from any number of assignments and share trades in `stocks`,
+/// break-even is their blended cost basis less the net premium (see
+/// [`ShareLedger::break_even`]). Otherwise it is the average strike of the open short
+/// puts less the net premium per share they cover.
 pub fn calculate_campaign_summary(
     trades: &[&OptionTrade],
+    stocks: &[StockTrade],
     target_exit_price: Option<f64>,
-) -> (Option<f64>, i32, Option<f64>, f64, f64) {
-    // Break-even calculation
-    let total_debits: f64 = trades
+) -> (Option<f64>, i32, Option<f64>, i64, i64) {
+    let total_debits: i64 = trades
         .iter()
         .filter(|t| {
             matches!(
@@ -14,61 +29,41 @@ pub fn calculate_campaign_summary(
                 Action::Assigned | Action::BuyCall | Action::BuyPut
             )
         })
-        .map(|t| t.credit * t.number_of_shares as f64)
+        .map(|t| t.premium_cents())
         .sum();
 
-    let total_credits: f64 = trades
+    let total_credits: i64 = trades
         .iter()
         .filter(|t| matches!(t.action, Action::SellPut | Action::SellCall))
-        .map(|t| t.credit * t.number_of_shares as f64)
+        .map(|t| t.premium_cents())
         .sum();
 
-    let total_shares_assigned: i32 = trades
-        .iter()
-        .filter(|t| matches!(t.action, Action::Assigned))
-        .map(|t| t.number_of_shares)
-        .sum();
+    let total_costs: i64 = trades.iter().map(|t| t.costs_cents()).sum();
+    let running_profit_loss = total_credits - total_debits - total_costs;
 
-    // Find the last open put option strike and shares
-    let last_open_put = trades
-        .iter()
-        .filter(|t| {
-            matches!(t.action, Action::SellPut)
-                && !trades.iter().any(|other| {
-                    matches!(other.action, Action::Assigned)
-                        && other.symbol == t.symbol
-                        && other.strike == t.strike
-                        && other.expiration_date == t.expiration_date
-                })
-        })
-        .max_by(|a, b| a.date_of_action.cmp(&b.date_of_action));
-
-    let running_profit_loss = total_credits - total_debits;
-
-    // Calculate break-even based on last open put strike
-    let break_even = if let Some(last_put) = last_open_put {
-        let last_strike = last_put.strike;
-        let last_shares = last_put.number_of_shares;
-        if last_shares > 0 {
-            let price_per_share = running_profit_loss / last_shares as f64;
-            Some(last_strike - price_per_share)
-        } else {
-            Some(last_strike)
-        }
-    } else {
-        // Fallback to original calculation if no open puts
-        if total_shares_assigned > 0 {
-            Some((total_debits - total_credits) / total_shares_assigned as f64)
-        } else {
-            None
-        }
-    };
+    // Break-even calculation
+    let owned: Vec<OptionTrade> = trades.iter().map(|&t| t.clone()).collect();
+    let today = today();
+    let ledger = ShareLedger::new(&owned, stocks, today);
+    let shares_held = ledger.shares();
+    let break_even = ledger.break_even(running_profit_loss).or_else(|| {
+        let open_puts: Vec<OpenLot> = match_lots(&owned, today)
+            .open
+            .into_iter()
+            .filter(|lot| lot.short && lot.kind == OptionKind::Put)
+            .collect();
+        let put_shares: i64 = open_puts.iter().map(|lot| lot.shares as i64).sum();
+        let strikes: i64 = open_puts
+            .iter()
+            .map(|lot| lot.trade.strike_cents() * lot.shares as i64)
+            .sum();
+        (put_shares > 0).then(|| to_dollars(strikes - running_profit_loss) / put_shares as f64)
+    });
 
     // Weeks running calculation
     let first_trade_date = trades.iter().map(|t| t.date_of_action).min();
 
     let weeks_running = if let Some(first_date) = first_trade_date {
-        let today = OffsetDateTime::now_local().unwrap().date();
         let days_diff = (today - first_date).whole_days();
         (days_diff / 7) as i32
     } else {
@@ -77,9 +72,8 @@ pub fn calculate_campaign_summary(
 
     // Profit per week calculation
     let profit_per_week = if let Some(target_price) = target_exit_price {
-        if total_shares_assigned > 0 && weeks_running > 0 {
-            let target_profit =
-                (target_price - break_even.unwrap_or(0.0)) * total_shares_assigned as f64;
+        if shares_held > 0 && weeks_running > 0 {
+            let target_profit = (target_price - break_even.unwrap_or(0.0)) * shares_held as f64;
             Some(target_profit / weeks_running as f64)
         } else {
             None
@@ -97,7 +91,8 @@ pub fn calculate_campaign_summary(
     )
 }
 
-pub fn calculate_total_premium_sold(trades: &[OptionTrade]) -> f64 {
+/// Net premium sold across all contracts less commissions and fees, in cents.
+pub fn calculate_total_premium_sold(trades: &[OptionTrade]) -> i64 {
     use std::collections::HashMap;
 
     // Group trades by (symbol, strike, expiration_date) using string key
@@ -106,19 +101,22 @@ pub fn calculate_total_premium_sold(trades: &[OptionTrade]) -> f64 {
     for trade in trades {
         let key = format!(
             "{}_{}_{}",
-            trade.symbol, trade.strike, trade.expiration_date
+            trade.symbol,
+            trade.strike_cents(),
+            trade.expiration_date
         );
         contract_groups.entry(key).or_default().push(trade);
     }
 
-    let mut total_net_premium = 0.0;
+    let mut total_net_premium = 0;
 
     for (_, contract_trades) in contract_groups {
-        let mut sold_premium = 0.0;
-        let mut bought_premium = 0.0;
+        let mut sold_premium = 0;
+        let mut bought_premium = 0;
 
         for trade in contract_trades {
-            let trade_premium = trade.credit * trade.number_of_shares as f64;
+            let trade_premium = trade.premium_cents();
+            total_net_premium -= trade.costs_cents();
 
             match trade.action {
                 Action::SellPut | Action::SellCall => {
@@ -127,8 +125,8 @@ pub fn calculate_total_premium_sold(trades: &[OptionTrade]) -> f64 {
                 Action::BuyPut | Action::BuyCall => {
                     bought_premium += trade_premium;
                 }
-                Action::Exercised | Action::Assigned => {
-                    // These are assignment/exercise events, not premium transactions
+                Action::Exercised | Action::Assigned | Action::Expired => {
+                    // These are assignment/exercise/expiration events, not premium transactions
                     // They don't affect the premium calculation
                 }
             }
@@ -140,11 +138,56 @@ pub fn calculate_total_premium_sold(trades: &[OptionTrade]) -> f64 {
     total_net_premium
 }
 
-pub fn calculate_weekly_premium(trades: &[OptionTrade]) -> f64 {
-    // Get this Friday's date
-    let now = OffsetDateTime::now_local().unwrap();
-    let today = now.date();
+/// Today's date in the local time zone, or in UTC when the local offset can't be
+/// determined.
+pub fn today() -> Date {
+    OffsetDateTime::now_local()
+        .unwrap_or_else(|_| OffsetDateTime::now_utc())
+        .date()
+}
+
+/// Premium in cents from options sold that expire this Friday.
+pub fn calculate_weekly_premium(trades: &[OptionTrade]) -> i64 {
+    let friday_date = this_friday(today());
+
+    // Filter trades that expire this Friday and are sell actions
+    let weekly_trades: Vec<&OptionTrade> = trades
+        .iter()
+        .filter(|t| {
+            t.expiration_date == friday_date
+                && matches!(t.action, Action::SellPut | Action::SellCall)
+        })
+        .collect();
+
+    // Calculate total premium from selling options this week
+    weekly_trades.iter().map(|t| t.premium_cents()).sum()
+}
+
+/// Net premium (credits less debits and costs) accumulated through each day with a
+/// trade, oldest first, in cents.
+pub fn cumulative_premium(trades: &[OptionTrade]) -> Vec<(Date, i64)> {
+    let mut by_day = std::collections::BTreeMap::new();
+    for trade in trades {
+        // Imported debits are negative and ones entered by hand positive
+        let premium = match trade.action {
+            Action::SellPut | Action::SellCall => trade.premium_cents().abs(),
+            Action::BuyPut | Action::BuyCall => -trade.premium_cents().abs(),
+            Action::Exercised | Action::Assigned | Action::Expired => 0,
+        };
+        *by_day.entry(trade.date_of_action).or_insert(0) += premium - trade.costs_cents();
+    }
+    let mut total = 0;
+    by_day
+        .into_iter()
+        .map(|(date, premium)| {
+            total += premium;
+            (date, total)
+        })
+        .collect()
+}
 
+/// The Friday weekly options expire on: `today` if it is a Friday, otherwise the next one.
+pub fn this_friday(today: Date) -> Date {
     // Calculate days until Friday (5 = Friday in ISO weekday)
     let current_weekday = today.weekday().number_from_monday();
     let days_until_friday = if current_weekday <= 5 {
@@ -155,24 +198,1982 @@ pub fn calculate_weekly_premium(trades: &[OptionTrade]) -> f64 {
         5 + (7 - current_weekday)
     };
 
-    let friday_date = if days_until_friday == 0 {
+    if days_until_friday == 0 {
         today // Today is Friday
     } else {
         today + time::Duration::days(days_until_friday as i64)
-    };
+    }
+}
 
-    // Filter trades that expire this Friday and are sell actions
-    let weekly_trades: Vec<&OptionTrade> = trades
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    Closed,
+    Expired,
+    Assigned,
+    Exercised,
+}
+
+impl CloseReason {
+    /// The reason's name, as shown in reports.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CloseReason::Closed => "Closed",
+            CloseReason::Expired => "Expired",
+            CloseReason::Assigned => "Assigned",
+            CloseReason::Exercised => "Exercised",
+        }
+    }
+}
+
+/// A portion of an opening trade that has been closed out.
+#[derive(Debug, Clone)]
+pub struct ClosedLot {
+    pub open_trade_id: Option<i32>,
+    pub close_trade_id: Option<i32>,
+    pub symbol: String,
+    pub campaign: String,
+    pub kind: OptionKind,
+    pub strike: f64,
+    pub expiration_date: Date,
+    pub short: bool,
+    pub shares: i32,
+    /// Shares per contract of the opening trade.
+    pub multiplier: i32,
+    pub opened: Date,
+    pub closed: Date,
+    /// Per-share premium received (short) or paid (long) when opening.
+    pub open_price: f64,
+    /// Per-share premium paid (short) or received (long) when closing.
+    pub close_price: f64,
+    pub reason: CloseReason,
+}
+
+impl ClosedLot {
+    /// Number of contracts closed.
+    pub fn contracts(&self) -> i32 {
+        self.shares / self.multiplier.max(1)
+    }
+
+    /// Proceeds in cents.
+    pub fn proceeds(&self) -> i64 {
+        let price = if self.short {
+            self.open_price
+        } else {
+            self.close_price
+        };
+        to_cents(price * self.shares as f64)
+    }
+
+    /// Cost basis in cents.
+    pub fn cost_basis(&self) -> i64 {
+        let price = if self.short {
+            self.close_price
+        } else {
+            self.open_price
+        };
+        to_cents(price * self.shares as f64)
+    }
+
+    /// Realized gain in cents.
+    pub fn gain(&self) -> i64 {
+        self.proceeds() - self.cost_basis()
+    }
+
+    /// Days between opening and closing.
+    pub fn days_held(&self) -> i64 {
+        (self.closed - self.opened).whole_days()
+    }
+}
+
+/// The still-open remainder of an opening trade.
+#[derive(Debug, Clone)]
+pub struct OpenLot {
+    pub trade: OptionTrade,
+    pub kind: OptionKind,
+    pub short: bool,
+    pub shares: i32,
+}
+
+impl OpenLot {
+    /// Number of contracts still open.
+    pub fn contracts(&self) -> i32 {
+        self.shares / self.trade.multiplier.max(1)
+    }
+
+    /// Days the position has been open as of `today`.
+    pub fn days_held(&self, today: Date) -> i64 {
+        (today - self.trade.date_of_action).whole_days()
+    }
+
+    /// Days left until the option expires as of `today`.
+    pub fn days_to_expiration(&self, today: Date) -> i64 {
+        (self.trade.expiration_date - today).whole_days()
+    }
+
+    /// Whether the option is in the money with the underlying at `price`.
+    pub fn in_the_money(&self, price: f64) -> bool {
+        match self.kind {
+            OptionKind::Put => price < self.trade.strike,
+            OptionKind::Call => price > self.trade.strike,
+        }
+    }
+
+    /// Cash in cents required to secure a short option, or the cost of the shares or long
+    /// call covering a covered call; long options tie up no collateral.
+    pub fn collateral(&self) -> i64 {
+        if self.short {
+            self.trade.capital_per_share_cents() * self.shares as i64
+        } else {
+            0
+        }
+    }
+
+    /// Premium in cents received (short) or paid (long) that is still riding on the position.
+    pub fn premium_at_risk(&self) -> i64 {
+        to_cents(self.trade.credit.abs() * self.shares as f64)
+    }
+
+    /// Gain in cents if the position were closed at `mark`, the option's per-share price.
+    pub fn unrealized(&self, mark: f64) -> i64 {
+        let change = to_cents((self.trade.credit.abs() - mark) * self.shares as f64);
+        if self.short { change } else { -change }
+    }
+
+    /// Share of a short option's premium kept if it were bought back at `mark`, e.g. 0.8
+    /// for an option sold at $1.00 and marked at $0.20. `None` for long options.
+    pub fn premium_captured(&self, mark: f64) -> Option<f64> {
+        let premium = self.trade.credit.abs();
+        (self.short && premium > 0.0).then(|| (premium - mark) / premium)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LotMatches {
+    pub closed: Vec<ClosedLot>,
+    pub open: Vec<OpenLot>,
+}
+
+impl LotMatches {
+    /// Realized gain in cents by opening trade id, summed over the parts of each
+    /// position closed so far.
+    pub fn realized_by_trade(&self) -> std::collections::HashMap<i32, i64> {
+        let mut realized = std::collections::HashMap::new();
+        for lot in &self.closed {
+            if let Some(id) = lot.open_trade_id {
+                *realized.entry(id).or_insert(0) += lot.gain();
+            }
+        }
+        realized
+    }
+}
+
+/// Pair opening and closing option trades FIFO per contract.
+///
+/// Trades are grouped by campaign, symbol, strike and expiration. A buy closes the oldest
+/// short lot of the same option type (and a sell the oldest long lot) before opening a new
+/// position; assignments close short lots, exercises close long lots and recorded
+/// expirations close lots on either side. A trade with `closes_trade_id` set closes that
+/// opening trade before falling back to FIFO. Lots still open after their expiration date
+/// (relative to `today`) are closed as expired worthless.
+pub fn match_lots(trades: &[OptionTrade], today: Date) -> LotMatches {
+    use std::collections::HashMap;
+
+    let mut ordered: Vec<&OptionTrade> = trades.iter().collect();
+    ordered.sort_by_key(|t| (t.date_of_action, t.id));
+
+    let mut contract_groups: HashMap<String, Vec<OpenLot>> = HashMap::new();
+    let mut group_order: Vec<String> = Vec::new();
+    let mut closed = Vec::new();
+
+    for trade in ordered {
+        let key = format!(
+            "{}_{}_{}_{}",
+            trade.campaign,
+            trade.symbol,
+            trade.strike_cents(),
+            trade.expiration_date
+        );
+        if !contract_groups.contains_key(&key) {
+            group_order.push(key.clone());
+        }
+        let lots = contract_groups.entry(key).or_default();
+
+        // Which existing lots this trade closes, and what it opens with any remainder
+        let (closes_short, kind) = match (&trade.action, trade.action.option_kind()) {
+            (Action::BuyPut | Action::BuyCall, Some(kind)) => (Some(true), Some(kind)),
+            (Action::SellPut | Action::SellCall, Some(kind)) => (Some(false), Some(kind)),
+            (Action::Assigned, _) => (Some(true), None),
+            (Action::Exercised, _) => (Some(false), None),
+            // Statements don't say whether the expiring option was long or short
+            (Action::Expired, _) => (Some(true), None),
+            _ => (None, None),
+        };
+        let either_side = trade.action == Action::Expired;
+        let reason = match trade.action {
+            Action::Assigned => CloseReason::Assigned,
+            Action::Exercised => CloseReason::Exercised,
+            Action::Expired => CloseReason::Expired,
+            _ => CloseReason::Closed,
+        };
+        let price = trade.credit.abs();
+        let mut remaining = trade.number_of_shares;
+
+        if let Some(closes_short) = closes_short {
+            // An explicitly linked opening trade is closed first, then the rest FIFO
+            let mut order: Vec<usize> = (0..lots.len()).collect();
+            if let Some(link) = trade.closes_trade_id {
+                order.sort_by_key(|&i| lots[i].trade.id != Some(link));
+            }
+            for i in order {
+                let lot = &mut lots[i];
+                if (lot.short != closes_short && !either_side)
+                    || kind.is_some_and(|k| lot.kind != k)
+                {
+                    continue;
+                }
+                if remaining <= 0 {
+                    break;
+                }
+                let shares = remaining.min(lot.shares);
+                if shares <= 0 {
+                    continue;
+                }
+                closed.push(ClosedLot {
+                    open_trade_id: lot.trade.id,
+                    close_trade_id: trade.id,
+                    symbol: lot.trade.symbol.clone(),
+                    campaign: lot.trade.campaign.clone(),
+                    kind: lot.kind,
+                    strike: lot.trade.strike,
+                    expiration_date: lot.trade.expiration_date,
+                    short: lot.short,
+                    shares,
+                    multiplier: lot.trade.multiplier,
+                    opened: lot.trade.date_of_action,
+                    closed: trade.date_of_action,
+                    open_price: lot.trade.credit.abs(),
+                    close_price: if kind.is_some() { price } else { 0.0 },
+                    reason,
+                });
+                lot.shares -= shares;
+                remaining -= shares;
+            }
+            lots.retain(|l| l.shares > 0);
+        }
+
+        if let Some(kind) = kind
+            && remaining > 0
+        {
+            lots.push(OpenLot {
+                trade: trade.clone(),
+                kind,
+                short: matches!(trade.action, Action::SellPut | Action::SellCall),
+                shares: remaining,
+            });
+        }
+    }
+
+    let mut open = Vec::new();
+    for key in group_order {
+        for lot in contract_groups.remove(&key).unwrap_or_default() {
+            if lot.trade.expiration_date < today {
+                closed.push(ClosedLot {
+                    open_trade_id: lot.trade.id,
+                    close_trade_id: None,
+                    symbol: lot.trade.symbol.clone(),
+                    campaign: lot.trade.campaign.clone(),
+                    kind: lot.kind,
+                    strike: lot.trade.strike,
+                    expiration_date: lot.trade.expiration_date,
+                    short: lot.short,
+                    shares: lot.shares,
+                    multiplier: lot.trade.multiplier,
+                    opened: lot.trade.date_of_action,
+                    closed: lot.trade.expiration_date,
+                    open_price: lot.trade.credit.abs(),
+                    close_price: 0.0,
+                    reason: CloseReason::Expired,
+                });
+            } else {
+                open.push(lot);
+            }
+        }
+    }
+    closed.sort_by_key(|c| (c.closed, c.opened));
+
+    LotMatches { closed, open }
+}
+
+/// The status of every stored trade by id, as of `today`.
+///
+/// A status set on the trade wins. Otherwise an opening trade is open while any of it
+/// is, rolled when a later trade was rolled out of it, and else ends the way its last lot
+/// was closed. Closing transactions are closed, or assigned or expired when that is what
+/// they record.
+pub fn trade_statuses(
+    trades: &[OptionTrade],
+    today: Date,
+) -> std::collections::HashMap<i32, TradeStatus> {
+    let matches = match_lots(trades, today);
+    let mut statuses = std::collections::HashMap::new();
+    for lot in &matches.closed {
+        if let Some(id) = lot.open_trade_id {
+            let status = match lot.reason {
+                CloseReason::Closed => TradeStatus::Closed,
+                CloseReason::Expired => TradeStatus::Expired,
+                CloseReason::Assigned | CloseReason::Exercised => TradeStatus::Assigned,
+            };
+            statuses.insert(id, status);
+        }
+    }
+    for id in trades.iter().filter_map(|t| t.rolled_from_id) {
+        statuses.insert(id, TradeStatus::Rolled);
+    }
+    for id in matches.open.iter().filter_map(|lot| lot.trade.id) {
+        statuses.insert(id, TradeStatus::Open);
+    }
+    for trade in trades {
+        let Some(id) = trade.id else {
+            continue;
+        };
+        if let Some(status) = trade.status {
+            statuses.insert(id, status);
+        } else {
+            statuses.entry(id).or_insert(match trade.action {
+                Action::Assigned | Action::Exercised => TradeStatus::Assigned,
+                Action::Expired => TradeStatus::Expired,
+                _ => TradeStatus::Closed,
+            });
+        }
+    }
+    statuses
+}
+
+/// Days after a loss in which buying back a replacement makes it a wash sale.
+pub const WASH_SALE_DAYS: i64 = 30;
+
+/// How far apart, as a fraction of the strike, two strikes still count as the same
+/// position for wash sales.
+pub const WASH_SALE_STRIKE_RANGE: f64 = 0.1;
+
+/// A loss the wash sale rule disallows, because a position in the same option was
+/// opened again soon after.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WashSale {
+    /// The opening trade that replaced the position.
+    pub replacement_id: Option<i32>,
+    /// Loss disallowed, in cents (positive), in proportion to the shares replaced.
+    pub disallowed: i64,
+}
+
+/// Wash sales among the closed lots, keyed by their index in `matches.closed`.
+///
+/// A lot closed at a loss is a wash sale when, within [`WASH_SALE_DAYS`] after it was
+/// closed, a trade opens a position in the same symbol and option type with a strike
+/// within [`WASH_SALE_STRIKE_RANGE`] of it. Each opening trade's shares replace only one
+/// loss, the earliest.
+pub fn wash_sales(
+    matches: &LotMatches,
+    trades: &[OptionTrade],
+) -> std::collections::HashMap<usize, WashSale> {
+    // Opening trades and the shares they opened, whether still open or since closed
+    let mut opened: std::collections::HashMap<i32, i32> = std::collections::HashMap::new();
+    for (id, shares) in matches
+        .open
         .iter()
-        .filter(|t| {
-            t.expiration_date == friday_date
-                && matches!(t.action, Action::SellPut | Action::SellCall)
+        .filter_map(|lot| Some((lot.trade.id?, lot.shares)))
+        .chain(
+            matches
+                .closed
+                .iter()
+                .filter_map(|lot| Some((lot.open_trade_id?, lot.shares))),
+        )
+    {
+        *opened.entry(id).or_default() += shares;
+    }
+    let mut replacements: Vec<(&OptionTrade, i32)> = trades
+        .iter()
+        .filter_map(|t| Some((t, *opened.get(&t.id?)?)))
+        .collect();
+    replacements.sort_by_key(|(t, _)| (t.date_of_action, t.id));
+
+    let mut washes = std::collections::HashMap::new();
+    for (i, lot) in matches.closed.iter().enumerate() {
+        let loss = -lot.gain();
+        if loss <= 0 {
+            continue;
+        }
+        let mut replaced = 0;
+        let mut replacement_id = None;
+        for (trade, shares) in replacements.iter_mut() {
+            let days = (trade.date_of_action - lot.closed).whole_days();
+            if *shares == 0
+                || trade.id == lot.open_trade_id
+                || !(0..=WASH_SALE_DAYS).contains(&days)
+                || !trade.symbol.eq_ignore_ascii_case(&lot.symbol)
+                || trade.action.option_kind() != Some(lot.kind)
+                || (trade.strike - lot.strike).abs() > lot.strike * WASH_SALE_STRIKE_RANGE
+            {
+                continue;
+            }
+            let used = (*shares).min(lot.shares - replaced);
+            *shares -= used;
+            replaced += used;
+            replacement_id = replacement_id.or(trade.id);
+            if replaced == lot.shares {
+                break;
+            }
+        }
+        if replaced > 0 {
+            washes.insert(
+                i,
+                WashSale {
+                    replacement_id,
+                    disallowed: loss * replaced as i64 / lot.shares as i64,
+                },
+            );
+        }
+    }
+    washes
+}
+
+/// Net shares and their total cost in cents from assignments and exercises.
+///
+/// Assigned puts and exercised calls add shares at the strike; assigned calls and
+/// exercised puts remove them.
+pub fn share_position(closed: &[ClosedLot]) -> (i32, i64) {
+    closed
+        .iter()
+        .map(|c| {
+            let shares = delivered_shares(c);
+            (shares, shares as i64 * to_cents(c.strike))
+        })
+        .fold((0, 0), |(shares, cost), (s, c)| (shares + s, cost + c))
+}
+
+/// Shares a closed lot delivered: positive when received, negative when given up, and
+/// zero unless it was assigned or exercised.
+fn delivered_shares(lot: &ClosedLot) -> i32 {
+    if !matches!(lot.reason, CloseReason::Assigned | CloseReason::Exercised) {
+        return 0;
+    }
+    match (lot.kind, lot.short) {
+        (OptionKind::Put, true) | (OptionKind::Call, false) => lot.shares,
+        (OptionKind::Call, true) | (OptionKind::Put, false) => -lot.shares,
+    }
+}
+
+/// Date, shares received (negative when given up), their value in cents at the strike
+/// and the premium adjusting that value of each assignment and exercise in `trades`.
+fn deliveries(trades: &[OptionTrade], today: Date) -> Vec<(Date, i32, i64, i64)> {
+    match_lots(trades, today)
+        .closed
+        .iter()
+        .filter_map(|lot| {
+            let shares = delivered_shares(lot);
+            let premium = to_cents(lot.open_price * lot.shares as f64);
+            (shares != 0).then(|| {
+                (
+                    lot.closed,
+                    shares,
+                    to_cents(lot.strike) * shares.abs() as i64,
+                    if lot.short { premium } else { -premium },
+                )
+            })
+        })
+        .collect()
+}
+
+/// The share trades in `stocks` that aren't a statement's record of one of `deliveries`.
+///
+/// Statements also list the shares an assignment delivers as a share trade, so a share
+/// trade matching an assignment's date, shares and strike stands for the assignment.
+fn unmatched_stock_trades<'a>(
+    deliveries: &[(Date, i32, i64, i64)],
+    stocks: &'a [StockTrade],
+) -> Vec<&'a StockTrade> {
+    let mut deliveries = deliveries.to_vec();
+    stocks
+        .iter()
+        .filter(|stock| {
+            let value = to_cents(stock.price) * stock.shares.abs() as i64;
+            match deliveries.iter().position(|&(date, shares, v, _)| {
+                (date, shares, v) == (stock.date, stock.shares, value)
+            }) {
+                Some(i) => {
+                    deliveries.swap_remove(i);
+                    false
+                }
+                None => true,
+            }
+        })
+        .collect()
+}
+
+/// Shares bought, or delivered by one assignment or exercise, still held.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareLot {
+    pub opened: Date,
+    /// Negative for shares sold short.
+    pub shares: i32,
+    /// What the shares cost in cents, less the premium collected on the option that
+    /// delivered them; for a short lot, what selling them brought in.
+    pub basis: i64,
+}
+
+/// Shares of one symbol held in a campaign as FIFO lots, from assignments, exercises and
+/// share trades taken in date order.
+///
+/// The premium of the option that delivered a lot adjusts its basis: an assigned put's
+/// premium lowers the cost of the shares, and an assigned call's premium adds to what
+/// the shares were sold for. Shares sold or called away use up the oldest lots first.
+/// Statements also list the shares an assignment delivers as a share trade, so a share
+/// trade matching an assignment's date, shares and strike isn't counted twice.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShareLedger {
+    pub lots: Vec<ShareLot>,
+    /// Gain in cents on shares sold or called away.
+    pub realized: i64,
+    /// Option premium in cents folded into lot bases and `realized`.
+    pub premium: i64,
+}
+
+impl ShareLedger {
+    /// The ledger of the shares `trades` and `stocks` deliver, which should all be for
+    /// one campaign and symbol.
+    pub fn new(trades: &[OptionTrade], stocks: &[StockTrade], today: Date) -> Self {
+        let mut changes = deliveries(trades, today);
+        for stock in unmatched_stock_trades(&changes, stocks) {
+            let value = to_cents(stock.price) * stock.shares.abs() as i64;
+            changes.push((stock.date, stock.shares, value, 0));
+        }
+        changes.sort_by_key(|&(date, ..)| date);
+
+        let mut ledger = Self::default();
+        for (date, shares, value, premium) in changes {
+            ledger.apply(date, shares, value, premium);
+        }
+        ledger
+    }
+
+    /// Receive (positive) or give up (negative) `shares` worth `value` cents, with
+    /// `premium` cents collected on the option that delivered them.
+    fn apply(&mut self, date: Date, shares: i32, value: i64, premium: i64) {
+        self.premium += premium;
+        // Premium collected lowers what received shares cost and raises what given up
+        // shares bring in
+        let value = if shares > 0 {
+            value - premium
+        } else {
+            value + premium
+        };
+        let total = shares.abs() as i64;
+        let mut remaining = shares;
+        while remaining != 0 {
+            let Some(lot) = self
+                .lots
+                .first_mut()
+                .filter(|lot| lot.shares.signum() == -remaining.signum())
+            else {
+                break;
+            };
+            let n = remaining.abs().min(lot.shares.abs());
+            let basis = lot.basis * n as i64 / lot.shares.abs() as i64;
+            let part = value * n as i64 / total;
+            self.realized += if lot.shares > 0 {
+                part - basis
+            } else {
+                basis - part
+            };
+            lot.basis -= basis;
+            lot.shares += n * remaining.signum();
+            remaining -= n * remaining.signum();
+            if lot.shares == 0 {
+                self.lots.remove(0);
+            }
+        }
+        if remaining != 0 {
+            self.lots.push(ShareLot {
+                opened: date,
+                shares: remaining,
+                basis: value * remaining.abs() as i64 / total,
+            });
+        }
+    }
+
+    /// Net shares held.
+    pub fn shares(&self) -> i32 {
+        self.lots.iter().map(|lot| lot.shares).sum()
+    }
+
+    /// Basis in cents of the shares held.
+    pub fn cost_basis(&self) -> i64 {
+        self.lots.iter().map(|lot| lot.basis).sum()
+    }
+
+    /// Basis per share of the shares held; `None` when none are held.
+    pub fn average_cost(&self) -> Option<f64> {
+        let shares = self.shares();
+        (shares > 0).then(|| to_dollars(self.cost_basis()) / shares as f64)
+    }
+
+    /// Share price at which selling the shares held would leave the campaign even, given
+    /// the net P/L in cents of its options; `None` when no shares are held.
+    pub fn break_even(&self, option_pl: i64) -> Option<f64> {
+        let shares = self.shares();
+        // Premium already in the lots and realized gain isn't counted twice
+        let other = option_pl - self.premium + self.realized;
+        (shares > 0).then(|| to_dollars(self.cost_basis() - other) / shares as f64)
+    }
+}
+
+/// The shares a campaign holds, seen as the stock side of a covered call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoveredCallStats {
+    pub shares: i32,
+    /// Per-share basis after all the premium collected: [`ShareLedger::break_even`].
+    pub adjusted_basis: f64,
+    /// Open short calls, soonest expiration first.
+    pub calls: Vec<CallYield>,
+}
+
+/// What an open short call returns on the adjusted basis of the shares it covers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallYield {
+    pub strike: f64,
+    pub expiration_date: Date,
+    pub shares: i32,
+    /// Strike less the adjusted basis, per share.
+    pub above_basis: f64,
+    /// The call's premium, the return if it expires and the shares are kept. `None`, as
+    /// is `if_called`, once premium has brought the adjusted basis to zero.
+    pub if_expired: Option<f64>,
+    /// Selling the shares at the strike; the premium is already in the adjusted basis.
+    pub if_called: Option<f64>,
+}
+
+/// Covered call figures for the shares in `ledger` and the short calls in `open`, given
+/// the campaign's net option P/L in cents; `None` unless shares are held.
+pub fn covered_call_stats(
+    ledger: &ShareLedger,
+    open: &[OpenLot],
+    option_pl: i64,
+) -> Option<CoveredCallStats> {
+    let adjusted_basis = ledger.break_even(option_pl)?;
+    let yield_of = |gain: f64| (adjusted_basis > 0.0).then(|| gain / adjusted_basis);
+    let mut calls: Vec<CallYield> = open
+        .iter()
+        .filter(|lot| lot.short && lot.kind == OptionKind::Call)
+        .map(|lot| CallYield {
+            strike: lot.trade.strike,
+            expiration_date: lot.trade.expiration_date,
+            shares: lot.shares,
+            above_basis: lot.trade.strike - adjusted_basis,
+            if_expired: yield_of(lot.trade.credit.abs()),
+            if_called: yield_of(lot.trade.strike - adjusted_basis),
         })
         .collect();
+    calls.sort_by_key(|call| call.expiration_date);
+    Some(CoveredCallStats {
+        shares: ledger.shares(),
+        adjusted_basis,
+        calls,
+    })
+}
 
-    // Calculate total premium from selling options this week
-    weekly_trades
+/// One underlying's figures across every campaign trading it, in cents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolExposure {
+    pub symbol: String,
+    pub net_premium: i64,
+    pub open_positions: usize,
+    /// Collateral tied up by the open short options.
+    pub capital_at_risk: i64,
+}
+
+/// Net premium, open positions and capital at risk per underlying as of `today`, most
+/// capital at risk first. Symbols are compared without case.
+pub fn symbol_exposure(trades: &[OptionTrade], today: Date) -> Vec<SymbolExposure> {
+    let mut by_symbol: std::collections::BTreeMap<String, Vec<OptionTrade>> =
+        std::collections::BTreeMap::new();
+    for trade in trades {
+        by_symbol
+            .entry(trade.symbol.to_uppercase())
+            .or_default()
+            .push(trade.clone());
+    }
+    let mut exposure: Vec<SymbolExposure> = by_symbol
+        .into_iter()
+        .map(|(symbol, trades)| {
+            // A trade marked closed by hand is no longer exposed
+            let open: Vec<OpenLot> = match_lots(&trades, today)
+                .open
+                .into_iter()
+                .filter(|lot| lot.trade.status.is_none_or(|s| s == TradeStatus::Open))
+                .collect();
+            SymbolExposure {
+                symbol,
+                net_premium: calculate_total_premium_sold(&trades),
+                open_positions: open.len(),
+                capital_at_risk: open.iter().map(OpenLot::collateral).sum(),
+            }
+        })
+        .collect();
+    exposure.sort_by_key(|e| std::cmp::Reverse(e.capital_at_risk));
+    exposure
+}
+
+/// How a campaign's positions have turned out, from their matched opens and closes.
+/// Amounts are in cents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TradeQuality {
+    /// Positions closed, counting one closed in parts once.
+    pub closed: usize,
+    /// Closed positions with a gain.
+    pub winners: usize,
+    /// Premium received per contract sold, over closed and open short positions.
+    pub avg_credit_per_contract: Option<i64>,
+    /// Average gain of the closed positions with a loss, negative.
+    pub avg_loss: Option<i64>,
+    pub largest_win: Option<i64>,
+    pub largest_loss: Option<i64>,
+}
+
+impl TradeQuality {
+    /// Share of closed positions that made money, e.g. 0.75.
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.closed > 0).then(|| self.winners as f64 / self.closed as f64)
+    }
+}
+
+/// Win rate, credit per contract and the size of wins and losses of matched `lots`. A
+/// position is an opening trade, and its gain the sum over the parts of it closed.
+pub fn trade_quality(lots: &LotMatches) -> TradeQuality {
+    let mut gains: Vec<i64> = Vec::new();
+    let mut positions: std::collections::HashMap<i32, usize> = std::collections::HashMap::new();
+    for lot in &lots.closed {
+        match lot.open_trade_id {
+            Some(id) => match positions.get(&id) {
+                Some(&i) => gains[i] += lot.gain(),
+                None => {
+                    positions.insert(id, gains.len());
+                    gains.push(lot.gain());
+                }
+            },
+            None => gains.push(lot.gain()),
+        }
+    }
+    let losses: Vec<i64> = gains.iter().copied().filter(|g| *g < 0).collect();
+
+    let sold = lots
+        .closed
         .iter()
-        .map(|t| t.credit * t.number_of_shares as f64)
-        .sum()
+        .filter(|lot| lot.short)
+        .map(|lot| (lot.proceeds(), lot.contracts()))
+        .chain(lots.open.iter().filter(|lot| lot.short).map(|lot| {
+            let premium = to_cents(lot.trade.credit.abs() * lot.shares as f64);
+            (premium, lot.contracts())
+        }));
+    let (premium, contracts) = sold.fold((0, 0), |(premium, contracts), (p, c)| {
+        (premium + p, contracts + i64::from(c))
+    });
+
+    TradeQuality {
+        closed: gains.len(),
+        winners: gains.iter().filter(|g| **g > 0).count(),
+        avg_credit_per_contract: (contracts > 0).then(|| premium / contracts),
+        avg_loss: (!losses.is_empty()).then(|| losses.iter().sum::<i64>() / losses.len() as i64),
+        largest_win: gains.iter().copied().filter(|g| *g > 0).max(),
+        largest_loss: losses.iter().copied().min(),
+    }
+}
+
+/// Collateral in cents tied up by open short options, the denominator behind ROIC.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapitalAtRisk {
+    /// Cash securing short puts: strike times shares.
+    pub puts: i64,
+    /// Cost of the shares or long calls covering short calls, or the strike of naked ones.
+    pub calls: i64,
+}
+
+impl CapitalAtRisk {
+    pub fn total(&self) -> i64 {
+        self.puts + self.calls
+    }
+}
+
+/// Collateral of the short puts and calls in `open`.
+pub fn capital_at_risk(open: &[OpenLot]) -> CapitalAtRisk {
+    let mut capital = CapitalAtRisk::default();
+    for lot in open {
+        match lot.kind {
+            OptionKind::Put => capital.puts += lot.collateral(),
+            OptionKind::Call => capital.calls += lot.collateral(),
+        }
+    }
+    capital
+}
+
+/// Open short premium expiring within a range of days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DteBucket {
+    /// e.g. "0-7"
+    pub label: &'static str,
+    /// Last day to expiration in the range; `None` for the open-ended last one.
+    pub max_days: Option<i64>,
+    pub positions: usize,
+    /// Premium in cents received for the positions.
+    pub premium: i64,
+}
+
+/// Premium of the open short positions in `open` by days to expiration as of `today`:
+/// within a week, within three weeks, and later.
+pub fn premium_by_dte(open: &[OpenLot], today: Date) -> [DteBucket; 3] {
+    let bucket = |label, max_days| DteBucket {
+        label,
+        max_days,
+        positions: 0,
+        premium: 0,
+    };
+    let mut buckets = [
+        bucket("0-7", Some(7)),
+        bucket("8-21", Some(21)),
+        bucket("22+", None),
+    ];
+    for lot in open.iter().filter(|lot| lot.short) {
+        let dte = lot.days_to_expiration(today);
+        if let Some(bucket) = buckets
+            .iter_mut()
+            .find(|b| b.max_days.is_none_or(|max| dte <= max))
+        {
+            bucket.positions += 1;
+            bucket.premium += lot.premium_at_risk();
+        }
+    }
+    buckets
+}
+
+/// One calendar month's income across every campaign, in cents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonthlyIncome {
+    /// First day of the month.
+    pub month: Date,
+    /// Net premium of the trades made in the month.
+    pub net_premium: i64,
+    /// Gain of the positions closed in the month.
+    pub realized: i64,
+    /// Trades made in the month.
+    pub trades: usize,
+}
+
+/// Net premium, realized P/L and trade count per calendar month with any of them as of
+/// `today`, oldest first. Positions count toward the month they were closed in.
+pub fn monthly_income(trades: &[OptionTrade], today: Date) -> Vec<MonthlyIncome> {
+    let month_of = |date: Date| date.replace_day(1).unwrap_or(date);
+    let mut made: std::collections::BTreeMap<Date, Vec<OptionTrade>> =
+        std::collections::BTreeMap::new();
+    for trade in trades {
+        made.entry(month_of(trade.date_of_action))
+            .or_default()
+            .push(trade.clone());
+    }
+    let mut realized: std::collections::BTreeMap<Date, i64> = std::collections::BTreeMap::new();
+    for lot in match_lots(trades, today).closed {
+        *realized.entry(month_of(lot.closed)).or_default() += lot.gain();
+    }
+    let mut months: Vec<Date> = made.keys().chain(realized.keys()).copied().collect();
+    months.sort();
+    months.dedup();
+    months
+        .into_iter()
+        .map(|month| {
+            let made = made.get(&month).map(Vec::as_slice).unwrap_or_default();
+            MonthlyIncome {
+                month,
+                net_premium: calculate_total_premium_sold(made),
+                realized: realized.get(&month).copied().unwrap_or(0),
+                trades: made.len(),
+            }
+        })
+        .collect()
+}
+
+/// Net premium collected toward a [`PremiumGoal`] in the period containing today.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GoalProgress {
+    pub goal: PremiumGoal,
+    /// First and last day of the period.
+    pub start: Date,
+    pub end: Date,
+    /// Net premium in cents of the trades made in the period so far.
+    pub premium: i64,
+}
+
+impl GoalProgress {
+    /// Share of the goal collected, above 1 once it is exceeded.
+    pub fn fraction(&self) -> f64 {
+        self.premium as f64 / to_cents(self.goal.amount) as f64
+    }
+
+    /// `this week` or `this month`.
+    pub fn period_label(&self) -> &'static str {
+        match self.goal.period {
+            GoalPeriod::Week => "this week",
+            GoalPeriod::Month => "this month",
+        }
+    }
+}
+
+/// Progress toward `goal` by `trades` in the week (beginning on `week_start`) or calendar
+/// month containing `today`.
+pub fn goal_progress(
+    trades: &[OptionTrade],
+    goal: PremiumGoal,
+    today: Date,
+    week_start: Weekday,
+) -> GoalProgress {
+    let (start, end) = match goal.period {
+        GoalPeriod::Week => week_bounds(today, week_start),
+        GoalPeriod::Month => {
+            let start = today.replace_day(1).unwrap_or(today);
+            let end = today
+                .replace_day(today.month().length(today.year()))
+                .unwrap_or(today);
+            (start, end)
+        }
+    };
+    let made: Vec<OptionTrade> = trades
+        .iter()
+        .filter(|t| (start..=end).contains(&t.date_of_action))
+        .cloned()
+        .collect();
+    GoalProgress {
+        goal,
+        start,
+        end,
+        premium: calculate_total_premium_sold(&made),
+    }
+}
+
+/// Something an account holds: shares of `symbol`, or an option contract on it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Instrument {
+    pub symbol: String,
+    /// Kind, strike in cents and expiration of an option; `None` for shares.
+    pub option: Option<(OptionKind, i64, Date)>,
+}
+
+impl std::fmt::Display for Instrument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.option {
+            None => write!(f, "{} shares", self.symbol),
+            Some((kind, strike, expiration)) => write!(
+                f,
+                "{} ${:.2} {} exp {}",
+                self.symbol,
+                to_dollars(strike),
+                match kind {
+                    OptionKind::Put => "Put",
+                    OptionKind::Call => "Call",
+                },
+                expiration
+            ),
+        }
+    }
+}
+
+/// Signed quantities held per instrument, as a broker would list them: contracts for
+/// options (negative when short) and shares from assignments and exercises. Instruments
+/// that net to zero are left out.
+pub fn holdings(matches: &LotMatches) -> std::collections::BTreeMap<Instrument, i32> {
+    let mut held = std::collections::BTreeMap::new();
+    for lot in &matches.open {
+        let instrument = Instrument {
+            symbol: lot.trade.symbol.to_uppercase(),
+            option: Some((
+                lot.kind,
+                lot.trade.strike_cents(),
+                lot.trade.expiration_date,
+            )),
+        };
+        let contracts = lot.contracts();
+        *held.entry(instrument).or_default() += if lot.short { -contracts } else { contracts };
+    }
+    for closed in &matches.closed {
+        let shares = share_position(std::slice::from_ref(closed)).0;
+        let instrument = Instrument {
+            symbol: closed.symbol.to_uppercase(),
+            option: None,
+        };
+        *held.entry(instrument).or_default() += shares;
+    }
+    held.retain(|_, quantity| *quantity != 0);
+    held
+}
+
+/// Symbols where a dividend matters: shares are held from assignments, or calls are sold
+/// against them.
+pub fn dividend_exposed_symbols(matches: &LotMatches) -> Vec<String> {
+    let mut symbols: Vec<String> = matches
+        .open
+        .iter()
+        .filter(|lot| lot.short && lot.kind == OptionKind::Call)
+        .map(|lot| lot.trade.symbol.clone())
+        .collect();
+    let mut assigned: Vec<&str> = matches.closed.iter().map(|c| c.symbol.as_str()).collect();
+    assigned.sort_unstable();
+    assigned.dedup();
+    for symbol in assigned {
+        let lots: Vec<ClosedLot> = matches
+            .closed
+            .iter()
+            .filter(|c| c.symbol == symbol)
+            .cloned()
+            .collect();
+        if share_position(&lots).0 > 0 {
+            symbols.push(symbol.to_string());
+        }
+    }
+    symbols.sort();
+    symbols.dedup();
+    symbols
+}
+
+/// A short call that holders may exercise early to collect a dividend.
+pub struct DividendRisk<'a> {
+    pub lot: &'a OpenLot,
+    pub dividend: &'a Dividend,
+    /// `None` when no underlying price was available to tell.
+    pub in_the_money: Option<bool>,
+}
+
+/// Open short calls still open on an upcoming ex-dividend date of their underlying.
+///
+/// Calls known to be out of the money are left out; `price` looks up the underlying's
+/// current price where one is available.
+pub fn dividend_risks<'a>(
+    open: &'a [OpenLot],
+    dividends: &'a [Dividend],
+    today: Date,
+    price: impl Fn(&str) -> Option<f64>,
+) -> Vec<DividendRisk<'a>> {
+    let mut risks = Vec::new();
+    for lot in open
+        .iter()
+        .filter(|l| l.short && l.kind == OptionKind::Call)
+    {
+        let next = dividends
+            .iter()
+            .filter(|d| d.symbol.eq_ignore_ascii_case(&lot.trade.symbol))
+            .filter(|d| d.ex_date >= today && d.ex_date <= lot.trade.expiration_date)
+            .min_by_key(|d| d.ex_date);
+        let Some(dividend) = next else {
+            continue;
+        };
+        let in_the_money = price(&lot.trade.symbol).map(|p| lot.in_the_money(p));
+        if in_the_money != Some(false) {
+            risks.push(DividendRisk {
+                lot,
+                dividend,
+                in_the_money,
+            });
+        }
+    }
+    risks
+}
+
+/// Compute account metrics as of `today` for the snapshots table.
+///
+/// Trades and cash events dated after `today` are ignored so back-dated snapshots are
+/// accurate. Share value is carried at cost since no market prices are stored. Cash is the
+/// account's cash as `account_cash` works it out, deposits and share trades included.
+pub fn calculate_snapshot(
+    trades: &[OptionTrade],
+    stocks: &[StockTrade],
+    events: &[CashEvent],
+    today: Date,
+) -> Snapshot {
+    let trades: Vec<OptionTrade> = trades
+        .iter()
+        .filter(|t| t.date_of_action <= today)
+        .cloned()
+        .collect();
+    let matches = match_lots(&trades, today);
+    let realized_pl = matches.closed.iter().map(ClosedLot::gain).sum();
+    let open_collateral = matches.open.iter().map(OpenLot::collateral).sum();
+    let (_, share_value) = share_position(&matches.closed);
+
+    Snapshot {
+        date: today,
+        realized_pl,
+        open_collateral,
+        cash: account_cash(&trades, stocks, events, today).cash,
+        share_value,
+    }
+}
+
+/// Cash in the account and the money put into it, in cents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccountCash {
+    /// Deposits less withdrawals.
+    pub contributed: i64,
+    pub cash: i64,
+}
+
+impl AccountCash {
+    /// `pl` cents as a fraction of the money contributed; `None` until some is.
+    pub fn return_on_contributions(&self, pl: i64) -> Option<f64> {
+        (self.contributed > 0).then(|| pl as f64 / self.contributed as f64)
+    }
+}
+
+/// Cash in the account as of `today`, from the deposits, withdrawals, dividends and
+/// interest in `events` plus the premium, costs, assignments and share trades of every
+/// campaign.
+pub fn account_cash(
+    trades: &[OptionTrade],
+    stocks: &[StockTrade],
+    events: &[CashEvent],
+    today: Date,
+) -> AccountCash {
+    let events: Vec<&CashEvent> = events.iter().filter(|e| e.date <= today).collect();
+    let contributed = events
+        .iter()
+        .filter(|e| matches!(e.kind, CashKind::Deposit | CashKind::Withdrawal))
+        .map(|e| to_cents(e.amount))
+        .sum();
+    let mut cash: i64 = events.iter().map(|e| to_cents(e.amount)).sum();
+
+    let trades: Vec<OptionTrade> = trades
+        .iter()
+        .filter(|t| t.date_of_action <= today)
+        .cloned()
+        .collect();
+    cash += trades
+        .iter()
+        .map(|t| {
+            let premium = t.premium_cents().abs();
+            let premium = match t.action {
+                Action::SellPut | Action::SellCall => premium,
+                Action::BuyPut | Action::BuyCall => -premium,
+                Action::Exercised | Action::Assigned | Action::Expired => 0,
+            };
+            premium - t.costs_cents()
+        })
+        .sum::<i64>();
+
+    // Assignments are matched against share trades one campaign and symbol at a time
+    let mut positions: Vec<(&str, &str)> = trades
+        .iter()
+        .map(|t| (t.campaign.as_str(), t.symbol.as_str()))
+        .chain(
+            stocks
+                .iter()
+                .map(|s| (s.campaign.as_str(), s.symbol.as_str())),
+        )
+        .collect();
+    positions.sort();
+    positions.dedup();
+    for (campaign, symbol) in positions {
+        let position_trades: Vec<OptionTrade> = trades
+            .iter()
+            .filter(|t| t.campaign == campaign && t.symbol == symbol)
+            .cloned()
+            .collect();
+        let position_stocks: Vec<StockTrade> = stocks
+            .iter()
+            .filter(|s| s.campaign == campaign && s.symbol == symbol && s.date <= today)
+            .cloned()
+            .collect();
+        let delivered = deliveries(&position_trades, today);
+        // Shares received are paid for at the strike; shares given up pay it
+        cash -= delivered
+            .iter()
+            .map(|&(_, shares, value, _)| value * shares.signum() as i64)
+            .sum::<i64>();
+        cash += unmatched_stock_trades(&delivered, &position_stocks)
+            .iter()
+            .map(|s| to_cents(s.amount))
+            .sum::<i64>();
+    }
+
+    AccountCash { contributed, cash }
+}
+
+/// First and last day of the week containing `today`, for weeks beginning on `start`.
+pub fn week_bounds(today: Date, start: Weekday) -> (Date, Date) {
+    let days_in =
+        (today.weekday().number_days_from_monday() + 7 - start.number_days_from_monday()) % 7;
+    let start_of_week = today - time::Duration::days(days_in as i64);
+    (start_of_week, start_of_week + time::Duration::days(6))
+}
+
+/// Black-Scholes delta of an option, assuming no interest or dividends.
+///
+/// `years` is the time left to expiration and `volatility` the annualized implied
+/// volatility (0.45 for 45%). Puts come out negative.
+pub fn black_scholes_delta(
+    kind: OptionKind,
+    spot: f64,
+    strike: f64,
+    years: f64,
+    volatility: f64,
+) -> f64 {
+    let spread = volatility * years.sqrt();
+    let d1 = ((spot / strike).ln() + spread * spread / 2.0) / spread;
+    match kind {
+        OptionKind::Call => normal_cdf(d1),
+        OptionKind::Put => normal_cdf(d1) - 1.0,
+    }
+}
+
+/// Annualized volatility of daily `closes` (oldest first), from the standard deviation of
+/// their log returns over 252 trading days a year. `None` with fewer than three closes.
+pub fn historical_volatility(closes: &[f64]) -> Option<f64> {
+    let returns: Vec<f64> = closes.windows(2).map(|w| (w[1] / w[0]).ln()).collect();
+    if returns.len() < 2 {
+        return None;
+    }
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance =
+        returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    Some((variance * 252.0).sqrt())
+}
+
+/// Standard normal CDF, via the Abramowitz and Stegun erf approximation (error < 1e-7).
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    if x >= 0.0 {
+        (1.0 + erf) / 2.0
+    } else {
+        (1.0 - erf) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn trade(action: Action, shares: i32, credit: f64, date_of_action: Date) -> OptionTrade {
+        OptionTrade {
+            symbol: "NVTS".to_string(),
+            campaign: "NVTS-wheel".to_string(),
+            action,
+            strike: 6.5,
+            expiration_date: date!(2025 - 07 - 03),
+            date_of_action,
+            number_of_shares: shares,
+            credit,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_match_lots_partial_close_and_expiry() {
+        let trades = vec![
+            trade(Action::SellPut, 300, 0.50, date!(2025 - 06 - 23)),
+            trade(Action::BuyPut, 100, -0.10, date!(2025 - 06 - 30)),
+        ];
+
+        let before_expiry = match_lots(&trades, date!(2025 - 07 - 01));
+        assert_eq!(before_expiry.closed.len(), 1);
+        assert_eq!(before_expiry.closed[0].shares, 100);
+        assert_eq!(before_expiry.closed[0].gain(), 4000);
+        assert_eq!(before_expiry.open.len(), 1);
+        assert_eq!(before_expiry.open[0].shares, 200);
+        assert!(before_expiry.open[0].in_the_money(6.0));
+        assert!(!before_expiry.open[0].in_the_money(7.0));
+        assert_eq!(before_expiry.closed[0].days_held(), 7);
+        assert_eq!(before_expiry.open[0].days_held(date!(2025 - 07 - 01)), 8);
+        assert_eq!(before_expiry.open[0].unrealized(0.10), 8000);
+        assert_eq!(before_expiry.open[0].premium_captured(0.10), Some(0.8));
+        assert_eq!(before_expiry.open[0].unrealized(0.75), -5000);
+
+        let partly_closed = OptionTrade {
+            id: Some(1),
+            ..trades[0].clone()
+        };
+        let realized = match_lots(&[partly_closed, trades[1].clone()], date!(2025 - 07 - 01))
+            .realized_by_trade();
+        assert_eq!(realized[&1], 4000);
+
+        let after_expiry = match_lots(&trades, date!(2025 - 07 - 07));
+        assert!(after_expiry.open.is_empty());
+        let expired = &after_expiry.closed[1];
+        assert_eq!(expired.reason, CloseReason::Expired);
+        assert_eq!(expired.shares, 200);
+        assert_eq!(expired.gain(), 10000);
+
+        // A recorded expiration closes the rest on the day rather than after it
+        let mut recorded = trades.clone();
+        recorded.push(trade(Action::Expired, 200, 0.0, date!(2025 - 07 - 03)));
+        let on_expiry = match_lots(&recorded, date!(2025 - 07 - 03));
+        assert!(on_expiry.open.is_empty());
+        assert_eq!(on_expiry.closed[1].reason, CloseReason::Expired);
+        assert_eq!(on_expiry.closed[1].gain(), 10000);
+    }
+
+    #[test]
+    fn test_trade_statuses() {
+        let with_id = |id, trade: OptionTrade| OptionTrade {
+            id: Some(id),
+            ..trade
+        };
+        let trades = vec![
+            with_id(1, trade(Action::SellPut, 100, 0.50, date!(2025 - 06 - 23))),
+            with_id(2, trade(Action::BuyPut, 100, -0.10, date!(2025 - 06 - 30))),
+            OptionTrade {
+                rolled_from_id: Some(1),
+                ..with_id(3, trade(Action::SellPut, 100, 0.60, date!(2025 - 06 - 30)))
+            },
+            with_id(4, trade(Action::SellCall, 100, 0.20, date!(2025 - 06 - 30))),
+            OptionTrade {
+                status: Some(TradeStatus::Closed),
+                ..with_id(5, trade(Action::SellCall, 100, 0.20, date!(2025 - 06 - 30)))
+            },
+        ];
+
+        let statuses = trade_statuses(&trades, date!(2025 - 07 - 01));
+        assert_eq!(statuses[&1], TradeStatus::Rolled);
+        assert_eq!(statuses[&2], TradeStatus::Closed);
+        assert_eq!(statuses[&3], TradeStatus::Open);
+        assert_eq!(statuses[&4], TradeStatus::Open);
+        assert_eq!(statuses[&5], TradeStatus::Closed);
+
+        let after_expiry = trade_statuses(&trades, date!(2025 - 07 - 07));
+        assert_eq!(after_expiry[&3], TradeStatus::Expired);
+        assert_eq!(after_expiry[&4], TradeStatus::Expired);
+    }
+
+    #[test]
+    fn test_lot_contracts_use_trade_multiplier() {
+        // Ten mini contracts of 10 shares each
+        let mini = OptionTrade {
+            multiplier: 10,
+            ..trade(Action::SellPut, 100, 0.50, date!(2025 - 06 - 23))
+        };
+        let closing = OptionTrade {
+            multiplier: 10,
+            ..trade(Action::BuyPut, 30, -0.10, date!(2025 - 06 - 30))
+        };
+
+        let lots = match_lots(&[mini, closing], date!(2025 - 07 - 01));
+        assert_eq!(lots.closed[0].contracts(), 3);
+        assert_eq!(lots.open[0].contracts(), 7);
+        assert_eq!(lots.open[0].premium_at_risk(), 3500);
+    }
+
+    #[test]
+    fn test_cumulative_premium() {
+        let mut costly = trade(Action::SellPut, 100, 0.20, date!(2025 - 06 - 30));
+        costly.commission = 0.65;
+        let trades = vec![
+            trade(Action::SellPut, 300, 0.50, date!(2025 - 06 - 23)),
+            trade(Action::BuyPut, 100, 0.10, date!(2025 - 06 - 30)),
+            costly,
+            trade(Action::Expired, 200, 0.0, date!(2025 - 07 - 03)),
+            // Imported debits are negative
+            trade(Action::BuyPut, 100, -0.05, date!(2025 - 07 - 03)),
+        ];
+        assert_eq!(
+            cumulative_premium(&trades),
+            vec![
+                (date!(2025 - 06 - 23), 15000),
+                (date!(2025 - 06 - 30), 15935),
+                (date!(2025 - 07 - 03), 15435),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trade_quality() {
+        let with = |id, strike, trade: OptionTrade| OptionTrade {
+            id: Some(id),
+            strike,
+            ..trade
+        };
+        let trades = vec![
+            with(
+                1,
+                6.5,
+                trade(Action::SellPut, 300, 0.50, date!(2025 - 06 - 23)),
+            ),
+            with(
+                2,
+                6.5,
+                trade(Action::BuyPut, 100, -0.10, date!(2025 - 06 - 30)),
+            ),
+            with(
+                3,
+                7.0,
+                trade(Action::SellPut, 100, 0.20, date!(2025 - 06 - 23)),
+            ),
+            with(
+                4,
+                7.0,
+                trade(Action::BuyPut, 100, -0.60, date!(2025 - 06 - 30)),
+            ),
+        ];
+
+        // Trade 1 is closed in two parts, the second when it expires
+        let quality = trade_quality(&match_lots(&trades, date!(2025 - 07 - 07)));
+        assert_eq!(quality.closed, 2);
+        assert_eq!(quality.winners, 1);
+        assert_eq!(quality.win_rate(), Some(0.5));
+        assert_eq!(quality.avg_credit_per_contract, Some(4250));
+        assert_eq!(quality.avg_loss, Some(-4000));
+        assert_eq!(quality.largest_win, Some(14000));
+        assert_eq!(quality.largest_loss, Some(-4000));
+
+        assert_eq!(trade_quality(&LotMatches::default()).win_rate(), None);
+    }
+
+    #[test]
+    fn test_premium_by_dte_and_capital_at_risk() {
+        let lot = |expiration_date, short| OpenLot {
+            trade: OptionTrade {
+                expiration_date,
+                ..trade(Action::SellPut, 100, 0.50, date!(2025 - 06 - 23))
+            },
+            kind: OptionKind::Put,
+            short,
+            shares: 100,
+        };
+        let open = [
+            lot(date!(2025 - 07 - 03), true),
+            lot(date!(2025 - 07 - 11), true),
+            lot(date!(2025 - 07 - 18), true),
+            lot(date!(2025 - 07 - 18), false),
+            lot(date!(2025 - 08 - 15), true),
+        ];
+
+        let capital = capital_at_risk(&open);
+        assert_eq!(capital.puts, 4 * 650 * 100);
+        assert_eq!(capital.calls, 0);
+
+        let buckets = premium_by_dte(&open, date!(2025 - 06 - 27));
+        assert_eq!(
+            buckets.map(|b| (b.label, b.positions, b.premium)),
+            [("0-7", 1, 5000), ("8-21", 2, 10000), ("22+", 1, 5000)]
+        );
+    }
+
+    #[test]
+    fn test_campaign_summary_counts_costs() {
+        // Still open whenever the test runs
+        let with_costs = |trade: OptionTrade| OptionTrade {
+            commission: 0.65,
+            fees: 0.05,
+            expiration_date: date!(2099 - 01 - 16),
+            ..trade
+        };
+        let sold = with_costs(trade(Action::SellPut, 100, 0.50, date!(2025 - 06 - 23)));
+        let (break_even, _, _, credits, running_pl) =
+            calculate_campaign_summary(&[&sold], &[], None);
+        assert_eq!(credits, 5000);
+        assert_eq!(running_pl, 5000 - 70);
+        assert_eq!(break_even, Some(600.70 / 100.0));
+
+        // Once assigned, the costs still add to what the shares have to recover
+        let assigned = with_costs(trade(Action::Assigned, 100, 0.0, date!(2025 - 07 - 03)));
+        let (break_even, _, _, _, running_pl) =
+            calculate_campaign_summary(&[&sold, &assigned], &[], None);
+        assert_eq!(running_pl, 5000 - 140);
+        assert_eq!(break_even, Some(601.40 / 100.0));
+    }
+
+    #[test]
+    fn test_campaign_summary_after_repeated_assignments() {
+        let option = |action, strike, credit, date, expiration_date| OptionTrade {
+            strike,
+            expiration_date,
+            ..trade(action, 100, credit, date)
+        };
+        // Put to the account at $6 after $0.30 of premium, then at $5 after $0.20
+        let mut trades = vec![
+            option(
+                Action::SellPut,
+                6.0,
+                0.30,
+                date!(2025 - 06 - 26),
+                date!(2025 - 07 - 03),
+            ),
+            option(
+                Action::Assigned,
+                6.0,
+                0.0,
+                date!(2025 - 07 - 03),
+                date!(2025 - 07 - 03),
+            ),
+            option(
+                Action::SellPut,
+                5.0,
+                0.20,
+                date!(2025 - 07 - 03),
+                date!(2025 - 07 - 10),
+            ),
+            option(
+                Action::Assigned,
+                5.0,
+                0.0,
+                date!(2025 - 07 - 10),
+                date!(2025 - 07 - 10),
+            ),
+        ];
+        let open_call = option(
+            Action::SellCall,
+            7.0,
+            0.25,
+            date!(2025 - 07 - 10),
+            date!(2099 - 01 - 16),
+        );
+        let refs: Vec<&OptionTrade> = trades.iter().chain([&open_call]).collect();
+        // $1,100 paid for 200 shares less $75 of premium
+        let (break_even, ..) = calculate_campaign_summary(&refs, &[], None);
+        assert_eq!(break_even, Some(5.125));
+
+        // A $6 call takes the first 100 shares away; another call is sold on the rest
+        trades.extend([
+            option(
+                Action::SellCall,
+                6.0,
+                0.25,
+                date!(2025 - 07 - 10),
+                date!(2025 - 07 - 17),
+            ),
+            option(
+                Action::Assigned,
+                6.0,
+                0.0,
+                date!(2025 - 07 - 17),
+                date!(2025 - 07 - 17),
+            ),
+            option(
+                Action::SellCall,
+                7.0,
+                0.10,
+                date!(2025 - 07 - 17),
+                date!(2099 - 01 - 16),
+            ),
+        ]);
+        let refs: Vec<&OptionTrade> = trades.iter().collect();
+        // $1,100 paid less $600 called away and $85 of premium, over 100 shares
+        let (break_even, _, profit_per_week, _, running_pl) =
+            calculate_campaign_summary(&refs, &[], Some(5.15));
+        assert_eq!(running_pl, 8500);
+        assert_eq!(break_even, Some(4.15));
+        assert!(profit_per_week.is_some());
+
+        // Selling the rest leaves nothing to break even on
+        let sold = StockTrade {
+            id: None,
+            symbol: "NVTS".to_string(),
+            campaign: "NVTS-wheel".to_string(),
+            date: date!(2025 - 07 - 18),
+            shares: -100,
+            price: 5.0,
+            amount: 500.0,
+        };
+        let (break_even, ..) = calculate_campaign_summary(&refs[..6], &[sold], None);
+        assert_eq!(break_even, None);
+    }
+
+    #[test]
+    fn test_covered_call_stats() {
+        let option = |action, strike, credit, date, expiration_date| OptionTrade {
+            strike,
+            expiration_date,
+            ..trade(action, 100, credit, date)
+        };
+        let mut trades = vec![
+            option(
+                Action::SellPut,
+                6.0,
+                0.30,
+                date!(2025 - 06 - 26),
+                date!(2025 - 07 - 03),
+            ),
+            option(
+                Action::Assigned,
+                6.0,
+                0.0,
+                date!(2025 - 07 - 03),
+                date!(2025 - 07 - 03),
+            ),
+            option(
+                Action::SellPut,
+                5.0,
+                0.20,
+                date!(2025 - 07 - 03),
+                date!(2025 - 07 - 10),
+            ),
+            option(
+                Action::Assigned,
+                5.0,
+                0.0,
+                date!(2025 - 07 - 10),
+                date!(2025 - 07 - 10),
+            ),
+        ];
+        let today = date!(2025 - 07 - 11);
+        let stats = |trades: &[OptionTrade], option_pl| {
+            let ledger = ShareLedger::new(trades, &[], today);
+            covered_call_stats(&ledger, &match_lots(trades, today).open, option_pl)
+        };
+        assert_eq!(stats(&[], 0), None);
+        assert!(stats(&trades, 5000).unwrap().calls.is_empty());
+
+        trades.push(option(
+            Action::SellCall,
+            7.0,
+            0.25,
+            date!(2025 - 07 - 10),
+            date!(2025 - 08 - 15),
+        ));
+        let covered = stats(&trades, 7500).unwrap();
+        assert_eq!(covered.shares, 200);
+        assert_eq!(covered.adjusted_basis, 5.125);
+        let call = &covered.calls[0];
+        assert_eq!((call.strike, call.shares), (7.0, 100));
+        assert_eq!(call.above_basis, 1.875);
+        assert_eq!(call.if_expired, Some(0.25 / 5.125));
+        assert_eq!(call.if_called, Some(1.875 / 5.125));
+    }
+
+    #[test]
+    fn test_monthly_income() {
+        let trades = vec![
+            trade(Action::SellPut, 300, 0.50, date!(2025 - 06 - 23)),
+            trade(Action::BuyPut, 100, 0.10, date!(2025 - 06 - 30)),
+            trade(Action::SellPut, 100, 0.30, date!(2025 - 07 - 01)),
+        ];
+
+        // The rest of the June put expires worthless on July 3rd
+        let months = monthly_income(&trades, date!(2025 - 08 - 01));
+        assert_eq!(months.len(), 2);
+        assert_eq!(months[0].month, date!(2025 - 06 - 01));
+        assert_eq!(months[0].net_premium, 14000);
+        assert_eq!(months[0].realized, 4000);
+        assert_eq!(months[0].trades, 2);
+        assert_eq!(months[1].month, date!(2025 - 07 - 01));
+        assert_eq!(months[1].net_premium, 3000);
+        assert_eq!(months[1].realized, 10000 + 3000);
+        assert_eq!(months[1].trades, 1);
+    }
+
+    #[test]
+    fn test_goal_progress() {
+        let trades = vec![
+            trade(Action::SellPut, 300, 0.50, date!(2025 - 06 - 30)),
+            trade(Action::SellPut, 100, 1.50, date!(2025 - 07 - 02)),
+            trade(Action::BuyPut, 100, 0.50, date!(2025 - 07 - 10)),
+            trade(Action::SellPut, 100, 0.80, date!(2025 - 07 - 15)),
+        ];
+
+        let goal: PremiumGoal = "$500/month".parse().unwrap();
+        let month = goal_progress(&trades, goal, date!(2025 - 07 - 16), Weekday::Monday);
+        assert_eq!(
+            (month.start, month.end),
+            (date!(2025 - 07 - 01), date!(2025 - 07 - 31))
+        );
+        assert_eq!(month.premium, 15000 - 5000 + 8000);
+        assert_eq!(month.fraction(), 0.36);
+
+        let goal: PremiumGoal = "100/wk".parse().unwrap();
+        let week = goal_progress(&trades, goal, date!(2025 - 07 - 16), Weekday::Monday);
+        assert_eq!(week.start, date!(2025 - 07 - 14));
+        assert_eq!(week.premium, 8000);
+        assert_eq!(week.fraction(), 0.8);
+        assert!("500".parse::<PremiumGoal>().is_err());
+        assert!("-5/month".parse::<PremiumGoal>().is_err());
+    }
+
+    #[test]
+    fn test_symbol_exposure() {
+        let mut hood = trade(Action::SellPut, 100, 0.40, date!(2025 - 06 - 23));
+        hood.symbol = "hood".to_string();
+        hood.strike = 60.0;
+        let trades = vec![
+            trade(Action::SellPut, 300, 0.50, date!(2025 - 06 - 23)),
+            trade(Action::BuyPut, 100, 0.10, date!(2025 - 06 - 30)),
+            hood.clone(),
+            OptionTrade {
+                campaign: "HOOD-wheel".to_string(),
+                symbol: "HOOD".to_string(),
+                ..hood
+            },
+        ];
+
+        let exposure = symbol_exposure(&trades, date!(2025 - 07 - 01));
+        assert_eq!(exposure.len(), 2);
+        assert_eq!(exposure[0].symbol, "HOOD");
+        assert_eq!(exposure[0].net_premium, 8000);
+        assert_eq!(exposure[0].open_positions, 2);
+        assert_eq!(exposure[0].capital_at_risk, 1_200_000);
+        assert_eq!(exposure[1].symbol, "NVTS");
+        assert_eq!(exposure[1].net_premium, 14000);
+        assert_eq!(exposure[1].capital_at_risk, 130_000);
+
+        // Everything has expired a week later
+        let expired = symbol_exposure(&trades, date!(2025 - 07 - 08));
+        assert!(expired.iter().all(|e| e.open_positions == 0));
+    }
+
+    #[test]
+    fn test_week_bounds() {
+        // Wednesday
+        let today = date!(2025 - 07 - 02);
+        assert_eq!(
+            week_bounds(today, Weekday::Monday),
+            (date!(2025 - 06 - 30), date!(2025 - 07 - 06))
+        );
+        assert_eq!(
+            week_bounds(today, Weekday::Sunday),
+            (date!(2025 - 06 - 29), date!(2025 - 07 - 05))
+        );
+        assert_eq!(week_bounds(today, Weekday::Wednesday).0, today);
+    }
+
+    #[test]
+    fn test_share_ledger_fifo_lots() {
+        let at = |strike, trade: OptionTrade| OptionTrade { strike, ..trade };
+        let stock = |date, shares, price| StockTrade {
+            id: None,
+            symbol: "NVTS".to_string(),
+            campaign: "NVTS-wheel".to_string(),
+            date,
+            shares,
+            price,
+            amount: -(shares as f64) * price,
+        };
+        // Assigned at $6 after $0.30 of premium, then at $5 after $0.20
+        let mut trades = vec![
+            at(
+                6.0,
+                trade(Action::SellPut, 100, 0.30, date!(2025 - 06 - 26)),
+            ),
+            at(
+                6.0,
+                trade(Action::Assigned, 100, 0.0, date!(2025 - 07 - 03)),
+            ),
+            OptionTrade {
+                expiration_date: date!(2025 - 07 - 10),
+                ..at(
+                    5.0,
+                    trade(Action::SellPut, 100, 0.20, date!(2025 - 07 - 03)),
+                )
+            },
+            OptionTrade {
+                expiration_date: date!(2025 - 07 - 10),
+                ..at(
+                    5.0,
+                    trade(Action::Assigned, 100, 0.0, date!(2025 - 07 - 10)),
+                )
+            },
+        ];
+        // The statement's record of the first delivery is the same shares again
+        let delivered = [stock(date!(2025 - 07 - 03), 100, 6.0)];
+
+        let ledger = ShareLedger::new(&trades, &delivered, date!(2025 - 07 - 11));
+        assert_eq!(ledger.shares(), 200);
+        assert_eq!(ledger.lots[0].basis, 57000);
+        assert_eq!(ledger.lots[1].basis, 48000);
+        assert_eq!(ledger.average_cost(), Some(5.25));
+        assert_eq!(ledger.break_even(5000), Some(5.25));
+
+        // A $6 call with $0.25 of premium takes the oldest lot away
+        trades.push(OptionTrade {
+            expiration_date: date!(2025 - 07 - 17),
+            ..at(
+                6.0,
+                trade(Action::SellCall, 100, 0.25, date!(2025 - 07 - 10)),
+            )
+        });
+        trades.push(OptionTrade {
+            expiration_date: date!(2025 - 07 - 17),
+            ..at(
+                6.0,
+                trade(Action::Assigned, 100, 0.0, date!(2025 - 07 - 17)),
+            )
+        });
+        let ledger = ShareLedger::new(&trades, &[], date!(2025 - 07 - 18));
+        assert_eq!(ledger.lots.len(), 1);
+        assert_eq!(ledger.lots[0].opened, date!(2025 - 07 - 10));
+        assert_eq!(ledger.realized, 62500 - 57000);
+        assert_eq!(ledger.break_even(7500), Some(4.25));
+    }
+
+    #[test]
+    fn test_account_cash() {
+        let event = |date, kind, amount| CashEvent {
+            id: None,
+            symbol: None,
+            campaign: None,
+            date,
+            kind,
+            amount,
+        };
+        let stock = |date, shares, price| StockTrade {
+            id: None,
+            symbol: "NVTS".to_string(),
+            campaign: "NVTS-wheel".to_string(),
+            date,
+            shares,
+            price,
+            amount: -(shares as f64) * price,
+        };
+        let events = [
+            event(date!(2025 - 06 - 01), CashKind::Deposit, 1000.0),
+            event(date!(2025 - 07 - 15), CashKind::Withdrawal, -100.0),
+            event(date!(2025 - 07 - 31), CashKind::Interest, 5.0),
+        ];
+        // $50 of premium less $0.65 commission, then 100 shares put to the account at
+        // $6.50 and sold for $7
+        let trades = [
+            OptionTrade {
+                commission: 0.65,
+                ..trade(Action::SellPut, 100, 0.50, date!(2025 - 06 - 23))
+            },
+            trade(Action::Assigned, 100, 0.0, date!(2025 - 07 - 03)),
+        ];
+        // The statement's record of the assignment isn't paid for twice
+        let stocks = [
+            stock(date!(2025 - 07 - 03), 100, 6.5),
+            stock(date!(2025 - 07 - 10), -100, 7.0),
+        ];
+
+        let account = account_cash(&trades, &stocks, &events, date!(2025 - 07 - 10));
+        assert_eq!(account.contributed, 100000);
+        assert_eq!(account.cash, 100000 + 5000 - 65 - 65000 + 70000);
+        assert_eq!(account.return_on_contributions(5000), Some(0.05));
+
+        let account = account_cash(&trades, &stocks, &events, date!(2025 - 08 - 01));
+        assert_eq!(account.contributed, 90000);
+        assert_eq!(account.cash, 90000 + 5000 - 65 - 65000 + 70000 + 500);
+        assert_eq!(AccountCash::default().return_on_contributions(5000), None);
+    }
+
+    #[test]
+    fn test_snapshot_cash_counts_deposits() {
+        let trades = [trade(Action::SellPut, 100, 0.50, date!(2025 - 06 - 23))];
+        let deposit = CashEvent {
+            id: None,
+            symbol: None,
+            campaign: None,
+            date: date!(2025 - 06 - 01),
+            kind: CashKind::Deposit,
+            amount: 1000.0,
+        };
+        let without = calculate_snapshot(&trades, &[], &[], date!(2025 - 06 - 30));
+        let with = calculate_snapshot(&trades, &[], &[deposit], date!(2025 - 06 - 30));
+        assert_eq!(without.cash, 5000);
+        assert_eq!(with.cash, without.cash + 100000);
+        assert_eq!(with.open_collateral, without.open_collateral);
+    }
+
+    #[test]
+    fn test_wash_sales() {
+        let with_id = |id, strike, trade: OptionTrade| OptionTrade {
+            id: Some(id),
+            strike,
+            ..trade
+        };
+        let trades = vec![
+            // Sold for $0.50, bought back for $1.50: a $100 loss
+            with_id(
+                1,
+                6.5,
+                trade(Action::SellPut, 100, 0.50, date!(2025 - 06 - 02)),
+            ),
+            OptionTrade {
+                closes_trade_id: Some(1),
+                ..with_id(
+                    2,
+                    6.5,
+                    trade(Action::BuyPut, 100, -1.50, date!(2025 - 06 - 20)),
+                )
+            },
+            // Half the position reopened at a nearby strike within 30 days
+            with_id(
+                3,
+                6.0,
+                trade(Action::SellPut, 50, 0.40, date!(2025 - 06 - 25)),
+            ),
+            // Too far away in strike to count
+            with_id(
+                4,
+                4.0,
+                trade(Action::SellPut, 100, 0.10, date!(2025 - 06 - 25)),
+            ),
+        ];
+        let matches = match_lots(&trades, date!(2025 - 06 - 30));
+        let washes = wash_sales(&matches, &trades);
+
+        assert_eq!(matches.closed[0].gain(), -10000);
+        assert_eq!(
+            washes.get(&0),
+            Some(&WashSale {
+                replacement_id: Some(3),
+                disallowed: 5000,
+            })
+        );
+        assert_eq!(washes.len(), 1);
+    }
+
+    #[test]
+    fn test_dividend_risks() {
+        let mut call = trade(Action::SellCall, 100, 0.30, date!(2025 - 06 - 23));
+        call.strike = 7.0;
+        let trades = vec![
+            trade(Action::SellPut, 100, 0.50, date!(2025 - 06 - 02)),
+            trade(Action::Assigned, 100, 0.0, date!(2025 - 06 - 20)),
+            call,
+        ];
+        let matches = match_lots(&trades, date!(2025 - 06 - 24));
+        assert_eq!(dividend_exposed_symbols(&matches), ["NVTS"]);
+
+        let dividend = |ex_date| Dividend {
+            symbol: "NVTS".to_string(),
+            ex_date,
+            amount: Some(0.1),
+        };
+        let dividends = [
+            dividend(date!(2025 - 06 - 30)),
+            dividend(date!(2025 - 07 - 10)),
+        ];
+        let today = date!(2025 - 06 - 24);
+        let risks = dividend_risks(&matches.open, &dividends, today, |_| Some(7.5));
+        assert_eq!(risks.len(), 1);
+        assert_eq!(risks[0].dividend.ex_date, date!(2025 - 06 - 30));
+        assert_eq!(risks[0].in_the_money, Some(true));
+        assert!(dividend_risks(&matches.open, &dividends, today, |_| Some(6.5)).is_empty());
+        assert_eq!(
+            dividend_risks(&matches.open, &dividends, today, |_| None)[0].in_the_money,
+            None
+        );
+    }
+
+    #[test]
+    fn test_black_scholes_delta() {
+        let call = black_scholes_delta(OptionKind::Call, 100.0, 100.0, 1.0, 0.2);
+        assert!((call - 0.5398).abs() < 1e-4);
+        let put = black_scholes_delta(OptionKind::Put, 100.0, 100.0, 1.0, 0.2);
+        assert!((put + 0.4602).abs() < 1e-4);
+        // Far out of the money puts have almost no delta
+        assert!(black_scholes_delta(OptionKind::Put, 10.0, 5.0, 0.05, 0.6) > -0.01);
+
+        // Steady growth has no volatility; a 10% swing each way is wildly volatile
+        let steady = historical_volatility(&[100.0, 101.0, 102.01, 103.0301]).unwrap();
+        assert!(steady < 1e-9);
+        let swing = historical_volatility(&[100.0, 110.0, 100.0]).unwrap();
+        assert!((swing - 2.1397).abs() < 1e-3);
+        assert_eq!(historical_volatility(&[100.0, 101.0]), None);
+    }
 }
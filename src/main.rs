@@ -1,23 +1,31 @@
 mod app;
-mod csv_processor;
-mod db;
-mod logic;
-mod models;
+mod commands;
+mod output;
+mod storage;
 mod ui;
 
-use app::{App, AppScreen};
+use profit_tracker::{
+    config, csv_processor, db, error, filter, integrity, logic, metrics, models, money, quotes,
+};
+
+use error::Result;
+
+use app::{ACTIONS, App, AppScreen, TradeColumn, TradeFilter};
 use clap::{Parser, Subcommand};
 use crossterm::{
-    event::{self, Event},
+    event::{Event, EventStream},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use csv_processor::{Broker, CsvProcessor};
-use models::{Campaign, OptionTrade};
+use csv_processor::{Broker, CsvProcessor, ParsedCsv};
+use futures_util::StreamExt;
+use integrity::Repair;
+use models::{Action, CashKind, OptionTrade};
+use output::{OutputFormat, Table};
 use ratatui::prelude::*;
+use serde_json::json;
 use std::io::{self, Stdout};
-use std::path::PathBuf;
-use time::Date;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "profit_tracker")]
@@ -25,18 +33,37 @@ use time::Date;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Open the TUI directly on this campaign's dashboard
+    campaign: Option<String>,
+
+    /// Output format for command results
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+
+    /// Database file, `postgres://` connection string, or `:memory:` for a throwaway
+    /// in-memory database (defaults to $PROFIT_TRACKER_DB or the data directory)
+    #[arg(long, global = true, value_name = "PATH")]
+    db: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Run the interactive TUI (the default when no subcommand is given)
+    Tui {
+        /// Open directly on this campaign's dashboard
+        #[arg(short, long)]
+        campaign: Option<String>,
+    },
     /// Import trades from a CSV file
     Import {
-        /// The broker format (etrade or robinhood)
-        broker: String,
+        /// The broker format (etrade, robinhood or schwab; defaults to `default_broker` from the config)
+        broker: Option<String>,
 
-        /// Path to the CSV file
-        #[arg(short, long)]
-        file: PathBuf,
+        /// CSV files to import, as paths or globs such as 'statements/*.csv'; all of
+        /// them are imported in one transaction
+        #[arg(short, long, num_args = 1.., required = true)]
+        file: Vec<PathBuf>,
 
         /// Campaign name for the imported trades
         #[arg(short, long)]
@@ -45,11 +72,458 @@ enum Commands {
         /// Symbol for the imported trades
         #[arg(short, long)]
         symbol: String,
+
+        /// Show the parsed trades and skipped rows without modifying the database
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Watch a folder and import broker CSVs as they appear, e.g. --dir ~/Downloads
+    Watch {
+        /// Folder to watch
+        #[arg(short, long)]
+        dir: PathBuf,
+
+        /// Campaign for the imported trades (defaults to one per symbol, as the broker
+        /// parser names them)
+        #[arg(short, long)]
+        campaign: Option<String>,
+
+        /// Symbol for the imported trades (defaults to the statement's)
+        #[arg(short, long)]
+        symbol: Option<String>,
+    },
+    /// Batch-update every trade matching a filter, e.g. --where 'symbol=NVTS' --set campaign=NVTS-wheel
+    Edit {
+        /// Filter expression selecting the trades to update (same syntax as query)
+        #[arg(short = 'w', long = "where")]
+        filter: String,
+
+        /// Field assignment to apply, e.g. campaign=NVTS-wheel (repeatable)
+        #[arg(long = "set", required = true)]
+        assignments: Vec<String>,
+
+        /// Preview the updated rows without modifying the database
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Fill in missing deltas and implied volatility (e.g. on imported trades) from the
+    /// option chain for open positions and historical underlying prices for the rest
+    BackfillGreeks {
+        /// Only backfill trades matching this filter expression (same syntax as query)
+        #[arg(short = 'w', long = "where", default_value = "")]
+        filter: String,
+
+        /// Annualized volatility to price with (0.45 for 45%) instead of the underlying's
+        /// historical volatility
+        #[arg(long)]
+        volatility: Option<f64>,
+
+        /// Preview the deltas and volatilities without modifying the database
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Export trades as a broker-format CSV (etrade, robinhood or schwab) for other tools,
+    /// or as an Excel workbook (xlsx) with a sheet per campaign
+    Export {
+        /// The layout to write (etrade, robinhood, schwab or xlsx; defaults to `default_broker`)
+        broker: Option<String>,
+
+        /// Path of the file to write
+        #[arg(short, long)]
+        out: PathBuf,
+
+        /// Only export trades matching this filter expression (same syntax as query)
+        #[arg(short = 'w', long = "where", default_value = "")]
+        filter: String,
+    },
+    /// Compare the database to a broker statement and list transactions missing from either
+    Diff {
+        /// The broker format (etrade, robinhood or schwab; defaults to `default_broker` from the config)
+        broker: Option<String>,
+
+        /// Path to the broker statement CSV
+        #[arg(short, long)]
+        file: PathBuf,
+
+        /// Symbol the statement's trades were imported under, when overridden at import
+        #[arg(short, long)]
+        symbol: Option<String>,
+
+        /// Only compare trades matching this filter expression (same syntax as query)
+        #[arg(short = 'w', long = "where", default_value = "")]
+        filter: String,
+    },
+    /// Compare a broker's positions export against the open positions and shares held
+    Reconcile {
+        /// Path to the broker positions CSV (needs Symbol and Quantity columns)
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+    /// Filter trades with an expression like 'symbol=NVDA and action=SellPut and credit>0.3'
+    Query {
+        /// Conditions joined with 'and' (fields: id, symbol, campaign, action, strike, delta,
+        /// expiration, date, shares, contracts, credit, total; operators: = != < <= > >=)
+        #[arg(default_value = "")]
+        expression: String,
+
+        /// Sort by a field, descending when prefixed with '-' (repeatable, e.g. --sort=-credit)
+        #[arg(long, allow_hyphen_values = true)]
+        sort: Vec<String>,
+
+        /// Show at most this many trades
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Skip this many trades first, for paging with --limit
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+    },
+    /// Report realized option gains/losses for a tax year
+    Tax {
+        /// Tax year to report (positions closed during this year)
+        #[arg(short, long)]
+        year: i32,
+
+        /// Write a Form 8949 style CSV to this path instead of printing a table
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Print summary metrics for one campaign, or the whole account, without the TUI
+    Report {
+        /// Campaign to report on (defaults to the account totals)
+        #[arg(short, long)]
+        campaign: Option<String>,
+    },
+    /// List currently open option positions across all campaigns
+    Positions,
+    /// Premium and P/L per trade tag (tags are set in the TUI's add and edit forms)
+    Tags,
+    /// Record an option trade without opening the TUI (e.g. from a script or over SSH)
+    Add {
+        /// Campaign to add the trade to; its symbol is used
+        #[arg(short, long)]
+        campaign: String,
+
+        /// SellPut, BuyPut, SellCall, BuyCall, ... (case and dashes are ignored)
+        #[arg(short, long)]
+        action: Action,
+
+        /// Strike price
+        #[arg(long)]
+        strike: f64,
+
+        /// Expiration date (YYYY-MM-DD)
+        #[arg(long)]
+        exp: String,
+
+        /// Number of contracts
+        #[arg(long)]
+        contracts: i32,
+
+        /// Per-share premium received or paid
+        #[arg(long)]
+        credit: f64,
+
+        /// Delta when the trade was placed
+        #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+        delta: f64,
+
+        /// Date of the trade (YYYY-MM-DD, defaults to today)
+        #[arg(short, long)]
+        date: Option<String>,
+    },
+    /// Record the closing transaction for an open trade
+    Close {
+        /// ID of the opening trade to close
+        trade_id: i32,
+
+        /// Per-share price paid (or received) to close
+        #[arg(short, long, required_unless_present = "expired")]
+        price: Option<f64>,
+
+        /// Date of the closing transaction (YYYY-MM-DD, defaults to today)
+        #[arg(short, long)]
+        date: Option<String>,
+
+        /// Record the position as expired worthless on its expiration date
+        #[arg(long, conflicts_with = "price")]
+        expired: bool,
+
+        /// Number of contracts to close (defaults to all open contracts)
+        #[arg(long)]
+        contracts: Option<i32>,
+    },
+    /// Record the current price of an open option, for its unrealized P/L
+    Mark {
+        /// ID of the opening trade
+        trade_id: i32,
+
+        /// Per-share option price
+        price: f64,
+
+        /// Date of the price (YYYY-MM-DD, defaults to today)
+        #[arg(short, long)]
+        date: Option<String>,
+    },
+    /// Remove empty campaigns, collapse duplicate trades, and normalize symbols
+    Prune {
+        /// List what would change without modifying the database
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Record an account snapshot (P/L, collateral, cash, share value) for the equity curve
+    Snapshot {
+        /// Date to record the snapshot under (YYYY-MM-DD, defaults to today)
+        #[arg(short, long)]
+        date: Option<String>,
+
+        /// Print the recorded snapshot history instead of taking a new snapshot
+        #[arg(long, conflicts_with = "date")]
+        list: bool,
+    },
+    /// Show upcoming ex-dividend dates and short calls at risk of early assignment
+    Dividends {
+        /// Record an ex-dividend date for this symbol (with --ex-date)
+        #[arg(long, value_name = "SYMBOL", requires = "ex_date")]
+        add: Option<String>,
+
+        /// Ex-dividend date of the dividend being added (YYYY-MM-DD)
+        #[arg(long, requires = "add")]
+        ex_date: Option<String>,
+
+        /// Per-share amount of the dividend being added
+        #[arg(long, requires = "add")]
+        amount: Option<f64>,
+
+        /// Look up ex-dates and prices with the configured quote provider
+        #[arg(long)]
+        fetch: bool,
+    },
+    /// Send a desktop notification for positions expiring soon or in the money (for cron)
+    Remind {
+        /// Warn about positions expiring within this many days (defaults to `remind_days`
+        /// from the config, or 3)
+        #[arg(long)]
+        days: Option<i64>,
+
+        /// Print the reminders without showing a notification
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Serve campaigns, trades, positions and summary metrics as a read-only JSON API
+    Serve {
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to bind; use 0.0.0.0 to reach it from other devices on the network
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+    /// Show database location, schema version, and record counts
+    Info,
+    /// Send the weekly summary to a Slack or Discord webhook or by email (e.g. from cron on Fridays)
+    Notify {
+        /// Incoming webhook URL (defaults to `webhook` from the config file)
+        #[arg(short, long)]
+        webhook: Option<String>,
+
+        /// Mail the summary using `[email]` from the config file
+        #[arg(long)]
+        email: bool,
+
+        /// Print the message instead of sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write every table to a single JSON file (a diffable, migration-proof backup)
+    Dump {
+        /// File to write (prints to stdout when omitted)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Rebuild the database from a JSON file written by dump
+    Load {
+        /// Dump file to read
+        file: PathBuf,
+
+        /// Delete all existing data before loading
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Merge trades with other devices through a shared folder (Syncthing, Dropbox, ...)
+    Sync {
+        /// Folder shared between the devices
+        #[arg(short, long)]
+        dir: PathBuf,
+    },
+    /// Start a line-based interactive shell (add, list, report, close, ...)
+    Repl,
+    /// List trades or campaigns, e.g. for piping `--format json` into jq
+    List {
+        #[command(subcommand)]
+        command: ListCommand,
+    },
+    /// Record deposits and withdrawals, or list them with dividends and interest
+    Cash {
+        #[command(subcommand)]
+        command: CashCommand,
+    },
+    /// Manage campaigns
+    Campaign {
+        #[command(subcommand)]
+        command: CampaignCommand,
+    },
+    /// Manage the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Close an open trade and open its replacement as a linked roll
+    Roll {
+        /// ID of the opening trade to roll
+        trade_id: i32,
+
+        /// Strike of the new position (defaults to the current strike)
+        #[arg(long)]
+        to_strike: Option<f64>,
+
+        /// Expiration of the new position (YYYY-MM-DD)
+        #[arg(long)]
+        to_exp: String,
+
+        /// Per-share price paid to close the current position
+        #[arg(long)]
+        debit: f64,
+
+        /// Per-share premium received for the new position
+        #[arg(long)]
+        credit: f64,
+
+        /// Date of the roll (YYYY-MM-DD, defaults to today)
+        #[arg(short, long)]
+        date: Option<String>,
+    },
+    /// Mark a short call as covered by shares or a long call (poor man's covered call),
+    /// so its capital at risk is the collateral's cost rather than its strike
+    Cover {
+        /// ID of the short call
+        trade_id: i32,
+
+        /// Per-share cost of the covering shares (defaults to the campaign's average
+        /// cost of the shares it holds)
+        #[arg(long, conflicts_with_all = ["leap", "naked"])]
+        cost: Option<f64>,
+
+        /// ID of the long call covering it
+        #[arg(long, conflicts_with = "naked")]
+        leap: Option<i32>,
+
+        /// Remove the cover, treating the call as naked again
+        #[arg(long)]
+        naked: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ListCommand {
+    /// List trades by date, optionally narrowed by campaign, date and action
+    Trades {
+        /// Only trades in this campaign
+        #[arg(short, long)]
+        campaign: Option<String>,
+
+        /// Only trades placed on or after this date (YYYY-MM-DD)
+        #[arg(short, long)]
+        since: Option<String>,
+
+        /// Only trades with this action (SellPut, BuyCall, ...)
+        #[arg(short, long)]
+        action: Option<Action>,
+    },
+    /// List campaigns with their symbol and target exit price
+    Campaigns,
+}
+
+#[derive(Subcommand)]
+enum CampaignCommand {
+    /// Move the trades of one or more campaigns into another and remove the emptied ones
+    Merge {
+        /// Campaigns to merge away
+        #[arg(required = true)]
+        from: Vec<String>,
+
+        /// Campaign to move the trades into (created if it doesn't exist)
+        #[arg(long)]
+        into: String,
+    },
+    /// Set the net premium a campaign aims to collect each week or month, e.g. '$500/month'
+    Goal {
+        /// Campaign to set the goal of
+        name: String,
+
+        /// Amount per period: '$500/month' or '150/week'
+        #[arg(required_unless_present = "clear")]
+        goal: Option<String>,
+
+        /// Remove the campaign's goal
+        #[arg(long, conflicts_with = "goal")]
+        clear: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CashCommand {
+    /// Record money added to the account
+    Deposit {
+        /// Amount deposited
+        amount: f64,
+
+        /// Date of the deposit (YYYY-MM-DD, defaults to today)
+        #[arg(short, long)]
+        date: Option<String>,
     },
+    /// Record money taken out of the account
+    Withdraw {
+        /// Amount withdrawn
+        amount: f64,
+
+        /// Date of the withdrawal (YYYY-MM-DD, defaults to today)
+        #[arg(short, long)]
+        date: Option<String>,
+    },
+    /// List deposits, withdrawals, dividends and interest, and the account's cash
+    List,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Write a commented config file with every setting at its default
+    Init {
+        /// Replace an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {err}");
+        std::process::exit(1);
+    }
 }
 
-fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+fn run() -> Result<()> {
     let cli = Cli::parse();
+    // Writing a fresh config must work even when the current one doesn't parse
+    if let Some(Commands::Config {
+        command: ConfigCommand::Init { force },
+    }) = cli.command
+    {
+        return commands::config::run_init(force);
+    }
+    config::init(config::load()?);
+    let db = cli.db.as_deref();
 
     match cli.command {
         Some(Commands::Import {
@@ -57,73 +531,566 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
             file,
             campaign,
             symbol,
+            dry_run,
         }) => {
             // Handle CSV import
-            import_csv(&broker, file, &campaign, &symbol)?;
+            import_csv(
+                db,
+                &broker_name(broker)?,
+                &file,
+                &campaign,
+                &symbol,
+                dry_run,
+                cli.format,
+            )?;
+        }
+        Some(Commands::BackfillGreeks {
+            filter,
+            volatility,
+            dry_run,
+        }) => {
+            commands::backfill_greeks::run(
+                &open_db(db)?,
+                &filter,
+                volatility,
+                dry_run,
+                cli.format,
+            )?;
+        }
+        Some(Commands::Edit {
+            filter,
+            assignments,
+            dry_run,
+        }) => {
+            commands::edit::run(
+                open_storage(db)?.as_mut(),
+                &filter,
+                &assignments,
+                dry_run,
+                cli.format,
+            )?;
+        }
+        Some(Commands::Export {
+            broker,
+            out,
+            filter,
+        }) => {
+            commands::export::run(
+                open_storage(db)?.as_mut(),
+                &broker_name(broker)?,
+                &out,
+                &filter,
+                cli.format,
+            )?;
+        }
+        Some(Commands::Watch {
+            dir,
+            campaign,
+            symbol,
+        }) => {
+            let mut storage = open_storage(db)?;
+            commands::watch::run(
+                storage.as_mut(),
+                &dir,
+                campaign.as_deref(),
+                symbol.as_deref(),
+            )?;
+        }
+        Some(Commands::Reconcile { file }) => {
+            commands::reconcile::run(open_storage(db)?.as_mut(), &file, cli.format)?;
+        }
+        Some(Commands::Diff {
+            broker,
+            file,
+            symbol,
+            filter,
+        }) => {
+            commands::diff::run(
+                open_storage(db)?.as_mut(),
+                &broker_name(broker)?,
+                &file,
+                symbol.as_deref(),
+                &filter,
+                cli.format,
+            )?;
+        }
+        Some(Commands::Query {
+            expression,
+            sort,
+            limit,
+            offset,
+        }) => {
+            commands::query::run(
+                open_storage(db)?.as_mut(),
+                &expression,
+                &sort,
+                limit,
+                offset,
+                cli.format,
+            )?;
+        }
+        Some(Commands::Tax { year, out }) => {
+            commands::tax::run(open_storage(db)?.as_mut(), year, out, cli.format)?;
+        }
+        Some(Commands::Report { campaign }) => {
+            commands::report::run(open_storage(db)?.as_mut(), campaign.as_deref(), cli.format)?;
+        }
+        Some(Commands::Positions) => {
+            commands::positions::run(open_storage(db)?.as_mut(), cli.format)?;
+        }
+        Some(Commands::Tags) => {
+            commands::tags::run(open_storage(db)?.as_mut(), cli.format)?;
+        }
+        Some(Commands::Add {
+            campaign,
+            action,
+            strike,
+            exp,
+            contracts,
+            credit,
+            delta,
+            date,
+        }) => {
+            let new = commands::add::NewTrade {
+                campaign,
+                action,
+                strike,
+                expiration_date: commands::parse_date(&exp)?,
+                contracts,
+                credit,
+                delta,
+                date: date.as_deref().map(commands::parse_date).transpose()?,
+            };
+            commands::add::run(open_storage(db)?.as_mut(), &new, cli.format)?;
+        }
+        Some(Commands::Close {
+            trade_id,
+            price,
+            date,
+            expired,
+            contracts,
+        }) => {
+            commands::close::run(
+                open_storage(db)?.as_mut(),
+                trade_id,
+                price,
+                date,
+                expired,
+                contracts,
+                cli.format,
+            )?;
+        }
+        Some(Commands::Prune { dry_run }) => {
+            commands::prune::run(&open_db(db)?, dry_run, cli.format)?;
+        }
+        Some(Commands::Snapshot { date, list }) => {
+            commands::snapshot::run(open_storage(db)?.as_mut(), date, list, cli.format)?;
+        }
+        Some(Commands::Dividends {
+            add,
+            ex_date,
+            amount,
+            fetch,
+        }) => {
+            let add = add.map(|symbol| commands::dividends::NewDividend {
+                symbol,
+                ex_date: ex_date.unwrap_or_default(),
+                amount,
+            });
+            commands::dividends::run(open_storage(db)?.as_mut(), add, fetch, cli.format)?;
+        }
+        Some(Commands::Remind { days, dry_run }) => {
+            commands::remind::run(open_storage(db)?.as_mut(), days, dry_run)?;
+        }
+        Some(Commands::Serve { port, host }) => {
+            commands::serve::run(open_storage(db)?.as_mut(), &host, port)?;
+        }
+        Some(Commands::Info) => {
+            commands::info::run(&open_db(db)?, &db_path(db)?, cli.format)?;
+        }
+        Some(Commands::Notify {
+            webhook,
+            email,
+            dry_run,
+        }) => {
+            commands::notify::run(
+                open_storage(db)?.as_mut(),
+                webhook.as_deref(),
+                email,
+                dry_run,
+            )?;
+        }
+        Some(Commands::Dump { out }) => {
+            commands::dump::run(open_storage(db)?.as_mut(), out.as_deref())?;
+        }
+        Some(Commands::Load { file, replace }) => {
+            commands::load::run(&open_db(db)?, &file, replace)?;
+        }
+        Some(Commands::Sync { dir }) => {
+            commands::sync::run(&open_db(db)?, &dir, cli.format)?;
+        }
+        Some(Commands::Repl) => {
+            commands::repl::run(open_storage(db)?.as_mut(), cli.format)?;
+        }
+        Some(Commands::Mark {
+            trade_id,
+            price,
+            date,
+        }) => {
+            commands::mark::run(
+                open_storage(db)?.as_mut(),
+                trade_id,
+                price,
+                date,
+                cli.format,
+            )?;
+        }
+        Some(Commands::Cover {
+            trade_id,
+            cost,
+            leap,
+            naked,
+        }) => {
+            let cover = match (leap, naked) {
+                (Some(leap), _) => commands::cover::Cover::Leap(leap),
+                (None, true) => commands::cover::Cover::Naked,
+                (None, false) => commands::cover::Cover::Shares(cost),
+            };
+            commands::cover::run(open_storage(db)?.as_mut(), trade_id, cover, cli.format)?;
+        }
+        Some(Commands::Roll {
+            trade_id,
+            to_strike,
+            to_exp,
+            debit,
+            credit,
+            date,
+        }) => {
+            let target = commands::roll::RollTarget {
+                strike: to_strike,
+                expiration_date: commands::parse_date(&to_exp)?,
+                debit,
+                credit,
+                date: date.as_deref().map(commands::parse_date).transpose()?,
+            };
+            commands::roll::run(open_storage(db)?.as_mut(), trade_id, target, cli.format)?;
+        }
+        Some(Commands::List {
+            command:
+                ListCommand::Trades {
+                    campaign,
+                    since,
+                    action,
+                },
+        }) => {
+            commands::list::trades(
+                open_storage(db)?.as_mut(),
+                campaign.as_deref(),
+                since.as_deref().map(commands::parse_date).transpose()?,
+                action.as_ref(),
+                cli.format,
+            )?;
+        }
+        Some(Commands::List {
+            command: ListCommand::Campaigns,
+        }) => {
+            commands::list::campaigns(open_storage(db)?.as_mut(), cli.format)?;
+        }
+        Some(Commands::Config { .. }) => unreachable!("handled before loading the config"),
+        Some(Commands::Cash { command }) => {
+            let db_conn = open_db(db)?;
+            match command {
+                CashCommand::Deposit { amount, date } => commands::cash::run_record(
+                    &db_conn,
+                    CashKind::Deposit,
+                    amount,
+                    date,
+                    cli.format,
+                )?,
+                CashCommand::Withdraw { amount, date } => commands::cash::run_record(
+                    &db_conn,
+                    CashKind::Withdrawal,
+                    amount,
+                    date,
+                    cli.format,
+                )?,
+                CashCommand::List => commands::cash::run_list(&db_conn, cli.format)?,
+            }
+        }
+        Some(Commands::Campaign {
+            command: CampaignCommand::Merge { from, into },
+        }) => {
+            commands::campaign::merge(&open_db(db)?, &from, &into, cli.format)?;
+        }
+        Some(Commands::Campaign {
+            command: CampaignCommand::Goal { name, goal, .. },
+        }) => {
+            commands::campaign::set_goal(&open_db(db)?, &name, goal.as_deref())?;
+        }
+        Some(Commands::Tui { campaign }) => {
+            run_tui(db, campaign.as_deref())?;
         }
         None => {
             // Run the normal TUI application
-            run_tui()?;
+            run_tui(db, cli.campaign.as_deref())?;
         }
     }
 
     Ok(())
 }
 
+/// The database given with `--db`, or the default location.
+fn db_path(db: Option<&Path>) -> io::Result<PathBuf> {
+    match db {
+        Some(path) => Ok(path.to_path_buf()),
+        None => db::default_db_path(),
+    }
+}
+
+/// The broker named on the command line, or `default_broker` from the config file.
+fn broker_name(broker: Option<String>) -> Result<String> {
+    broker
+        .or_else(|| config::get().default_broker.clone())
+        .ok_or_else(|| "No broker given and no default_broker set in the config file".into())
+}
+
+/// Open the database, SQLite or PostgreSQL, for commands that go through `Storage`.
+fn open_storage(db: Option<&Path>) -> Result<Box<dyn storage::Storage>> {
+    storage::open(&db_path(db)?)
+}
+
+/// Open the SQLite database for commands that work on it directly.
+fn open_db(db: Option<&Path>) -> Result<rusqlite::Connection> {
+    let path = db_path(db)?;
+    if storage::is_postgres_url(&path.to_string_lossy()) {
+        return Err(
+            "This command needs a SQLite database; prune, info, load, sync, cash, \
+            backfill-greeks and campaign merge/goal don't support PostgreSQL yet"
+                .into(),
+        );
+    }
+    db::open_database(path, &config::get().multipliers)
+}
+
+/// Expand `patterns` into the files they name. Each is a path or, for shells that
+/// don't expand them (or when quoted), a glob such as `statements/*.csv`.
+fn expand_paths(patterns: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        if pattern.exists() {
+            files.push(pattern.clone());
+            continue;
+        }
+        let text = pattern.to_string_lossy();
+        let matches = glob::glob(&text)
+            .map_err(|e| format!("Invalid pattern {text}: {e}"))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io::Error::from)?;
+        if matches.is_empty() {
+            return Err(format!("No files match {text}").into());
+        }
+        files.extend(matches);
+    }
+    Ok(files)
+}
+
+/// A statement read for import: its path, trades, and the rows passed over.
+type ImportFile = (PathBuf, ParsedCsv);
+
 fn import_csv(
+    db: Option<&Path>,
     broker_str: &str,
-    file_path: PathBuf,
+    patterns: &[PathBuf],
     campaign_name: &str,
     symbol: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+    dry_run: bool,
+    format: OutputFormat,
+) -> Result<()> {
     // Parse broker
     let broker: Broker = broker_str.parse()?;
 
     // Create CSV processor
     let processor = CsvProcessor::new(broker);
 
-    // Process CSV file
-    let trades = processor.process_csv(&file_path)?;
+    // Every file is read before anything is written, so a bad one stops the whole import
+    let mut files: Vec<ImportFile> = Vec::new();
+    for path in expand_paths(patterns)? {
+        let mut parsed = processor
+            .parse_csv(&path)
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        // Override campaign and symbol from CLI arguments
+        for trade in &mut parsed.trades {
+            trade.campaign = campaign_name.to_string();
+            trade.symbol = symbol.to_string();
+        }
+        for stock in &mut parsed.stocks {
+            stock.campaign = campaign_name.to_string();
+            stock.symbol = symbol.to_string();
+        }
+        for event in &mut parsed.cash {
+            event.campaign = Some(campaign_name.to_string());
+            event.symbol = Some(symbol.to_string());
+        }
+        files.push((path, parsed));
+    }
+
+    if dry_run {
+        return preview_import(&files, format);
+    }
 
-    if trades.is_empty() {
+    if files.iter().all(|(_, parsed)| {
+        parsed.trades.is_empty() && parsed.stocks.is_empty() && parsed.cash.is_empty()
+    }) && format == OutputFormat::Table
+    {
         println!("No valid trades found in CSV file");
         return Ok(());
     }
 
-    // Create database connection
-    let db_conn = rusqlite::Connection::open("options_trades.db")?;
-
-    // Initialize database tables
-    db::init_database(&db_conn)?;
-
-    // Create campaign if it doesn't exist
-    let _campaign = Campaign::insert(&db_conn, campaign_name, symbol, None);
-
-    // Import trades
-    let mut imported_count = 0;
-    for mut trade in trades {
-        // Override campaign and symbol from CLI arguments
-        trade.campaign = campaign_name.to_string();
-        trade.symbol = symbol.to_string();
+    // Open database connection and make sure tables exist
+    let mut storage = open_storage(db)?;
+    let mut counts = Vec::new();
+    storage.transaction(&mut |storage| {
+        counts.clear();
+        for (_, parsed) in &files {
+            counts.push(storage::import_trades(
+                storage,
+                campaign_name,
+                symbol,
+                parsed,
+            )?);
+        }
+        Ok(())
+    })?;
+    let imported_count: usize = counts.iter().map(|(imported, _)| imported).sum();
+    let duplicate_count: usize = counts.iter().map(|(_, duplicates)| duplicates).sum();
 
-        // Skip duplicates
-        if !trade.exists_in_db(&db_conn) && trade.insert(&db_conn).is_ok() {
-            imported_count += 1;
+    if format == OutputFormat::Table {
+        let source = match &files[..] {
+            [(path, ..)] => path.display().to_string(),
+            _ => {
+                for ((path, ..), (imported, duplicates)) in files.iter().zip(&counts) {
+                    println!(
+                        "{}: {imported} imported, {duplicates} duplicates",
+                        path.display()
+                    );
+                }
+                format!("{} files", files.len())
+            }
+        };
+        println!(
+            "Successfully imported {} trades from {} for campaign '{}' ({})",
+            imported_count, source, campaign_name, symbol
+        );
+        if duplicate_count > 0 {
+            println!("Skipped {duplicate_count} trades already in the database");
+        }
+    } else {
+        let mut table = Table::new(&["file", "campaign", "symbol", "imported", "duplicates"]);
+        for ((path, ..), (imported, duplicates)) in files.iter().zip(&counts) {
+            table.push_row(vec![
+                json!(path.display().to_string()),
+                json!(campaign_name),
+                json!(symbol),
+                json!(imported),
+                json!(duplicates),
+            ]);
         }
+        table.print(format)?;
     }
 
+    Ok(())
+}
+
+/// Print what an import would store, and the rows it would pass over, without opening
+/// the database.
+fn preview_import(files: &[ImportFile], format: OutputFormat) -> Result<()> {
+    commands::query::trades_table(files.iter().flat_map(|(_, parsed)| &parsed.trades))
+        .print(format)?;
+    if format != OutputFormat::Table {
+        return Ok(());
+    }
+    let mut stocks = Table::new(&["date", "symbol", "shares", "price", "amount"]);
+    for stock in files.iter().flat_map(|(_, parsed)| &parsed.stocks) {
+        stocks.push_row(vec![
+            json!(stock.date.to_string()),
+            json!(stock.symbol),
+            json!(stock.shares),
+            json!(stock.price),
+            json!(stock.amount),
+        ]);
+    }
+    let stock_count: usize = files.iter().map(|(_, parsed)| parsed.stocks.len()).sum();
+    if stock_count > 0 {
+        println!("\nStock trades:");
+        stocks.print(format)?;
+    }
+    let mut cash = Table::new(&["date", "symbol", "kind", "amount"]);
+    for event in files.iter().flat_map(|(_, parsed)| &parsed.cash) {
+        cash.push_row(vec![
+            json!(event.date.to_string()),
+            json!(event.symbol),
+            json!(event.kind.as_str()),
+            json!(event.amount),
+        ]);
+    }
+    let cash_count: usize = files.iter().map(|(_, parsed)| parsed.cash.len()).sum();
+    if cash_count > 0 {
+        println!("\nDividends and interest:");
+        cash.print(format)?;
+    }
+    let trades: usize = files.iter().map(|(_, parsed)| parsed.trades.len()).sum();
+    let source = match files {
+        [(path, _)] => path.display().to_string(),
+        _ => format!("{} files", files.len()),
+    };
     println!(
-        "Successfully imported {} trades from {} for campaign '{}' ({})",
-        imported_count,
-        file_path.display(),
-        campaign_name,
-        symbol
+        "Would import {trades} trades, {stock_count} stock trades and {cash_count} dividend or interest payments from {source}; nothing was written"
     );
-
+    let mut table = Table::new(&["file", "line", "reason"]);
+    for (path, parsed) in files {
+        for row in &parsed.skipped {
+            table.push_row(vec![
+                json!(path.display().to_string()),
+                json!(row.line),
+                json!(row.reason),
+            ]);
+        }
+    }
+    let skipped: usize = files.iter().map(|(_, parsed)| parsed.skipped.len()).sum();
+    if skipped > 0 {
+        println!("\nSkipped {skipped} rows:");
+        table.print(format)?;
+    }
     Ok(())
 }
 
-fn run_tui() -> std::result::Result<(), Box<dyn std::error::Error>> {
+fn run_tui(db: Option<&Path>, campaign: Option<&str>) -> Result<()> {
+    // Network calls run on the runtime's blocking pool so they never hold up key handling
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    let _runtime = runtime.enter();
+    let mut app = App::new(&db_path(db)?)?;
+    if let Some(name) = campaign
+        && !app.open_campaign(name)
+    {
+        return Err(format!("No campaign named '{name}'").into());
+    }
+
+    // Checked before the terminal switches to the TUI so errors stay readable
+    if config::get().remind_on_start && app.issues.is_empty() {
+        let days = config::get().remind_days;
+        if let Err(err) = commands::remind::check(&app.trades, days, false) {
+            eprintln!("Could not send reminders: {err}");
+        }
+    }
+
+    // A panic would otherwise leave the terminal in raw mode on the alternate screen
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        default_hook(info);
+    }));
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -131,8 +1098,7 @@ fn run_tui() -> std::result::Result<(), Box<dyn std::error::Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
-    let res = run_app(&mut terminal, &mut app);
+    let res = runtime.block_on(run_app(&mut terminal, &mut app));
 
     // Restore terminal
     disable_raw_mode()?;
@@ -140,377 +1106,721 @@ fn run_tui() -> std::result::Result<(), Box<dyn std::error::Error>> {
     terminal.show_cursor()?;
 
     if let Err(err) = res {
-        println!("Error: {err:?}");
+        eprintln!("Error: {err}");
+    }
+
+    // Every session leaves a data point on the equity curve; trades that could not be
+    // loaded (repairs skipped) would record a bogus one
+    if app.issues.is_empty()
+        && let Err(err) = app.record_snapshot()
+    {
+        eprintln!("Could not record today's snapshot: {err}");
     }
     Ok(())
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) -> io::Result<()> {
+/// Draw the current screen, then wait for either a key press or finished background
+/// work, whichever comes first.
+async fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+) -> io::Result<()> {
+    let keys = &config::get().keys;
+    let mut input = EventStream::new();
     loop {
-        terminal.draw(|f| match app.screen {
-            AppScreen::Summary => ui::summary::draw_summary(f, app),
-            AppScreen::CampaignSelect => ui::campaign_select::draw_campaign_select(f, app),
-            AppScreen::NewCampaign => ui::new_campaign::draw_new_campaign(f, app),
-            AppScreen::CampaignDashboard => ui::campaign_dashboard::draw_campaign_dashboard(f, app),
-            AppScreen::MainMenu => draw_main_menu(f),
-            AppScreen::AddTrade => ui::add_trade::draw_add_trade(f, app),
-            AppScreen::ViewTrades => ui::view_trades::draw_view_trades(f, app),
-            AppScreen::EditTrade => ui::edit_trade::draw_edit_trade(f, app),
+        terminal.draw(|f| {
+            draw_screen(f, app);
+            if let Some(error) = &app.error {
+                ui::error_popup::draw_error_popup(f, error);
+            }
         })?;
 
-        if event::poll(std::time::Duration::from_millis(100))?
-            && let Event::Key(key) = event::read()?
-        {
-            match app.screen {
-                AppScreen::CampaignSelect => match key.code {
-                    crossterm::event::KeyCode::Down => {
-                        if app.campaign_select_index + 1 < app.campaigns.len() {
-                            app.campaign_select_index += 1;
-                            app.campaign_list_state
-                                .select(Some(app.campaign_select_index));
-                        }
-                    }
-                    crossterm::event::KeyCode::Up => {
-                        if app.campaign_select_index > 0 {
-                            app.campaign_select_index -= 1;
-                            app.campaign_list_state
-                                .select(Some(app.campaign_select_index));
-                        }
-                    }
-                    crossterm::event::KeyCode::Char('q') => return Ok(()),
-                    crossterm::event::KeyCode::Char('n') => {
-                        app.screen = AppScreen::NewCampaign;
+        let key = tokio::select! {
+            event = input.next() => match event {
+                Some(Ok(Event::Key(key))) => key,
+                // Resizes and other terminal events only need a redraw
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e),
+                None => return Ok(()),
+            },
+            Some(event) = app.events.recv() => {
+                app.handle_event(event);
+                continue;
+            }
+        };
+        // Any key dismisses an error popup
+        if app.error.take().is_some() {
+            continue;
+        }
+        match app.screen {
+            // Any key but `y` cancels a pending delete
+            AppScreen::CampaignSelect if app.delete_pending.is_some() => {
+                if key.code == crossterm::event::KeyCode::Char('y') {
+                    app.confirm_delete_campaign();
+                } else {
+                    app.delete_pending = None;
+                }
+            }
+            AppScreen::CampaignSelect => match key.code {
+                crossterm::event::KeyCode::Down
+                    if app.campaign_select_index + 1 < app.listed_campaigns().len() =>
+                {
+                    app.campaign_select_index += 1;
+                    app.campaign_list_state
+                        .select(Some(app.campaign_select_index));
+                }
+                crossterm::event::KeyCode::Up if app.campaign_select_index > 0 => {
+                    app.campaign_select_index -= 1;
+                    app.campaign_list_state
+                        .select(Some(app.campaign_select_index));
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.quit => return Ok(()),
+                crossterm::event::KeyCode::Char(c) if c == keys.new_campaign => {
+                    app.screen = AppScreen::NewCampaign;
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.archive_campaign => {
+                    app.toggle_campaign_archived();
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.delete_campaign => {
+                    app.delete_pending = app.highlighted_campaign().map(|c| c.name);
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.show_archived => {
+                    app.toggle_archived();
+                }
+                crossterm::event::KeyCode::Esc => {
+                    app.screen = AppScreen::Summary;
+                }
+                crossterm::event::KeyCode::Enter => {
+                    if let Some(camp) = app.highlighted_campaign() {
+                        app.selected_campaign = Some(camp);
+                        app.refresh_quote();
+                        app.screen = AppScreen::CampaignDashboard;
                     }
-                    crossterm::event::KeyCode::Esc => {
-                        app.screen = AppScreen::Summary;
+                }
+                _ => {}
+            },
+            AppScreen::CampaignDashboard => match key.code {
+                crossterm::event::KeyCode::Esc => {
+                    app.selected_campaign = None;
+                    app.screen = AppScreen::Summary;
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.add_trade => app.open_add_trade(),
+                crossterm::event::KeyCode::Char(c) if c == keys.view_trades => {
+                    app.select_trade_row(0);
+                    app.screen = AppScreen::ViewTrades;
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.refresh_quotes => {
+                    app.refresh_quote();
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.undo => app.undo(),
+                crossterm::event::KeyCode::Char(c) if c == keys.redo => app.redo(),
+                _ => {}
+            },
+            AppScreen::ViewTrades if app.mark_input.is_some() => match key.code {
+                crossterm::event::KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => {
+                    if let Some((_, typed)) = &mut app.mark_input {
+                        typed.push(c);
                     }
-                    crossterm::event::KeyCode::Enter => {
-                        if let Some(camp) = app.campaigns.get(app.campaign_select_index).cloned() {
-                            app.selected_campaign = Some(camp);
-                            app.screen = AppScreen::CampaignDashboard;
-                        }
+                }
+                crossterm::event::KeyCode::Backspace => {
+                    if let Some((_, typed)) = &mut app.mark_input {
+                        typed.pop();
                     }
-                    _ => {}
-                },
-                AppScreen::CampaignDashboard => match key.code {
-                    crossterm::event::KeyCode::Esc => {
-                        app.selected_campaign = None;
-                        app.screen = AppScreen::Summary;
+                }
+                crossterm::event::KeyCode::Enter => app.save_mark(),
+                crossterm::event::KeyCode::Esc => app.mark_input = None,
+                _ => {}
+            },
+            AppScreen::ViewTrades if app.searching => match key.code {
+                crossterm::event::KeyCode::Char(c) => app.filter_trades(|f| f.search.push(c)),
+                crossterm::event::KeyCode::Backspace => app.filter_trades(|f| {
+                    f.search.pop();
+                }),
+                crossterm::event::KeyCode::Enter => app.searching = false,
+                crossterm::event::KeyCode::Esc => {
+                    app.searching = false;
+                    app.filter_trades(|f| f.search.clear());
+                }
+                _ => {}
+            },
+            AppScreen::ViewTrades => match key.code {
+                crossterm::event::KeyCode::Esc => {
+                    app.screen = AppScreen::Summary;
+                }
+                crossterm::event::KeyCode::Down => app.scroll_trades(1),
+                crossterm::event::KeyCode::Up => app.scroll_trades(-1),
+                crossterm::event::KeyCode::PageDown => app.scroll_trades(trade_page_rows()),
+                crossterm::event::KeyCode::PageUp => app.scroll_trades(-trade_page_rows()),
+                crossterm::event::KeyCode::Home => app.select_trade_row(0),
+                crossterm::event::KeyCode::End => app.select_trade_row(isize::MAX),
+                crossterm::event::KeyCode::Char(c) if c == keys.edit_trade => {
+                    // Rows are the campaign's trades, not the full list
+                    if let Some(trade) = app.selected_trade().cloned() {
+                        app.set_edit_trade(&trade);
+                        app.screen = AppScreen::EditTrade;
                     }
-                    crossterm::event::KeyCode::Char('a') => {
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.move_trade => {
+                    app.open_move_trade();
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.trade_status => {
+                    app.cycle_trade_status();
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.mark => {
+                    app.open_mark_input();
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.clone_trade => {
+                    if let Some(trade) = app.selected_trade().cloned() {
+                        app.fill_form_from(&trade);
                         app.screen = AppScreen::AddTrade;
                     }
-                    crossterm::event::KeyCode::Char('v') => {
-                        app.screen = AppScreen::ViewTrades;
-                    }
-                    _ => {}
-                },
-                AppScreen::ViewTrades => match key.code {
-                    crossterm::event::KeyCode::Esc => {
-                        app.screen = AppScreen::Summary;
-                    }
-                    crossterm::event::KeyCode::Down => {
-                        if app.table_scroll + 1 < app.trades.len() {
-                            app.table_scroll += 1;
-                        }
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.delete_trade => {
+                    app.delete_selected_trade();
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.undo => app.undo(),
+                crossterm::event::KeyCode::Char(c) if c == keys.redo => app.redo(),
+                crossterm::event::KeyCode::Char(c) if c == keys.search => app.searching = true,
+                crossterm::event::KeyCode::Char(c) if c == keys.filter_action => {
+                    app.filter_trades(TradeFilter::cycle_action);
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.filter_open => {
+                    app.filter_trades(TradeFilter::cycle_open);
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.filter_dates => {
+                    app.filter_trades(TradeFilter::cycle_days);
+                }
+                crossterm::event::KeyCode::Char(c) if c.is_ascii_digit() => {
+                    if let Some(column) = TradeColumn::from_digit(c) {
+                        app.sort_trades_by(column);
                     }
-                    crossterm::event::KeyCode::Up => {
-                        if app.table_scroll > 0 {
-                            app.table_scroll -= 1;
-                        }
+                }
+                _ => {}
+            },
+            AppScreen::MoveTrade => match key.code {
+                crossterm::event::KeyCode::Down => {
+                    let next = app.move_list_state.selected().map_or(0, |i| i + 1);
+                    if next < app.move_targets.len() {
+                        app.move_list_state.select(Some(next));
                     }
-                    crossterm::event::KeyCode::Char('e') => {
-                        if let Some(trade) = app.trades.get(app.table_scroll).cloned() {
-                            app.set_edit_trade(&trade);
-                            app.screen = AppScreen::EditTrade;
-                        }
+                }
+                crossterm::event::KeyCode::Up => {
+                    let prev = app.move_list_state.selected().unwrap_or(0);
+                    app.move_list_state.select(Some(prev.saturating_sub(1)));
+                }
+                crossterm::event::KeyCode::Enter => app.pick_move_target(),
+                crossterm::event::KeyCode::Esc => {
+                    app.move_trade = None;
+                    app.screen = AppScreen::ViewTrades;
+                }
+                _ => {}
+            },
+            AppScreen::NewCampaign => match key.code {
+                crossterm::event::KeyCode::Tab => {
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::SHIFT)
+                    {
+                        app.new_campaign_field = if app.new_campaign_field == 0 {
+                            2
+                        } else {
+                            app.new_campaign_field - 1
+                        };
+                    } else {
+                        app.new_campaign_field = (app.new_campaign_field + 1) % 3;
                     }
+                }
+                crossterm::event::KeyCode::Char(ch) => match app.new_campaign_field {
+                    0 => app.new_campaign_name.push(ch),
+                    1 => app.new_campaign_symbol.push(ch),
+                    2 => app.new_campaign_target_price.push(ch),
                     _ => {}
                 },
-                AppScreen::NewCampaign => match key.code {
-                    crossterm::event::KeyCode::Tab => {
-                        if key
-                            .modifiers
-                            .contains(crossterm::event::KeyModifiers::SHIFT)
-                        {
-                            app.new_campaign_field = if app.new_campaign_field == 0 {
-                                2
-                            } else {
-                                app.new_campaign_field - 1
-                            };
-                        } else {
-                            app.new_campaign_field = (app.new_campaign_field + 1) % 3;
-                        }
+                crossterm::event::KeyCode::Backspace => match app.new_campaign_field {
+                    0 => {
+                        app.new_campaign_name.pop();
                     }
-                    crossterm::event::KeyCode::Char(ch) => match app.new_campaign_field {
-                        0 => app.new_campaign_name.push(ch),
-                        1 => app.new_campaign_symbol.push(ch),
-                        2 => app.new_campaign_target_price.push(ch),
-                        _ => {}
-                    },
-                    crossterm::event::KeyCode::Backspace => match app.new_campaign_field {
-                        0 => {
-                            app.new_campaign_name.pop();
-                        }
-                        1 => {
-                            app.new_campaign_symbol.pop();
-                        }
-                        2 => {
-                            app.new_campaign_target_price.pop();
-                        }
-                        _ => {}
-                    },
-                    crossterm::event::KeyCode::Enter => {
-                        if !app.new_campaign_name.is_empty() && !app.new_campaign_symbol.is_empty()
-                        {
-                            let target_price = app.new_campaign_target_price.parse::<f64>().ok();
-                            Campaign::insert(
-                                &app.db_conn,
-                                &app.new_campaign_name,
-                                &app.new_campaign_symbol,
-                                target_price,
-                            );
-                            app.reload_campaigns();
-                            app.new_campaign_name.clear();
-                            app.new_campaign_symbol.clear();
-                            app.new_campaign_target_price.clear();
-                            app.new_campaign_field = 0;
-                            app.screen = AppScreen::CampaignSelect;
-                        }
+                    1 => {
+                        app.new_campaign_symbol.pop();
                     }
-                    crossterm::event::KeyCode::Esc => {
-                        app.new_campaign_name.clear();
-                        app.new_campaign_symbol.clear();
-                        app.new_campaign_target_price.clear();
-                        app.new_campaign_field = 0;
-                        app.screen = AppScreen::Summary;
+                    2 => {
+                        app.new_campaign_target_price.pop();
                     }
                     _ => {}
                 },
-                AppScreen::AddTrade => match key.code {
-                    crossterm::event::KeyCode::Tab => {
-                        if key
-                            .modifiers
-                            .contains(crossterm::event::KeyModifiers::SHIFT)
-                        {
-                            app.form_index = if app.form_index == 0 {
-                                6
-                            } else {
-                                app.form_index - 1
-                            };
-                        } else {
-                            app.form_index = (app.form_index + 1) % 7;
+                crossterm::event::KeyCode::Enter
+                    if !app.new_campaign_name.is_empty() && !app.new_campaign_symbol.is_empty() =>
+                {
+                    let typed = app.new_campaign_target_price.trim().to_string();
+                    let target_price = match parse_form_number::<f64>("target exit price", &typed) {
+                        Ok(_) if typed.is_empty() => None,
+                        Ok(price) => Some(price),
+                        Err(e) => {
+                            app.show_error("Could not create the campaign", e);
+                            continue;
                         }
+                    };
+                    if let Err(e) = app.storage.insert_campaign(
+                        &app.new_campaign_name,
+                        &app.new_campaign_symbol,
+                        target_price,
+                    ) {
+                        app.show_error("Could not create the campaign", e);
+                        continue;
                     }
-                    crossterm::event::KeyCode::Left => {
-                        if app.form_index == 0 {
-                            // Action field
-                            app.action_index = if app.action_index == 0 {
-                                5
-                            } else {
-                                app.action_index - 1
-                            };
-                        }
+                    app.reload_campaigns();
+                    app.new_campaign_name.clear();
+                    app.new_campaign_symbol.clear();
+                    app.new_campaign_target_price.clear();
+                    app.new_campaign_field = 0;
+                    app.screen = AppScreen::CampaignSelect;
+                }
+                crossterm::event::KeyCode::Esc => {
+                    app.new_campaign_name.clear();
+                    app.new_campaign_symbol.clear();
+                    app.new_campaign_target_price.clear();
+                    app.new_campaign_field = 0;
+                    app.screen = AppScreen::Summary;
+                }
+                _ => {}
+            },
+            AppScreen::AddTrade => match key.code {
+                crossterm::event::KeyCode::Tab => {
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::SHIFT)
+                    {
+                        app.form_index = if app.form_index == 0 {
+                            8
+                        } else {
+                            app.form_index - 1
+                        };
+                    } else {
+                        app.form_index = (app.form_index + 1) % 9;
                     }
-                    crossterm::event::KeyCode::Right => {
-                        if app.form_index == 0 {
-                            // Action field
-                            app.action_index = (app.action_index + 1) % 6;
-                        }
+                }
+                crossterm::event::KeyCode::Left if app.form_index == 0 => {
+                    // Action field
+                    app.action_index = if app.action_index == 0 {
+                        ACTIONS.len() - 1
+                    } else {
+                        app.action_index - 1
+                    };
+                }
+                crossterm::event::KeyCode::Right if app.form_index == 0 => {
+                    // Action field
+                    app.action_index = (app.action_index + 1) % ACTIONS.len();
+                }
+                crossterm::event::KeyCode::Left if app.form_index == 8 => {
+                    app.cycle_add_campaign(false);
+                }
+                crossterm::event::KeyCode::Right if app.form_index == 8 => {
+                    app.cycle_add_campaign(true);
+                }
+                crossterm::event::KeyCode::Char(c)
+                    if app.form_index == 0 && c == keys.option_chain =>
+                {
+                    if let Err(e) = app.load_option_chain() {
+                        app.form_error = Some(e.to_string());
                     }
-                    crossterm::event::KeyCode::Char(ch) => {
-                        if app.form_index > 0 {
-                            let idx = app.form_index - 1;
-                            if idx < app.form_fields.len() {
-                                app.form_fields[idx].push(ch);
-                            }
-                        }
+                }
+                crossterm::event::KeyCode::Char(c)
+                    if app.form_index == 0 && c == keys.clone_trade =>
+                {
+                    app.fill_form_from_last();
+                }
+                crossterm::event::KeyCode::Char(ch) if app.form_index > 0 => {
+                    let idx = app.form_index - 1;
+                    if idx < app.form_fields.len() {
+                        app.form_fields[idx].push(ch);
                     }
-                    crossterm::event::KeyCode::Backspace => {
-                        if app.form_index > 0 {
-                            let idx = app.form_index - 1;
-                            if idx < app.form_fields.len() {
-                                app.form_fields[idx].pop();
-                            }
-                        }
+                }
+                crossterm::event::KeyCode::Backspace if app.form_index > 0 => {
+                    let idx = app.form_index - 1;
+                    if idx < app.form_fields.len() {
+                        app.form_fields[idx].pop();
                     }
-                    crossterm::event::KeyCode::Enter => {
-                        if let Some(campaign) = &app.selected_campaign {
-                            let action = match app.action_index {
-                                0 => crate::models::Action::BuyPut,
-                                1 => crate::models::Action::SellPut,
-                                2 => crate::models::Action::BuyCall,
-                                3 => crate::models::Action::SellCall,
-                                4 => crate::models::Action::Exercised,
-                                5 => crate::models::Action::Assigned,
-                                _ => crate::models::Action::BuyPut,
-                            };
+                }
+                crossterm::event::KeyCode::Enter => {
+                    let Some(campaign) = app.add_trade_target().cloned() else {
+                        app.form_error = Some("Pick a campaign for the trade".to_string());
+                        continue;
+                    };
+                    {
+                        let action = match app.action_index {
+                            0 => crate::models::Action::BuyPut,
+                            1 => crate::models::Action::SellPut,
+                            2 => crate::models::Action::BuyCall,
+                            3 => crate::models::Action::SellCall,
+                            4 => crate::models::Action::Exercised,
+                            5 => crate::models::Action::Assigned,
+                            6 => crate::models::Action::Expired,
+                            _ => crate::models::Action::BuyPut,
+                        };
 
-                            use time::macros::format_description;
-                            let date_fmt = format_description!("[year]-[month]-[day]");
-                            let expiration_date = Date::parse(&app.form_fields[2], &date_fmt)
-                                .unwrap_or_else(|_| {
-                                    time::OffsetDateTime::now_local().unwrap().date()
-                                });
-                            let date_of_action = Date::parse(&app.form_fields[3], &date_fmt)
-                                .unwrap_or_else(|_| {
-                                    time::OffsetDateTime::now_local().unwrap().date()
-                                });
-
-                            let trade = OptionTrade {
-                                id: None,
-                                symbol: campaign.symbol.clone(),
-                                campaign: campaign.name.clone(),
-                                action,
-                                strike: app.form_fields[0].parse().unwrap_or(0.0),
-                                delta: app.form_fields[1].parse().unwrap_or(0.0),
-                                expiration_date,
-                                date_of_action,
-                                number_of_shares: app.form_fields[4].parse().unwrap_or(0),
-                                credit: app.form_fields[5].parse().unwrap_or(0.0),
+                        let fields = &app.form_fields;
+                        let parsed = (|| {
+                            Ok::<_, String>((
+                                parse_form_number::<f64>("strike", &fields[0])?,
+                                parse_form_number::<f64>("delta", &fields[1])?,
+                                commands::parse_date(fields[2].trim())?,
+                                commands::parse_date(fields[3].trim())?,
+                                parse_form_number::<i32>("contracts", &fields[4])?,
+                                parse_form_number::<f64>("credit", &fields[5])?,
+                            ))
+                        })();
+                        let (strike, delta, expiration_date, date_of_action, contracts, credit) =
+                            match parsed {
+                                Ok(parsed) => parsed,
+                                Err(e) => {
+                                    app.form_error = Some(e);
+                                    continue;
+                                }
                             };
 
-                            if trade.insert(&app.db_conn).is_ok() {
+                        let multiplier = config::get().multiplier(&campaign.symbol);
+                        let trade = OptionTrade {
+                            id: None,
+                            symbol: campaign.symbol.clone(),
+                            campaign: campaign.name.clone(),
+                            action,
+                            strike,
+                            delta,
+                            expiration_date,
+                            date_of_action,
+                            number_of_shares: contracts * multiplier,
+                            credit,
+                            closes_trade_id: None,
+                            rolled_from_id: None,
+                            commission: 0.0,
+                            fees: 0.0,
+                            multiplier,
+                            status: None,
+                            collateral: None,
+                            covered_by_id: None,
+                            implied_volatility: None,
+                            uuid: None,
+                        };
+
+                        let tags = crate::models::parse_tags(&app.form_fields[6]);
+                        match app.save_trade(&trade, Some(&tags)) {
+                            Ok(_) => {
                                 app.reset_form();
-                                app.reload_trades();
+                                // Show the dashboard of the campaign the trade went into
+                                if app.selected_campaign.as_ref().map(|c| &c.name)
+                                    != Some(&campaign.name)
+                                {
+                                    app.selected_campaign = Some(campaign);
+                                    app.refresh_quote();
+                                }
                                 app.screen = AppScreen::CampaignDashboard;
-                            } else {
-                                app.form_error = Some("Failed to save trade".to_string());
                             }
+                            Err(e) => app.show_error("Could not save the trade", e),
                         }
                     }
-                    crossterm::event::KeyCode::Esc => {
-                        app.reset_form();
-                        app.screen = AppScreen::Summary;
-                    }
-                    _ => {}
-                },
-                AppScreen::EditTrade => match key.code {
-                    crossterm::event::KeyCode::Tab => {
-                        if key
-                            .modifiers
-                            .contains(crossterm::event::KeyModifiers::SHIFT)
-                        {
-                            app.edit_form_index = if app.edit_form_index == 0 {
-                                7
-                            } else {
-                                app.edit_form_index - 1
-                            };
+                }
+                crossterm::event::KeyCode::Esc => {
+                    app.reset_form();
+                    app.screen = AppScreen::Summary;
+                }
+                _ => {}
+            },
+            AppScreen::EditTrade => match key.code {
+                crossterm::event::KeyCode::Tab => {
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::SHIFT)
+                    {
+                        app.edit_form_index = if app.edit_form_index == 0 {
+                            9
                         } else {
-                            app.edit_form_index = (app.edit_form_index + 1) % 8;
-                        }
-                    }
-                    crossterm::event::KeyCode::Left => {
-                        if app.edit_form_index == 1 {
-                            // Action field
-                            app.edit_action_index = if app.edit_action_index == 0 {
-                                5
-                            } else {
-                                app.edit_action_index - 1
-                            };
-                        }
-                    }
-                    crossterm::event::KeyCode::Right => {
-                        if app.edit_form_index == 1 {
-                            // Action field
-                            app.edit_action_index = (app.edit_action_index + 1) % 6;
-                        }
-                    }
-                    crossterm::event::KeyCode::Char(ch) => {
-                        if app.edit_form_index != 1 {
-                            // Not action field
-                            app.edit_trade_fields[app.edit_form_index].push(ch);
-                        }
-                    }
-                    crossterm::event::KeyCode::Backspace => {
-                        if app.edit_form_index != 1 {
-                            // Not action field
-                            app.edit_trade_fields[app.edit_form_index].pop();
-                        }
+                            app.edit_form_index - 1
+                        };
+                    } else {
+                        app.edit_form_index = (app.edit_form_index + 1) % 10;
                     }
-                    crossterm::event::KeyCode::Enter => {
-                        if let Some(trade_id) = app.edit_trade_id {
-                            let action = match app.edit_action_index {
-                                0 => crate::models::Action::BuyPut,
-                                1 => crate::models::Action::SellPut,
-                                2 => crate::models::Action::BuyCall,
-                                3 => crate::models::Action::SellCall,
-                                4 => crate::models::Action::Exercised,
-                                5 => crate::models::Action::Assigned,
-                                _ => crate::models::Action::BuyPut,
-                            };
+                }
+                // Campaign field
+                crossterm::event::KeyCode::Left if app.edit_form_index == 9 => {
+                    app.cycle_edit_campaign(false);
+                }
+                crossterm::event::KeyCode::Right if app.edit_form_index == 9 => {
+                    app.cycle_edit_campaign(true);
+                }
+                crossterm::event::KeyCode::Left if app.edit_form_index == 1 => {
+                    // Action field
+                    app.edit_action_index = if app.edit_action_index == 0 {
+                        ACTIONS.len() - 1
+                    } else {
+                        app.edit_action_index - 1
+                    };
+                }
+                crossterm::event::KeyCode::Right if app.edit_form_index == 1 => {
+                    // Action field
+                    app.edit_action_index = (app.edit_action_index + 1) % ACTIONS.len();
+                }
+                crossterm::event::KeyCode::Char(ch) if !matches!(app.edit_form_index, 1 | 9) => {
+                    // Not action or campaign field
+                    app.edit_trade_fields[app.edit_form_index].push(ch);
+                }
+                crossterm::event::KeyCode::Backspace if !matches!(app.edit_form_index, 1 | 9) => {
+                    // Not action or campaign field
+                    app.edit_trade_fields[app.edit_form_index].pop();
+                }
+                crossterm::event::KeyCode::Enter => {
+                    if let Some(trade_id) = app.edit_trade_id {
+                        // Links are managed by close, roll and cover; keep them across edits
+                        let existing = app.trades.iter().find(|t| t.id == Some(trade_id));
+                        let closes_trade_id = existing.and_then(|t| t.closes_trade_id);
+                        let rolled_from_id = existing.and_then(|t| t.rolled_from_id);
+                        let status = existing.and_then(|t| t.status);
+                        let collateral = existing.and_then(|t| t.collateral);
+                        let covered_by_id = existing.and_then(|t| t.covered_by_id);
+                        let implied_volatility = existing.and_then(|t| t.implied_volatility);
+                        let uuid = existing.and_then(|t| t.uuid.clone());
+                        let (commission, fees) =
+                            existing.map_or((0.0, 0.0), |t| (t.commission, t.fees));
+                        let multiplier = existing.map_or_else(
+                            || config::get().multiplier(&app.edit_trade_fields[0]),
+                            |t| t.multiplier,
+                        );
+                        let action = match app.edit_action_index {
+                            0 => crate::models::Action::BuyPut,
+                            1 => crate::models::Action::SellPut,
+                            2 => crate::models::Action::BuyCall,
+                            3 => crate::models::Action::SellCall,
+                            4 => crate::models::Action::Exercised,
+                            5 => crate::models::Action::Assigned,
+                            6 => crate::models::Action::Expired,
+                            _ => crate::models::Action::BuyPut,
+                        };
 
-                            use time::macros::format_description;
-                            let date_fmt = format_description!("[year]-[month]-[day]");
-                            let expiration_date = Date::parse(&app.edit_trade_fields[4], &date_fmt)
-                                .unwrap_or_else(|_| {
-                                    time::OffsetDateTime::now_local().unwrap().date()
-                                });
-                            let date_of_action = Date::parse(&app.edit_trade_fields[5], &date_fmt)
-                                .unwrap_or_else(|_| {
-                                    time::OffsetDateTime::now_local().unwrap().date()
-                                });
-
-                            let updated_trade = OptionTrade {
-                                id: Some(trade_id),
-                                symbol: app.edit_trade_fields[0].clone(),
-                                campaign: app.edit_trade_fields[1].clone(),
-                                action,
-                                strike: app.edit_trade_fields[2].parse().unwrap_or(0.0),
-                                delta: app.edit_trade_fields[3].parse().unwrap_or(0.0),
-                                expiration_date,
-                                date_of_action,
-                                number_of_shares: app.edit_trade_fields[6].parse().unwrap_or(0),
-                                credit: app.edit_trade_fields[7].parse().unwrap_or(0.0),
+                        let fields = &app.edit_trade_fields;
+                        let parsed = (|| {
+                            Ok::<_, String>((
+                                parse_form_number::<f64>("strike", &fields[2])?,
+                                parse_form_number::<f64>("delta", &fields[3])?,
+                                commands::parse_date(fields[4].trim())?,
+                                commands::parse_date(fields[5].trim())?,
+                                parse_form_number::<i32>("contracts", &fields[6])?,
+                                parse_form_number::<f64>("credit", &fields[7])?,
+                            ))
+                        })();
+                        let (strike, delta, expiration_date, date_of_action, contracts, credit) =
+                            match parsed {
+                                Ok(parsed) => parsed,
+                                Err(e) => {
+                                    app.show_error("Could not save the trade", e);
+                                    continue;
+                                }
                             };
 
-                            if updated_trade.update(&app.db_conn).is_ok() {
-                                app.reload_trades();
+                        let updated_trade = OptionTrade {
+                            id: Some(trade_id),
+                            symbol: app.edit_trade_fields[0].clone(),
+                            campaign: app.edit_trade_campaign.clone(),
+                            action,
+                            strike,
+                            delta,
+                            expiration_date,
+                            date_of_action,
+                            number_of_shares: contracts * multiplier,
+                            credit,
+                            closes_trade_id,
+                            rolled_from_id,
+                            commission,
+                            fees,
+                            multiplier,
+                            status,
+                            collateral,
+                            covered_by_id,
+                            implied_volatility,
+                            uuid,
+                        };
+
+                        let tags = crate::models::parse_tags(&app.edit_trade_fields[8]);
+                        match app.save_trade(&updated_trade, Some(&tags)) {
+                            Ok(_) => {
                                 app.edit_trade_id = None;
                                 app.screen = AppScreen::ViewTrades;
                             }
+                            Err(e) => app.show_error("Could not save the trade", e),
                         }
                     }
-                    crossterm::event::KeyCode::Esc => {
-                        app.edit_trade_id = None;
-                        app.screen = AppScreen::Summary;
+                }
+                crossterm::event::KeyCode::Esc => {
+                    app.edit_trade_id = None;
+                    app.screen = AppScreen::Summary;
+                }
+                _ => {}
+            },
+            AppScreen::OptionChain => match key.code {
+                crossterm::event::KeyCode::Down => {
+                    let next = app.chain_list_state.selected().map_or(0, |i| i + 1);
+                    if next < app.chain.len() {
+                        app.chain_list_state.select(Some(next));
                     }
-                    _ => {}
-                },
-                AppScreen::Summary => match key.code {
-                    crossterm::event::KeyCode::Char('c') => {
-                        app.screen = AppScreen::CampaignSelect;
-                    }
-                    crossterm::event::KeyCode::Char('n') => {
-                        app.screen = AppScreen::NewCampaign;
+                }
+                crossterm::event::KeyCode::Up => {
+                    let prev = app.chain_list_state.selected().unwrap_or(0);
+                    app.chain_list_state.select(Some(prev.saturating_sub(1)));
+                }
+                crossterm::event::KeyCode::Enter => {
+                    app.pick_chain_quote();
+                    app.screen = AppScreen::AddTrade;
+                }
+                crossterm::event::KeyCode::Esc => {
+                    app.screen = AppScreen::AddTrade;
+                }
+                _ => {}
+            },
+            AppScreen::ImportCsv => match key.code {
+                crossterm::event::KeyCode::Tab => {
+                    if key
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::SHIFT)
+                    {
+                        app.import.field = (app.import.field + 3) % 4;
+                    } else {
+                        app.import.field = (app.import.field + 1) % 4;
                     }
-                    crossterm::event::KeyCode::Char('s') => {
-                        // Already on summary, do nothing
-                    }
-                    crossterm::event::KeyCode::Char('q') => return Ok(()),
-                    crossterm::event::KeyCode::Char('1') | crossterm::event::KeyCode::Char('2') => {
-                        app.screen = AppScreen::CampaignSelect;
+                }
+                crossterm::event::KeyCode::Down if app.import.field == 0 => {
+                    let next = app.import.list_state.selected().map_or(0, |i| i + 1);
+                    if next < app.import.entries.len() {
+                        app.import.list_state.select(Some(next));
                     }
+                }
+                crossterm::event::KeyCode::Up if app.import.field == 0 => {
+                    let prev = app.import.list_state.selected().unwrap_or(0);
+                    app.import.list_state.select(Some(prev.saturating_sub(1)));
+                }
+                crossterm::event::KeyCode::Enter if app.import.field == 0 => {
+                    app.import.choose_entry();
+                }
+                crossterm::event::KeyCode::Left if app.import.field == 1 => {
+                    let brokers = Broker::supported_brokers().len();
+                    app.import.broker_index = (app.import.broker_index + brokers - 1) % brokers;
+                }
+                crossterm::event::KeyCode::Right if app.import.field == 1 => {
+                    let brokers = Broker::supported_brokers().len();
+                    app.import.broker_index = (app.import.broker_index + 1) % brokers;
+                }
+                crossterm::event::KeyCode::Char(ch) => match app.import.field {
+                    2 => app.import.campaign.push(ch),
+                    3 => app.import.symbol.push(ch),
                     _ => {}
                 },
-                AppScreen::MainMenu => match key.code {
-                    crossterm::event::KeyCode::Char('s') => {
-                        app.screen = AppScreen::Summary;
-                    }
-                    crossterm::event::KeyCode::Char('1') => {
-                        app.screen = AppScreen::AddTrade;
+                crossterm::event::KeyCode::Backspace => match app.import.field {
+                    2 => {
+                        app.import.campaign.pop();
                     }
-                    crossterm::event::KeyCode::Char('2') => {
-                        app.screen = AppScreen::ViewTrades;
+                    3 => {
+                        app.import.symbol.pop();
                     }
-                    crossterm::event::KeyCode::Char('q') => return Ok(()),
                     _ => {}
                 },
-            }
+                crossterm::event::KeyCode::Enter => app.start_import(),
+                crossterm::event::KeyCode::Esc => {
+                    app.screen = AppScreen::Summary;
+                }
+                _ => {}
+            },
+            AppScreen::Repair => match key.code {
+                crossterm::event::KeyCode::Down if app.issue_index + 1 < app.issues.len() => {
+                    app.issue_index += 1;
+                }
+                crossterm::event::KeyCode::Up if app.issue_index > 0 => {
+                    app.issue_index -= 1;
+                }
+                crossterm::event::KeyCode::Char('f') => app.repair_selected(Repair::Fix),
+                crossterm::event::KeyCode::Char('d') => app.repair_selected(Repair::DeleteTrade),
+                crossterm::event::KeyCode::Esc => app.finish_repairs(),
+                crossterm::event::KeyCode::Char(c) if c == keys.quit => return Ok(()),
+                _ => {}
+            },
+            AppScreen::Summary => match key.code {
+                crossterm::event::KeyCode::Char(c) if c == keys.campaigns => {
+                    app.screen = AppScreen::CampaignSelect;
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.backup => app.start_backup(),
+                crossterm::event::KeyCode::Char(c) if c == keys.import => app.open_import(),
+                crossterm::event::KeyCode::Char(c) if c == keys.new_campaign => {
+                    app.screen = AppScreen::NewCampaign;
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.add_trade => app.open_add_trade(),
+                crossterm::event::KeyCode::Char(c) if c == keys.monthly_report => {
+                    app.screen = AppScreen::MonthlyReport;
+                }
+                crossterm::event::KeyCode::Char('s') => {
+                    // Already on summary, do nothing
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.undo => app.undo(),
+                crossterm::event::KeyCode::Char(c) if c == keys.redo => app.redo(),
+                crossterm::event::KeyCode::Char(c) if c == keys.quit => return Ok(()),
+                crossterm::event::KeyCode::Char('1') | crossterm::event::KeyCode::Char('2') => {
+                    app.screen = AppScreen::CampaignSelect;
+                }
+                _ => {}
+            },
+            AppScreen::MonthlyReport => match key.code {
+                crossterm::event::KeyCode::Esc => app.screen = AppScreen::Summary,
+                crossterm::event::KeyCode::Char(c) if c == keys.quit => return Ok(()),
+                _ => {}
+            },
+            AppScreen::MainMenu => match key.code {
+                crossterm::event::KeyCode::Char('s') => {
+                    app.screen = AppScreen::Summary;
+                }
+                crossterm::event::KeyCode::Char('1') => app.open_add_trade(),
+                crossterm::event::KeyCode::Char('2') => {
+                    app.screen = AppScreen::ViewTrades;
+                }
+                crossterm::event::KeyCode::Char(c) if c == keys.quit => return Ok(()),
+                _ => {}
+            },
         }
     }
 }
 
+/// Parse a number typed into a form, where a blank field means zero.
+fn parse_form_number<T: std::str::FromStr + Default>(
+    field: &str,
+    typed: &str,
+) -> Result<T, String> {
+    let typed = typed.trim();
+    if typed.is_empty() {
+        return Ok(T::default());
+    }
+    typed
+        .parse()
+        .map_err(|_| format!("Invalid {field} '{typed}'"))
+}
+
+/// Draw the screen the app is on.
+/// Trade rows that fit on one View Trades page, less the borders and header row.
+fn trade_page_rows() -> isize {
+    crossterm::terminal::size().map_or(10, |(_, height)| height.saturating_sub(3).max(1) as isize)
+}
+
+fn draw_screen(f: &mut Frame, app: &mut App) {
+    match app.screen {
+        AppScreen::Summary => ui::summary::draw_summary(f, app),
+        AppScreen::CampaignSelect => ui::campaign_select::draw_campaign_select(f, app),
+        AppScreen::NewCampaign => ui::new_campaign::draw_new_campaign(f, app),
+        AppScreen::CampaignDashboard => ui::campaign_dashboard::draw_campaign_dashboard(f, app),
+        AppScreen::MainMenu => draw_main_menu(f),
+        AppScreen::AddTrade => ui::add_trade::draw_add_trade(f, app),
+        AppScreen::ViewTrades => ui::view_trades::draw_view_trades(f, app),
+        AppScreen::EditTrade => ui::edit_trade::draw_edit_trade(f, app),
+        AppScreen::Repair => ui::repair::draw_repair(f, app),
+        AppScreen::OptionChain => ui::option_chain::draw_option_chain(f, app),
+        AppScreen::ImportCsv => ui::import_csv::draw_import_csv(f, app),
+        AppScreen::MoveTrade => ui::move_trade::draw_move_trade(f, app),
+        AppScreen::MonthlyReport => ui::monthly_report::draw_monthly_report(f, app),
+    }
+}
+
 fn draw_main_menu(f: &mut Frame) {
     use ratatui::widgets::*;
     let size = f.area();
@@ -0,0 +1,311 @@
+//! Running totals over the trade list, updated one trade at a time as trades are added,
+//! edited or deleted instead of re-summing every trade.
+
+use crate::logic::week_bounds;
+use crate::models::{Action, OptionTrade};
+use std::collections::{BTreeMap, HashMap};
+use time::{Date, Duration, Weekday};
+
+/// Premium and capital totals for a set of trades, in cents. Premium is summed with the
+/// sign it is stored with, as in [`crate::logic::calculate_campaign_summary`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    /// Premium of options sold.
+    pub sold: i64,
+    /// Premium of options bought.
+    pub bought: i64,
+    /// Premium recorded on assignments.
+    pub assigned: i64,
+    /// Commissions and fees paid.
+    pub costs: i64,
+    /// Capital tied up by every option sold, the denominator of ROIC: strike times shares,
+    /// or the collateral's cost for a covered call.
+    pub capital_at_risk: i64,
+    pub trades: usize,
+}
+
+impl Totals {
+    /// Premium sold less premium bought and costs, as
+    /// [`crate::logic::calculate_total_premium_sold`].
+    pub fn net_premium(&self) -> i64 {
+        self.sold - self.bought - self.costs
+    }
+
+    /// Credits less debits, assignments and costs included, as a campaign's running P/L.
+    pub fn running_pl(&self) -> i64 {
+        self.sold - self.bought - self.assigned - self.costs
+    }
+
+    /// Net premium as a fraction of capital at risk.
+    pub fn roic(&self) -> Option<f64> {
+        (self.capital_at_risk > 0).then(|| self.net_premium() as f64 / self.capital_at_risk as f64)
+    }
+
+    fn apply(&mut self, trade: &OptionTrade, sign: i64) {
+        let premium = sign * trade.premium_cents();
+        match trade.action {
+            Action::SellPut | Action::SellCall => {
+                self.sold += premium;
+                self.capital_at_risk +=
+                    sign * trade.capital_per_share_cents() * trade.number_of_shares as i64;
+            }
+            Action::BuyPut | Action::BuyCall => self.bought += premium,
+            Action::Assigned => self.assigned += premium,
+            Action::Exercised | Action::Expired => {}
+        }
+        self.costs += sign * trade.costs_cents();
+        self.trades = self.trades.wrapping_add_signed(sign as isize);
+    }
+}
+
+/// Weeks averaged by [`WeeklyIncome::moving_average`].
+pub const MOVING_AVERAGE_WEEKS: usize = 4;
+
+/// One week's net premium, in cents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeeklyIncome {
+    /// First day of the week.
+    pub week: Date,
+    pub net_premium: i64,
+    /// Average net premium of this week and the three before it.
+    pub moving_average: i64,
+}
+
+/// Account-wide and per-campaign totals plus weekly series, kept current with
+/// [`Metrics::add`], [`Metrics::remove`] and [`Metrics::update`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metrics {
+    week_start: Weekday,
+    total: Totals,
+    campaigns: HashMap<String, Totals>,
+    /// Net premium by the first day of the week the trade was made in.
+    weekly_premium: BTreeMap<Date, i64>,
+    /// Premium of options sold, by expiration date.
+    sold_by_expiration: BTreeMap<Date, i64>,
+}
+
+impl Metrics {
+    /// Totals over `trades`, with weeks beginning on `week_start`.
+    pub fn new(trades: &[OptionTrade], week_start: Weekday) -> Self {
+        let mut metrics = Self {
+            week_start,
+            total: Totals::default(),
+            campaigns: HashMap::new(),
+            weekly_premium: BTreeMap::new(),
+            sold_by_expiration: BTreeMap::new(),
+        };
+        for trade in trades {
+            metrics.add(trade);
+        }
+        metrics
+    }
+
+    pub fn add(&mut self, trade: &OptionTrade) {
+        self.apply(trade, 1);
+    }
+
+    pub fn remove(&mut self, trade: &OptionTrade) {
+        self.apply(trade, -1);
+    }
+
+    /// Replace `old` with its edited version `new`.
+    pub fn update(&mut self, old: &OptionTrade, new: &OptionTrade) {
+        self.remove(old);
+        self.add(new);
+    }
+
+    pub fn total(&self) -> Totals {
+        self.total
+    }
+
+    /// Totals of the named campaign's trades; zero for a campaign without any.
+    pub fn campaign(&self, name: &str) -> Totals {
+        self.campaigns.get(name).copied().unwrap_or_default()
+    }
+
+    /// Net premium per week with at least one trade, oldest first, keyed by the week's
+    /// first day.
+    pub fn weekly_premium(&self) -> &BTreeMap<Date, i64> {
+        &self.weekly_premium
+    }
+
+    /// The `count` weeks up to the one containing `today`, oldest first, with weeks
+    /// without trades at zero.
+    pub fn recent_weeks(&self, today: Date, count: usize) -> Vec<WeeklyIncome> {
+        let (this_week, _) = week_bounds(today, self.week_start);
+        let premiums: Vec<(Date, i64)> = (0..count + MOVING_AVERAGE_WEEKS - 1)
+            .rev()
+            .map(|back| {
+                let week = this_week - Duration::weeks(back as i64);
+                (week, self.weekly_premium.get(&week).copied().unwrap_or(0))
+            })
+            .collect();
+        premiums
+            .windows(MOVING_AVERAGE_WEEKS)
+            .map(|window| {
+                let (week, net_premium) = window[MOVING_AVERAGE_WEEKS - 1];
+                let sum: i64 = window.iter().map(|(_, premium)| premium).sum();
+                WeeklyIncome {
+                    week,
+                    net_premium,
+                    moving_average: sum / MOVING_AVERAGE_WEEKS as i64,
+                }
+            })
+            .collect()
+    }
+
+    /// Premium of options sold that expire on `date`.
+    pub fn sold_expiring_on(&self, date: Date) -> i64 {
+        self.sold_by_expiration.get(&date).copied().unwrap_or(0)
+    }
+
+    fn apply(&mut self, trade: &OptionTrade, sign: i64) {
+        self.total.apply(trade, sign);
+        let campaign = self.campaigns.entry(trade.campaign.clone()).or_default();
+        campaign.apply(trade, sign);
+        if campaign.trades == 0 {
+            self.campaigns.remove(&trade.campaign);
+        }
+
+        let premium = sign * trade.premium_cents();
+        let (week, _) = week_bounds(trade.date_of_action, self.week_start);
+        match trade.action {
+            Action::SellPut | Action::SellCall => {
+                adjust(&mut self.weekly_premium, week, premium);
+                adjust(&mut self.sold_by_expiration, trade.expiration_date, premium);
+            }
+            Action::BuyPut | Action::BuyCall => adjust(&mut self.weekly_premium, week, -premium),
+            Action::Assigned | Action::Exercised | Action::Expired => {}
+        }
+        // Costs count against the week as they do in `logic::monthly_income`
+        adjust(&mut self.weekly_premium, week, -sign * trade.costs_cents());
+    }
+}
+
+/// Add `amount` to the entry for `date`, dropping entries that fall back to zero so
+/// removing a trade leaves no trace of it.
+fn adjust(series: &mut BTreeMap<Date, i64>, date: Date, amount: i64) {
+    let value = series.entry(date).or_default();
+    *value += amount;
+    if *value == 0 {
+        series.remove(&date);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{calculate_total_premium_sold, monthly_income};
+    use time::macros::date;
+
+    fn trade(campaign: &str, action: Action, credit: f64, date_of_action: Date) -> OptionTrade {
+        OptionTrade {
+            symbol: "NVTS".to_string(),
+            campaign: campaign.to_string(),
+            action,
+            strike: 6.5,
+            expiration_date: date!(2025 - 07 - 03),
+            date_of_action,
+            number_of_shares: 100,
+            credit,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_incremental_updates_match_rebuild() {
+        let mut trades = vec![
+            trade("wheel", Action::SellPut, 0.50, date!(2025 - 06 - 23)),
+            trade("wheel", Action::BuyPut, 0.10, date!(2025 - 06 - 30)),
+            trade("hedge", Action::SellCall, 0.25, date!(2025 - 06 - 30)),
+        ];
+        trades[0].commission = 0.65;
+        let mut metrics = Metrics::new(&trades, Weekday::Monday);
+        assert_eq!(
+            metrics.total().net_premium(),
+            calculate_total_premium_sold(&trades)
+        );
+        assert_eq!(metrics.campaign("wheel").running_pl(), 3935);
+        assert_eq!(metrics.total().capital_at_risk, 2 * 650 * 100);
+        assert_eq!(
+            metrics.weekly_premium().iter().collect::<Vec<_>>(),
+            [
+                (&date!(2025 - 06 - 23), &4935),
+                (&date!(2025 - 06 - 30), &1500)
+            ]
+        );
+        assert_eq!(metrics.sold_expiring_on(date!(2025 - 07 - 03)), 7500);
+        let weeks = metrics.recent_weeks(date!(2025 - 07 - 09), 3);
+        assert_eq!(
+            weeks
+                .iter()
+                .map(|w| (w.week, w.net_premium, w.moving_average))
+                .collect::<Vec<_>>(),
+            [
+                (date!(2025 - 06 - 23), 4935, 1233),
+                (date!(2025 - 06 - 30), 1500, 1608),
+                (date!(2025 - 07 - 07), 0, 1608)
+            ]
+        );
+
+        // Covered by shares bought at $5, the call ties up their cost rather than its strike
+        let covered = OptionTrade {
+            collateral: Some(5.0),
+            ..trades[2].clone()
+        };
+        assert_eq!(
+            Metrics::new(&[covered], Weekday::Monday)
+                .total()
+                .capital_at_risk,
+            500 * 100
+        );
+
+        let edited = trade("hedge", Action::SellCall, 0.40, date!(2025 - 07 - 01));
+        metrics.update(&trades[2], &edited);
+        trades[2] = edited;
+        let assigned = trade("wheel", Action::Assigned, 0.0, date!(2025 - 07 - 03));
+        metrics.add(&assigned);
+        trades.push(assigned);
+        assert_eq!(metrics, Metrics::new(&trades, Weekday::Monday));
+
+        for trade in &trades {
+            metrics.remove(trade);
+        }
+        assert_eq!(metrics, Metrics::new(&[], Weekday::Monday));
+    }
+
+    #[test]
+    fn test_weekly_premium_reconciles_with_monthly_income() {
+        let trades = [
+            OptionTrade {
+                commission: 0.65,
+                fees: 0.04,
+                ..trade("wheel", Action::SellPut, 0.50, date!(2025 - 06 - 23))
+            },
+            OptionTrade {
+                commission: 0.65,
+                ..trade("wheel", Action::BuyPut, 0.10, date!(2025 - 06 - 27))
+            },
+            // An assignment carries no premium, but its fee still comes out of the week
+            OptionTrade {
+                fees: 5.0,
+                ..trade("wheel", Action::Assigned, 0.0, date!(2025 - 07 - 03))
+            },
+        ];
+        let metrics = Metrics::new(&trades, Weekday::Monday);
+        assert_eq!(
+            metrics.weekly_premium().iter().collect::<Vec<_>>(),
+            [
+                (&date!(2025 - 06 - 23), &(5000 - 1000 - 69 - 65)),
+                (&date!(2025 - 06 - 30), &-500)
+            ]
+        );
+        let weekly: i64 = metrics.weekly_premium().values().sum();
+        let monthly: i64 = monthly_income(&trades, date!(2025 - 07 - 31))
+            .iter()
+            .map(|m| m.net_premium)
+            .sum();
+        assert_eq!(weekly, monthly);
+        assert_eq!(weekly, metrics.total().net_premium());
+    }
+}
@@ -1,6 +1,14 @@
-use rusqlite::{Connection, Result, params};
+//! Database records (trades, stock trades, cash events, campaigns, snapshots, dividends)
+//! and their queries.
+
+use crate::filter::{Filter, Sort};
+use crate::money::{per_share, to_cents, to_dollars};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Type, ValueRef};
+use rusqlite::{Connection, OptionalExtension, Result, Row, params, params_from_iter};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use time::Date;
+use time::macros::format_description;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Action {
@@ -10,123 +18,811 @@ pub enum Action {
     SellCall,
     Exercised,
     Assigned,
+    /// The option expired worthless, closing whatever position was left in it.
+    Expired,
+}
+
+impl std::str::FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['_', '-'], "").as_str() {
+            "buyput" => Ok(Action::BuyPut),
+            "sellput" => Ok(Action::SellPut),
+            "buycall" => Ok(Action::BuyCall),
+            "sellcall" => Ok(Action::SellCall),
+            "exercised" => Ok(Action::Exercised),
+            "assigned" => Ok(Action::Assigned),
+            "expired" => Ok(Action::Expired),
+            _ => Err(format!(
+                "Invalid action: '{s}'. Expected one of: BuyPut, SellPut, BuyCall, SellCall, Exercised, Assigned, Expired"
+            )),
+        }
+    }
+}
+
+/// Read a `YYYY-MM-DD` date column, failing the row instead of panicking on bad text.
+fn date_column(row: &Row, idx: usize) -> Result<Date> {
+    let s: String = row.get(idx)?;
+    Date::parse(&s, format_description!("[year]-[month]-[day]"))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptionKind {
+    Put,
+    Call,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::BuyPut,
+        Action::SellPut,
+        Action::BuyCall,
+        Action::SellCall,
+        Action::Exercised,
+        Action::Assigned,
+        Action::Expired,
+    ];
+
+    /// The name stored in the database's `action` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::BuyPut => "BuyPut",
+            Action::SellPut => "SellPut",
+            Action::BuyCall => "BuyCall",
+            Action::SellCall => "SellCall",
+            Action::Exercised => "Exercised",
+            Action::Assigned => "Assigned",
+            Action::Expired => "Expired",
+        }
+    }
+
+    /// The option type traded, or `None` for assignment, exercise and expiration events.
+    pub fn option_kind(&self) -> Option<OptionKind> {
+        match self {
+            Action::BuyPut | Action::SellPut => Some(OptionKind::Put),
+            Action::BuyCall | Action::SellCall => Some(OptionKind::Call),
+            Action::Exercised | Action::Assigned | Action::Expired => None,
+        }
+    }
+}
+
+impl ToSql for Action {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for Action {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let s = value.as_str()?;
+        Action::ALL
+            .into_iter()
+            .find(|a| a.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Unknown action '{s}'").into()))
+    }
+}
+
+/// Where a trade is in its life: still open, or how it ended.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TradeStatus {
+    Open,
+    /// Bought or sold back before expiration.
+    Closed,
+    Expired,
+    /// Assigned or exercised, so shares changed hands.
+    Assigned,
+    /// Closed and reopened at another strike or expiration.
+    Rolled,
+}
+
+impl TradeStatus {
+    pub const ALL: [TradeStatus; 5] = [
+        TradeStatus::Open,
+        TradeStatus::Closed,
+        TradeStatus::Expired,
+        TradeStatus::Assigned,
+        TradeStatus::Rolled,
+    ];
+
+    /// The name stored in the database's `status` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeStatus::Open => "Open",
+            TradeStatus::Closed => "Closed",
+            TradeStatus::Expired => "Expired",
+            TradeStatus::Assigned => "Assigned",
+            TradeStatus::Rolled => "Rolled",
+        }
+    }
+}
+
+impl std::str::FromStr for TradeStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        TradeStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "Invalid status: '{s}'. Expected one of: Open, Closed, Expired, Assigned, Rolled"
+                )
+            })
+    }
+}
+
+impl ToSql for TradeStatus {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for TradeStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let s = value.as_str()?;
+        TradeStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| FromSqlError::Other(format!("Unknown status '{s}'").into()))
+    }
+}
+
+/// Columns `OptionTrade::from_row` reads, in order.
+const TRADE_COLUMNS: &str = "t.id, t.symbol, c.name, t.action, t.strike_cents, t.delta, t.expiration_date, t.date_of_action, t.number_of_shares, t.premium_cents, t.closes_trade_id, t.rolled_from_id, t.commission_cents, t.fees_cents, t.multiplier, t.status, t.collateral_cents, t.covered_by_id, t.implied_volatility, t.uuid";
+
+/// Trades joined to their campaign's name; `Filter` and `Sort` SQL refers to these aliases.
+const TRADE_TABLES: &str = "option_trades t JOIN campaigns c ON c.id = t.campaign_id";
+
+/// Which trades `OptionTrade::query` returns, in what order, and which page of them.
+#[derive(Debug, Clone, Default)]
+pub struct TradeQuery {
+    pub filter: Filter,
+    /// Sort keys in priority order; ties fall back to date of action, then id.
+    pub sort: Vec<Sort>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+impl TradeQuery {
+    /// The page of `trades` the query selects, for storage that can't run it as SQL.
+    pub fn apply(&self, trades: Vec<OptionTrade>) -> Vec<OptionTrade> {
+        let mut trades: Vec<OptionTrade> = trades
+            .into_iter()
+            .filter(|t| self.filter.matches(t))
+            .collect();
+        trades.sort_by(|a, b| {
+            self.sort
+                .iter()
+                .map(|sort| sort.compare(a, b))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| (a.date_of_action, a.id).cmp(&(b.date_of_action, b.id)))
+        });
+        trades
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct OptionTrade {
     pub id: Option<i32>,
     pub symbol: String,
+    /// Campaign name, stored as a reference to the campaign's row.
     pub campaign: String,
     pub action: Action,
+    /// Strike price per share, stored as cents.
     pub strike: f64,
     pub delta: f64,
     pub expiration_date: Date,
     pub date_of_action: Date,
+    /// Shares the trade covers: contracts times `multiplier`.
     pub number_of_shares: i32,
+    /// Premium per share; the database stores the trade's total premium in cents.
     pub credit: f64,
+    /// The opening trade this transaction closes, when recorded explicitly.
+    pub closes_trade_id: Option<i32>,
+    /// The trade this position was rolled out of, forming a roll chain.
+    pub rolled_from_id: Option<i32>,
+    /// Broker commission for the whole trade, stored as cents. `credit` is before costs.
+    #[serde(default)]
+    pub commission: f64,
+    /// Regulatory and exchange fees for the whole trade, stored as cents.
+    #[serde(default)]
+    pub fees: f64,
+    /// Shares per contract when the trade was made: 100 for standard equity and index
+    /// options, other values for mini or adjusted contracts.
+    #[serde(default = "default_multiplier")]
+    pub multiplier: i32,
+    /// Status set by hand, overriding the one worked out from the trades (see
+    /// `logic::trade_statuses`).
+    #[serde(default)]
+    pub status: Option<TradeStatus>,
+    /// Per-share cost of what covers a short call: the shares held, or the long call of a
+    /// poor man's covered call. Stored as cents; `None` counts the call as naked.
+    #[serde(default)]
+    pub collateral: Option<f64>,
+    /// The long call covering this short call in a poor man's covered call.
+    #[serde(default)]
+    pub covered_by_id: Option<i32>,
+    /// Annualized implied volatility (0.45 for 45%) from the option chain, which
+    /// `backfill-greeks` looks up for open positions.
+    #[serde(default)]
+    pub implied_volatility: Option<f64>,
+    /// Identifier `sync` matches the trade by across devices; the database assigns one
+    /// when the trade is first stored.
+    #[serde(default)]
+    pub uuid: Option<String>,
+}
+
+/// An empty sell-to-open with the default multiplier and no costs, links or overrides,
+/// as a base to fill in with struct update syntax.
+impl Default for OptionTrade {
+    fn default() -> Self {
+        OptionTrade {
+            id: None,
+            symbol: String::new(),
+            campaign: String::new(),
+            action: Action::SellPut,
+            strike: 0.0,
+            delta: 0.0,
+            expiration_date: Date::MIN,
+            date_of_action: Date::MIN,
+            number_of_shares: 0,
+            credit: 0.0,
+            closes_trade_id: None,
+            rolled_from_id: None,
+            commission: 0.0,
+            fees: 0.0,
+            multiplier: default_multiplier(),
+            status: None,
+            collateral: None,
+            covered_by_id: None,
+            implied_volatility: None,
+            uuid: None,
+        }
+    }
+}
+
+fn default_multiplier() -> i32 {
+    crate::config::DEFAULT_MULTIPLIER
 }
 
 impl OptionTrade {
+    /// Number of contracts traded.
+    pub fn contracts(&self) -> i32 {
+        self.number_of_shares / self.multiplier.max(1)
+    }
+
+    pub fn strike_cents(&self) -> i64 {
+        to_cents(self.strike)
+    }
+
+    /// Total premium of the trade in cents, signed like `credit`.
+    pub fn premium_cents(&self) -> i64 {
+        to_cents(self.credit * self.number_of_shares as f64)
+    }
+
+    /// Commission plus fees in cents, as a positive amount paid.
+    pub fn costs_cents(&self) -> i64 {
+        to_cents(self.commission) + to_cents(self.fees)
+    }
+
+    /// Capital in cents per share a sold option ties up: the cost of the collateral
+    /// covering a covered call, and otherwise the strike.
+    pub fn capital_per_share_cents(&self) -> i64 {
+        match self.collateral {
+            Some(collateral) if self.action == Action::SellCall => to_cents(collateral),
+            _ => self.strike_cents(),
+        }
+    }
+
     pub fn insert(&self, conn: &Connection) -> Result<usize> {
         conn.execute(
-            "INSERT INTO option_trades (symbol, campaign, action, strike, delta, expiration_date, date_of_action, number_of_shares, credit)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            "INSERT INTO option_trades (symbol, campaign_id, action, strike_cents, delta, expiration_date, date_of_action, number_of_shares, premium_cents, closes_trade_id, rolled_from_id, commission_cents, fees_cents, multiplier, status, collateral_cents, covered_by_id, implied_volatility)
+            VALUES (?1, (SELECT id FROM campaigns WHERE name = ?2), ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
             params![
                 self.symbol,
                 self.campaign,
-                format!("{:?}", self.action),
-                self.strike,
+                self.action,
+                self.strike_cents(),
                 self.delta,
                 self.expiration_date.to_string(),
                 self.date_of_action.to_string(),
                 self.number_of_shares,
-                self.credit,
+                self.premium_cents(),
+                self.closes_trade_id,
+                self.rolled_from_id,
+                to_cents(self.commission),
+                to_cents(self.fees),
+                self.multiplier,
+                self.status,
+                self.collateral.map(to_cents),
+                self.covered_by_id,
+                self.implied_volatility,
             ],
         )
     }
 
     pub fn get_all(conn: &Connection) -> Result<Vec<OptionTrade>> {
-        use time::macros::format_description;
-        let date_fmt = format_description!("[year]-[month]-[day]");
+        let mut stmt = conn.prepare(&format!("SELECT {TRADE_COLUMNS} FROM {TRADE_TABLES}"))?;
+        let trade_iter = stmt.query_map([], Self::from_row)?;
+        trade_iter.collect()
+    }
+
+    /// Trades matching `query`, filtered, sorted and paged in the database rather than in
+    /// memory.
+    pub fn query(conn: &Connection, query: &TradeQuery) -> Result<Vec<OptionTrade>> {
+        let (condition, params) = query.filter.to_sql();
+        let mut order: Vec<String> = query.sort.iter().map(Sort::to_sql).collect();
+        order.extend(["t.date_of_action".to_string(), "t.id".to_string()]);
+        // SQLite reads a negative LIMIT as no limit
+        let limit = query.limit.map_or(-1, |l| l as i64);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {TRADE_COLUMNS} FROM {TRADE_TABLES} WHERE {condition} ORDER BY {} LIMIT {limit} OFFSET {}",
+            order.join(", "),
+            query.offset
+        ))?;
+        let trade_iter = stmt.query_map(params_from_iter(params), Self::from_row)?;
+        trade_iter.collect()
+    }
+
+    /// How many trades match `filter`.
+    pub fn count(conn: &Connection, filter: &Filter) -> Result<usize> {
+        let (condition, params) = filter.to_sql();
+        conn.query_row(
+            &format!("SELECT count(*) FROM {TRADE_TABLES} WHERE {condition}"),
+            params_from_iter(params),
+            |row| row.get(0),
+        )
+    }
+
+    pub fn get_by_id(conn: &Connection, id: i32) -> Result<Option<OptionTrade>> {
+        conn.query_row(
+            &format!("SELECT {TRADE_COLUMNS} FROM {TRADE_TABLES} WHERE t.id = ?1"),
+            [id],
+            Self::from_row,
+        )
+        .optional()
+    }
+
+    fn from_row(row: &Row) -> Result<OptionTrade> {
+        let number_of_shares: i32 = row.get(8)?;
+        let premium_cents: i64 = row.get(9)?;
+        Ok(OptionTrade {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            campaign: row.get(2)?,
+            action: row.get(3)?,
+            strike: to_dollars(row.get(4)?),
+            delta: row.get(5)?,
+            expiration_date: date_column(row, 6)?,
+            date_of_action: date_column(row, 7)?,
+            number_of_shares,
+            credit: per_share(premium_cents, number_of_shares),
+            closes_trade_id: row.get(10)?,
+            rolled_from_id: row.get(11)?,
+            commission: to_dollars(row.get(12)?),
+            fees: to_dollars(row.get(13)?),
+            multiplier: row.get(14)?,
+            status: row.get(15)?,
+            collateral: row.get::<_, Option<i64>>(16)?.map(to_dollars),
+            covered_by_id: row.get(17)?,
+            implied_volatility: row.get(18)?,
+            uuid: row.get(19)?,
+        })
+    }
+
+    pub fn update(&self, conn: &Connection) -> Result<usize> {
+        conn.execute(
+            "UPDATE option_trades SET symbol = ?1, campaign_id = (SELECT id FROM campaigns WHERE name = ?2), action = ?3, strike_cents = ?4, delta = ?5, expiration_date = ?6, date_of_action = ?7, number_of_shares = ?8, premium_cents = ?9, closes_trade_id = ?10, rolled_from_id = ?11, commission_cents = ?12, fees_cents = ?13, multiplier = ?14, status = ?15, collateral_cents = ?16, covered_by_id = ?17, implied_volatility = ?18 WHERE id = ?19",
+            params![
+                self.symbol,
+                self.campaign,
+                self.action,
+                self.strike_cents(),
+                self.delta,
+                self.expiration_date.to_string(),
+                self.date_of_action.to_string(),
+                self.number_of_shares,
+                self.premium_cents(),
+                self.closes_trade_id,
+                self.rolled_from_id,
+                to_cents(self.commission),
+                to_cents(self.fees),
+                self.multiplier,
+                self.status,
+                self.collateral.map(to_cents),
+                self.covered_by_id,
+                self.implied_volatility,
+                self.id,
+            ],
+        )
+    }
+
+    /// Insert the trade unless an identical one is already recorded.
+    ///
+    /// Returns `false` when the trade was a duplicate and nothing was written.
+    pub fn insert_or_ignore(&self, conn: &Connection) -> Result<bool> {
+        // Not a UNIQUE constraint: closing two identical lots, or two identical fills on
+        // one day, are separate trades when entered by hand
+        let inserted = conn.execute(
+            "INSERT INTO option_trades (symbol, campaign_id, action, strike_cents, delta, expiration_date, date_of_action, number_of_shares, premium_cents, closes_trade_id, rolled_from_id, commission_cents, fees_cents, multiplier, status, collateral_cents, covered_by_id, implied_volatility)
+            SELECT ?1, (SELECT id FROM campaigns WHERE name = ?2), ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18
+            WHERE NOT EXISTS (
+                SELECT 1 FROM option_trades WHERE dedup_key = ?1 || '|' ||
+                    (SELECT id FROM campaigns WHERE name = ?2) || '|' || ?3 || '|' || ?4 || '|' ||
+                    ?6 || '|' || ?7 || '|' || ?8 || '|' || ?9
+            )",
+            params![
+                self.symbol,
+                self.campaign,
+                self.action,
+                self.strike_cents(),
+                self.delta,
+                self.expiration_date.to_string(),
+                self.date_of_action.to_string(),
+                self.number_of_shares,
+                self.premium_cents(),
+                self.closes_trade_id,
+                self.rolled_from_id,
+                to_cents(self.commission),
+                to_cents(self.fees),
+                self.multiplier,
+                self.status,
+                self.collateral.map(to_cents),
+                self.covered_by_id,
+                self.implied_volatility,
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn delete(conn: &Connection, id: i32) -> Result<usize> {
+        conn.execute("DELETE FROM option_trades WHERE id = ?1", params![id])
+    }
+
+    /// Insert the trade under its own id and uuid rather than new ones, as when bringing
+    /// back a deleted trade, so links and other devices still find it.
+    pub fn restore(&self, conn: &Connection) -> Result<usize> {
+        self.insert(conn)?;
+        conn.execute(
+            "UPDATE option_trades SET id = ?1, uuid = coalesce(?3, uuid) WHERE id = ?2",
+            params![self.id, conn.last_insert_rowid(), self.uuid],
+        )
+    }
+
+    /// Point close, roll and collateral links at `new_id` instead of `old_id`, and give
+    /// `new_id` the tags and mark of `old_id`. Returns the number of links changed.
+    pub fn replace_links(conn: &Connection, old_id: i32, new_id: i32) -> Result<usize> {
+        let closes = conn.execute(
+            "UPDATE option_trades SET closes_trade_id = ?2 WHERE closes_trade_id = ?1",
+            params![old_id, new_id],
+        )?;
+        let rolls = conn.execute(
+            "UPDATE option_trades SET rolled_from_id = ?2 WHERE rolled_from_id = ?1",
+            params![old_id, new_id],
+        )?;
+        let covers = conn.execute(
+            "UPDATE option_trades SET covered_by_id = ?2 WHERE covered_by_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO trade_tags (trade_id, tag)
+            SELECT ?2, tag FROM trade_tags WHERE trade_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO marks (trade_id, date, price_cents)
+            SELECT ?2, date, price_cents FROM marks WHERE trade_id = ?1",
+            params![old_id, new_id],
+        )?;
+        Ok(closes + rolls + covers)
+    }
+
+    /// The tags of every tagged trade by trade id, each list sorted.
+    pub fn tags_by_trade(conn: &Connection) -> Result<HashMap<i32, Vec<String>>> {
+        let mut stmt =
+            conn.prepare("SELECT trade_id, tag FROM trade_tags ORDER BY trade_id, tag")?;
+        let mut tags: HashMap<i32, Vec<String>> = HashMap::new();
+        for row in stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+            let (id, tag) = row?;
+            tags.entry(id).or_default().push(tag);
+        }
+        Ok(tags)
+    }
+
+    /// Replace the tags of trade `id` with `tags`.
+    pub fn set_tags(conn: &Connection, id: i32, tags: &[String]) -> Result<()> {
+        conn.execute("DELETE FROM trade_tags WHERE trade_id = ?1", params![id])?;
+        for tag in tags {
+            conn.execute(
+                "INSERT OR IGNORE INTO trade_tags (trade_id, tag) VALUES (?1, ?2)",
+                params![id, tag],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The tags in a comma-separated list as typed in the trade forms, e.g. "earnings,
+/// High IV": trimmed, lowercased, sorted and without blanks or repeats.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = input
+        .split(',')
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Shares bought or sold outright, rather than through assignment or exercise.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StockTrade {
+    pub id: Option<i32>,
+    pub symbol: String,
+    /// Campaign name, stored as a reference to the campaign's row.
+    pub campaign: String,
+    pub date: Date,
+    /// Shares bought, negative when sold.
+    pub shares: i32,
+    /// Price per share, stored as cents.
+    pub price: f64,
+    /// Cash received after fees, negative when paid; stored as cents.
+    pub amount: f64,
+}
+
+impl StockTrade {
+    /// Insert the trade unless an identical one is already recorded.
+    ///
+    /// Returns `false` when the trade was a duplicate and nothing was written.
+    pub fn insert_or_ignore(&self, conn: &Connection) -> Result<bool> {
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO stock_trades (symbol, campaign_id, date, shares, price_cents, amount_cents)
+            VALUES (?1, (SELECT id FROM campaigns WHERE name = ?2), ?3, ?4, ?5, ?6)",
+            params![
+                self.symbol,
+                self.campaign,
+                self.date.to_string(),
+                self.shares,
+                to_cents(self.price),
+                to_cents(self.amount),
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn get_all(conn: &Connection) -> Result<Vec<StockTrade>> {
         let mut stmt = conn.prepare(
-            "SELECT id, symbol, campaign, action, strike, delta, expiration_date, date_of_action, number_of_shares, credit FROM option_trades"
+            "SELECT s.id, s.symbol, c.name, s.date, s.shares, s.price_cents, s.amount_cents
+            FROM stock_trades s JOIN campaigns c ON c.id = s.campaign_id
+            ORDER BY s.date, s.id",
         )?;
-        let trade_iter = stmt.query_map([], |row| {
-            Ok(OptionTrade {
+        let iter = stmt.query_map([], |row| {
+            Ok(StockTrade {
                 id: row.get(0)?,
                 symbol: row.get(1)?,
                 campaign: row.get(2)?,
-                action: match row.get::<_, String>(3)?.as_str() {
-                    "BuyPut" => Action::BuyPut,
-                    "SellPut" => Action::SellPut,
-                    "BuyCall" => Action::BuyCall,
-                    "SellCall" => Action::SellCall,
-                    "Exercised" => Action::Exercised,
-                    "Assigned" => Action::Assigned,
-                    _ => Action::SellPut, // fallback
-                },
-                strike: row.get(4)?,
-                delta: row.get(5)?,
-                expiration_date: {
-                    let s: String = row.get(6)?;
-                    Date::parse(&s, &date_fmt).unwrap()
-                },
-                date_of_action: {
-                    let s: String = row.get(7)?;
-                    Date::parse(&s, &date_fmt).unwrap()
-                },
-                number_of_shares: row.get(8)?,
-                credit: row.get(9)?,
+                date: date_column(row, 3)?,
+                shares: row.get(4)?,
+                price: to_dollars(row.get(5)?),
+                amount: to_dollars(row.get(6)?),
             })
         })?;
-        Ok(trade_iter.filter_map(Result::ok).collect())
+        iter.collect()
     }
+}
 
-    pub fn update(&self, conn: &Connection) -> Result<usize> {
+/// What a [`CashEvent`] paid.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CashKind {
+    Dividend,
+    Interest,
+    /// Money added to the account.
+    Deposit,
+    /// Money taken out of the account, recorded as a negative amount.
+    Withdrawal,
+}
+
+impl CashKind {
+    /// The name stored in the database's `kind` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CashKind::Dividend => "Dividend",
+            CashKind::Interest => "Interest",
+            CashKind::Deposit => "Deposit",
+            CashKind::Withdrawal => "Withdrawal",
+        }
+    }
+
+    /// Whether the event is income earned, rather than money moved in or out.
+    pub fn is_income(&self) -> bool {
+        matches!(self, CashKind::Dividend | CashKind::Interest)
+    }
+}
+
+impl std::str::FromStr for CashKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "Dividend" => Ok(CashKind::Dividend),
+            "Interest" => Ok(CashKind::Interest),
+            "Deposit" => Ok(CashKind::Deposit),
+            "Withdrawal" => Ok(CashKind::Withdrawal),
+            _ => Err(format!("Unknown cash event kind '{s}'")),
+        }
+    }
+}
+
+/// A dividend or interest payment read from a broker statement, or a deposit or
+/// withdrawal recorded by hand.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CashEvent {
+    pub id: Option<i32>,
+    /// `None` for interest paid on the account's cash.
+    pub symbol: Option<String>,
+    /// Campaign name, or `None` when the payment belongs to the account as a whole.
+    pub campaign: Option<String>,
+    pub date: Date,
+    pub kind: CashKind,
+    /// Cash received, negative when paid out; stored as cents.
+    pub amount: f64,
+}
+
+impl CashEvent {
+    /// Insert the event, even when an identical one is already recorded, as two deposits
+    /// of the same amount on one day are.
+    pub fn insert(&self, conn: &Connection) -> Result<usize> {
         conn.execute(
-            "UPDATE option_trades SET symbol = ?1, campaign = ?2, action = ?3, strike = ?4, delta = ?5, expiration_date = ?6, date_of_action = ?7, number_of_shares = ?8, credit = ?9 WHERE id = ?10",
+            "INSERT INTO cash_events (symbol, campaign_id, date, kind, amount_cents)
+            VALUES (?1, (SELECT id FROM campaigns WHERE name = ?2), ?3, ?4, ?5)",
             params![
                 self.symbol,
                 self.campaign,
-                format!("{:?}", self.action),
-                self.strike,
-                self.delta,
-                self.expiration_date.to_string(),
-                self.date_of_action.to_string(),
-                self.number_of_shares,
-                self.credit,
-                self.id,
+                self.date.to_string(),
+                self.kind.as_str(),
+                to_cents(self.amount),
             ],
         )
     }
 
-    pub fn exists_in_db(&self, conn: &Connection) -> bool {
-        let mut stmt = conn
-            .prepare(
-                "SELECT 1 FROM option_trades WHERE \
-                symbol = ?1 AND \
-                campaign = ?2 AND \
-                action = ?3 AND \
-                strike = ?4 AND \
-                delta = ?5 AND \
-                expiration_date = ?6 AND \
-                date_of_action = ?7 AND \
-                number_of_shares = ?8 AND \
-                credit = ?9 LIMIT 1",
-            )
-            .unwrap();
-        stmt.exists(params![
-            self.symbol,
-            self.campaign,
-            format!("{:?}", self.action),
-            self.strike,
-            self.delta,
-            self.expiration_date.to_string(),
-            self.date_of_action.to_string(),
-            self.number_of_shares,
-            self.credit,
-        ])
-        .unwrap_or(false)
+    /// Insert the payment unless an identical one is already recorded.
+    ///
+    /// Returns `false` when it was a duplicate and nothing was written.
+    pub fn insert_or_ignore(&self, conn: &Connection) -> Result<bool> {
+        // Not a UNIQUE constraint: symbol and campaign may be NULL, which never conflict
+        let inserted = conn.execute(
+            "INSERT INTO cash_events (symbol, campaign_id, date, kind, amount_cents)
+            SELECT ?1, (SELECT id FROM campaigns WHERE name = ?2), ?3, ?4, ?5
+            WHERE NOT EXISTS (
+                SELECT 1 FROM cash_events WHERE symbol IS ?1
+                    AND campaign_id IS (SELECT id FROM campaigns WHERE name = ?2)
+                    AND date = ?3 AND kind = ?4 AND amount_cents = ?5
+            )",
+            params![
+                self.symbol,
+                self.campaign,
+                self.date.to_string(),
+                self.kind.as_str(),
+                to_cents(self.amount),
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn get_all(conn: &Connection) -> Result<Vec<CashEvent>> {
+        let mut stmt = conn.prepare(
+            "SELECT e.id, e.symbol, c.name, e.date, e.kind, e.amount_cents
+            FROM cash_events e LEFT JOIN campaigns c ON c.id = e.campaign_id
+            ORDER BY e.date, e.id",
+        )?;
+        let iter = stmt.query_map([], |row| {
+            let kind: String = row.get(4)?;
+            Ok(CashEvent {
+                id: row.get(0)?,
+                symbol: row.get(1)?,
+                campaign: row.get(2)?,
+                date: date_column(row, 3)?,
+                kind: kind.parse().map_err(|e: String| {
+                    rusqlite::Error::FromSqlConversionFailure(4, Type::Text, e.into())
+                })?,
+                amount: to_dollars(row.get(5)?),
+            })
+        })?;
+        iter.collect()
+    }
+}
+
+/// How often a [`PremiumGoal`] is meant to be met.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoalPeriod {
+    Week,
+    Month,
+}
+
+impl GoalPeriod {
+    /// The name stored in the database's `goal_period` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GoalPeriod::Week => "Week",
+            GoalPeriod::Month => "Month",
+        }
+    }
+}
+
+impl std::str::FromStr for GoalPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "week" | "wk" | "weekly" => Ok(GoalPeriod::Week),
+            "month" | "mo" | "monthly" => Ok(GoalPeriod::Month),
+            _ => Err(format!("Unknown goal period '{s}', expected week or month")),
+        }
+    }
+}
+
+/// Net premium a campaign aims to collect each week or month, written like `$500/month`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PremiumGoal {
+    /// Stored as cents.
+    pub amount: f64,
+    pub period: GoalPeriod,
+}
+
+impl PremiumGoal {
+    /// The goal stored in a campaign's `goal_cents` and `goal_period` columns.
+    pub fn from_columns(
+        cents: Option<i64>,
+        period: Option<String>,
+    ) -> std::result::Result<Option<Self>, String> {
+        match (cents, period) {
+            (Some(cents), Some(period)) => Ok(Some(PremiumGoal {
+                amount: to_dollars(cents),
+                period: period.parse()?,
+            })),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl std::str::FromStr for PremiumGoal {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid =
+            || format!("Invalid goal '{s}', expected an amount per period like $500/month");
+        let (amount, period) = s.split_once('/').ok_or_else(invalid)?;
+        let amount: f64 = amount
+            .trim()
+            .trim_start_matches('$')
+            .replace(',', "")
+            .parse()
+            .map_err(|_| invalid())?;
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(invalid());
+        }
+        Ok(PremiumGoal {
+            amount,
+            period: period.parse()?,
+        })
+    }
+}
+
+impl std::fmt::Display for PremiumGoal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "${:.2}/{}",
+            self.amount,
+            self.period.as_str().to_ascii_lowercase()
+        )
     }
 }
 
@@ -135,42 +831,273 @@ pub struct Campaign {
     pub name: String,
     pub symbol: String,
     pub target_exit_price: Option<f64>,
+    /// Hidden from the campaign list unless archived campaigns are shown.
+    pub archived: bool,
+    pub goal: Option<PremiumGoal>,
 }
 
 impl Campaign {
-    pub fn get_all(conn: &Connection) -> Vec<Campaign> {
-        let mut stmt = conn
-            .prepare(
-                "SELECT name, symbol, target_exit_price FROM campaigns ORDER BY created_at DESC",
-            )
-            .unwrap();
-        let iter = stmt
-            .query_map([], |row| {
-                Ok(Campaign {
-                    name: row.get(0)?,
-                    symbol: row.get(1)?,
-                    target_exit_price: row.get(2)?,
-                })
+    pub fn get_all(conn: &Connection) -> Result<Vec<Campaign>> {
+        let mut stmt = conn.prepare(
+            "SELECT name, symbol, target_exit_price_cents, archived, goal_cents, goal_period
+            FROM campaigns ORDER BY created_at DESC",
+        )?;
+        let iter = stmt.query_map([], |row| {
+            Ok(Campaign {
+                name: row.get(0)?,
+                symbol: row.get(1)?,
+                target_exit_price: row.get::<_, Option<i64>>(2)?.map(to_dollars),
+                archived: row.get(3)?,
+                goal: PremiumGoal::from_columns(row.get(4)?, row.get(5)?).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(5, Type::Text, e.into())
+                })?,
             })
-            .unwrap();
-        iter.filter_map(Result::ok).collect()
+        })?;
+        iter.collect()
     }
+
+    /// Create the campaign, dated today, unless one with the same name already exists.
+    /// Returns the number of campaigns created.
     pub fn insert(
         conn: &Connection,
         name: &str,
         symbol: &str,
         target_exit_price: Option<f64>,
-    ) -> Option<Campaign> {
-        use time::OffsetDateTime;
-        let now = OffsetDateTime::now_local().unwrap().date().to_string();
-        let _ = conn.execute(
-            "INSERT INTO campaigns (name, symbol, created_at, target_exit_price) VALUES (?1, ?2, ?3, ?4)",
-            params![name, symbol, now, target_exit_price],
-        );
-        Some(Campaign {
-            name: name.to_string(),
-            symbol: symbol.to_string(),
-            target_exit_price,
-        })
+    ) -> Result<usize> {
+        conn.execute(
+            "INSERT INTO campaigns (name, symbol, created_at, target_exit_price_cents)
+            VALUES (?1, ?2, ?3, ?4) ON CONFLICT(name) DO NOTHING",
+            params![
+                name,
+                symbol,
+                crate::logic::today().to_string(),
+                target_exit_price.map(to_cents)
+            ],
+        )
+    }
+
+    pub fn update(&self, conn: &Connection) -> Result<usize> {
+        conn.execute(
+            "UPDATE campaigns SET symbol = ?1, target_exit_price_cents = ?2 WHERE name = ?3",
+            params![self.symbol, self.target_exit_price.map(to_cents), self.name],
+        )
+    }
+
+    pub fn delete(conn: &Connection, name: &str) -> Result<usize> {
+        conn.execute("DELETE FROM campaigns WHERE name = ?1", params![name])
+    }
+
+    pub fn set_archived(conn: &Connection, name: &str, archived: bool) -> Result<usize> {
+        conn.execute(
+            "UPDATE campaigns SET archived = ?1 WHERE name = ?2",
+            params![archived, name],
+        )
+    }
+
+    /// Set or, with `None`, clear the campaign's premium goal.
+    pub fn set_goal(conn: &Connection, name: &str, goal: Option<&PremiumGoal>) -> Result<usize> {
+        conn.execute(
+            "UPDATE campaigns SET goal_cents = ?1, goal_period = ?2 WHERE name = ?3",
+            params![
+                goal.map(|g| to_cents(g.amount)),
+                goal.map(|g| g.period.as_str()),
+                name
+            ],
+        )
+    }
+
+    /// Move the option trades, share trades and cash events of campaign `from` into
+    /// `into`. Trades `into` already has an identical copy of are deleted instead, with
+    /// links to them pointed at the copy. Returns the number of option trades moved and
+    /// the number dropped as duplicates. Both campaigns must exist; run it in a
+    /// transaction.
+    pub fn move_records(conn: &Connection, from: &str, into: &str) -> Result<(usize, usize)> {
+        let duplicates: Vec<(i32, i32)> = conn
+            .prepare(
+                "SELECT s.id, MIN(t.id) FROM option_trades s
+                JOIN option_trades t ON t.campaign_id = (SELECT id FROM campaigns WHERE name = ?2)
+                    AND t.symbol = s.symbol AND t.action = s.action
+                    AND t.strike_cents = s.strike_cents AND t.delta = s.delta
+                    AND t.expiration_date = s.expiration_date
+                    AND t.date_of_action = s.date_of_action
+                    AND t.number_of_shares = s.number_of_shares
+                    AND t.premium_cents = s.premium_cents
+                WHERE s.campaign_id = (SELECT id FROM campaigns WHERE name = ?1)
+                GROUP BY s.id",
+            )?
+            .query_map(params![from, into], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+        for (id, keep) in &duplicates {
+            OptionTrade::replace_links(conn, *id, *keep)?;
+            OptionTrade::delete(conn, *id)?;
+        }
+        conn.execute(
+            "DELETE FROM stock_trades AS s
+            WHERE campaign_id = (SELECT id FROM campaigns WHERE name = ?1)
+                AND EXISTS (SELECT 1 FROM stock_trades t
+                    WHERE t.campaign_id = (SELECT id FROM campaigns WHERE name = ?2)
+                        AND t.symbol = s.symbol AND t.date = s.date AND t.shares = s.shares
+                        AND t.price_cents = s.price_cents AND t.amount_cents = s.amount_cents)",
+            params![from, into],
+        )?;
+
+        let mut moved = 0;
+        for table in ["option_trades", "stock_trades", "cash_events"] {
+            let rows = conn.execute(
+                &format!(
+                    "UPDATE {table} SET campaign_id = (SELECT id FROM campaigns WHERE name = ?2)
+                    WHERE campaign_id = (SELECT id FROM campaigns WHERE name = ?1)"
+                ),
+                params![from, into],
+            )?;
+            if table == "option_trades" {
+                moved = rows;
+            }
+        }
+        Ok((moved, duplicates.len()))
+    }
+
+    /// Delete the campaign along with its option trades, share trades and cash events,
+    /// returning the number of option trades removed. Close, roll and collateral links
+    /// from trades in other campaigns are cleared. Run it in a transaction.
+    pub fn delete_with_trades(conn: &Connection, name: &str) -> Result<usize> {
+        let campaign_id = "(SELECT id FROM campaigns WHERE name = ?1)";
+        for link in ["closes_trade_id", "rolled_from_id", "covered_by_id"] {
+            conn.execute(
+                &format!(
+                    "UPDATE option_trades SET {link} = NULL
+                    WHERE {link} IN (SELECT id FROM option_trades WHERE campaign_id = {campaign_id})"
+                ),
+                params![name],
+            )?;
+        }
+        let trades = conn.execute(
+            &format!("DELETE FROM option_trades WHERE campaign_id = {campaign_id}"),
+            params![name],
+        )?;
+        for table in ["stock_trades", "cash_events"] {
+            conn.execute(
+                &format!("DELETE FROM {table} WHERE campaign_id = {campaign_id}"),
+                params![name],
+            )?;
+        }
+        Self::delete(conn, name)?;
+        Ok(trades)
+    }
+}
+
+/// Point-in-time account metrics used to build the historical equity curve, in cents.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub date: Date,
+    pub realized_pl: i64,
+    pub open_collateral: i64,
+    pub cash: i64,
+    pub share_value: i64,
+}
+
+impl Snapshot {
+    /// Record the snapshot, replacing any earlier snapshot taken on the same date.
+    pub fn insert(&self, conn: &Connection) -> Result<usize> {
+        conn.execute(
+            "INSERT INTO snapshots (date, realized_pl_cents, open_collateral_cents, cash_cents, share_value_cents)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(date) DO UPDATE SET realized_pl_cents = ?2, open_collateral_cents = ?3, cash_cents = ?4, share_value_cents = ?5",
+            params![
+                self.date.to_string(),
+                self.realized_pl,
+                self.open_collateral,
+                self.cash,
+                self.share_value,
+            ],
+        )
+    }
+
+    pub fn get_all(conn: &Connection) -> Result<Vec<Snapshot>> {
+        let mut stmt = conn.prepare(
+            "SELECT date, realized_pl_cents, open_collateral_cents, cash_cents, share_value_cents FROM snapshots ORDER BY date",
+        )?;
+        let iter = stmt.query_map([], |row| {
+            Ok(Snapshot {
+                date: date_column(row, 0)?,
+                realized_pl: row.get(1)?,
+                open_collateral: row.get(2)?,
+                cash: row.get(3)?,
+                share_value: row.get(4)?,
+            })
+        })?;
+        iter.collect()
+    }
+}
+
+/// An announced dividend, used to warn about early assignment of short calls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dividend {
+    pub symbol: String,
+    pub ex_date: Date,
+    /// Per-share amount, when known.
+    pub amount: Option<f64>,
+}
+
+impl Dividend {
+    /// Record the dividend. A date already on file keeps its amount unless a new one is given.
+    pub fn insert(&self, conn: &Connection) -> Result<usize> {
+        conn.execute(
+            "INSERT INTO dividends (symbol, ex_date, amount_cents) VALUES (?1, ?2, ?3)
+            ON CONFLICT(symbol, ex_date) DO UPDATE SET
+                amount_cents = coalesce(excluded.amount_cents, amount_cents)",
+            params![
+                self.symbol,
+                self.ex_date.to_string(),
+                self.amount.map(to_cents)
+            ],
+        )
+    }
+
+    pub fn get_all(conn: &Connection) -> Result<Vec<Dividend>> {
+        let mut stmt =
+            conn.prepare("SELECT symbol, ex_date, amount_cents FROM dividends ORDER BY ex_date")?;
+        let iter = stmt.query_map([], |row| {
+            Ok(Dividend {
+                symbol: row.get(0)?,
+                ex_date: date_column(row, 1)?,
+                amount: row.get::<_, Option<i64>>(2)?.map(to_dollars),
+            })
+        })?;
+        iter.collect()
+    }
+}
+
+/// The latest price entered for an open option, used to value it without closing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mark {
+    pub trade_id: i32,
+    pub date: Date,
+    /// Per-share option price.
+    pub price: f64,
+}
+
+impl Mark {
+    /// Record the mark, replacing the trade's earlier one.
+    pub fn insert(&self, conn: &Connection) -> Result<usize> {
+        conn.execute(
+            "INSERT INTO marks (trade_id, date, price_cents) VALUES (?1, ?2, ?3)
+            ON CONFLICT(trade_id) DO UPDATE SET
+                date = excluded.date, price_cents = excluded.price_cents",
+            params![self.trade_id, self.date.to_string(), to_cents(self.price)],
+        )
+    }
+
+    /// Every trade's mark by trade id.
+    pub fn by_trade(conn: &Connection) -> Result<HashMap<i32, Mark>> {
+        let mut stmt = conn.prepare("SELECT trade_id, date, price_cents FROM marks")?;
+        let iter = stmt.query_map([], |row| {
+            Ok(Mark {
+                trade_id: row.get(0)?,
+                date: date_column(row, 1)?,
+                price: to_dollars(row.get(2)?),
+            })
+        })?;
+        iter.map(|mark| mark.map(|m| (m.trade_id, m))).collect()
     }
 }
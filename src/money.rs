@@ -0,0 +1,46 @@
+//! Conversions between dollar amounts and the whole cents they are stored as.
+
+/// Round a dollar amount to whole cents. Money is stored and summed in cents so totals
+/// don't accumulate floating-point error.
+pub fn to_cents(dollars: f64) -> i64 {
+    (dollars * 100.0).round() as i64
+}
+
+/// Dollar value of `cents`, for display and per-share arithmetic.
+pub fn to_dollars(cents: i64) -> f64 {
+    cents as f64 / 100.0
+}
+
+/// Per-share amount of a `cents` total over `shares`, rounded to a millionth of a dollar
+/// so prices like 0.1748 read back exactly as entered.
+pub fn per_share(cents: i64, shares: i32) -> f64 {
+    if shares == 0 {
+        return 0.0;
+    }
+    (cents as f64 * 10_000.0 / shares as f64).round() / 1_000_000.0
+}
+
+/// Format cents as dollars, e.g. `$1234.56` or `-$12.50`.
+pub fn format_cents(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    format!("{sign}${}.{:02}", cents / 100, cents % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cents_conversions() {
+        assert_eq!(to_cents(0.1748 * 1500.0), 26220);
+        assert_eq!(to_cents(-0.0548 * 1500.0), -8220);
+        assert_eq!((0..10).map(|_| to_cents(0.1)).sum::<i64>(), 100);
+        assert_eq!(format_cents(123456), "$1234.56");
+        assert_eq!(format_cents(-1250), "-$12.50");
+        assert_eq!(format_cents(-5), "-$0.05");
+        assert_eq!(to_dollars(-1250), -12.5);
+        assert_eq!(per_share(26220, 1500), 0.1748);
+        assert_eq!(per_share(-8220, 1500), -0.0548);
+    }
+}
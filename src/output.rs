@@ -0,0 +1,118 @@
+use crate::error::Result;
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+
+/// Output format for CLI commands that print tabular data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+/// Rows of values with named columns, rendered as an aligned table, CSV, or JSON.
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push_row(&mut self, row: Vec<Value>) {
+        self.rows.push(row);
+    }
+
+    pub fn render(&self, format: OutputFormat) -> Result<String> {
+        match format {
+            OutputFormat::Table => Ok(self.render_table()),
+            OutputFormat::Csv => self.render_csv(),
+            OutputFormat::Json => self.render_json(),
+        }
+    }
+
+    pub fn print(&self, format: OutputFormat) -> Result<()> {
+        let rendered = self.render(format)?;
+        print!("{rendered}");
+        if !rendered.ends_with('\n') {
+            println!();
+        }
+        Ok(())
+    }
+
+    fn render_table(&self) -> String {
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(cell_text).collect())
+            .collect();
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let format_row = |row: &[String]| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut out = format_row(&self.headers);
+        out.push('\n');
+        let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        out.push_str(&format_row(&rule));
+        out.push('\n');
+        for row in &cells {
+            out.push_str(&format_row(row));
+            out.push('\n');
+        }
+        out
+    }
+
+    fn render_csv(&self) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(&self.headers)?;
+        for row in &self.rows {
+            writer.write_record(row.iter().map(cell_text))?;
+        }
+        let bytes = writer.into_inner().map_err(|e| e.into_error())?;
+        Ok(String::from_utf8(bytes).map_err(|e| e.to_string())?)
+    }
+
+    fn render_json(&self) -> Result<String> {
+        let objects: Vec<Value> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let map: Map<String, Value> = self
+                    .headers
+                    .iter()
+                    .cloned()
+                    .zip(row.iter().cloned())
+                    .collect();
+                Value::Object(map)
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&objects)?)
+    }
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,319 @@
+//! Market data (prices, option chains, dividends and price history) from Yahoo Finance
+//! or Finnhub.
+
+use crate::config::{Config, QuoteSource};
+use crate::error::Result;
+use crate::logic::black_scholes_delta;
+use crate::models::{Dividend, OptionKind};
+use serde_json::Value;
+use std::time::Duration;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime};
+
+/// How long a quote request may take before the dashboard gives up on it.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many upcoming expirations an option chain covers.
+const CHAIN_EXPIRATIONS: usize = 3;
+
+/// Yahoo rejects requests without a browser-like user agent.
+const YAHOO_HEADERS: &[(&str, &str)] = &[("User-Agent", "Mozilla/5.0")];
+
+/// One contract of an option chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainQuote {
+    pub kind: OptionKind,
+    pub expiration_date: Date,
+    pub strike: f64,
+    pub bid: f64,
+    pub ask: f64,
+    pub delta: Option<f64>,
+    /// Annualized implied volatility, 0.45 for 45%.
+    pub implied_volatility: Option<f64>,
+}
+
+impl ChainQuote {
+    /// Midpoint of the bid/ask spread, rounded to the cent.
+    pub fn mid(&self) -> f64 {
+        ((self.bid + self.ask) * 50.0).round() / 100.0
+    }
+}
+
+/// A source of current prices for underlying symbols. Shared with background tasks, so
+/// providers must be thread-safe.
+pub trait QuoteProvider: Send + Sync {
+    /// Last traded price of `symbol` in dollars.
+    fn last_price(&self, symbol: &str) -> Result<f64>;
+
+    /// Puts and calls for the next few expirations of `symbol`, ordered by expiration.
+    fn option_chain(&self, symbol: &str) -> Result<Vec<ChainQuote>>;
+
+    /// The next ex-dividend date of `symbol`, if one has been announced.
+    fn next_dividend(&self, symbol: &str) -> Result<Option<Dividend>>;
+
+    /// Daily closing prices of `symbol` from `from` through `to`, oldest first.
+    fn daily_closes(&self, symbol: &str, from: Date, to: Date) -> Result<Vec<(Date, f64)>>;
+}
+
+/// Yahoo Finance's public endpoints; needs no API key.
+pub struct Yahoo;
+
+impl QuoteProvider for Yahoo {
+    fn last_price(&self, symbol: &str) -> Result<f64> {
+        let url = format!("https://query1.finance.yahoo.com/v8/finance/chart/{symbol}");
+        let body = get_json(&url, YAHOO_HEADERS)?;
+        body.pointer("/chart/result/0/meta/regularMarketPrice")
+            .and_then(Value::as_f64)
+            .ok_or_else(|| format!("No Yahoo Finance quote for {symbol}").into())
+    }
+
+    fn option_chain(&self, symbol: &str) -> Result<Vec<ChainQuote>> {
+        let url = format!("https://query2.finance.yahoo.com/v7/finance/options/{symbol}");
+        let first = get_json(&url, YAHOO_HEADERS)?;
+        let result = first
+            .pointer("/optionChain/result/0")
+            .ok_or_else(|| format!("No Yahoo Finance option chain for {symbol}"))?;
+        // Yahoo has implied volatility but no greeks, so delta is derived from it
+        let spot = result
+            .pointer("/quote/regularMarketPrice")
+            .and_then(Value::as_f64);
+        let expirations: Vec<i64> = result["expirationDates"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_i64)
+            .take(CHAIN_EXPIRATIONS)
+            .collect();
+
+        let today = OffsetDateTime::now_utc().date();
+        let mut chain = Vec::new();
+        for (i, &expiration) in expirations.iter().enumerate() {
+            // The first page already holds the nearest expiration
+            let page = if i == 0 {
+                first.clone()
+            } else {
+                get_json(&format!("{url}?date={expiration}"), YAHOO_HEADERS)?
+            };
+            let Some(options) = page.pointer("/optionChain/result/0/options/0") else {
+                continue;
+            };
+            let expiration_date = OffsetDateTime::from_unix_timestamp(expiration)?.date();
+            let years = (expiration_date - today).whole_days().max(1) as f64 / 365.0;
+            for (kind, key) in [(OptionKind::Put, "puts"), (OptionKind::Call, "calls")] {
+                for contract in options[key].as_array().into_iter().flatten() {
+                    let iv = contract["impliedVolatility"]
+                        .as_f64()
+                        .filter(|iv| *iv > 0.0);
+                    let delta = match (spot, iv) {
+                        (Some(spot), Some(iv)) => {
+                            let strike = contract["strike"].as_f64().unwrap_or(spot);
+                            Some(black_scholes_delta(kind, spot, strike, years, iv))
+                        }
+                        _ => None,
+                    };
+                    chain.extend(chain_quote(kind, expiration_date, contract, delta, iv));
+                }
+            }
+        }
+        Ok(chain)
+    }
+
+    fn next_dividend(&self, symbol: &str) -> Result<Option<Dividend>> {
+        let url = format!(
+            "https://query2.finance.yahoo.com/v10/finance/quoteSummary/{symbol}?modules=calendarEvents"
+        );
+        let body = get_json(&url, YAHOO_HEADERS)?;
+        // Yahoo keeps showing the last ex-date until the next one is announced
+        let Some(timestamp) = body
+            .pointer("/quoteSummary/result/0/calendarEvents/exDividendDate/raw")
+            .and_then(Value::as_i64)
+        else {
+            return Ok(None);
+        };
+        let ex_date = OffsetDateTime::from_unix_timestamp(timestamp)?.date();
+        Ok(
+            (ex_date >= OffsetDateTime::now_utc().date()).then(|| Dividend {
+                symbol: symbol.to_string(),
+                ex_date,
+                amount: None,
+            }),
+        )
+    }
+
+    fn daily_closes(&self, symbol: &str, from: Date, to: Date) -> Result<Vec<(Date, f64)>> {
+        let url = format!(
+            "https://query1.finance.yahoo.com/v8/finance/chart/{symbol}?period1={}&period2={}&interval=1d",
+            unix_time(from),
+            unix_time(to.next_day().unwrap_or(to)),
+        );
+        let body = get_json(&url, YAHOO_HEADERS)?;
+        let result = body
+            .pointer("/chart/result/0")
+            .ok_or_else(|| format!("No Yahoo Finance history for {symbol}"))?;
+        candles(
+            &result["timestamp"],
+            &result
+                .pointer("/indicators/quote/0/close")
+                .cloned()
+                .unwrap_or_default(),
+        )
+    }
+}
+
+/// Finnhub's quote API, using `api_keys.finnhub` from the config file.
+pub struct Finnhub {
+    api_key: String,
+}
+
+impl Finnhub {
+    fn get(&self, url: &str) -> Result<Value> {
+        get_json(url, &[("X-Finnhub-Token", &self.api_key)])
+    }
+}
+
+impl QuoteProvider for Finnhub {
+    fn last_price(&self, symbol: &str) -> Result<f64> {
+        let body = self.get(&format!("https://finnhub.io/api/v1/quote?symbol={symbol}"))?;
+        // Unknown symbols come back as all zeroes rather than an error
+        match body.get("c").and_then(Value::as_f64) {
+            Some(price) if price > 0.0 => Ok(price),
+            _ => Err(format!("No Finnhub quote for {symbol}").into()),
+        }
+    }
+
+    fn option_chain(&self, symbol: &str) -> Result<Vec<ChainQuote>> {
+        let body = self.get(&format!(
+            "https://finnhub.io/api/v1/stock/option-chain?symbol={symbol}"
+        ))?;
+        let iso = format_description!("[year]-[month]-[day]");
+        let today = OffsetDateTime::now_utc().date();
+        let mut chain = Vec::new();
+        let expirations = body["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|e| {
+                let date = Date::parse(e["expirationDate"].as_str()?, iso).ok()?;
+                (date >= today).then_some((date, e))
+            })
+            .take(CHAIN_EXPIRATIONS);
+        for (expiration_date, expiration) in expirations {
+            for (kind, key) in [(OptionKind::Put, "PUT"), (OptionKind::Call, "CALL")] {
+                for contract in expiration["options"][key].as_array().into_iter().flatten() {
+                    let delta = contract["delta"].as_f64();
+                    // Finnhub quotes implied volatility in percent
+                    let iv = contract["impliedVolatility"]
+                        .as_f64()
+                        .filter(|iv| *iv > 0.0)
+                        .map(|iv| iv / 100.0);
+                    chain.extend(chain_quote(kind, expiration_date, contract, delta, iv));
+                }
+            }
+        }
+        Ok(chain)
+    }
+
+    fn next_dividend(&self, symbol: &str) -> Result<Option<Dividend>> {
+        let today = OffsetDateTime::now_utc().date();
+        let body = self.get(&format!(
+            "https://finnhub.io/api/v1/stock/dividend?symbol={symbol}&from={today}&to={}",
+            today + time::Duration::days(120)
+        ))?;
+        let iso = format_description!("[year]-[month]-[day]");
+        Ok(body
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|d| {
+                Some(Dividend {
+                    symbol: symbol.to_string(),
+                    // Finnhub's `date` is the ex-dividend date
+                    ex_date: Date::parse(d["date"].as_str()?, iso).ok()?,
+                    amount: d["amount"].as_f64(),
+                })
+            })
+            .filter(|d| d.ex_date >= today)
+            .min_by_key(|d| d.ex_date))
+    }
+
+    fn daily_closes(&self, symbol: &str, from: Date, to: Date) -> Result<Vec<(Date, f64)>> {
+        let body = self.get(&format!(
+            "https://finnhub.io/api/v1/stock/candle?symbol={symbol}&resolution=D&from={}&to={}",
+            unix_time(from),
+            unix_time(to.next_day().unwrap_or(to)),
+        ))?;
+        // "no_data" rather than an error when the range has no trading days
+        if body["s"] == "no_data" {
+            return Ok(Vec::new());
+        }
+        candles(&body["t"], &body["c"])
+    }
+}
+
+/// Seconds since the epoch at midnight UTC on `date`.
+fn unix_time(date: Date) -> i64 {
+    date.midnight().assume_utc().unix_timestamp()
+}
+
+/// Pair parallel arrays of timestamps and closes, skipping days without a close.
+fn candles(timestamps: &Value, closes: &Value) -> Result<Vec<(Date, f64)>> {
+    let mut days = Vec::new();
+    let timestamps = timestamps.as_array().into_iter().flatten();
+    let closes = closes.as_array().into_iter().flatten();
+    for (timestamp, close) in timestamps.zip(closes) {
+        if let (Some(timestamp), Some(close)) = (timestamp.as_i64(), close.as_f64()) {
+            days.push((
+                OffsetDateTime::from_unix_timestamp(timestamp)?.date(),
+                close,
+            ));
+        }
+    }
+    Ok(days)
+}
+
+/// Build a chain row from a provider's contract object, skipping ones without a strike.
+fn chain_quote(
+    kind: OptionKind,
+    expiration_date: Date,
+    contract: &Value,
+    delta: Option<f64>,
+    implied_volatility: Option<f64>,
+) -> Option<ChainQuote> {
+    Some(ChainQuote {
+        kind,
+        expiration_date,
+        strike: contract["strike"].as_f64()?,
+        bid: contract["bid"].as_f64().unwrap_or(0.0),
+        ask: contract["ask"].as_f64().unwrap_or(0.0),
+        delta,
+        implied_volatility,
+    })
+}
+
+fn get_json(url: &str, headers: &[(&str, &str)]) -> Result<Value> {
+    let mut request = ureq::get(url)
+        .config()
+        .timeout_global(Some(TIMEOUT))
+        .build();
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    Ok(request.call()?.body_mut().read_json()?)
+}
+
+/// The provider selected by `quote_provider` in the config, or `None` when quotes are off.
+pub fn from_config(config: &Config) -> Result<Option<Box<dyn QuoteProvider>>> {
+    Ok(match config.quote_provider {
+        None => None,
+        Some(QuoteSource::Yahoo) => Some(Box::new(Yahoo)),
+        Some(QuoteSource::Finnhub) => {
+            let api_key = config
+                .api_keys
+                .get("finnhub")
+                .ok_or("quote_provider = \"finnhub\" needs an api_keys.finnhub entry")?;
+            Some(Box::new(Finnhub {
+                api_key: api_key.clone(),
+            }))
+        }
+    })
+}
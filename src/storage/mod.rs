@@ -0,0 +1,503 @@
+#[cfg(feature = "postgres")]
+mod postgres;
+
+use crate::csv_processor::ParsedCsv;
+use crate::error::Result;
+use crate::filter::Filter;
+use crate::integrity::{self, Issue, Repair};
+use crate::models::{
+    Campaign, CashEvent, Dividend, Mark, OptionTrade, Snapshot, StockTrade, TradeQuery,
+};
+use crate::{config, db};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Writes run by [`Storage::transaction`].
+pub type TransactionWork<'a> = dyn FnMut(&mut dyn Storage) -> Result<()> + 'a;
+
+/// Campaign and trade persistence used by the TUI and imports, so the same code runs
+/// against a local SQLite file or a shared PostgreSQL database.
+pub trait Storage {
+    fn campaigns(&mut self) -> Result<Vec<Campaign>>;
+
+    /// Create the campaign unless one with the same name already exists.
+    fn insert_campaign(
+        &mut self,
+        name: &str,
+        symbol: &str,
+        target_exit_price: Option<f64>,
+    ) -> Result<()>;
+
+    /// Hide the campaign from the campaign list, or list it again.
+    fn set_campaign_archived(&mut self, name: &str, archived: bool) -> Result<()>;
+
+    /// Delete the campaign with every trade and cash event recorded in it, returning the
+    /// number of option trades removed.
+    fn delete_campaign(&mut self, name: &str) -> Result<usize>;
+
+    fn trades(&mut self) -> Result<Vec<OptionTrade>>;
+
+    /// Trades matching `query`, filtered, sorted and paged.
+    fn query_trades(&mut self, query: &TradeQuery) -> Result<Vec<OptionTrade>> {
+        Ok(query.apply(self.trades()?))
+    }
+
+    /// How many trades match `filter`.
+    fn count_trades(&mut self, filter: &Filter) -> Result<usize> {
+        Ok(self.trades()?.iter().filter(|t| filter.matches(t)).count())
+    }
+
+    /// Insert the trade, returning its new id.
+    fn insert_trade(&mut self, trade: &OptionTrade) -> Result<i32>;
+
+    /// Insert the trade unless an identical one is already recorded.
+    ///
+    /// Returns `false` when the trade was a duplicate and nothing was written.
+    fn insert_trade_if_new(&mut self, trade: &OptionTrade) -> Result<bool>;
+
+    fn update_trade(&mut self, trade: &OptionTrade) -> Result<()>;
+
+    /// Insert the trade under its own id, as when undoing its deletion.
+    fn restore_trade(&mut self, trade: &OptionTrade) -> Result<()>;
+
+    /// Delete the trade with its tags and mark. Fails while other trades close, roll
+    /// from or are covered by it.
+    fn delete_trade(&mut self, id: i32) -> Result<()>;
+
+    /// The tags of every tagged trade by trade id, each list sorted.
+    fn trade_tags(&mut self) -> Result<HashMap<i32, Vec<String>>>;
+
+    /// Replace the tags of trade `id`.
+    fn set_trade_tags(&mut self, id: i32, tags: &[String]) -> Result<()>;
+
+    /// The latest mark of every marked trade by trade id.
+    fn marks(&mut self) -> Result<HashMap<i32, Mark>>;
+
+    /// Record the mark, replacing the trade's earlier one.
+    fn save_mark(&mut self, mark: &Mark) -> Result<()>;
+
+    fn stock_trades(&mut self) -> Result<Vec<StockTrade>>;
+
+    /// Insert the stock trade unless an identical one is already recorded.
+    ///
+    /// Returns `false` when the trade was a duplicate and nothing was written.
+    fn insert_stock_trade_if_new(&mut self, trade: &StockTrade) -> Result<bool>;
+
+    fn cash_events(&mut self) -> Result<Vec<CashEvent>>;
+
+    /// Insert the dividend or interest payment unless an identical one is already
+    /// recorded.
+    ///
+    /// Returns `false` when it was a duplicate and nothing was written.
+    fn insert_cash_event_if_new(&mut self, event: &CashEvent) -> Result<bool>;
+
+    /// Run `work` in one transaction: everything it writes is kept if it succeeds and
+    /// nothing if it fails.
+    fn transaction(&mut self, work: &mut TransactionWork) -> Result<()>;
+
+    /// Record the snapshot, replacing any earlier snapshot taken on the same date.
+    fn save_snapshot(&mut self, snapshot: &Snapshot) -> Result<()>;
+
+    /// Every snapshot, oldest first.
+    fn snapshots(&mut self) -> Result<Vec<Snapshot>>;
+
+    fn dividends(&mut self) -> Result<Vec<Dividend>>;
+
+    /// Record an ex-dividend date, keeping a known amount if the new one is missing.
+    fn save_dividend(&mut self, dividend: &Dividend) -> Result<()>;
+
+    /// Every row of `db::DUMP_TABLES` as a `db::dump_json` document, which `load` reads
+    /// into a SQLite database.
+    fn dump(&mut self) -> Result<serde_json::Value>;
+
+    /// Stored trades that can't be loaded or would be miscounted. Backends that enforce
+    /// column types and references themselves have nothing to report.
+    fn integrity_issues(&mut self) -> Result<Vec<Issue>> {
+        Ok(Vec::new())
+    }
+
+    fn repair(&mut self, _issue: &Issue, _repair: Repair) -> Result<()> {
+        Err("This database does not support repairs".into())
+    }
+}
+
+/// Store an imported statement's trades and cash events, creating `campaign` for
+/// `symbol` if it doesn't exist yet and skipping rows that are already recorded. Returns
+/// the number of rows imported and the number of duplicates skipped.
+pub fn import_trades(
+    storage: &mut dyn Storage,
+    campaign: &str,
+    symbol: &str,
+    parsed: &ParsedCsv,
+) -> Result<(usize, usize)> {
+    storage.insert_campaign(campaign, symbol, None)?;
+    let mut imported = 0;
+    for trade in &parsed.trades {
+        // Rows already in the database (e.g. from an earlier import) are skipped
+        if storage.insert_trade_if_new(trade)? {
+            imported += 1;
+        }
+    }
+    for stock in &parsed.stocks {
+        if storage.insert_stock_trade_if_new(stock)? {
+            imported += 1;
+        }
+    }
+    for event in &parsed.cash {
+        if storage.insert_cash_event_if_new(event)? {
+            imported += 1;
+        }
+    }
+    let total = parsed.trades.len() + parsed.stocks.len() + parsed.cash.len();
+    Ok((imported, total - imported))
+}
+
+/// Whether `location` is a PostgreSQL connection string rather than a file path.
+pub fn is_postgres_url(location: &str) -> bool {
+    location.starts_with("postgres://") || location.starts_with("postgresql://")
+}
+
+/// Open the database at `location`: a `postgres://` connection string or a SQLite file.
+pub fn open(location: &Path) -> Result<Box<dyn Storage>> {
+    match location.to_str() {
+        Some(url) if is_postgres_url(url) => open_postgres(url),
+        _ => Ok(Box::new(SqliteStorage(db::open_database(
+            location,
+            &config::get().multipliers,
+        )?))),
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn open_postgres(url: &str) -> Result<Box<dyn Storage>> {
+    Ok(Box::new(postgres::PostgresStorage::connect(url)?))
+}
+
+#[cfg(not(feature = "postgres"))]
+fn open_postgres(_url: &str) -> Result<Box<dyn Storage>> {
+    Err("PostgreSQL support is not compiled in; rebuild with `--features postgres`".into())
+}
+
+pub struct SqliteStorage(pub Connection);
+
+impl Storage for SqliteStorage {
+    fn campaigns(&mut self) -> Result<Vec<Campaign>> {
+        Ok(Campaign::get_all(&self.0)?)
+    }
+
+    fn insert_campaign(
+        &mut self,
+        name: &str,
+        symbol: &str,
+        target_exit_price: Option<f64>,
+    ) -> Result<()> {
+        Campaign::insert(&self.0, name, symbol, target_exit_price)?;
+        Ok(())
+    }
+
+    fn set_campaign_archived(&mut self, name: &str, archived: bool) -> Result<()> {
+        Campaign::set_archived(&self.0, name, archived)?;
+        Ok(())
+    }
+
+    fn delete_campaign(&mut self, name: &str) -> Result<usize> {
+        let tx = self.0.unchecked_transaction()?;
+        let trades = Campaign::delete_with_trades(&tx, name)?;
+        tx.commit()?;
+        Ok(trades)
+    }
+
+    fn trades(&mut self) -> Result<Vec<OptionTrade>> {
+        Ok(OptionTrade::get_all(&self.0)?)
+    }
+
+    fn query_trades(&mut self, query: &TradeQuery) -> Result<Vec<OptionTrade>> {
+        Ok(OptionTrade::query(&self.0, query)?)
+    }
+
+    fn count_trades(&mut self, filter: &Filter) -> Result<usize> {
+        Ok(OptionTrade::count(&self.0, filter)?)
+    }
+
+    fn insert_trade(&mut self, trade: &OptionTrade) -> Result<i32> {
+        trade.insert(&self.0)?;
+        Ok(self.0.last_insert_rowid() as i32)
+    }
+
+    fn insert_trade_if_new(&mut self, trade: &OptionTrade) -> Result<bool> {
+        Ok(trade.insert_or_ignore(&self.0)?)
+    }
+
+    fn update_trade(&mut self, trade: &OptionTrade) -> Result<()> {
+        trade.update(&self.0)?;
+        Ok(())
+    }
+
+    fn restore_trade(&mut self, trade: &OptionTrade) -> Result<()> {
+        let tx = self.0.unchecked_transaction()?;
+        trade.restore(&tx)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn delete_trade(&mut self, id: i32) -> Result<()> {
+        OptionTrade::delete(&self.0, id)?;
+        Ok(())
+    }
+
+    fn trade_tags(&mut self) -> Result<HashMap<i32, Vec<String>>> {
+        Ok(OptionTrade::tags_by_trade(&self.0)?)
+    }
+
+    fn set_trade_tags(&mut self, id: i32, tags: &[String]) -> Result<()> {
+        let tx = self.0.unchecked_transaction()?;
+        OptionTrade::set_tags(&tx, id, tags)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn marks(&mut self) -> Result<HashMap<i32, Mark>> {
+        Ok(Mark::by_trade(&self.0)?)
+    }
+
+    fn save_mark(&mut self, mark: &Mark) -> Result<()> {
+        mark.insert(&self.0)?;
+        Ok(())
+    }
+
+    fn stock_trades(&mut self) -> Result<Vec<StockTrade>> {
+        Ok(StockTrade::get_all(&self.0)?)
+    }
+
+    fn insert_stock_trade_if_new(&mut self, trade: &StockTrade) -> Result<bool> {
+        Ok(trade.insert_or_ignore(&self.0)?)
+    }
+
+    fn cash_events(&mut self) -> Result<Vec<CashEvent>> {
+        Ok(CashEvent::get_all(&self.0)?)
+    }
+
+    fn insert_cash_event_if_new(&mut self, event: &CashEvent) -> Result<bool> {
+        Ok(event.insert_or_ignore(&self.0)?)
+    }
+
+    fn transaction(&mut self, work: &mut TransactionWork) -> Result<()> {
+        self.0.execute_batch("BEGIN")?;
+        match work(self) {
+            Ok(()) => Ok(self.0.execute_batch("COMMIT")?),
+            Err(e) => {
+                self.0.execute_batch("ROLLBACK")?;
+                Err(e)
+            }
+        }
+    }
+
+    fn save_snapshot(&mut self, snapshot: &Snapshot) -> Result<()> {
+        snapshot.insert(&self.0)?;
+        Ok(())
+    }
+
+    fn snapshots(&mut self) -> Result<Vec<Snapshot>> {
+        Ok(Snapshot::get_all(&self.0)?)
+    }
+
+    fn dividends(&mut self) -> Result<Vec<Dividend>> {
+        Ok(Dividend::get_all(&self.0)?)
+    }
+
+    fn save_dividend(&mut self, dividend: &Dividend) -> Result<()> {
+        dividend.insert(&self.0)?;
+        Ok(())
+    }
+
+    fn integrity_issues(&mut self) -> Result<Vec<Issue>> {
+        Ok(integrity::check(&self.0)?)
+    }
+
+    fn dump(&mut self) -> Result<serde_json::Value> {
+        db::dump_json(&self.0)
+    }
+
+    fn repair(&mut self, issue: &Issue, repair: Repair) -> Result<()> {
+        Ok(integrity::repair(&self.0, issue, repair)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv_processor::{Broker, CsvProcessor};
+    use crate::models::Action;
+    use time::macros::date;
+
+    #[test]
+    fn test_delete_campaign_keeps_other_campaigns() {
+        let mut storage = open(Path::new(":memory:")).unwrap();
+        let trade = |campaign: &str, action, closes_trade_id| OptionTrade {
+            symbol: "NVTS".to_string(),
+            campaign: campaign.to_string(),
+            action,
+            strike: 6.5,
+            expiration_date: date!(2025 - 07 - 03),
+            date_of_action: date!(2025 - 06 - 26),
+            number_of_shares: 100,
+            credit: 0.18,
+            closes_trade_id,
+            ..Default::default()
+        };
+        for name in ["old", "wheel"] {
+            storage.insert_campaign(name, "NVTS", None).unwrap();
+        }
+        storage
+            .insert_trade(&trade("old", Action::SellPut, None))
+            .unwrap();
+        let opened = storage.trades().unwrap()[0].id;
+        storage
+            .insert_trade(&trade("wheel", Action::BuyPut, opened))
+            .unwrap();
+        storage.set_campaign_archived("wheel", true).unwrap();
+
+        assert_eq!(storage.delete_campaign("old").unwrap(), 1);
+
+        let campaigns = storage.campaigns().unwrap();
+        assert_eq!(campaigns.len(), 1);
+        assert!(campaigns[0].archived);
+        let trades = storage.trades().unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].closes_trade_id, None);
+    }
+
+    #[test]
+    fn test_identical_trades_entered_by_hand_are_kept() {
+        let mut storage = open(Path::new(":memory:")).unwrap();
+        storage.insert_campaign("wheel", "NVTS", None).unwrap();
+        let trade = |action, closes_trade_id| OptionTrade {
+            symbol: "NVTS".to_string(),
+            campaign: "wheel".to_string(),
+            action,
+            strike: 6.5,
+            expiration_date: date!(2025 - 07 - 03),
+            date_of_action: date!(2025 - 06 - 26),
+            number_of_shares: 100,
+            credit: 0.18,
+            closes_trade_id,
+            ..Default::default()
+        };
+        // Two lots opened and closed together, one close per lot
+        for _ in 0..2 {
+            let opened = storage.insert_trade(&trade(Action::SellPut, None)).unwrap();
+            storage
+                .insert_trade(&trade(Action::BuyPut, Some(opened)))
+                .unwrap();
+        }
+        assert_eq!(storage.trades().unwrap().len(), 4);
+
+        // Imports still skip a trade that is already recorded
+        assert!(
+            !storage
+                .insert_trade_if_new(&trade(Action::SellPut, None))
+                .unwrap()
+        );
+        assert_eq!(storage.trades().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_rejected_writes_are_described() {
+        let mut storage = open(Path::new(":memory:")).unwrap();
+        let trade = OptionTrade {
+            symbol: "NVTS".to_string(),
+            campaign: "wheel".to_string(),
+            ..Default::default()
+        };
+        let err = storage.insert_trade(&trade).unwrap_err();
+        assert_eq!(err.to_string(), "There is no campaign with that name");
+
+        storage.insert_campaign("wheel", "NVTS", None).unwrap();
+        let id = storage.insert_trade(&trade).unwrap();
+        let err = storage
+            .restore_trade(&OptionTrade {
+                id: Some(id),
+                ..trade
+            })
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "option_trades already has a row with the same id"
+        );
+    }
+
+    #[test]
+    fn test_reimport_after_backfill_inserts_nothing() {
+        let mut storage = open(Path::new(":memory:")).unwrap();
+        let mut parsed = CsvProcessor::new(Broker::ETrade)
+            .parse_csv("tests/etrade.csv")
+            .unwrap();
+        for trade in &mut parsed.trades {
+            trade.campaign = "wheel".to_string();
+        }
+        for stock in &mut parsed.stocks {
+            stock.campaign = "wheel".to_string();
+        }
+        for event in &mut parsed.cash {
+            event.campaign = Some("wheel".to_string());
+        }
+        let (imported, skipped) = import_trades(&mut *storage, "wheel", "NVTS", &parsed).unwrap();
+        assert!(imported > 0);
+        assert_eq!(skipped, 0);
+
+        // What backfill-greeks writes for trades imported without a delta
+        for mut trade in storage.trades().unwrap() {
+            trade.delta = 0.3;
+            storage.update_trade(&trade).unwrap();
+        }
+
+        assert_eq!(
+            import_trades(&mut *storage, "wheel", "NVTS", &parsed).unwrap(),
+            (0, imported)
+        );
+    }
+
+    #[test]
+    fn test_restore_trade_keeps_its_id_and_uuid() {
+        let mut storage = open(Path::new(":memory:")).unwrap();
+        storage.insert_campaign("wheel", "NVTS", None).unwrap();
+        let mut trade = OptionTrade {
+            symbol: "NVTS".to_string(),
+            campaign: "wheel".to_string(),
+            strike: 6.5,
+            delta: 0.2,
+            expiration_date: date!(2025 - 07 - 03),
+            date_of_action: date!(2025 - 06 - 26),
+            number_of_shares: 100,
+            credit: 0.18,
+            ..Default::default()
+        };
+        storage
+            .insert_trade(&OptionTrade {
+                strike: 6.0,
+                ..trade.clone()
+            })
+            .unwrap();
+        let id = storage.insert_trade(&trade).unwrap();
+        trade = storage
+            .trades()
+            .unwrap()
+            .into_iter()
+            .find(|t| t.id == Some(id))
+            .unwrap();
+        assert!(trade.uuid.is_some());
+        storage
+            .set_trade_tags(trade.id.unwrap(), &["earnings".to_string()])
+            .unwrap();
+
+        storage.delete_trade(trade.id.unwrap()).unwrap();
+        assert_eq!(storage.trades().unwrap().len(), 1);
+        assert!(storage.trade_tags().unwrap().is_empty());
+
+        storage.restore_trade(&trade).unwrap();
+        let trades = storage.trades().unwrap();
+        assert_eq!(trades.len(), 2);
+        assert!(trades.iter().any(|t| t == &trade));
+    }
+}
@@ -1,35 +1,38 @@
 use crate::app::{ACTIONS, App};
+use crate::config;
 use ratatui::{
     prelude::*,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     widgets::*,
 };
 
 pub fn draw_add_trade(f: &mut Frame, app: &App) {
+    let theme = &config::get().theme;
     let size = f.area();
-    let block = Block::default().title("Add Trade [Tab: next, Shift+Tab: prev, ←/→: change action, Enter: submit, ESC: return]").borders(Borders::ALL).style(Style::default().fg(Color::Cyan));
+    let block = Block::default().title(format!("Add Trade [Tab: next, Shift+Tab: prev, ←/→: change action or campaign, {}: option chain, {}: copy last trade, Enter: submit, ESC: return]", config::get().keys.option_chain, config::get().keys.clone_trade)).borders(Borders::ALL).style(Style::default().fg(theme.accent));
     let fields = [
         "Action",
         "Strike",
         "Delta",
         "Expiration (YYYY-MM-DD)",
         "Date of Action (YYYY-MM-DD)",
-        "Shares",
+        "Contracts",
         "Credit",
+        "Tags (comma separated)",
+        "Campaign",
     ];
     let items: Vec<ListItem> = fields
         .iter()
         .enumerate()
         .map(|(i, label)| {
-            let content = if i == 0 {
-                format!("{}: < {} >", label, ACTIONS[app.action_index])
-            } else {
-                let idx = i - 1;
-                format!("{}: {}", label, app.form_fields[idx])
+            let content = match i {
+                0 => format!("{}: < {} >", label, ACTIONS[app.action_index]),
+                8 => format!("{}: < {} >", label, app.add_trade_campaign),
+                _ => format!("{}: {}", label, app.form_fields[i - 1]),
             };
             let style = if i == app.form_index {
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(theme.highlight)
                     .add_modifier(Modifier::BOLD)
             } else {
                 Style::default()
@@ -46,7 +49,8 @@ pub fn draw_add_trade(f: &mut Frame, app: &App) {
             width: size.width.saturating_sub(4),
             height: 1,
         };
-        let error_paragraph = Paragraph::new(err.as_str()).style(Style::default().fg(Color::Red));
+        let error_paragraph =
+            Paragraph::new(err.as_str()).style(Style::default().fg(theme.negative));
         f.render_widget(error_paragraph, area);
     }
 }
@@ -1,5 +1,8 @@
-use crate::app::App;
-use crate::logic::{calculate_campaign_summary, calculate_weekly_premium};
+use crate::app::{App, CampaignStats};
+use crate::config;
+use crate::logic::{GoalProgress, ShareLedger, dividend_risks, premium_by_dte};
+use crate::models::{OptionKind, StockTrade};
+use crate::money::{format_cents, to_dollars};
 use ratatui::{
     prelude::*,
     style::{Color, Modifier, Style},
@@ -7,8 +10,10 @@ use ratatui::{
 };
 
 pub fn draw_campaign_dashboard(f: &mut Frame, app: &App) {
+    let theme = &config::get().theme;
     let size = f.area();
-    if app.selected_campaign.is_none() {
+    let (Some(stats), Some(campaign)) = (app.selected_stats(), app.selected_campaign.as_ref())
+    else {
         let block = ratatui::widgets::Block::default()
             .title("No Campaign Selected")
             .borders(ratatui::widgets::Borders::ALL);
@@ -16,55 +21,23 @@ pub fn draw_campaign_dashboard(f: &mut Frame, app: &App) {
             ratatui::widgets::Paragraph::new("No campaign is currently selected.").block(block);
         f.render_widget(para, size);
         return;
-    }
-    let title = if let Some(camp) = &app.selected_campaign {
-        format!(
-            "Campaign: {} [a: add trade, v: view trades, ESC: back]",
-            camp.name
-        )
-    } else {
-        "Campaign Dashboard".to_string()
     };
+    let keys = &config::get().keys;
+    let title = format!(
+        "Campaign: {} [{}: add trade, {}: view trades, {}: refresh quote, {}/{}: undo/redo, ESC: back]",
+        campaign.name, keys.add_trade, keys.view_trades, keys.refresh_quotes, keys.undo, keys.redo
+    );
     let block = Block::default()
         .title(title)
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::Cyan));
-
-    // Calculate campaign summary
-    let campaign_trades: Vec<&crate::models::OptionTrade> = app
-        .trades
-        .iter()
-        .filter(|t| {
-            t.campaign == app.selected_campaign.as_ref().unwrap().name
-                && t.symbol == app.selected_campaign.as_ref().unwrap().symbol
-        })
-        .collect();
-
-    let (break_even, weeks_running, profit_per_week, total_credits, running_profit_loss) =
-        calculate_campaign_summary(
-            &campaign_trades,
-            app.selected_campaign.as_ref().unwrap().target_exit_price,
-        );
+        .style(Style::default().fg(theme.accent));
 
-    // Calculate weekly premium for this campaign
-    let campaign_trades_vec: Vec<crate::models::OptionTrade> = app
-        .trades
-        .iter()
-        .filter(|t| {
-            t.campaign == app.selected_campaign.as_ref().unwrap().name
-                && t.symbol == app.selected_campaign.as_ref().unwrap().symbol
-        })
-        .cloned()
-        .collect();
-
-    let weekly_premium = calculate_weekly_premium(&campaign_trades_vec);
-
-    let pl_color = if running_profit_loss >= 0.0 {
-        Color::Green
+    let pl_color = if stats.running_pl >= 0 {
+        theme.positive
     } else {
-        Color::Red
+        theme.negative
     };
-    let summary_lines = vec![
+    let mut summary_lines = vec![
         Line::from(vec![Span::raw("")]),
         Line::from(vec![Span::styled(
             "Campaign Summary:",
@@ -72,45 +45,458 @@ pub fn draw_campaign_dashboard(f: &mut Frame, app: &App) {
         )]),
         Line::from(vec![Span::raw(format!(
             "Target Exit Price: {}",
-            app.selected_campaign
-                .as_ref()
-                .unwrap()
+            campaign
                 .target_exit_price
                 .map(|p| format!("${p:.2}"))
                 .unwrap_or_else(|| "N/A".to_string())
         ))]),
         Line::from(vec![Span::raw(format!(
-            "Total Credits: ${total_credits:.2}"
+            "Total Credits: {}",
+            format_cents(stats.total_credits)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Commissions & Fees: {}",
+            format_cents(stats.costs)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Dividends & Interest: {}",
+            format_cents(stats.cash_income)
         ))]),
         Line::from(vec![
             Span::raw("Running P/L: "),
             Span::styled(
-                format!("${running_profit_loss:.2}"),
+                format_cents(stats.running_pl),
                 Style::default().fg(pl_color).add_modifier(Modifier::BOLD),
             ),
         ]),
         Line::from(vec![Span::raw(format!(
+            "Capital at Risk: {}",
+            super::summary::capital_line(&stats.capital)
+        ))]),
+    ];
+    // With shares held, the covered call figures below show break-even as the adjusted basis
+    if stats.covered_calls.is_none() {
+        summary_lines.push(Line::from(vec![Span::raw(format!(
             "Break Even: {}",
-            break_even
+            stats
+                .break_even
                 .map(|be| format!("${be:.2}"))
                 .unwrap_or_else(|| "N/A".to_string())
+        ))]));
+    }
+    summary_lines.extend([
+        Line::from(vec![Span::raw(format!(
+            "Weeks Running: {}",
+            stats.weeks_running
         ))]),
-        Line::from(vec![Span::raw(format!("Weeks Running: {weeks_running}"))]),
         Line::from(vec![Span::raw(format!(
             "Profit per Week: {}",
-            profit_per_week
+            stats
+                .profit_per_week
                 .map(|ppw| format!("${ppw:.2}"))
                 .unwrap_or_else(|| "N/A".to_string())
         ))]),
         Line::from(vec![Span::styled(
-            format!("This Week's Premium: ${weekly_premium:.2}"),
+            format!(
+                "This Week's Premium: {}",
+                format_cents(stats.weekly_premium)
+            ),
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.highlight)
                 .add_modifier(Modifier::BOLD),
         )]),
-    ];
+    ]);
+    if let Some(goal) = &stats.goal {
+        let color = if goal.fraction() >= 1.0 {
+            theme.positive
+        } else {
+            theme.highlight
+        };
+        summary_lines.push(Line::from(vec![
+            Span::raw("Premium Goal: "),
+            Span::styled(goal_text(goal), Style::default().fg(color)),
+        ]));
+    }
+    // Open positions priced with a mark, valued as if closed at it
+    let unrealized: Vec<i64> = stats
+        .open
+        .iter()
+        .filter_map(|lot| Some(lot.unrealized(app.marks.get(&lot.trade.id?)?.price)))
+        .collect();
+    if !unrealized.is_empty() {
+        summary_lines.push(Line::from(vec![Span::raw(format!(
+            "Unrealized P/L: {} ({} of {} open position(s) marked)",
+            format_cents(unrealized.iter().sum()),
+            unrealized.len(),
+            stats.open.len()
+        ))]));
+    }
+    summary_lines.extend(covered_call_lines(stats));
+    summary_lines.extend(quality_lines(stats));
+    summary_lines.extend(dte_lines(stats));
+    summary_lines.extend(market_lines(app, stats));
+    summary_lines.extend(dividend_lines(app, stats));
+    summary_lines.extend(share_lines(app, stats));
     let para = Paragraph::new(summary_lines)
         .block(block)
         .style(Style::default().fg(Color::White));
-    f.render_widget(para, size);
+    // A line needs two points; with fewer the chart would be empty
+    if stats.cumulative_premium.len() < 2 {
+        f.render_widget(para, size);
+        return;
+    }
+    let [summary_area, chart_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).areas(size);
+    f.render_widget(para, summary_area);
+    draw_premium_chart(f, chart_area, &stats.cumulative_premium);
+}
+
+/// Cumulative net premium by trade date, to show whether the campaign is speeding up or
+/// stalling.
+/// `$180.00 of $500.00/month this month (36%)`, for the dashboard and campaign list.
+pub fn goal_text(goal: &GoalProgress) -> String {
+    format!(
+        "{} of {} {} ({:.0}%)",
+        format_cents(goal.premium),
+        goal.goal,
+        goal.period_label(),
+        goal.fraction() * 100.0
+    )
+}
+
+fn draw_premium_chart(f: &mut Frame, area: Rect, series: &[(time::Date, i64)]) {
+    let theme = &config::get().theme;
+    let (Some(&(first, _)), Some(&(last, total))) = (series.first(), series.last()) else {
+        return;
+    };
+    let points: Vec<(f64, f64)> = series
+        .iter()
+        .map(|&(date, cents)| ((date - first).whole_days() as f64, to_dollars(cents)))
+        .collect();
+    // Keep zero in view so a campaign under water is visible at a glance
+    let low = series.iter().map(|&(_, c)| c).min().unwrap_or(0).min(0);
+    let high = series.iter().map(|&(_, c)| c).max().unwrap_or(0).max(0);
+    let color = if total >= 0 {
+        theme.positive
+    } else {
+        theme.negative
+    };
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(&points);
+    let chart = Chart::new(vec![dataset])
+        .block(
+            Block::default()
+                .title("Cumulative Net Premium")
+                .borders(Borders::ALL)
+                .style(Style::default().fg(theme.accent)),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, (last - first).whole_days().max(1) as f64])
+                .labels([first.to_string(), last.to_string()])
+                .style(Style::default().fg(Color::Gray)),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([to_dollars(low), to_dollars(high.max(low + 1))])
+                .labels([format_cents(low), format_cents(high)])
+                .style(Style::default().fg(Color::Gray)),
+        );
+    f.render_widget(chart, area);
+}
+
+/// Win rate and the size of wins and losses, once a position has been closed.
+/// Shares held, their adjusted basis and what each open short call returns on it.
+fn covered_call_lines(stats: &CampaignStats) -> Vec<Line<'static>> {
+    let Some(covered) = &stats.covered_calls else {
+        return Vec::new();
+    };
+    let theme = &config::get().theme;
+    let percent = |fraction: Option<f64>| {
+        fraction
+            .map(|f| format!("{:.1}%", f * 100.0))
+            .unwrap_or_else(|| "N/A".to_string())
+    };
+    let mut lines = vec![
+        Line::from(vec![Span::raw("")]),
+        Line::from(vec![Span::styled(
+            "Covered Calls:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(vec![Span::raw(format!(
+            "Shares Held: {}, adjusted basis ${:.2}/share after premium",
+            covered.shares, covered.adjusted_basis
+        ))]),
+    ];
+    if covered.calls.is_empty() {
+        lines.push(Line::from(vec![Span::raw(
+            "No short calls open against the shares",
+        )]));
+    }
+    for call in &covered.calls {
+        // A strike below the basis locks in a loss if the shares are called away
+        let color = if call.above_basis >= 0.0 {
+            theme.positive
+        } else {
+            theme.negative
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!(
+                "Short ${:.2} Call exp {} on {} shares: ",
+                call.strike, call.expiration_date, call.shares
+            )),
+            Span::styled(
+                format!(
+                    "${:.2} {} basis",
+                    call.above_basis.abs(),
+                    if call.above_basis >= 0.0 {
+                        "above"
+                    } else {
+                        "below"
+                    }
+                ),
+                Style::default().fg(color),
+            ),
+            Span::raw(format!(
+                ", yield if called {}, if expired {}",
+                percent(call.if_called),
+                percent(call.if_expired)
+            )),
+        ]));
+    }
+    lines
+}
+
+fn quality_lines(stats: &CampaignStats) -> Vec<Line<'static>> {
+    let quality = &stats.quality;
+    let Some(win_rate) = quality.win_rate() else {
+        return Vec::new();
+    };
+    let or_na = |cents: Option<i64>| cents.map_or_else(|| "N/A".to_string(), format_cents);
+    vec![
+        Line::from(vec![Span::raw("")]),
+        Line::from(vec![Span::styled(
+            "Trade Quality:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(vec![Span::raw(format!(
+            "Win Rate: {:.1}% ({} of {} closed position(s))",
+            win_rate * 100.0,
+            quality.winners,
+            quality.closed
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Avg Credit per Contract: {}",
+            or_na(quality.avg_credit_per_contract)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Avg Loss: {}",
+            or_na(quality.avg_loss)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Largest Win / Loss: {} / {}",
+            or_na(quality.largest_win),
+            or_na(quality.largest_loss)
+        ))]),
+    ]
+}
+
+/// Open short premium by days to expiration, to show how much of it comes due soon.
+fn dte_lines(stats: &CampaignStats) -> Vec<Line<'static>> {
+    if !stats.open.iter().any(|lot| lot.short) {
+        return Vec::new();
+    }
+    let mut lines = vec![
+        Line::from(vec![Span::raw("")]),
+        Line::from(vec![Span::styled(
+            "Open Premium by Days to Expiration:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]),
+    ];
+    for bucket in premium_by_dte(&stats.open, crate::logic::today()) {
+        lines.push(Line::from(vec![Span::raw(format!(
+            "{:>5} days: {} ({} position(s))",
+            bucket.label,
+            format_cents(bucket.premium),
+            bucket.positions
+        ))]));
+    }
+    lines
+}
+
+/// Underlying price and how far open options and the target exit are from it.
+fn market_lines(app: &App, stats: &CampaignStats) -> Vec<Line<'static>> {
+    let theme = &config::get().theme;
+    let Some(campaign) = &app.selected_campaign else {
+        return Vec::new();
+    };
+    let mut lines = vec![
+        Line::from(vec![Span::raw("")]),
+        Line::from(vec![Span::styled(
+            "Market:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]),
+    ];
+    let price = match app.quotes.get(&campaign.symbol) {
+        Some(&price) => price,
+        None => {
+            let reason = match (&app.quote_error, &app.quote_provider) {
+                (Some(err), _) => err.clone(),
+                (None, None) => "set quote_provider in the config file".to_string(),
+                (None, Some(_)) => "not fetched yet".to_string(),
+            };
+            lines.push(Line::from(vec![Span::raw(format!(
+                "{}: N/A ({reason})",
+                campaign.symbol
+            ))]));
+            return lines;
+        }
+    };
+    lines.push(Line::from(vec![Span::raw(format!(
+        "{}: ${price:.2}",
+        campaign.symbol
+    ))]));
+    if let Some(target) = campaign.target_exit_price {
+        lines.push(Line::from(vec![Span::raw(format!(
+            "Distance to Target Exit: ${:.2} ({:+.1}%)",
+            target - price,
+            (target - price) / price * 100.0
+        ))]));
+    }
+
+    for lot in &stats.open {
+        let itm = lot.in_the_money(price);
+        // Short options going in the money are the ones that need attention
+        let color = if itm == lot.short {
+            theme.negative
+        } else {
+            theme.positive
+        };
+        let distance = price - lot.trade.strike;
+        lines.push(Line::from(vec![
+            Span::raw(format!(
+                "{} {} ${:.2} exp {}: ",
+                if lot.short { "Short" } else { "Long" },
+                match lot.kind {
+                    OptionKind::Put => "Put",
+                    OptionKind::Call => "Call",
+                },
+                lot.trade.strike,
+                lot.trade.expiration_date
+            )),
+            Span::styled(
+                if itm { "ITM" } else { "OTM" },
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                ", ${:.2} ({:.1}%) from strike",
+                distance.abs(),
+                distance.abs() / price * 100.0
+            )),
+        ]));
+    }
+    lines
+}
+
+/// The next ex-dividend date and any short calls that could be assigned early before it.
+fn dividend_lines(app: &App, stats: &CampaignStats) -> Vec<Line<'static>> {
+    let theme = &config::get().theme;
+    let Some(campaign) = &app.selected_campaign else {
+        return Vec::new();
+    };
+    let today = crate::logic::today();
+    let Some(next) = app
+        .dividends
+        .iter()
+        .filter(|d| d.symbol.eq_ignore_ascii_case(&campaign.symbol) && d.ex_date >= today)
+        .min_by_key(|d| d.ex_date)
+    else {
+        return Vec::new();
+    };
+    let mut lines = vec![Line::from(vec![Span::raw(format!(
+        "Next Ex-Dividend: {}{}",
+        next.ex_date,
+        next.amount
+            .map(|a| format!(" (${a:.2}/share)"))
+            .unwrap_or_default()
+    ))])];
+    let price = app.quotes.get(&campaign.symbol).copied();
+    for risk in dividend_risks(&stats.open, &app.dividends, today, |_| price) {
+        lines.push(Line::from(vec![Span::styled(
+            format!(
+                "Early assignment risk: short ${:.2} call exp {} is {} before the {} ex-date",
+                risk.lot.trade.strike,
+                risk.lot.trade.expiration_date,
+                match risk.in_the_money {
+                    Some(true) => "in the money",
+                    _ => "open",
+                },
+                risk.dividend.ex_date
+            ),
+            Style::default()
+                .fg(theme.negative)
+                .add_modifier(Modifier::BOLD),
+        )]));
+    }
+    lines
+}
+
+/// Shares held in the campaign from assignments and share trades, lot by lot, and the
+/// stock trades.
+fn share_lines(app: &App, stats: &CampaignStats) -> Vec<Line<'static>> {
+    let Some(campaign) = &app.selected_campaign else {
+        return Vec::new();
+    };
+    let trades: Vec<&StockTrade> = app
+        .stock_trades
+        .iter()
+        .filter(|t| t.campaign == campaign.name)
+        .collect();
+    let ledger = &stats.shares;
+    if *ledger == ShareLedger::default() && trades.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = vec![
+        Line::from(vec![Span::raw("")]),
+        Line::from(vec![Span::styled(
+            "Shares:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(vec![Span::raw(format!(
+            "Held: {} (basis {}{})",
+            ledger.shares(),
+            format_cents(ledger.cost_basis()),
+            ledger
+                .average_cost()
+                .map(|cost| format!(", ${cost:.2}/share"))
+                .unwrap_or_default()
+        ))]),
+    ];
+    for lot in &ledger.lots {
+        lines.push(Line::from(vec![Span::raw(format!(
+            "Lot {}: {} @ ${:.2}",
+            lot.opened,
+            lot.shares,
+            to_dollars(lot.basis) / lot.shares.abs() as f64
+        ))]));
+    }
+    if ledger.realized != 0 {
+        lines.push(Line::from(vec![Span::raw(format!(
+            "Realized on Shares: {}",
+            format_cents(ledger.realized)
+        ))]));
+    }
+    for trade in trades {
+        lines.push(Line::from(vec![Span::raw(format!(
+            "{} {} {} @ ${:.2}",
+            trade.date,
+            if trade.shares < 0 { "Sold" } else { "Bought" },
+            trade.shares.abs(),
+            trade.price
+        ))]));
+    }
+    lines
 }
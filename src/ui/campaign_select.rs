@@ -1,38 +1,92 @@
 use crate::app::App;
-use crate::logic::{calculate_total_premium_sold, calculate_weekly_premium};
+use crate::config;
+use crate::money::format_cents;
 use ratatui::{prelude::*, widgets::*};
 
 pub fn draw_campaign_select(f: &mut Frame, app: &mut App) {
+    let theme = &config::get().theme;
     let size = f.area();
-    let total_premium = calculate_total_premium_sold(&app.trades);
-    let weekly_premium = calculate_weekly_premium(&app.trades);
+    let total_premium = app.summary.total_pnl;
+    let weekly_premium = app.summary.weekly_premium;
 
     // Create colored spans for the title
+    let keys = &config::get().keys;
     let title_spans = vec![
-        Span::raw("Select Campaign [n: new, ↑/↓: move, Enter: select, q: quit] | "),
+        Span::raw(format!(
+            "Select Campaign [{}: new, ↑/↓: move, Enter: select, {}: archive, {}: delete, {}: {} archived, {}: quit] | ",
+            keys.new_campaign,
+            keys.archive_campaign,
+            keys.delete_campaign,
+            keys.show_archived,
+            if app.show_archived { "hide" } else { "show" },
+            keys.quit
+        )),
         Span::styled(
-            format!("Total Premium: ${total_premium:.2}"),
+            format!("Total Premium: {}", format_cents(total_premium)),
             Style::default()
-                .fg(Color::Green)
+                .fg(theme.positive)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(" | "),
         Span::styled(
-            format!("This Week: ${weekly_premium:.2}"),
+            format!("This Week: {}", format_cents(weekly_premium)),
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.highlight)
                 .add_modifier(Modifier::BOLD),
         ),
     ];
 
+    // A line under the list asks to confirm a delete or reports the last change
+    let footer = match (&app.delete_pending, &app.status) {
+        (Some(name), _) => {
+            let trades = app.trades.iter().filter(|t| t.campaign == *name).count();
+            Some(Line::styled(
+                format!(
+                    "Delete campaign '{name}' and its {trades} trade(s)? y: delete, any other key: cancel"
+                ),
+                Style::default()
+                    .fg(theme.negative)
+                    .add_modifier(Modifier::BOLD),
+            ))
+        }
+        (None, Some(status)) => Some(Line::styled(
+            status.clone(),
+            Style::default().fg(theme.highlight),
+        )),
+        (None, None) => None,
+    };
+    let (list_area, footer_area) = match footer {
+        Some(_) => {
+            let chunks = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).split(size);
+            (chunks[0], Some(chunks[1]))
+        }
+        None => (size, None),
+    };
+
     let block = Block::default()
         .title(Line::from(title_spans))
         .borders(Borders::ALL);
     let items: Vec<ListItem> = app
-        .campaigns
-        .iter()
-        .map(|c| ListItem::new(c.name.clone()))
+        .listed_campaigns()
+        .into_iter()
+        .map(|c| {
+            let goal = app
+                .campaign_stats(&c.name)
+                .and_then(|stats| stats.goal.as_ref())
+                .map(super::campaign_dashboard::goal_text)
+                .map(|text| format!("  {text}"))
+                .unwrap_or_default();
+            if c.archived {
+                ListItem::new(format!("{} (archived){goal}", c.name))
+                    .style(Style::default().fg(Color::DarkGray))
+            } else {
+                ListItem::new(format!("{}{goal}", c.name))
+            }
+        })
         .collect();
     let list = List::new(items).block(block).highlight_symbol("> ");
-    f.render_stateful_widget(list, size, &mut app.campaign_list_state);
+    f.render_stateful_widget(list, list_area, &mut app.campaign_list_state);
+    if let (Some(footer), Some(area)) = (footer, footer_area) {
+        f.render_widget(Paragraph::new(footer), area);
+    }
 }
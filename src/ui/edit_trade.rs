@@ -5,7 +5,7 @@ pub fn draw_edit_trade(f: &mut Frame, app: &mut App) {
     let size = f.area();
     let block = Block::default()
         .title(
-            "Edit Trade [Tab: next, Shift+Tab: prev, ←/→: change action, Enter: save, ESC: cancel]",
+            "Edit Trade [Tab: next, Shift+Tab: prev, ←/→: change action or campaign, Enter: save, ESC: cancel]",
         )
         .borders(Borders::ALL);
     let fields = [
@@ -15,18 +15,19 @@ pub fn draw_edit_trade(f: &mut Frame, app: &mut App) {
         "Delta",
         "Expiration (YYYY-MM-DD)",
         "Date of Action (YYYY-MM-DD)",
-        "Shares",
+        "Contracts",
         "Credit",
+        "Tags (comma separated)",
+        "Campaign",
     ];
     let items: Vec<ListItem> = fields
         .iter()
         .enumerate()
         .map(|(i, label)| {
-            let mut content = if i == 1 {
-                format!("{}: < {} >", label, ACTIONS[app.edit_action_index])
-            } else {
-                let idx = if i < 1 { i } else { i - 1 };
-                format!("{}: {}", label, app.edit_trade_fields[idx])
+            let mut content = match i {
+                1 => format!("{}: < {} >", label, ACTIONS[app.edit_action_index]),
+                9 => format!("{}: < {} >", label, app.edit_trade_campaign),
+                _ => format!("{}: {}", label, app.edit_trade_fields[i]),
             };
             if i == app.edit_form_index {
                 content.push_str(" <");
@@ -0,0 +1,35 @@
+use crate::config;
+use ratatui::{
+    prelude::*,
+    style::{Modifier, Style},
+    widgets::*,
+};
+
+/// Draw `message` in a box over the middle of the screen.
+pub fn draw_error_popup(f: &mut Frame, message: &str) {
+    let theme = &config::get().theme;
+    let size = f.area();
+    let width = (size.width * 3 / 5).max(40).min(size.width);
+    // Room for the wrapped message and the borders
+    let lines = message.len() as u16 / width.saturating_sub(4).max(1) + 1;
+    let height = (lines + 2).min(size.height);
+    let area = Rect {
+        x: size.x + (size.width - width) / 2,
+        y: size.y + (size.height - height) / 2,
+        width,
+        height,
+    };
+    let block = Block::default()
+        .title("Error [any key: dismiss]")
+        .borders(Borders::ALL)
+        .style(
+            Style::default()
+                .fg(theme.negative)
+                .add_modifier(Modifier::BOLD),
+        );
+    let para = Paragraph::new(message)
+        .block(block)
+        .wrap(Wrap { trim: true });
+    f.render_widget(Clear, area);
+    f.render_widget(para, area);
+}
@@ -0,0 +1,74 @@
+use crate::app::App;
+use crate::config;
+use ratatui::{
+    prelude::*,
+    style::{Modifier, Style},
+    widgets::*,
+};
+
+pub fn draw_import_csv(f: &mut Frame, app: &mut App) {
+    let theme = &config::get().theme;
+    let form = &mut app.import;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(7)])
+        .split(f.area());
+    let focused = |field: usize| {
+        if form.field == field {
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        }
+    };
+
+    let files_block = Block::default()
+        .title(format!(
+            "Import CSV: {} [↑/↓: move, Enter: open/pick, Tab: next field, ESC: cancel]",
+            form.dir.display()
+        ))
+        .borders(Borders::ALL)
+        .style(Style::default().fg(theme.accent));
+    let items: Vec<ListItem> = form
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            // The first entry is the parent directory
+            if i == 0 || path.is_dir() {
+                ListItem::new(format!("{name}/"))
+            } else {
+                ListItem::new(name.into_owned())
+            }
+        })
+        .collect();
+    let list = List::new(items)
+        .block(files_block)
+        .highlight_style(focused(0))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, chunks[0], &mut form.list_state);
+
+    let file = form
+        .file
+        .as_ref()
+        .map_or_else(|| "(none)".to_string(), |p| p.display().to_string());
+    let mut lines = vec![
+        Line::raw(format!("File: {file}")),
+        Line::styled(format!("Broker: < {} >", form.broker_name()), focused(1)),
+        Line::styled(format!("Campaign: {}", form.campaign), focused(2)),
+        Line::styled(format!("Symbol: {}", form.symbol), focused(3)),
+    ];
+    if let Some(err) = &form.error {
+        lines.push(Line::styled(
+            err.clone(),
+            Style::default().fg(theme.negative),
+        ));
+    }
+    let form_block = Block::default()
+        .title("[←/→: change broker, Enter: import]")
+        .borders(Borders::ALL)
+        .style(Style::default().fg(theme.accent));
+    f.render_widget(Paragraph::new(lines).block(form_block), chunks[1]);
+}
@@ -2,6 +2,12 @@ pub mod add_trade;
 pub mod campaign_dashboard;
 pub mod campaign_select;
 pub mod edit_trade;
+pub mod error_popup;
+pub mod import_csv;
+pub mod monthly_report;
+pub mod move_trade;
 pub mod new_campaign;
+pub mod option_chain;
+pub mod repair;
 pub mod summary;
 pub mod view_trades;
@@ -0,0 +1,121 @@
+use crate::app::App;
+use crate::config;
+use crate::logic::today;
+use crate::metrics::MOVING_AVERAGE_WEEKS;
+use crate::money::format_cents;
+use ratatui::prelude::*;
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Row, Table};
+
+/// Income by month next to the latest weeks' premium.
+pub fn draw_monthly_report(f: &mut Frame, app: &App) {
+    let [months_area, weeks_area] =
+        Layout::horizontal([Constraint::Length(54), Constraint::Min(0)]).areas(f.area());
+    draw_months(f, months_area, app);
+    draw_weeks(f, weeks_area, app);
+}
+
+/// Right-aligned cents, colored by sign.
+fn money(cents: i64) -> Cell<'static> {
+    let theme = &config::get().theme;
+    let color = if cents >= 0 {
+        theme.positive
+    } else {
+        theme.negative
+    };
+    Cell::from(Text::from(format_cents(cents)).right_aligned()).style(Style::default().fg(color))
+}
+
+fn right(text: &str) -> Cell<'static> {
+    Cell::from(Text::from(text.to_string()).right_aligned())
+}
+
+/// Net premium, realized P/L and trade count per month across campaigns, newest first,
+/// with the totals kept in view below.
+fn draw_months(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &config::get().theme;
+    let months = &app.summary.months;
+    let bold = Style::default()
+        .fg(theme.highlight)
+        .add_modifier(Modifier::BOLD);
+    let header = Row::new(vec![
+        Cell::from("Month"),
+        right("Net Premium"),
+        right("Realized P/L"),
+        right("Trades"),
+    ])
+    .style(bold);
+    let rows = months.iter().rev().map(|m| {
+        Row::new(vec![
+            Cell::from(format!("{}-{:02}", m.month.year(), m.month.month() as u8)),
+            money(m.net_premium),
+            money(m.realized),
+            right(&m.trades.to_string()),
+        ])
+    });
+    let footer = Row::new(vec![
+        Cell::from("Total"),
+        money(months.iter().map(|m| m.net_premium).sum()),
+        money(months.iter().map(|m| m.realized).sum()),
+        right(&months.iter().map(|m| m.trades).sum::<usize>().to_string()),
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD));
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Length(14),
+        Constraint::Length(14),
+        Constraint::Length(8),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .footer(footer)
+        .column_spacing(2)
+        .block(
+            Block::default()
+                .title("Monthly Income [ESC: back]")
+                .borders(Borders::ALL)
+                .style(Style::default().fg(theme.accent)),
+        );
+    f.render_widget(table, area);
+}
+
+/// Net premium of each of the latest weeks that fit, newest first, with its moving
+/// average to smooth out the weeks between expirations.
+fn draw_weeks(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &config::get().theme;
+    // Borders and the header take three rows
+    let count = area.height.saturating_sub(3) as usize;
+    let weeks = app.metrics.recent_weeks(today(), count);
+    let header = Row::new(vec![
+        Cell::from("Week of"),
+        right("Net Premium"),
+        right(&format!("{MOVING_AVERAGE_WEEKS}-Week Avg")),
+    ])
+    .style(
+        Style::default()
+            .fg(theme.highlight)
+            .add_modifier(Modifier::BOLD),
+    );
+    let rows = weeks.iter().rev().map(|w| {
+        Row::new(vec![
+            Cell::from(w.week.to_string()),
+            money(w.net_premium),
+            money(w.moving_average),
+        ])
+    });
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Length(14),
+        Constraint::Length(14),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(2)
+        .block(
+            Block::default()
+                .title("Weekly Income")
+                .borders(Borders::ALL)
+                .style(Style::default().fg(theme.accent)),
+        );
+    f.render_widget(table, area);
+}
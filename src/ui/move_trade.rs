@@ -0,0 +1,40 @@
+use crate::app::App;
+use crate::config;
+use ratatui::{
+    prelude::*,
+    style::{Modifier, Style},
+    widgets::*,
+};
+
+pub fn draw_move_trade(f: &mut Frame, app: &mut App) {
+    let theme = &config::get().theme;
+    let size = f.area();
+    let title = match &app.move_trade {
+        Some(trade) => format!(
+            "Move {} {} {} from {} to [↑/↓: move, Enter: move trade, ESC: back]",
+            trade.symbol,
+            trade.action.as_str(),
+            trade.strike,
+            trade.campaign
+        ),
+        None => "Move Trade".to_string(),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .style(Style::default().fg(theme.accent));
+    let items: Vec<ListItem> = app
+        .move_targets
+        .iter()
+        .map(|name| ListItem::new(name.clone()))
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, size, &mut app.move_list_state);
+}
@@ -0,0 +1,54 @@
+use crate::app::App;
+use crate::config;
+use crate::models::OptionKind;
+use ratatui::{
+    prelude::*,
+    style::{Modifier, Style},
+    widgets::*,
+};
+
+pub fn draw_option_chain(f: &mut Frame, app: &mut App) {
+    let theme = &config::get().theme;
+    let size = f.area();
+    let symbol = app
+        .selected_campaign
+        .as_ref()
+        .map(|c| c.symbol.as_str())
+        .unwrap_or_default();
+    let block = Block::default()
+        .title(format!(
+            "{symbol} Option Chain [↑/↓: move, Enter: fill trade form, ESC: back]"
+        ))
+        .borders(Borders::ALL)
+        .style(Style::default().fg(theme.accent));
+    let items: Vec<ListItem> = app
+        .chain
+        .iter()
+        .map(|quote| {
+            ListItem::new(format!(
+                "{}  {:<4}  ${:>8.2}  bid {:>6.2}  ask {:>6.2}  delta {}",
+                quote.expiration_date,
+                match quote.kind {
+                    OptionKind::Put => "Put",
+                    OptionKind::Call => "Call",
+                },
+                quote.strike,
+                quote.bid,
+                quote.ask,
+                quote
+                    .delta
+                    .map(|d| format!("{d:>5.2}"))
+                    .unwrap_or_else(|| "  N/A".to_string())
+            ))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, size, &mut app.chain_list_state);
+}
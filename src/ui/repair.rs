@@ -0,0 +1,50 @@
+use crate::app::App;
+use crate::config;
+use ratatui::{
+    prelude::*,
+    style::{Modifier, Style},
+    widgets::*,
+};
+
+pub fn draw_repair(f: &mut Frame, app: &App) {
+    let theme = &config::get().theme;
+    let size = f.area();
+    let block = Block::default()
+        .title(format!(
+            "{} data problem(s) found [↑/↓: move, f: apply fix, d: delete trade, ESC: skip]",
+            app.issues.len()
+        ))
+        .borders(Borders::ALL)
+        .style(Style::default().fg(theme.negative));
+    let items: Vec<ListItem> = app
+        .issues
+        .iter()
+        .enumerate()
+        .map(|(i, issue)| {
+            let fix = issue
+                .fix_description()
+                .unwrap_or_else(|| "no fix, delete the trade".to_string());
+            let style = if i == app.issue_index {
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(format!("{} (fix: {fix})", issue.describe())).style(style)
+        })
+        .collect();
+    let list = List::new(items).block(block).highlight_symbol("> ");
+    f.render_widget(list, size);
+    if let Some(ref err) = app.form_error {
+        let area = Rect {
+            x: size.x + 2,
+            y: size.y + size.height.saturating_sub(2),
+            width: size.width.saturating_sub(4),
+            height: 1,
+        };
+        let error_paragraph =
+            Paragraph::new(err.as_str()).style(Style::default().fg(theme.negative));
+        f.render_widget(error_paragraph, area);
+    }
+}
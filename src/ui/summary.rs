@@ -1,41 +1,77 @@
 use crate::app::App;
+use crate::config;
+use crate::logic::{CapitalAtRisk, SymbolExposure};
+use crate::money::format_cents;
 use ratatui::prelude::*;
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table};
 
 pub fn draw_summary(f: &mut Frame, app: &App) {
+    let theme = &config::get().theme;
     let area = f.area();
     let block = Block::default()
         .title("Summary Dashboard")
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::Cyan));
+        .style(Style::default().fg(theme.accent));
 
     // Gather metrics
-    let total_pnl = app.total_pnl();
+    let total_pnl = app.summary.total_pnl;
     let trades_in_progress = app.trades_in_progress_this_week();
-    // TODO: Add free cash calculation
-    let _free_cash = app.free_cash();
-    let roic = app.roic();
+    let account = &app.summary.account;
+    let roic = app.summary.roic;
 
-    let pnl_color = if total_pnl >= 0.0 {
-        Color::Green
+    let pnl_color = if total_pnl >= 0 {
+        theme.positive
     } else {
-        Color::Red
+        theme.negative
     };
     let roic_str = roic
         .map(|r| format!("{:.2}%", r * 100.0))
         .unwrap_or_else(|| "N/A".to_string());
 
-    let weekly_premium = crate::logic::calculate_weekly_premium(&app.trades);
+    let weekly_premium = app.summary.weekly_premium;
 
     let mut lines = vec![
         Line::from(vec![
             Span::styled("Total P&L: ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::styled(format!("${total_pnl:.2}"), Style::default().fg(pnl_color)),
+            Span::styled(format_cents(total_pnl), Style::default().fg(pnl_color)),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "Account Cash: ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                "{} of {} contributed",
+                format_cents(account.cash),
+                format_cents(account.contributed)
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "Return on Contributions: ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(
+                account
+                    .return_on_contributions(total_pnl)
+                    .map(|r| format!("{:.2}%", r * 100.0))
+                    .unwrap_or_else(|| "N/A (record deposits with `cash deposit`)".to_string()),
+            ),
         ]),
         Line::from(vec![
             Span::styled("ROIC: ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::raw(roic_str),
+            Span::raw(format!(
+                "{roic_str} on {} committed to options sold",
+                format_cents(app.metrics.total().capital_at_risk)
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "Capital at Risk: ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(capital_line(&app.summary.capital)),
         ]),
         Line::from(vec![
             Span::styled(
@@ -50,8 +86,8 @@ pub fn draw_summary(f: &mut Frame, app: &App) {
                 Style::default().add_modifier(Modifier::BOLD),
             ),
             Span::styled(
-                format!("${weekly_premium:.2}"),
-                Style::default().fg(Color::Yellow),
+                format_cents(weekly_premium),
+                Style::default().fg(theme.highlight),
             ),
         ]),
         Line::from(vec![Span::styled(
@@ -79,16 +115,88 @@ pub fn draw_summary(f: &mut Frame, app: &App) {
         "Hotkeys:",
         Style::default().add_modifier(Modifier::BOLD),
     )]));
-    lines.push(Line::from(vec![Span::raw(
-        "c: Campaigns   n: New Campaign   q: Quit",
-    )]));
+    let keys = &config::get().keys;
+    lines.push(Line::from(vec![Span::raw(format!(
+        "{}: Campaigns   {}: New Campaign   {}: Add Trade   {}: Import CSV   {}: Backup   {}: Income Report   {}/{}: Undo/Redo   {}: Quit",
+        keys.campaigns, keys.new_campaign, keys.add_trade, keys.import, keys.backup, keys.monthly_report, keys.undo, keys.redo, keys.quit
+    ))]));
     lines.push(Line::from(vec![Span::styled(
         "Press a hotkey to navigate.",
         Style::default().fg(Color::DarkGray),
     )]));
+    if let Some(status) = &app.status {
+        lines.push(Line::from(vec![Span::raw("")]));
+        lines.push(Line::from(vec![Span::styled(
+            status.clone(),
+            Style::default().fg(theme.highlight),
+        )]));
+    }
 
     let para = Paragraph::new(lines)
         .block(block)
         .style(Style::default().fg(Color::White));
-    f.render_widget(para, area);
+    let symbols = &app.summary.symbols;
+    if symbols.is_empty() {
+        f.render_widget(para, area);
+        return;
+    }
+    // Header row and borders, with room left for the summary above
+    let height = (symbols.len() as u16 + 3).min(area.height / 2);
+    let [summary_area, symbols_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(height)]).areas(area);
+    f.render_widget(para, summary_area);
+    draw_symbol_table(f, symbols_area, symbols);
+}
+
+/// Net premium, open positions and capital at risk per underlying across campaigns.
+fn draw_symbol_table(f: &mut Frame, area: Rect, symbols: &[SymbolExposure]) {
+    let theme = &config::get().theme;
+    let header = Row::new(vec![
+        Cell::from("Symbol"),
+        Cell::from("Net Premium"),
+        Cell::from("Open"),
+        Cell::from("Capital at Risk"),
+    ])
+    .style(
+        Style::default()
+            .fg(theme.highlight)
+            .add_modifier(Modifier::BOLD),
+    );
+    let rows = symbols.iter().map(|s| {
+        let color = if s.net_premium >= 0 {
+            theme.positive
+        } else {
+            theme.negative
+        };
+        Row::new(vec![
+            Cell::from(s.symbol.clone()),
+            Cell::from(format_cents(s.net_premium)).style(Style::default().fg(color)),
+            Cell::from(s.open_positions.to_string()),
+            Cell::from(format_cents(s.capital_at_risk)),
+        ])
+    });
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Length(14),
+        Constraint::Length(6),
+        Constraint::Length(16),
+    ];
+    let table = Table::new(rows, widths).header(header).block(
+        Block::default()
+            .title("By Symbol")
+            .borders(Borders::ALL)
+            .style(Style::default().fg(theme.accent)),
+    );
+    f.render_widget(table, area);
+}
+
+/// Collateral of open short options, split into puts and calls, e.g.
+/// "$6500.00 (puts $5000.00, calls $1500.00)".
+pub fn capital_line(capital: &CapitalAtRisk) -> String {
+    format!(
+        "{} (puts {}, calls {})",
+        format_cents(capital.total()),
+        format_cents(capital.puts),
+        format_cents(capital.calls)
+    )
 }
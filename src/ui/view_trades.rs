@@ -1,13 +1,16 @@
-use crate::app::App;
+use crate::app::{App, CampaignStats, TradeColumn};
+use crate::config;
+use crate::models::{OptionTrade, TradeStatus};
 use ratatui::{
     prelude::*,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     widgets::*,
 };
 
-pub fn draw_view_trades(f: &mut Frame, app: &App) {
+pub fn draw_view_trades(f: &mut Frame, app: &mut App) {
+    let theme = &config::get().theme;
     let size = f.area();
-    if app.selected_campaign.is_none() {
+    let Some(stats) = app.selected_stats() else {
         let block = ratatui::widgets::Block::default()
             .title("No Campaign Selected")
             .borders(ratatui::widgets::Borders::ALL);
@@ -15,85 +18,160 @@ pub fn draw_view_trades(f: &mut Frame, app: &App) {
             ratatui::widgets::Paragraph::new("No campaign is currently selected.").block(block);
         f.render_widget(para, size);
         return;
-    }
+    };
+    let keys = &config::get().keys;
+    let visible = app.visible_trades();
+    let title = match &app.mark_input {
+        Some((id, typed)) => {
+            format!("Mark trade {id} at price: {typed}_ [Enter: save, ESC: cancel]")
+        }
+        None if app.searching => format!(
+            "Search: {}_ [Enter: keep, ESC: clear]",
+            app.trade_filter.search
+        ),
+        None => format!(
+            "View Trades{} [Up/Down/PgUp/PgDn/Home/End: move, 1-0: sort, {}: search, {}/{}/{}: action/open/date filter, {}: edit, {}: move to campaign, {}: status, {}: mark, {}: copy to new trade, {}: delete, {}/{}: undo/redo, ESC: return]",
+            if app.trade_filter.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    " ({} of {}: {})",
+                    visible.len(),
+                    stats.trades.len(),
+                    app.trade_filter.describe()
+                )
+            },
+            keys.search,
+            keys.filter_action,
+            keys.filter_open,
+            keys.filter_dates,
+            keys.edit_trade,
+            keys.move_trade,
+            keys.trade_status,
+            keys.mark,
+            keys.clone_trade,
+            keys.delete_trade,
+            keys.undo,
+            keys.redo
+        ),
+    };
     let block = Block::default()
-        .title("View Trades [Up/Down: scroll, ESC: return]")
+        .title(title)
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::Cyan));
-    let header = Row::new(vec![
-        Cell::from("Symbol"),
-        Cell::from("Campaign"),
-        Cell::from("Action"),
-        Cell::from("Strike"),
-        Cell::from("Delta"),
-        Cell::from("Exp."),
-        Cell::from("Date"),
-        Cell::from("Shares"),
-        Cell::from("Credit"),
-        Cell::from("Total Credit"),
-    ])
+        .style(Style::default().fg(theme.accent));
+    // The sorted column is marked with the direction
+    let header = Row::new(
+        [
+            ("Symbol", Some(TradeColumn::Symbol)),
+            ("Campaign", Some(TradeColumn::Campaign)),
+            ("Action", Some(TradeColumn::Action)),
+            ("Strike", Some(TradeColumn::Strike)),
+            ("Delta", Some(TradeColumn::Delta)),
+            ("Exp.", Some(TradeColumn::Expiration)),
+            ("DTE", None),
+            ("Date", Some(TradeColumn::Date)),
+            ("Shares", Some(TradeColumn::Shares)),
+            ("Credit", Some(TradeColumn::Credit)),
+            ("Total Credit", Some(TradeColumn::TotalCredit)),
+            ("Status", None),
+            ("Mark", None),
+        ]
+        .into_iter()
+        .map(|(name, column)| {
+            if column == Some(app.trade_sort.column) {
+                let arrow = if app.trade_sort.descending {
+                    "▼"
+                } else {
+                    "▲"
+                };
+                Cell::from(format!("{name} {arrow}"))
+            } else {
+                Cell::from(name)
+            }
+        }),
+    )
     .style(
         Style::default()
-            .fg(Color::Yellow)
+            .fg(theme.highlight)
             .add_modifier(Modifier::BOLD),
     );
-    let mut rows: Vec<Row> = vec![header];
-    // Filter and sort trades for current campaign
-    let mut campaign_trades: Vec<&crate::models::OptionTrade> = app
-        .trades
+    let today = crate::logic::today();
+    let rows: Vec<Row> = visible
         .iter()
-        .filter(|t| {
-            t.campaign == app.selected_campaign.as_ref().unwrap().name
-                && t.symbol == app.selected_campaign.as_ref().unwrap().symbol
+        .map(|t| {
+            let status = app.trade_status(t);
+            let pl = t.number_of_shares as f64 * t.credit;
+            let pl_color = match t.action {
+                crate::models::Action::BuyPut => theme.negative,
+                _ => {
+                    if pl >= 0.0 {
+                        theme.positive
+                    } else {
+                        theme.negative
+                    }
+                }
+            };
+            Row::new(vec![
+                Cell::from(t.symbol.clone()),
+                Cell::from(t.campaign.clone()),
+                Cell::from(format!("{:?}", t.action)),
+                Cell::from(t.strike.to_string()),
+                Cell::from(t.delta.to_string()),
+                Cell::from(t.expiration_date.to_string()),
+                // Only open trades still have time left
+                Cell::from(if status == TradeStatus::Open {
+                    (t.expiration_date - today).whole_days().to_string()
+                } else {
+                    String::new()
+                }),
+                Cell::from(t.date_of_action.to_string()),
+                Cell::from(t.number_of_shares.to_string()),
+                Cell::from(t.credit.to_string()),
+                Cell::from(format!("{pl:.2}")).style(Style::default().fg(pl_color)),
+                // Statuses set by hand are marked with '*'
+                Cell::from(format!(
+                    "{}{}",
+                    status.as_str(),
+                    if t.status.is_some() { "*" } else { "" }
+                )),
+                Cell::from(mark_cell(stats, app, t)),
+            ])
         })
         .collect();
-
-    // Sort by expiration date (earliest first)
-    campaign_trades.sort_by(|a, b| a.expiration_date.cmp(&b.expiration_date));
-
-    rows.extend(
-        campaign_trades
-            .iter()
-            .skip(app.table_scroll)
-            .take((size.height as usize).saturating_sub(3))
-            .map(|t| {
-                let pl = t.number_of_shares as f64 * t.credit;
-                let pl_color = match t.action {
-                    crate::models::Action::BuyPut => Color::Red,
-                    _ => {
-                        if pl >= 0.0 {
-                            Color::Green
-                        } else {
-                            Color::Red
-                        }
-                    }
-                };
-                Row::new(vec![
-                    Cell::from(t.symbol.clone()),
-                    Cell::from(t.campaign.clone()),
-                    Cell::from(format!("{:?}", t.action)),
-                    Cell::from(t.strike.to_string()),
-                    Cell::from(t.delta.to_string()),
-                    Cell::from(t.expiration_date.to_string()),
-                    Cell::from(t.date_of_action.to_string()),
-                    Cell::from(t.number_of_shares.to_string()),
-                    Cell::from(t.credit.to_string()),
-                    Cell::from(format!("{pl:.2}")).style(Style::default().fg(pl_color)),
-                ])
-            }),
-    );
+    // Wide enough for a header with the sort arrow
     let widths = [
         Constraint::Length(8),
         Constraint::Length(12),
         Constraint::Length(8),
+        Constraint::Length(8),
         Constraint::Length(7),
-        Constraint::Length(6),
         Constraint::Length(12),
+        Constraint::Length(5),
         Constraint::Length(12),
-        Constraint::Length(6),
-        Constraint::Length(7),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(14),
+        Constraint::Length(9),
         Constraint::Length(12),
     ];
-    let table = Table::new(rows, widths).block(block);
-    f.render_widget(table, size);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(table, size, &mut app.trade_table_state);
+}
+
+/// The mark of an open trade with the share of its premium captured, e.g. "0.20 (80%)".
+fn mark_cell(stats: &CampaignStats, app: &App, trade: &OptionTrade) -> String {
+    let Some(mark) = trade.id.and_then(|id| app.marks.get(&id)) else {
+        return String::new();
+    };
+    let Some(lot) = stats.open.iter().find(|lot| lot.trade.id == trade.id) else {
+        return String::new();
+    };
+    match lot.premium_captured(mark.price) {
+        Some(captured) => format!("{:.2} ({:.0}%)", mark.price, captured * 100.0),
+        None => format!("{:.2}", mark.price),
+    }
 }
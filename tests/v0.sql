@@ -0,0 +1,28 @@
+-- A database as written by the first release, before schema versions were recorded
+CREATE TABLE campaigns (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    symbol TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    target_exit_price REAL
+);
+CREATE TABLE option_trades (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    campaign TEXT NOT NULL,
+    action TEXT NOT NULL,
+    strike REAL NOT NULL,
+    delta REAL NOT NULL,
+    expiration_date TEXT NOT NULL,
+    date_of_action TEXT NOT NULL,
+    number_of_shares INTEGER NOT NULL,
+    credit REAL NOT NULL
+);
+INSERT INTO campaigns (name, symbol, created_at, target_exit_price)
+    VALUES ('SOFI Wheel', 'SOFI', '2025-05-01', 16.5);
+INSERT INTO option_trades (symbol, campaign, action, strike, delta, expiration_date,
+    date_of_action, number_of_shares, credit) VALUES
+    ('SOFI', 'SOFI Wheel', 'SellPut', 14.0, 0.25, '2025-05-16', '2025-05-02', 200, 0.31),
+    ('SOFI', 'SOFI Wheel', 'BuyPut', 14.0, 0.0, '2025-05-16', '2025-05-09', 200, -0.08),
+    ('SOFI', 'SOFI Wheel', 'SellPut', 14.0, 0.25, '2025-05-16', '2025-05-02', 200, 0.31),
+    ('HOOD', 'HOOD', 'SellCall', 45.0, 0.3, '2025-05-23', '2025-05-05', 100, 1.12);